@@ -76,6 +76,18 @@ impl<'a> SubstituteTypeParams<'a> {
             Some(result)
         }
     }
+
+    /// Substitutes the explicit type arguments of a use of `generic_def`, like
+    /// the turbofish of a function call, for its type parameters.
+    pub fn for_generic_args(
+        source_scope: &'a SemanticsScope<'a, RootDatabase>,
+        generic_def: hir::GenericDef,
+        type_args: Vec<ast::TypeRef>,
+    ) -> SubstituteTypeParams<'a> {
+        let substs = generic_def.params(source_scope.db).into_iter().zip(type_args).collect();
+        SubstituteTypeParams { source_scope, substs, previous: Box::new(NullTransformer) }
+    }
+
     fn get_substitution_inner(
         &self,
         node: &ra_syntax::SyntaxNode,
@@ -148,6 +160,7 @@ impl<'a> QualifyPaths<'a> {
             }
             PathResolution::Local(_)
             | PathResolution::TypeParam(_)
+            | PathResolution::ConstParam(_)
             | PathResolution::SelfType(_) => None,
             PathResolution::Macro(_) => None,
             PathResolution::AssocItem(_) => None,
@@ -175,7 +188,7 @@ impl<'a> AstTransform<'a> for QualifyPaths<'a> {
     }
 }
 
-pub(crate) fn path_to_ast(path: hir::ModPath) -> ast::Path {
+pub fn path_to_ast(path: hir::ModPath) -> ast::Path {
     let parse = ast::SourceFile::parse(&path.to_string());
     parse
         .tree()
@@ -257,6 +257,34 @@ fn main() {
     )
 }
 
+#[test]
+fn doctest_extract_function() {
+    check(
+        "extract_function",
+        r#####"
+fn main() {
+    let n = 1;
+    <|>let m = n + 2;
+    let k = m * n;<|>
+    let g = k + 3;
+}
+"#####,
+        r#####"
+fn main() {
+    let n = 1;
+    let k = fun_name(n);
+    let g = k + 3;
+}
+
+fn fun_name(n: i32) -> i32 {
+    let m = n + 2;
+    let k = m * n;
+    k
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_fill_match_arms() {
     check(
@@ -330,6 +358,56 @@ fn foo<T: Copy + Clone>() { }
     )
 }
 
+#[test]
+fn doctest_generate_function() {
+    check(
+        "generate_function",
+        r#####"
+struct Baz;
+fn baz() -> Baz { Baz }
+fn foo() {
+    bar<|>("", baz());
+}
+"#####,
+        r#####"
+struct Baz;
+fn baz() -> Baz { Baz }
+fn foo() {
+    bar("", baz());
+}
+
+fn bar(arg: &str, baz: Baz) {
+    todo!()
+}
+"#####,
+    )
+}
+
+#[test]
+fn doctest_inline_call() {
+    check(
+        "inline_call",
+        r#####"
+fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
+
+fn main() {
+    let x = add<|>(1, 2);
+}
+"#####,
+        r#####"
+fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
+
+fn main() {
+    let x = 1 + 2;
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_inline_local_variable() {
     check(
@@ -528,6 +606,27 @@ fn handle(action: Action) {
     )
 }
 
+#[test]
+fn doctest_organize_imports() {
+    check(
+        "organize_imports",
+        r#####"
+mod foo { pub struct Bar; pub struct Baz; pub struct Qux; }
+use foo::<|>Qux;
+use foo::Baz;
+use foo::Bar;
+
+fn main() { let _ = (Bar, Qux); }
+"#####,
+        r#####"
+mod foo { pub struct Bar; pub struct Baz; pub struct Qux; }
+use foo::{Bar, Qux};
+
+fn main() { let _ = (Bar, Qux); }
+"#####,
+    )
+}
+
 #[test]
 fn doctest_remove_dbg() {
     check(
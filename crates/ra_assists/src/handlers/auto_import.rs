@@ -59,6 +59,15 @@ pub(crate) fn auto_import(ctx: AssistCtx) -> Option<Assist> {
     group.finish()
 }
 
+/// Finds the paths to import the item named by the last segment of `path`,
+/// which is the path of an unresolved `use` tree.
+pub fn use_path_candidates(sema: &Semantics<RootDatabase>, path: &ast::Path) -> BTreeSet<ModPath> {
+    match AutoImportAssets::for_use_tree_path(sema, path) {
+        Some(auto_import_assets) => auto_import_assets.search_for_imports(sema.db),
+        None => BTreeSet::new(),
+    }
+}
+
 #[derive(Debug)]
 struct AutoImportAssets {
     import_candidate: ImportCandidate,
@@ -99,6 +108,19 @@ impl AutoImportAssets {
         })
     }
 
+    /// The qualifier of an unresolved `use` path is usually what's wrong with
+    /// it, so we only search for the last segment.
+    fn for_use_tree_path(sema: &Semantics<RootDatabase>, path: &ast::Path) -> Option<Self> {
+        let name_ref = path.segment()?.name_ref()?;
+        let syntax_under_caret = path.syntax().to_owned();
+        let module_with_name_to_import = sema.scope(&syntax_under_caret).module()?;
+        Some(Self {
+            import_candidate: ImportCandidate::UnqualifiedName(name_ref.text().to_string()),
+            module_with_name_to_import,
+            syntax_under_caret,
+        })
+    }
+
     fn get_search_query(&self) -> &str {
         match &self.import_candidate {
             ImportCandidate::UnqualifiedName(name) => name,
@@ -0,0 +1,1013 @@
+use hir::{HasSource, HirDisplay, Local, PathResolution, Type};
+use itertools::Itertools;
+use ra_fmt::leading_indent;
+use ra_ide_db::defs::Definition;
+use ra_syntax::{
+    ast::{self, AstNode, SelfParamKind},
+    match_ast, NodeOrToken,
+    SyntaxKind::{
+        BREAK_EXPR, CONTINUE_EXPR, FOR_EXPR, LAMBDA_EXPR, LIFETIME, LOOP_EXPR, RETURN_EXPR,
+        TRY_EXPR, WHILE_EXPR, WHITESPACE,
+    },
+    SyntaxNode, TextRange, TextUnit,
+};
+use stdx::format_to;
+
+use crate::{
+    utils::{line_indent, reindent, replace_ranges},
+    Assist, AssistCtx, AssistId,
+};
+
+// Assist: extract_function
+//
+// Extracts the selected statements or expression into a new function.
+//
+// ```
+// fn main() {
+//     let n = 1;
+//     <|>let m = n + 2;
+//     let k = m * n;<|>
+//     let g = k + 3;
+// }
+// ```
+// ->
+// ```
+// fn main() {
+//     let n = 1;
+//     let k = fun_name(n);
+//     let g = k + 3;
+// }
+//
+// fn fun_name(n: i32) -> i32 {
+//     let m = n + 2;
+//     let k = m * n;
+//     k
+// }
+// ```
+pub(crate) fn extract_function(ctx: AssistCtx) -> Option<Assist> {
+    if ctx.frange.range.is_empty() {
+        return None;
+    }
+    let body = FunctionBody::from_selection(&ctx)?;
+    let fn_def = body.elements[0].ancestors().find_map(ast::FnDef::cast)?;
+    if !body.range.is_subrange(&fn_def.body()?.syntax().text_range()) {
+        return None;
+    }
+    // Inherent methods get a sibling method, everything else a free function.
+    let in_impl = fn_def
+        .syntax()
+        .parent()
+        .and_then(|it| it.parent())
+        .and_then(ast::ImplDef::cast)
+        .map_or(false, |it| it.target_trait().is_none());
+
+    let exit = Exit::of(&body, &fn_def)?;
+    let (params, self_param) = body.params(&ctx, &fn_def)?;
+    if self_param.is_some() && !in_impl {
+        return None;
+    }
+    let outputs = body.outputs(&ctx)?;
+    let tail_ty = match &body.tail {
+        Some(tail) => ctx.sema.type_of_expr(tail).filter(|it| !it.is_unit()),
+        None => None,
+    };
+    // If the new function returns whether to leave, it can't return anything
+    // else.
+    let returns_exit = !matches!(exit, Exit::Fallthrough | Exit::Try(_));
+    if returns_exit && (!outputs.is_empty() || tail_ty.is_some()) {
+        return None;
+    }
+
+    let fn_text = fn_def.syntax().text().to_string();
+    let fn_start = fn_def.syntax().text_range().start();
+    let old_indent = line_indent(&fn_text, body.range.start() - fn_start);
+    let fn_indent = leading_indent(fn_def.syntax()).map_or(String::new(), |it| it.to_string());
+    let body_indent = format!("{}    ", fn_indent);
+
+    let mut replacements = Vec::new();
+    for param in params.iter() {
+        param.adjust_usages(&mut replacements);
+    }
+    exit.adjust_body(&body, &mut replacements);
+    let selected = TextRange::from_to(body.range.start() - fn_start, body.range.end() - fn_start);
+    let mut fun_body = reindent(
+        &replace_ranges(&fn_text, selected, fn_start, replacements),
+        &old_indent,
+        &body_indent,
+    );
+    if let Some(last_line) = exit.last_line(&ctx, &outputs, body.tail.is_some()) {
+        format_to!(fun_body, "\n{}{}", body_indent, last_line);
+    }
+
+    let mut fun = String::new();
+    format_to!(fun, "\n\n{}fn fun_name(", fn_indent);
+    let params_text =
+        self_param.clone().into_iter().chain(params.iter().map(|it| it.to_param(&ctx))).join(", ");
+    fun.push_str(&params_text);
+    fun.push_str(")");
+    if let Some(ret_ty) = exit.ret_ty(&ctx, &outputs, tail_ty.as_ref()) {
+        format_to!(fun, " -> {}", ret_ty);
+    }
+    format_to!(fun, " {{\n{}\n{}}}", fun_body, fn_indent);
+
+    let callee = match self_param {
+        Some(_) => "self.fun_name",
+        None if in_impl => "Self::fun_name",
+        None => "fun_name",
+    };
+    let args = params.iter().map(|it| it.to_arg(&ctx)).join(", ");
+    let call = exit.make_call(&ctx, format!("{}({})", callee, args), &outputs, &old_indent);
+    let call = match &body.tail {
+        Some(_) => call,
+        None if call.ends_with('}') || call.ends_with(';') => call,
+        None => format!("{};", call),
+    };
+
+    let insert_offset = fn_def.syntax().text_range().end();
+    let cursor_offset = insert_offset + TextUnit::of_str(&call) - body.range.len()
+        + TextUnit::of_str(&format!("\n\n{}fn ", fn_indent));
+    ctx.add_assist(AssistId("extract_function"), "Extract into function", move |edit| {
+        edit.target(body.range);
+        edit.replace(body.range, call);
+        edit.insert(insert_offset, fun);
+        edit.set_cursor(cursor_offset);
+    })
+}
+
+/// The statements or the expression to extract.
+struct FunctionBody {
+    elements: Vec<SyntaxNode>,
+    /// The expression whose value the extracted code evaluates to.
+    tail: Option<ast::Expr>,
+    range: TextRange,
+}
+
+impl FunctionBody {
+    fn from_selection(ctx: &AssistCtx) -> Option<FunctionBody> {
+        let range = trimmed_selection(ctx)?;
+        let node = match ctx.covering_node_for_range(range) {
+            NodeOrToken::Node(it) => it,
+            NodeOrToken::Token(it) => it.parent(),
+        };
+
+        if let Some(block) = ast::Block::cast(node.clone()) {
+            let mut elements = Vec::new();
+            for child in block.syntax().children() {
+                let child_range = child.text_range();
+                if child_range.is_subrange(&range) {
+                    elements.push(child);
+                } else if child_range.start() < range.end() && range.start() < child_range.end() {
+                    return None;
+                }
+            }
+            let first = elements.first()?.text_range().start();
+            let last = elements.last()?.text_range().end();
+            if TextRange::from_to(first, last) != range {
+                return None;
+            }
+            let tail = block.expr().filter(|it| elements.last() == Some(it.syntax()));
+            return Some(FunctionBody { elements, tail, range });
+        }
+
+        let same_range = node.ancestors().take_while(|it| it.text_range() == range).collect_vec();
+        let stmt = same_range
+            .iter()
+            .find(|it| it.parent().map_or(false, |parent| ast::Block::can_cast(parent.kind())));
+        if let Some(stmt) = stmt {
+            let tail = ast::Expr::cast(stmt.clone());
+            return Some(FunctionBody { elements: vec![stmt.clone()], tail, range });
+        }
+
+        let expr = same_range.iter().rev().find_map(|it| ast::Expr::cast(it.clone()))?;
+        match &expr {
+            ast::Expr::BlockExpr(block) if !block.is_standalone() => return None,
+            _ => (),
+        }
+        Some(FunctionBody { elements: vec![expr.syntax().clone()], tail: Some(expr), range })
+    }
+
+    fn contains(&self, range: TextRange) -> bool {
+        range.is_subrange(&self.range)
+    }
+
+    /// Finds the locals defined outside of the extracted code which it uses,
+    /// and `self` separately.
+    fn params(&self, ctx: &AssistCtx, fn_def: &ast::FnDef) -> Option<(Vec<Param>, Option<String>)> {
+        let mut params: Vec<Param> = Vec::new();
+        let mut self_usages = Vec::new();
+        let path_exprs = self.elements.iter().flat_map(|it| it.descendants());
+        for path_expr in path_exprs.filter_map(ast::PathExpr::cast) {
+            let local = match ctx.sema.resolve_path(&path_expr.path()?) {
+                Some(PathResolution::Local(it)) => it,
+                _ => continue,
+            };
+            let def_range = local_range(ctx, local)?;
+            if self.contains(def_range) {
+                continue;
+            }
+            if local.is_self(ctx.db) {
+                self_usages.push(path_expr);
+                continue;
+            }
+            match params.iter_mut().find(|it| it.local == local) {
+                Some(param) => param.usages.push(path_expr),
+                None => params.push(Param {
+                    local,
+                    ty: local.ty(ctx.db),
+                    kind: ParamKind::Value,
+                    usages: vec![path_expr],
+                }),
+            }
+        }
+
+        for param in params.iter_mut() {
+            let mutated = param.usages.iter().any(|it| is_mutated(ctx, it));
+            let used_after = self.is_used_after(ctx, param.local);
+            param.kind = match (used_after, mutated) {
+                (true, true) => ParamKind::MutRef,
+                (true, false) if !param.ty.is_copy(ctx.db) && !param.ty.is_mutable_reference() => {
+                    ParamKind::SharedRef
+                }
+                (_, true) => ParamKind::MutValue,
+                (_, false) => ParamKind::Value,
+            };
+        }
+
+        let self_param = if self_usages.is_empty() {
+            None
+        } else {
+            let mutated = self_usages.iter().any(|it| is_mutated(ctx, it));
+            let outer = fn_def.param_list()?.self_param()?.kind();
+            let self_param = match outer {
+                SelfParamKind::MutRef if mutated => "&mut self",
+                SelfParamKind::Ref | SelfParamKind::MutRef => "&self",
+                // We only borrow `self` if it's still needed afterwards.
+                SelfParamKind::Owned => {
+                    let self_local = self_usages.iter().find_map(|it| {
+                        match ctx.sema.resolve_path(&it.path()?)? {
+                            PathResolution::Local(it) => Some(it),
+                            _ => None,
+                        }
+                    })?;
+                    match (self.is_used_after(ctx, self_local), mutated) {
+                        (true, true) => "&mut self",
+                        (true, false) => "&self",
+                        (false, true) => "mut self",
+                        (false, false) => "self",
+                    }
+                }
+            };
+            Some(self_param.to_string())
+        };
+        Some((params, self_param))
+    }
+
+    /// Finds the locals defined in the extracted code which are used after it.
+    fn outputs(&self, ctx: &AssistCtx) -> Option<Vec<Local>> {
+        let mut outputs = Vec::new();
+        let bind_pats = self.elements.iter().flat_map(|it| it.descendants());
+        for bind_pat in bind_pats.filter_map(ast::BindPat::cast) {
+            let local = match ctx.sema.to_def(&bind_pat) {
+                Some(it) => it,
+                None => continue,
+            };
+            if !outputs.contains(&local) && self.is_used_after(ctx, local) {
+                local.name(ctx.db)?;
+                outputs.push(local);
+            }
+        }
+        Some(outputs)
+    }
+
+    fn is_used_after(&self, ctx: &AssistCtx, local: Local) -> bool {
+        // In a loop, the extracted code sees the values of the previous
+        // iteration.
+        let def_range = local_range(ctx, local);
+        let in_loop = self.elements[0]
+            .ancestors()
+            .skip(1)
+            .take_while(|it| !def_range.map_or(false, |def| def.is_subrange(&it.text_range())))
+            .any(|it| matches!(it.kind(), LOOP_EXPR | WHILE_EXPR | FOR_EXPR));
+        if in_loop {
+            return true;
+        }
+        Definition::Local(local).find_usages(ctx.db, None).iter().any(|it| {
+            it.file_range.file_id == ctx.frange.file_id
+                && it.file_range.range.start() >= self.range.end()
+        })
+    }
+}
+
+struct Param {
+    local: Local,
+    ty: Type,
+    kind: ParamKind,
+    usages: Vec<ast::PathExpr>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ParamKind {
+    Value,
+    MutValue,
+    SharedRef,
+    MutRef,
+}
+
+impl Param {
+    fn name(&self, ctx: &AssistCtx) -> String {
+        self.local.name(ctx.db).map(|it| it.to_string()).unwrap_or_else(|| "_".to_string())
+    }
+
+    fn to_param(&self, ctx: &AssistCtx) -> String {
+        let name = self.name(ctx);
+        let ty = self.ty.display(ctx.db);
+        match self.kind {
+            ParamKind::Value => format!("{}: {}", name, ty),
+            ParamKind::MutValue => format!("mut {}: {}", name, ty),
+            ParamKind::SharedRef => format!("{}: &{}", name, ty),
+            ParamKind::MutRef => format!("{}: &mut {}", name, ty),
+        }
+    }
+
+    fn to_arg(&self, ctx: &AssistCtx) -> String {
+        let name = self.name(ctx);
+        match self.kind {
+            ParamKind::Value | ParamKind::MutValue => name,
+            ParamKind::SharedRef => format!("&{}", name),
+            ParamKind::MutRef => format!("&mut {}", name),
+        }
+    }
+
+    /// Dereferences the usages of parameters which are passed by reference.
+    fn adjust_usages(&self, acc: &mut Vec<(TextRange, String)>) {
+        if self.kind != ParamKind::SharedRef && self.kind != ParamKind::MutRef {
+            return;
+        }
+        for usage in self.usages.iter() {
+            let usage_node = usage.syntax();
+            let parent = match usage_node.parent() {
+                Some(it) => it,
+                None => continue,
+            };
+            // Method calls and field accesses dereference on their own.
+            let auto_deref = match_ast! {
+                match parent {
+                    ast::MethodCallExpr(it) => it.expr().map_or(false, |it| it.syntax() == usage_node),
+                    ast::FieldExpr(it) => it.expr().map_or(false, |it| it.syntax() == usage_node),
+                    _ => false,
+                }
+            };
+            if auto_deref {
+                continue;
+            }
+            let borrow = ast::RefExpr::cast(parent).filter(|it| match self.kind {
+                ParamKind::MutRef => it.is_mut(),
+                _ => !it.is_mut(),
+            });
+            match borrow {
+                Some(borrow) => acc.push((borrow.syntax().text_range(), usage.to_string())),
+                None => acc.push((usage_node.text_range(), format!("*{}", usage))),
+            }
+        }
+    }
+}
+
+/// How the extracted code leaves the enclosing function, other than by
+/// finishing normally.
+enum Exit {
+    Fallthrough,
+    /// The code uses `?`, the function returns `Option`s or `Result`s.
+    Try(TryKind),
+    /// The code has `return` expressions, with the type of the returned
+    /// value if there is one.
+    Return(Vec<ast::ReturnExpr>, Option<String>),
+    /// The code has `break` or `continue` expressions leaving a loop around
+    /// it.
+    LoopExit(Vec<SyntaxNode>, &'static str),
+}
+
+enum TryKind {
+    Option,
+    Result { err_ty: String },
+}
+
+impl Exit {
+    fn of(body: &FunctionBody, fn_def: &ast::FnDef) -> Option<Exit> {
+        let mut flow = ControlFlow::default();
+        for element in body.elements.iter() {
+            flow.collect(element, false)?;
+        }
+
+        if flow.has_try {
+            if !flow.returns.is_empty() || !flow.loop_exits.is_empty() {
+                return None;
+            }
+            let ret_ty = match fn_def.ret_type()?.type_ref()? {
+                ast::TypeRef::PathType(it) => it.path()?.segment()?,
+                _ => return None,
+            };
+            let kind = match ret_ty.name_ref()?.text().as_str() {
+                "Option" => TryKind::Option,
+                "Result" => {
+                    let err_ty = ret_ty.type_arg_list()?.type_args().nth(1)?;
+                    TryKind::Result { err_ty: err_ty.syntax().to_string() }
+                }
+                _ => return None,
+            };
+            return Some(Exit::Try(kind));
+        }
+
+        if !flow.returns.is_empty() {
+            if !flow.loop_exits.is_empty() {
+                return None;
+            }
+            let ret_ty = match fn_def.ret_type() {
+                Some(it) => Some(it.type_ref()?.syntax().to_string()),
+                None => None,
+            };
+            return Some(Exit::Return(flow.returns, ret_ty));
+        }
+
+        if let Some(first) = flow.loop_exits.first() {
+            let kind = first.kind();
+            if flow.loop_exits.iter().any(|it| it.kind() != kind) {
+                return None;
+            }
+            let keyword = if kind == BREAK_EXPR { "break" } else { "continue" };
+            return Some(Exit::LoopExit(flow.loop_exits, keyword));
+        }
+
+        Some(Exit::Fallthrough)
+    }
+
+    fn adjust_body(&self, body: &FunctionBody, acc: &mut Vec<(TextRange, String)>) {
+        match self {
+            Exit::Fallthrough => (),
+            Exit::Try(kind) => {
+                if let Some(tail) = &body.tail {
+                    let range = tail.syntax().text_range();
+                    acc.push((TextRange::offset_len(range.start(), 0.into()), kind.wrap_start()));
+                    acc.push((TextRange::offset_len(range.end(), 0.into()), ")".to_string()));
+                }
+            }
+            Exit::Return(returns, Some(_)) => {
+                for ret in returns.iter() {
+                    if let Some(expr) = ret.expr() {
+                        let range = expr.syntax().text_range();
+                        acc.push((TextRange::offset_len(range.start(), 0.into()), "Some(".into()));
+                        acc.push((TextRange::offset_len(range.end(), 0.into()), ")".into()));
+                    }
+                }
+            }
+            Exit::Return(returns, None) => {
+                for ret in returns.iter() {
+                    acc.push((ret.syntax().text_range(), "return true".to_string()));
+                }
+            }
+            Exit::LoopExit(exits, _) => {
+                for exit in exits.iter() {
+                    acc.push((exit.text_range(), "return true".to_string()));
+                }
+            }
+        }
+    }
+
+    /// The expression the new function ends with, if any.
+    fn last_line(&self, ctx: &AssistCtx, outputs: &[Local], has_tail: bool) -> Option<String> {
+        match self {
+            Exit::Fallthrough => {
+                if has_tail {
+                    None
+                } else {
+                    outputs_expr(ctx, outputs)
+                }
+            }
+            Exit::Try(kind) => {
+                if has_tail {
+                    None
+                } else {
+                    let value = outputs_expr(ctx, outputs).unwrap_or_else(|| "()".to_string());
+                    Some(format!("{}{})", kind.wrap_start(), value))
+                }
+            }
+            Exit::Return(_, Some(_)) => Some("None".to_string()),
+            Exit::Return(_, None) | Exit::LoopExit(..) => Some("false".to_string()),
+        }
+    }
+
+    fn ret_ty(&self, ctx: &AssistCtx, outputs: &[Local], tail_ty: Option<&Type>) -> Option<String> {
+        let value_ty = match tail_ty {
+            Some(ty) => Some(ty.display(ctx.db).to_string()),
+            None => match outputs {
+                [] => None,
+                [output] => Some(output.ty(ctx.db).display(ctx.db).to_string()),
+                _ => Some(format!(
+                    "({})",
+                    outputs.iter().map(|it| it.ty(ctx.db).display(ctx.db).to_string()).join(", ")
+                )),
+            },
+        };
+        match self {
+            Exit::Fallthrough => value_ty,
+            Exit::Try(kind) => {
+                let value_ty = value_ty.unwrap_or_else(|| "()".to_string());
+                Some(match kind {
+                    TryKind::Option => format!("Option<{}>", value_ty),
+                    TryKind::Result { err_ty } => format!("Result<{}, {}>", value_ty, err_ty),
+                })
+            }
+            Exit::Return(_, Some(ret_ty)) => Some(format!("Option<{}>", ret_ty)),
+            Exit::Return(_, None) | Exit::LoopExit(..) => Some("bool".to_string()),
+        }
+    }
+
+    fn make_call(&self, ctx: &AssistCtx, call: String, outputs: &[Local], indent: &str) -> String {
+        let call = match self {
+            Exit::Fallthrough => call,
+            Exit::Try(_) => format!("{}?", call),
+            Exit::Return(_, Some(_)) => {
+                return format!(
+                    "if let Some(value) = {} {{\n{}    return value;\n{}}}",
+                    call, indent, indent
+                )
+            }
+            Exit::Return(_, None) => {
+                return format!("if {} {{\n{}    return;\n{}}}", call, indent, indent)
+            }
+            Exit::LoopExit(_, keyword) => {
+                return format!("if {} {{\n{}    {};\n{}}}", call, indent, keyword, indent)
+            }
+        };
+        let pat = match outputs {
+            [] => return call,
+            [output] => output_pat(ctx, *output),
+            _ => format!("({})", outputs.iter().map(|it| output_pat(ctx, *it)).join(", ")),
+        };
+        format!("let {} = {};", pat, call)
+    }
+}
+
+impl TryKind {
+    fn wrap_start(&self) -> String {
+        match self {
+            TryKind::Option => "Some(".to_string(),
+            TryKind::Result { .. } => "Ok(".to_string(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct ControlFlow {
+    returns: Vec<ast::ReturnExpr>,
+    loop_exits: Vec<SyntaxNode>,
+    has_try: bool,
+}
+
+impl ControlFlow {
+    /// Collects the expressions which leave the extracted code early.
+    /// Returns `None` if we can't handle some of them.
+    fn collect(&mut self, node: &SyntaxNode, in_loop: bool) -> Option<()> {
+        if ast::ModuleItem::can_cast(node.kind()) || node.kind() == LAMBDA_EXPR {
+            return Some(());
+        }
+        match node.kind() {
+            RETURN_EXPR => self.returns.push(ast::ReturnExpr::cast(node.clone())?),
+            TRY_EXPR => self.has_try = true,
+            BREAK_EXPR | CONTINUE_EXPR => {
+                // A label might refer to a loop outside of the extracted code.
+                if node.children_with_tokens().any(|it| it.kind() == LIFETIME) {
+                    return None;
+                }
+                if !in_loop {
+                    if ast::BreakExpr::cast(node.clone()).and_then(|it| it.expr()).is_some() {
+                        return None;
+                    }
+                    self.loop_exits.push(node.clone());
+                }
+            }
+            _ => (),
+        }
+        let in_loop = in_loop || matches!(node.kind(), LOOP_EXPR | WHILE_EXPR | FOR_EXPR);
+        for child in node.children() {
+            self.collect(&child, in_loop)?;
+        }
+        Some(())
+    }
+}
+
+/// Checks whether the local is written to, or mutably borrowed.
+fn is_mutated(ctx: &AssistCtx, usage: &ast::PathExpr) -> bool {
+    let mut node = usage.syntax().clone();
+    while let Some(parent) = node.parent() {
+        let is_base = |it: Option<ast::Expr>| it.map_or(false, |it| it.syntax() == &node);
+        match_ast! {
+            match parent {
+                ast::FieldExpr(it) => if !is_base(it.expr()) { return false },
+                ast::IndexExpr(it) => if !is_base(it.base()) { return false },
+                ast::ParenExpr(_it) => (),
+                ast::BinExpr(it) => {
+                    return it.op_kind().map_or(false, |op| op.is_assignment()) && is_base(it.lhs())
+                },
+                ast::RefExpr(it) => return it.is_mut(),
+                ast::MethodCallExpr(it) => {
+                    if !is_base(it.expr()) {
+                        return false;
+                    }
+                    return ctx
+                        .sema
+                        .resolve_method_call(&it)
+                        .and_then(|it| it.source(ctx.db).value.param_list()?.self_param())
+                        .map_or(false, |it| it.kind() == SelfParamKind::MutRef);
+                },
+                _ => return false,
+            }
+        }
+        node = parent;
+    }
+    false
+}
+
+/// The range of the definition of the local, if it's in the current file.
+fn local_range(ctx: &AssistCtx, local: Local) -> Option<TextRange> {
+    let src = local.source(ctx.db);
+    if src.file_id != ctx.frange.file_id.into() {
+        return None;
+    }
+    Some(src.value.either(|it| it.syntax().text_range(), |it| it.syntax().text_range()))
+}
+
+fn output_pat(ctx: &AssistCtx, output: Local) -> String {
+    let name = output.name(ctx.db).map(|it| it.to_string()).unwrap_or_default();
+    if output.is_mut(ctx.db) {
+        format!("mut {}", name)
+    } else {
+        name
+    }
+}
+
+fn outputs_expr(ctx: &AssistCtx, outputs: &[Local]) -> Option<String> {
+    let name = |it: &Local| it.name(ctx.db).map(|it| it.to_string()).unwrap_or_default();
+    match outputs {
+        [] => None,
+        [output] => Some(name(output)),
+        _ => Some(format!("({})", outputs.iter().map(name).join(", "))),
+    }
+}
+
+/// The selection without surrounding whitespace.
+fn trimmed_selection(ctx: &AssistCtx) -> Option<TextRange> {
+    let node = match ctx.covering_element() {
+        NodeOrToken::Node(it) => it,
+        NodeOrToken::Token(it) => it.parent(),
+    };
+    let mut tokens = node
+        .descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|it| it.kind() != WHITESPACE && it.text_range().is_subrange(&ctx.frange.range));
+    let first = tokens.next()?.text_range();
+    let last = tokens.last().map_or(first, |it| it.text_range());
+    Some(TextRange::from_to(first.start(), last.end()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::helpers::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn extract_statements() {
+        check_assist(
+            extract_function,
+            r#"
+fn foo() {
+    let n = 1;
+    <|>let m = n + 2;
+    let k = m * n;<|>
+    let g = k + 3;
+}"#,
+            r#"
+fn foo() {
+    let n = 1;
+    let k = fun_name(n);
+    let g = k + 3;
+}
+
+fn <|>fun_name(n: i32) -> i32 {
+    let m = n + 2;
+    let k = m * n;
+    k
+}"#,
+        );
+    }
+
+    #[test]
+    fn extract_expression() {
+        check_assist(
+            extract_function,
+            r#"
+fn foo() {
+    let n = 1;
+    let k = <|>n * 2 + 1<|>;
+}"#,
+            r#"
+fn foo() {
+    let n = 1;
+    let k = fun_name(n);
+}
+
+fn <|>fun_name(n: i32) -> i32 {
+    n * 2 + 1
+}"#,
+        );
+    }
+
+    #[test]
+    fn extract_tail_expression() {
+        check_assist(
+            extract_function,
+            r#"
+fn foo() -> i32 {
+    let n = 1;
+    <|>let m = n * 2;
+    m + 1<|>
+}"#,
+            r#"
+fn foo() -> i32 {
+    let n = 1;
+    fun_name(n)
+}
+
+fn <|>fun_name(n: i32) -> i32 {
+    let m = n * 2;
+    m + 1
+}"#,
+        );
+    }
+
+    #[test]
+    fn extract_multiple_outputs() {
+        check_assist(
+            extract_function,
+            r#"
+fn foo() {
+    <|>let a = 1;
+    let mut b = true;<|>
+    b = a > 0;
+}"#,
+            r#"
+fn foo() {
+    let (a, mut b) = fun_name();
+    b = a > 0;
+}
+
+fn <|>fun_name() -> (i32, bool) {
+    let a = 1;
+    let mut b = true;
+    (a, b)
+}"#,
+        );
+    }
+
+    #[test]
+    fn extract_passes_mutated_locals_by_mut_ref() {
+        check_assist(
+            extract_function,
+            r#"
+struct S { x: i32 }
+
+fn foo() {
+    let mut n = 1;
+    let mut s = S { x: 1 };
+    <|>n += 1;
+    s.x = n;<|>
+    let m = n + s.x;
+}"#,
+            r#"
+struct S { x: i32 }
+
+fn foo() {
+    let mut n = 1;
+    let mut s = S { x: 1 };
+    fun_name(&mut n, &mut s);
+    let m = n + s.x;
+}
+
+fn <|>fun_name(n: &mut i32, s: &mut S) {
+    *n += 1;
+    s.x = *n;
+}"#,
+        );
+    }
+
+    #[test]
+    fn extract_passes_used_non_copy_locals_by_ref() {
+        check_assist(
+            extract_function,
+            r#"
+struct S;
+
+fn bar(s: &S) {}
+
+fn foo() {
+    let s = S;
+    <|>bar(&s);<|>
+    bar(&s);
+}"#,
+            r#"
+struct S;
+
+fn bar(s: &S) {}
+
+fn foo() {
+    let s = S;
+    fun_name(&s);
+    bar(&s);
+}
+
+fn <|>fun_name(s: &S) {
+    bar(s);
+}"#,
+        );
+    }
+
+    #[test]
+    fn extract_moves_unused_locals() {
+        check_assist(
+            extract_function,
+            r#"
+struct S;
+
+fn bar(s: S) {}
+
+fn foo() {
+    let mut s = S;
+    <|>s = S;
+    bar(s);<|>
+}"#,
+            r#"
+struct S;
+
+fn bar(s: S) {}
+
+fn foo() {
+    let mut s = S;
+    fun_name(s);
+}
+
+fn <|>fun_name(mut s: S) {
+    s = S;
+    bar(s);
+}"#,
+        );
+    }
+
+    #[test]
+    fn extract_method() {
+        check_assist(
+            extract_function,
+            r#"
+struct S { x: i32 }
+
+impl S {
+    fn foo(&mut self) {
+        <|>self.x += 1;<|>
+    }
+}"#,
+            r#"
+struct S { x: i32 }
+
+impl S {
+    fn foo(&mut self) {
+        self.fun_name();
+    }
+
+    fn <|>fun_name(&mut self) {
+        self.x += 1;
+    }
+}"#,
+        );
+    }
+
+    #[test]
+    fn extract_with_try() {
+        check_assist(
+            extract_function,
+            r#"
+fn bar() -> Option<()> { None }
+
+fn foo() -> Option<()> {
+    let n = 1;
+    <|>let m = n + 1;
+    bar()?;<|>
+    let k = m * 2;
+    Some(())
+}"#,
+            r#"
+fn bar() -> Option<()> { None }
+
+fn foo() -> Option<()> {
+    let n = 1;
+    let m = fun_name(n)?;
+    let k = m * 2;
+    Some(())
+}
+
+fn <|>fun_name(n: i32) -> Option<i32> {
+    let m = n + 1;
+    bar()?;
+    Some(m)
+}"#,
+        );
+    }
+
+    #[test]
+    fn extract_with_return() {
+        check_assist(
+            extract_function,
+            r#"
+fn foo() -> i32 {
+    let n = 1;
+    <|>if n > 1 {
+        return n;
+    }<|>
+    0
+}"#,
+            r#"
+fn foo() -> i32 {
+    let n = 1;
+    if let Some(value) = fun_name(n) {
+        return value;
+    }
+    0
+}
+
+fn <|>fun_name(n: i32) -> Option<i32> {
+    if n > 1 {
+        return Some(n);
+    }
+    None
+}"#,
+        );
+    }
+
+    #[test]
+    fn extract_with_break() {
+        check_assist(
+            extract_function,
+            r#"
+fn foo() {
+    let mut n = 1;
+    loop {
+        <|>n += 1;
+        if n > 5 {
+            break;
+        }<|>
+    }
+}"#,
+            r#"
+fn foo() {
+    let mut n = 1;
+    loop {
+        if fun_name(&mut n) {
+            break;
+        }
+    }
+}
+
+fn <|>fun_name(n: &mut i32) -> bool {
+    *n += 1;
+    if *n > 5 {
+        return true;
+    }
+    false
+}"#,
+        );
+    }
+
+    #[test]
+    fn extract_function_not_applicable_to_partial_statements() {
+        check_assist_not_applicable(
+            extract_function,
+            r#"
+fn foo() {
+    let n = 1;
+    let m <|>= n + 2;
+    let k<|> = m * n;
+}"#,
+        );
+    }
+
+    #[test]
+    fn extract_function_not_applicable_to_break_with_value() {
+        check_assist_not_applicable(
+            extract_function,
+            r#"
+fn foo() {
+    let n = loop {
+        <|>break 92;<|>
+    };
+}"#,
+        );
+    }
+}
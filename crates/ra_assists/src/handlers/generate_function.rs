@@ -0,0 +1,576 @@
+use hir::{Adt, HasSource, HirDisplay, InFile, Module, ModuleDef, ModuleSource, PathResolution};
+use ra_db::SourceDatabaseExt;
+use ra_syntax::{
+    ast::{self, make, ArgListOwner, AstNode, NameOwner, TypeAscriptionOwner, TypeParamsOwner},
+    SyntaxKind, SyntaxNode, TextRange, TextUnit, T,
+};
+use rustc_hash::FxHashMap;
+use stdx::{format_to, SepBy};
+
+use crate::{utils::line_indent, Assist, AssistCtx, AssistId};
+
+// Assist: generate_function
+//
+// Adds a stub function with a signature matching the unresolved call under
+// the cursor.
+//
+// ```
+// struct Baz;
+// fn baz() -> Baz { Baz }
+// fn foo() {
+//     bar<|>("", baz());
+// }
+// ```
+// ->
+// ```
+// struct Baz;
+// fn baz() -> Baz { Baz }
+// fn foo() {
+//     bar("", baz());
+// }
+//
+// fn bar(arg: &str, baz: Baz) {
+//     todo!()
+// }
+// ```
+pub(crate) fn generate_function(ctx: AssistCtx) -> Option<Assist> {
+    let name_ref = ctx.find_node_at_offset::<ast::NameRef>()?;
+    let call = UnresolvedCall::new(&ctx, &name_ref)?;
+    let target = call.target(&ctx)?;
+    let text = ctx.db.file_text(ctx.frange.file_id);
+
+    let mut params = Vec::new();
+    if call.has_self_param {
+        params.push("&self".to_string());
+    }
+    let arg_names = arg_names(&call.args);
+    for (arg, name) in call.args.iter().zip(arg_names) {
+        let ty = match ctx.sema.type_of_expr(arg) {
+            Some(ty) if !ty.contains_unknown() => ty.display(ctx.db).to_string(),
+            _ => "()".to_string(),
+        };
+        params.push(format!("{}: {}", name, ty));
+    }
+
+    let (insert_range, indent, leading, trailing) = match &target {
+        Target::AfterItem(item) => {
+            let indent = line_indent(&text, item.text_range().start());
+            (TextRange::offset_len(item.text_range().end(), 0.into()), indent, "\n\n", "")
+        }
+        Target::InItemList(item_list) => {
+            let outer_indent = line_indent(&text, item_list.text_range().start());
+            let indent = format!("{}    ", outer_indent);
+            match item_list.children().last() {
+                Some(last) => {
+                    (TextRange::offset_len(last.text_range().end(), 0.into()), indent, "\n\n", "")
+                }
+                None => {
+                    let l_curly = token_end(item_list, T!['{'])?;
+                    let r_curly = token_start(item_list, T!['}'])?;
+                    let trailing = format!("\n{}", outer_indent);
+                    let range = TextRange::from_to(l_curly, r_curly);
+                    return add_function(ctx, call, range, &indent, "\n", &trailing, &params);
+                }
+            }
+        }
+        Target::NewImpl { adt, name, type_params } => {
+            let indent = line_indent(&text, adt.text_range().start());
+            let mut header = String::new();
+            format_to!(header, "\n\n{}impl", indent);
+            if let Some(type_params) = type_params {
+                format_to!(header, "{}", type_params.syntax());
+            }
+            format_to!(header, " {}", name);
+            if let Some(type_params) = type_params {
+                let lifetime_params = type_params
+                    .lifetime_params()
+                    .filter_map(|it| it.lifetime_token())
+                    .map(|it| it.text().clone());
+                let type_params = type_params
+                    .type_params()
+                    .filter_map(|it| it.name())
+                    .map(|it| it.text().clone());
+                format_to!(header, "<{}>", lifetime_params.chain(type_params).sep_by(", "));
+            }
+            format_to!(header, " {{\n");
+            let trailing = format!("\n{}}}", indent);
+            let range = TextRange::offset_len(adt.text_range().end(), 0.into());
+            let indent = format!("{}    ", indent);
+            return add_function(ctx, call, range, &indent, &header, &trailing, &params);
+        }
+    };
+    add_function(ctx, call, insert_range, &indent, leading, trailing, &params)
+}
+
+fn add_function(
+    ctx: AssistCtx,
+    call: UnresolvedCall,
+    range: TextRange,
+    indent: &str,
+    leading: &str,
+    trailing: &str,
+    params: &[String],
+) -> Option<Assist> {
+    let mut buf = String::new();
+    format_to!(
+        buf,
+        "{}{}{}fn {}({})",
+        leading,
+        indent,
+        call.visibility,
+        call.name,
+        params.join(", ")
+    );
+    if let Some(ret_type) = &call.ret_type {
+        format_to!(buf, " -> {}", ret_type);
+    }
+    format_to!(buf, " {{\n{}    ", indent);
+    let cursor_offset = range.start() + TextUnit::of_str(&buf);
+    format_to!(buf, "{}\n{}}}{}", make::expr_todo(), indent, trailing);
+
+    let call_range = call.expr.syntax().text_range();
+    ctx.add_assist(AssistId("generate_function"), format!("Generate `{}`", call.name), |edit| {
+        edit.target(call_range);
+        edit.replace(range, buf);
+        edit.set_cursor(cursor_offset);
+    })
+}
+
+/// A call of a function or a method which doesn't exist yet.
+struct UnresolvedCall {
+    expr: ast::Expr,
+    name: String,
+    args: Vec<ast::Expr>,
+    /// The qualifier of a path call, like `m` in `m::foo()`, or the type
+    /// of the receiver of a method call.
+    container: Option<Container>,
+    has_self_param: bool,
+    ret_type: Option<String>,
+    visibility: &'static str,
+}
+
+#[derive(Clone, Copy)]
+enum Container {
+    Module(Module),
+    Adt(Adt),
+}
+
+/// Where to insert the new function.
+enum Target {
+    AfterItem(SyntaxNode),
+    InItemList(SyntaxNode),
+    NewImpl { adt: SyntaxNode, name: String, type_params: Option<ast::TypeParamList> },
+}
+
+impl UnresolvedCall {
+    fn new(ctx: &AssistCtx, name_ref: &ast::NameRef) -> Option<UnresolvedCall> {
+        let parent = name_ref.syntax().parent()?;
+        let (expr, args, container, has_self_param) = if let Some(method_call) =
+            ast::MethodCallExpr::cast(parent.clone())
+        {
+            if ctx.sema.resolve_method_call(&method_call).is_some() {
+                return None;
+            }
+            let receiver_ty = ctx.sema.type_of_expr(&method_call.expr()?)?;
+            let adt = receiver_ty.autoderef(ctx.db).find_map(|it| it.as_adt())?;
+            let args = method_call.arg_list()?.args().collect();
+            (ast::Expr::from(method_call), args, Some(Container::Adt(adt)), true)
+        } else {
+            let path = ast::PathSegment::cast(parent)?.parent_path();
+            let path_expr = ast::PathExpr::cast(path.syntax().parent()?)?;
+            let call = ast::CallExpr::cast(path_expr.syntax().parent()?)?;
+            if call.expr()?.syntax() != path_expr.syntax() || ctx.sema.resolve_path(&path).is_some()
+            {
+                return None;
+            }
+            let container = match path.qualifier() {
+                None => None,
+                Some(qualifier) => match ctx.sema.resolve_path(&qualifier)? {
+                    PathResolution::Def(ModuleDef::Module(it)) => Some(Container::Module(it)),
+                    PathResolution::Def(ModuleDef::Adt(it)) => Some(Container::Adt(it)),
+                    _ => return None,
+                },
+            };
+            let args = call.arg_list()?.args().collect();
+            (ast::Expr::from(call), args, container, false)
+        };
+
+        let current_module = ctx.sema.scope(expr.syntax()).module()?;
+        let target_module = match container {
+            Some(Container::Module(it)) => it,
+            Some(Container::Adt(it)) => it.module(ctx.db),
+            None => current_module,
+        };
+        if target_module.krate() != current_module.krate() {
+            return None;
+        }
+        let visibility = if target_module == current_module { "" } else { "pub(crate) " };
+        Some(UnresolvedCall {
+            ret_type: expected_type(ctx, &expr),
+            name: name_ref.text().to_string(),
+            expr,
+            args,
+            container,
+            has_self_param,
+            visibility,
+        })
+    }
+
+    fn target(&self, ctx: &AssistCtx) -> Option<Target> {
+        let adt = match self.container {
+            None => return containing_item(self.expr.syntax()).map(Target::AfterItem),
+            Some(Container::Module(module)) => {
+                let src = module.definition_source(ctx.db);
+                if src.file_id.original_file(ctx.db) != ctx.frange.file_id {
+                    return None;
+                }
+                return match src.value {
+                    ModuleSource::SourceFile(_) => {
+                        let file = ctx.sema.parse(ctx.frange.file_id);
+                        let last = file.syntax().children().last()?;
+                        Some(Target::AfterItem(last))
+                    }
+                    ModuleSource::Module(module) => {
+                        let module = find_in_file::<ast::Module>(ctx, module.syntax())?;
+                        Some(Target::InItemList(module.item_list()?.syntax().clone()))
+                    }
+                };
+            }
+            Some(Container::Adt(adt)) => adt,
+        };
+
+        let adt_node = match adt {
+            Adt::Struct(it) => in_current_file(ctx, it.source(ctx.db))?,
+            Adt::Enum(it) => in_current_file(ctx, it.source(ctx.db))?,
+            Adt::Union(it) => in_current_file(ctx, it.source(ctx.db))?,
+        };
+        let adt_def = find_in_file::<ast::NominalDef>(ctx, &adt_node)?;
+
+        // Prefer an existing inherent impl in the same module as the type.
+        let module_node = adt_def.syntax().parent()?;
+        let impl_def = module_node.children().filter_map(ast::ImplDef::cast).find(|impl_def| {
+            ctx.sema.to_def(impl_def).map_or(false, |it| {
+                it.target_trait(ctx.db).is_none() && it.target_ty(ctx.db).as_adt() == Some(adt)
+            })
+        });
+        if let Some(impl_def) = impl_def {
+            return Some(Target::InItemList(impl_def.item_list()?.syntax().clone()));
+        }
+        Some(Target::NewImpl {
+            name: adt_def.name()?.text().to_string(),
+            type_params: adt_def.type_param_list(),
+            adt: adt_def.syntax().clone(),
+        })
+    }
+}
+
+fn in_current_file<N: AstNode>(ctx: &AssistCtx, src: InFile<N>) -> Option<SyntaxNode> {
+    if src.file_id.call_node(ctx.db).is_some()
+        || src.file_id.original_file(ctx.db) != ctx.frange.file_id
+    {
+        return None;
+    }
+    Some(src.value.syntax().clone())
+}
+
+/// Finds the node corresponding to `node` in the tree of the current file.
+fn find_in_file<N: AstNode>(ctx: &AssistCtx, node: &SyntaxNode) -> Option<N> {
+    let file = ctx.sema.parse(ctx.frange.file_id);
+    let range = node.text_range();
+    file.syntax().descendants().filter(|it| it.text_range() == range).find_map(N::cast)
+}
+
+/// Returns the module-level item containing `node`.
+fn containing_item(node: &SyntaxNode) -> Option<SyntaxNode> {
+    node.ancestors().find(|it| {
+        ast::ModuleItem::can_cast(it.kind())
+            && it.parent().map_or(false, |parent| match parent.kind() {
+                SyntaxKind::SOURCE_FILE => true,
+                SyntaxKind::ITEM_LIST => {
+                    parent.parent().map_or(false, |it| it.kind() == SyntaxKind::MODULE)
+                }
+                _ => false,
+            })
+    })
+}
+
+fn token_end(node: &SyntaxNode, kind: SyntaxKind) -> Option<TextUnit> {
+    node.children_with_tokens().find(|it| it.kind() == kind).map(|it| it.text_range().end())
+}
+
+fn token_start(node: &SyntaxNode, kind: SyntaxKind) -> Option<TextUnit> {
+    node.children_with_tokens().find(|it| it.kind() == kind).map(|it| it.text_range().start())
+}
+
+/// The type the result of `call` is expected to have, judging from where it
+/// is used.
+fn expected_type(ctx: &AssistCtx, call: &ast::Expr) -> Option<String> {
+    let parent = call.syntax().parent()?;
+    if let Some(let_stmt) = ast::LetStmt::cast(parent.clone()) {
+        return let_stmt.ascribed_type().map(|it| it.syntax().to_string());
+    }
+    if let Some(arg_list) = ast::ArgList::cast(parent.clone()) {
+        let idx = arg_list.args().position(|it| it.syntax() == call.syntax())?;
+        let callee = arg_list.syntax().parent()?;
+        let function = if let Some(method_call) = ast::MethodCallExpr::cast(callee.clone()) {
+            ctx.sema.resolve_method_call(&method_call)?
+        } else {
+            let call = ast::CallExpr::cast(callee)?;
+            let path = match call.expr()? {
+                ast::Expr::PathExpr(it) => it.path()?,
+                _ => return None,
+            };
+            match ctx.sema.resolve_path(&path)? {
+                PathResolution::Def(ModuleDef::Function(it)) => it,
+                PathResolution::AssocItem(hir::AssocItem::Function(it)) => it,
+                _ => return None,
+            }
+        };
+        let param_list = function.source(ctx.db).value.param_list()?;
+        let param = param_list.params().nth(idx)?;
+        return param.ascribed_type().map(|it| it.syntax().to_string());
+    }
+
+    let is_tail = ast::Block::cast(parent.clone())
+        .and_then(|it| it.expr())
+        .map_or(false, |it| it.syntax() == call.syntax())
+        && parent
+            .parent()
+            .and_then(|it| it.parent())
+            .map_or(false, |it| ast::FnDef::can_cast(it.kind()));
+    if is_tail || ast::ReturnExpr::can_cast(parent.kind()) {
+        let fn_def = call.syntax().ancestors().find_map(|it| {
+            if ast::LambdaExpr::can_cast(it.kind()) {
+                return Some(None);
+            }
+            ast::FnDef::cast(it).map(Some)
+        })??;
+        return fn_def.ret_type()?.type_ref().map(|it| it.syntax().to_string());
+    }
+    None
+}
+
+/// Derives the parameter names from the arguments, numbering duplicates.
+fn arg_names(args: &[ast::Expr]) -> Vec<String> {
+    let names: Vec<String> = args.iter().map(arg_name).collect();
+    let mut counts: FxHashMap<&str, usize> = FxHashMap::default();
+    for name in &names {
+        *counts.entry(name.as_str()).or_default() += 1;
+    }
+    let mut seen: FxHashMap<&str, usize> = FxHashMap::default();
+    names
+        .iter()
+        .map(|name| {
+            if counts[name.as_str()] == 1 {
+                return name.clone();
+            }
+            let idx = seen.entry(name.as_str()).or_default();
+            *idx += 1;
+            format!("{}{}", name, idx)
+        })
+        .collect()
+}
+
+fn arg_name(arg: &ast::Expr) -> String {
+    let name = match arg {
+        ast::Expr::PathExpr(it) => {
+            it.path().and_then(|it| it.segment()).and_then(|it| it.name_ref())
+        }
+        ast::Expr::FieldExpr(it) => it.name_ref(),
+        ast::Expr::MethodCallExpr(it) => it.name_ref(),
+        ast::Expr::CallExpr(it) => match it.expr() {
+            Some(ast::Expr::PathExpr(it)) => {
+                it.path().and_then(|it| it.segment()).and_then(|it| it.name_ref())
+            }
+            _ => None,
+        },
+        ast::Expr::RefExpr(it) => return it.expr().map_or("arg".to_string(), |it| arg_name(&it)),
+        ast::Expr::PrefixExpr(it) => {
+            return it.expr().map_or("arg".to_string(), |it| arg_name(&it))
+        }
+        _ => None,
+    };
+    match name {
+        Some(name) if !matches!(name.text().as_str(), "self" | "Self" | "super" | "crate") => {
+            name.text().to_lowercase()
+        }
+        _ => "arg".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::helpers::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn generate_function_with_args_and_expected_type() {
+        check_assist(
+            generate_function,
+            r"
+fn foo() {
+    let a = 1u32;
+    let flag = true;
+    let x: u64 = bar<|>(a, &flag);
+}
+",
+            r"
+fn foo() {
+    let a = 1u32;
+    let flag = true;
+    let x: u64 = bar(a, &flag);
+}
+
+fn bar(a: u32, flag: &bool) -> u64 {
+    <|>todo!()
+}
+",
+        )
+    }
+
+    #[test]
+    fn generate_function_numbers_duplicate_names() {
+        check_assist(
+            generate_function,
+            r"
+fn foo() {
+    bar<|>(1, 2);
+}
+",
+            r"
+fn foo() {
+    bar(1, 2);
+}
+
+fn bar(arg1: i32, arg2: i32) {
+    <|>todo!()
+}
+",
+        )
+    }
+
+    #[test]
+    fn generate_function_returning_tail_type() {
+        check_assist(
+            generate_function,
+            r"
+mod m {
+    fn foo() -> String {
+        bar<|>()
+    }
+}
+",
+            r"
+mod m {
+    fn foo() -> String {
+        bar()
+    }
+
+    fn bar() -> String {
+        <|>todo!()
+    }
+}
+",
+        )
+    }
+
+    #[test]
+    fn generate_function_in_other_module() {
+        check_assist(
+            generate_function,
+            r"
+mod m {}
+
+fn main() {
+    m::bar<|>(true);
+}
+",
+            r"
+mod m {
+    pub(crate) fn bar(arg: bool) {
+        <|>todo!()
+    }
+}
+
+fn main() {
+    m::bar(true);
+}
+",
+        )
+    }
+
+    #[test]
+    fn generate_method_in_existing_impl() {
+        check_assist(
+            generate_function,
+            r"
+struct S;
+impl S {
+    fn foo(&self) {}
+}
+
+fn main() {
+    let s = S;
+    s.bar<|>(1);
+}
+",
+            r"
+struct S;
+impl S {
+    fn foo(&self) {}
+
+    fn bar(&self, arg: i32) {
+        <|>todo!()
+    }
+}
+
+fn main() {
+    let s = S;
+    s.bar(1);
+}
+",
+        )
+    }
+
+    #[test]
+    fn generate_associated_function_in_new_impl() {
+        check_assist(
+            generate_function,
+            r"
+struct S<T> { x: T }
+
+fn main() {
+    let s: S<u32> = S::new<|>(1u32);
+}
+",
+            r"
+struct S<T> { x: T }
+
+impl<T> S<T> {
+    fn new(arg: u32) -> S<u32> {
+        <|>todo!()
+    }
+}
+
+fn main() {
+    let s: S<u32> = S::new(1u32);
+}
+",
+        )
+    }
+
+    #[test]
+    fn generate_function_not_applicable_if_resolved() {
+        check_assist_not_applicable(
+            generate_function,
+            r"
+fn bar() {}
+
+fn foo() {
+    bar<|>();
+}
+",
+        )
+    }
+}
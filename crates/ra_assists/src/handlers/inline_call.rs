@@ -0,0 +1,1040 @@
+use std::iter::successors;
+
+use either::Either;
+use hir::{
+    AsAssocItem, AssocItem, AssocItemContainer, Function, HasSource, ModuleDef, PathResolution,
+    Semantics, SemanticsScope,
+};
+use ra_db::SourceDatabaseExt;
+use ra_ide_db::{defs::Definition, RootDatabase};
+use ra_syntax::{
+    algo::find_node_at_offset,
+    ast::{self, ArgListOwner, AstNode, NameOwner, SelfParamKind},
+    match_ast, SyntaxKind, SyntaxNode, TextRange, TextUnit, T,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    ast_transform::{AstTransform, QualifyPaths, SubstituteTypeParams},
+    utils::{line_indent, reindent, replace_ranges},
+    Assist, AssistCtx, AssistId,
+};
+
+// Assist: inline_call
+//
+// Inlines the body of the called function at the call site.
+//
+// ```
+// fn add(a: u32, b: u32) -> u32 {
+//     a + b
+// }
+//
+// fn main() {
+//     let x = add<|>(1, 2);
+// }
+// ```
+// ->
+// ```
+// fn add(a: u32, b: u32) -> u32 {
+//     a + b
+// }
+//
+// fn main() {
+//     let x = 1 + 2;
+// }
+// ```
+pub(crate) fn inline_call(ctx: AssistCtx) -> Option<Assist> {
+    let name_ref = ctx.find_node_at_offset::<ast::NameRef>()?;
+    let call = Call::new(ctx.sema, &name_ref)?;
+    let function = call.function;
+    if function.is_unsafe(ctx.db) {
+        return None;
+    }
+    if let Some(AssocItemContainer::Trait(_)) =
+        function.as_assoc_item(ctx.db).map(|it| it.container(ctx.db))
+    {
+        // We don't know which implementation is called.
+        return None;
+    }
+    let src = function.source(ctx.db);
+    if src.file_id.call_node(ctx.db).is_some() {
+        return None;
+    }
+    let fn_file_id = src.file_id.original_file(ctx.db);
+    let fn_file = ctx.sema.parse(fn_file_id);
+    let fn_def = find_node_at_offset::<ast::FnDef>(
+        fn_file.syntax(),
+        src.value.name()?.syntax().text_range().start(),
+    )?;
+    if fn_def.syntax().children_with_tokens().any(|it| it.kind() == T![async]) {
+        return None;
+    }
+    let fn_text = ctx.db.file_text(fn_file_id);
+    let inliner = Inliner::new(ctx.sema, function, &fn_def, &fn_text)?;
+
+    let text = ctx.db.file_text(ctx.frange.file_id);
+    let call_range = call.expr.syntax().text_range();
+    let inlined = inliner.inline(&call, &text)?;
+    let all_calls = if fn_file_id == ctx.frange.file_id {
+        inline_all_calls(&ctx, &inliner, &fn_def, &text)
+    } else {
+        None
+    };
+
+    let mut group = ctx.add_assist_group(format!("Inline `{}`", function.name(ctx.db)));
+    group.add_assist(AssistId("inline_call"), "Inline call", |edit| {
+        edit.target(call_range);
+        edit.replace(call_range, inlined);
+        edit.set_cursor(call_range.start());
+    });
+    if let Some(all_calls) = all_calls {
+        group.add_assist(
+            AssistId("inline_call"),
+            "Inline all calls and remove the function",
+            |edit| {
+                edit.target(call_range);
+                let mut cursor = call_range.start();
+                for (range, inlined) in all_calls {
+                    if range.end() <= call_range.start() {
+                        cursor = cursor + TextUnit::of_str(&inlined) - range.len();
+                    }
+                    edit.replace(range, inlined);
+                }
+                edit.set_cursor(cursor);
+            },
+        );
+    }
+    group.finish()
+}
+
+/// Returns the edits inlining every call of the function and deleting its
+/// definition, if all of its usages are calls in the current file.
+fn inline_all_calls(
+    ctx: &AssistCtx,
+    inliner: &Inliner,
+    fn_def: &ast::FnDef,
+    text: &str,
+) -> Option<Vec<(TextRange, String)>> {
+    let file = ctx.sema.parse(ctx.frange.file_id);
+    let fn_range = fn_def.syntax().text_range();
+    let mut res = Vec::new();
+    for reference in Definition::ModuleDef(inliner.function.into()).find_usages(ctx.db, None) {
+        if reference.file_range.file_id != ctx.frange.file_id {
+            return None;
+        }
+        let name_ref =
+            find_node_at_offset::<ast::NameRef>(file.syntax(), reference.file_range.range.start())?;
+        let call = Call::new(ctx.sema, &name_ref)?;
+        let range = call.expr.syntax().text_range();
+        if range.is_subrange(&fn_range) {
+            // Recursive calls can't be inlined.
+            return None;
+        }
+        res.push((range, inliner.inline(&call, text)?));
+    }
+    res.sort_by_key(|(range, _)| range.start());
+    if res.windows(2).any(|it| it[0].0.end() > it[1].0.start()) {
+        // Calls nested in the arguments of other calls.
+        return None;
+    }
+
+    // Remove the function together with the whitespace after it.
+    let end = fn_def
+        .syntax()
+        .next_sibling_or_token()
+        .filter(|it| it.kind() == SyntaxKind::WHITESPACE)
+        .map_or(fn_range.end(), |it| it.text_range().end());
+    res.push((TextRange::from_to(fn_range.start(), end), String::new()));
+    Some(res)
+}
+
+/// A call of the function to inline.
+struct Call {
+    expr: ast::Expr,
+    function: Function,
+    /// The receiver of a method call.
+    receiver: Option<ast::Expr>,
+    args: Vec<ast::Expr>,
+    type_args: Vec<ast::TypeRef>,
+}
+
+impl Call {
+    fn new(sema: &Semantics<RootDatabase>, name_ref: &ast::NameRef) -> Option<Call> {
+        let parent = name_ref.syntax().parent()?;
+        if let Some(method_call) = ast::MethodCallExpr::cast(parent.clone()) {
+            return Some(Call {
+                function: sema.resolve_method_call(&method_call)?,
+                receiver: Some(method_call.expr()?),
+                args: method_call.arg_list()?.args().collect(),
+                type_args: type_args(method_call.type_arg_list()),
+                expr: method_call.into(),
+            });
+        }
+        let segment = ast::PathSegment::cast(parent)?;
+        let path = segment.parent_path();
+        let path_expr = ast::PathExpr::cast(path.syntax().parent()?)?;
+        let call = ast::CallExpr::cast(path_expr.syntax().parent()?)?;
+        let function = match sema.resolve_path(&path)? {
+            PathResolution::Def(ModuleDef::Function(it)) => it,
+            PathResolution::AssocItem(AssocItem::Function(it)) => it,
+            _ => return None,
+        };
+        Some(Call {
+            function,
+            receiver: None,
+            args: call.arg_list()?.args().collect(),
+            type_args: type_args(segment.type_arg_list()),
+            expr: call.into(),
+        })
+    }
+}
+
+fn type_args(type_arg_list: Option<ast::TypeArgList>) -> Vec<ast::TypeRef> {
+    type_arg_list.into_iter().flat_map(|it| it.type_args()).filter_map(|it| it.type_ref()).collect()
+}
+
+/// A parameter of the function to inline.
+struct Param {
+    /// The pattern of the parameter, `None` for `self`.
+    pat: Option<ast::Pat>,
+    /// The range of the pattern or of the `self` parameter, which is where
+    /// the locals of usages in the body are defined.
+    binding_range: TextRange,
+    usages: Vec<ast::PathExpr>,
+    /// Whether the parameter is also used where we can't rewrite its usages,
+    /// like in macro calls or in shorthand record fields.
+    has_hidden_usages: bool,
+}
+
+impl Param {
+    /// The name of a parameter that is a plain immutable binding.
+    fn simple_name(&self) -> Option<String> {
+        match self.pat.as_ref()? {
+            ast::Pat::BindPat(it) if !it.is_mutable() && !it.is_ref() && it.pat().is_none() => {
+                Some(it.name()?.text().to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+struct Inliner<'a> {
+    sema: &'a Semantics<'a, RootDatabase>,
+    function: Function,
+    fn_text: &'a str,
+    self_param: Option<ast::SelfParam>,
+    /// `self` comes first, if present.
+    params: Vec<Param>,
+    body: ast::BlockExpr,
+    /// All names bound by the parameters and in the body, which arguments
+    /// substituted into the body must not refer to.
+    bound_names: FxHashSet<String>,
+    /// Whether the body refers to the type parameters of the function, which
+    /// is only possible to inline with explicit type arguments.
+    uses_type_params: bool,
+}
+
+impl<'a> Inliner<'a> {
+    fn new(
+        sema: &'a Semantics<'a, RootDatabase>,
+        function: Function,
+        fn_def: &ast::FnDef,
+        fn_text: &'a str,
+    ) -> Option<Inliner<'a>> {
+        let body = fn_def.body()?;
+        let param_list = fn_def.param_list()?;
+        let self_param = param_list.self_param();
+
+        let mut params = Vec::new();
+        if let Some(self_param) = &self_param {
+            params.push(Param {
+                pat: None,
+                binding_range: self_param.syntax().text_range(),
+                usages: Vec::new(),
+                has_hidden_usages: false,
+            });
+        }
+        for param in param_list.params() {
+            let pat = param.pat()?;
+            params.push(Param {
+                binding_range: pat.syntax().text_range(),
+                pat: Some(pat),
+                usages: Vec::new(),
+                has_hidden_usages: false,
+            });
+        }
+
+        let mut bound_names: FxHashSet<String> = param_list
+            .syntax()
+            .descendants()
+            .chain(body.syntax().descendants())
+            .filter_map(ast::BindPat::cast)
+            .filter_map(|it| it.name())
+            .map(|it| it.text().to_string())
+            .collect();
+        bound_names.insert("this".to_string());
+
+        let fn_type_params = hir::GenericDef::from(function).params(sema.db);
+        let mut uses_type_params = false;
+        let mut hidden_names = FxHashSet::default();
+        for node in body.syntax().descendants() {
+            if let Some(path) = ast::Path::cast(node.clone()) {
+                match sema.resolve_path(&path) {
+                    Some(PathResolution::TypeParam(it)) if fn_type_params.contains(&it) => {
+                        uses_type_params = true
+                    }
+                    // Generic parameters of the impl can't be substituted.
+                    Some(PathResolution::TypeParam(_)) | Some(PathResolution::ConstParam(_)) => {
+                        return None
+                    }
+                    _ => (),
+                }
+            } else if let Some(path_expr) = ast::PathExpr::cast(node.clone()) {
+                let binding_range = match path_expr.path().and_then(|it| sema.resolve_path(&it)) {
+                    Some(PathResolution::Local(local)) => match local.source(sema.db).value {
+                        Either::Left(pat) => pat.syntax().text_range(),
+                        Either::Right(self_param) => self_param.syntax().text_range(),
+                    },
+                    _ => continue,
+                };
+                if let Some(param) = params.iter_mut().find(|it| it.binding_range == binding_range)
+                {
+                    param.usages.push(path_expr);
+                }
+            } else if let Some(token_tree) = ast::TokenTree::cast(node.clone()) {
+                hidden_names.extend(
+                    token_tree
+                        .syntax()
+                        .children_with_tokens()
+                        .filter_map(|it| it.into_token())
+                        .filter(|it| it.kind() == SyntaxKind::IDENT || it.kind() == T![self])
+                        .map(|it| it.text().to_string()),
+                );
+            } else if let Some(field) = ast::RecordField::cast(node) {
+                if field.expr().is_none() {
+                    hidden_names.extend(field.name_ref().map(|it| it.text().to_string()));
+                }
+            }
+        }
+        for param in &mut params {
+            let name = match &param.pat {
+                None => Some("self".to_string()),
+                Some(_) => param.simple_name(),
+            };
+            param.has_hidden_usages = name.map_or(false, |it| hidden_names.contains(&it));
+        }
+
+        Some(Inliner {
+            sema,
+            function,
+            fn_text,
+            self_param,
+            params,
+            body,
+            bound_names,
+            uses_type_params,
+        })
+    }
+
+    /// Returns the code replacing `call`, whose file has the text `text`.
+    fn inline(&self, call: &Call, text: &str) -> Option<String> {
+        let args: Vec<&ast::Expr> = call.receiver.iter().chain(call.args.iter()).collect();
+        if args.len() != self.params.len() || (self.uses_type_params && call.type_args.is_empty()) {
+            return None;
+        }
+
+        // The parameters which are not substituted by their arguments are
+        // bound with `let`s, as pattern, initializer and the bound names.
+        let mut lets: Vec<(String, String, Vec<String>)> = Vec::new();
+        let mut lets_shadow_args = false;
+        let mut substitutions: FxHashMap<TextRange, String> = FxHashMap::default();
+        for (param, arg) in self.params.iter().zip(args) {
+            let (pat, substitute) = match (&param.pat, &self.self_param) {
+                (None, Some(self_param)) => {
+                    if param.has_hidden_usages {
+                        return None;
+                    }
+                    let substitute = if call.receiver.is_some() {
+                        self.is_place(arg) && param.usages.iter().all(is_receiver)
+                    } else {
+                        self.is_trivial(arg) && param.usages.len() <= 1
+                    };
+                    (self_pattern(self_param), substitute)
+                }
+                (None, None) => return None,
+                (Some(pat), _) => match param.simple_name() {
+                    Some(name) => (
+                        name,
+                        !param.has_hidden_usages && self.is_trivial(arg) && param.usages.len() <= 1,
+                    ),
+                    None => (pat.syntax().to_string(), false),
+                },
+            };
+            if substitute {
+                let arg_text = arg.syntax().to_string();
+                substitutions.extend(
+                    param.usages.iter().map(|it| (it.syntax().text_range(), arg_text.clone())),
+                );
+                continue;
+            }
+
+            let mut init = arg.syntax().to_string();
+            let bound = match &param.pat {
+                Some(pat) => pat
+                    .syntax()
+                    .descendants()
+                    .filter_map(ast::BindPat::cast)
+                    .filter_map(|it| it.name())
+                    .map(|it| it.text().to_string())
+                    .collect(),
+                None => {
+                    if let (Some(self_param), Some(_)) = (&self.self_param, &call.receiver) {
+                        // The receiver of a method call is borrowed implicitly.
+                        let is_ref =
+                            self.sema.type_of_expr(arg).map_or(false, |it| it.is_reference());
+                        match self_param.kind() {
+                            SelfParamKind::Ref if !is_ref => init = format!("&{}", init),
+                            SelfParamKind::MutRef if !is_ref => init = format!("&mut {}", init),
+                            _ => (),
+                        }
+                    }
+                    substitutions.extend(
+                        param
+                            .usages
+                            .iter()
+                            .map(|it| (it.syntax().text_range(), "this".to_string())),
+                    );
+                    vec!["this".to_string()]
+                }
+            };
+            let refers_to_bound =
+                arg.syntax().descendants().filter_map(ast::NameRef::cast).any(|name_ref| {
+                    lets.iter().any(|(_, _, bound)| bound.contains(&name_ref.text().to_string()))
+                });
+            lets_shadow_args |= refers_to_bound;
+            lets.push((pat, init, bound));
+        }
+        let let_stmts: Vec<String> = if lets_shadow_args {
+            // Bind all parameters at once, so that the arguments still refer
+            // to the locals of the call site.
+            let pats = lets.iter().map(|(pat, _, _)| pat.as_str()).collect::<Vec<_>>();
+            let inits = lets.iter().map(|(_, init, _)| init.as_str()).collect::<Vec<_>>();
+            vec![format!("let ({}) = ({});", pats.join(", "), inits.join(", "))]
+        } else {
+            lets.iter().map(|(pat, init, _)| format!("let {} = {};", pat, init)).collect()
+        };
+
+        let source_scope = self.sema.scope(self.body.syntax());
+        let target_scope = self.sema.scope(call.expr.syntax());
+        let transform = QualifyPaths::new(&target_scope, &source_scope).or(
+            SubstituteTypeParams::for_generic_args(
+                &source_scope,
+                self.function.into(),
+                call.type_args.clone(),
+            ),
+        );
+        let mut rewriter = BodyRewriter {
+            substitutions: &substitutions,
+            has_return: false,
+            replacements: Vec::new(),
+        };
+        rewriter.rewrite(&source_scope, &*transform, self.body.syntax(), false)?;
+        let render = |range: TextRange| {
+            let replacements = rewriter
+                .replacements
+                .iter()
+                .filter(|(it, _)| it.is_subrange(&range))
+                .cloned()
+                .collect();
+            replace_ranges(self.fn_text, range, 0.into(), replacements)
+        };
+
+        let block = self.body.block()?;
+        let tail = block.expr();
+        let content: Vec<TextRange> = block
+            .syntax()
+            .children_with_tokens()
+            .filter(|it| !matches!(it.kind(), T!['{'] | T!['}'] | SyntaxKind::WHITESPACE))
+            .map(|it| it.text_range())
+            .collect();
+        let (content_start, content_end) = match (content.first(), content.last()) {
+            (Some(first), Some(last)) => (first.start(), last.end()),
+            _ => (block.syntax().text_range().end(), block.syntax().text_range().end()),
+        };
+        let old_indent = line_indent(self.fn_text, content_start);
+        let call_indent = line_indent(text, call.expr.syntax().text_range().start());
+        let inner_indent = format!("{}    ", call_indent);
+
+        // Everything before the tail expression, including comments.
+        let statements_end =
+            tail.as_ref().map_or(content_end, |it| it.syntax().text_range().start());
+        let statements = if content_start < statements_end {
+            let statements = render(TextRange::from_to(content_start, statements_end));
+            Some(statements.trim_end().to_string())
+        } else {
+            None
+        };
+        let tail_text = tail.as_ref().map(|it| render(it.syntax().text_range()));
+        let tail_text = match (&tail, tail_text) {
+            (_, tail_text) if !rewriter.has_return => tail_text,
+            (Some(ast::Expr::ReturnExpr(_)), tail_text) => tail_text,
+            (_, Some(tail_text)) => Some(format!("break 'inlined {}", tail_text)),
+            (_, None) => Some("break 'inlined".to_string()),
+        };
+
+        if let_stmts.is_empty() && statements.is_none() && !rewriter.has_return {
+            let tail_text = match tail_text {
+                Some(it) => it,
+                None => return Some("{}".to_string()),
+            };
+            let inlined = reindent(&tail_text, &old_indent, &call_indent).trim_start().to_string();
+            let atomic = tail.map_or(false, |it| {
+                matches!(
+                    it,
+                    ast::Expr::Literal(_)
+                        | ast::Expr::PathExpr(_)
+                        | ast::Expr::CallExpr(_)
+                        | ast::Expr::MethodCallExpr(_)
+                        | ast::Expr::FieldExpr(_)
+                        | ast::Expr::IndexExpr(_)
+                        | ast::Expr::ParenExpr(_)
+                        | ast::Expr::TupleExpr(_)
+                        | ast::Expr::ArrayExpr(_)
+                        | ast::Expr::MacroCall(_)
+                )
+            });
+            return Some(if !atomic && needs_parens(&call.expr) {
+                format!("({})", inlined)
+            } else {
+                inlined
+            });
+        }
+
+        let mut lines: Vec<String> =
+            let_stmts.iter().map(|it| format!("{}{}", inner_indent, it)).collect();
+        lines.extend(statements.map(|it| reindent(&it, &old_indent, &inner_indent)));
+        lines.extend(tail_text.map(|it| reindent(&it, &old_indent, &inner_indent)));
+        let mut inlined = format!("{{\n{}\n{}}}", lines.join("\n"), call_indent);
+        if rewriter.has_return {
+            inlined = format!("'inlined: loop {}", inlined);
+        }
+        Some(if needs_parens(&call.expr) { format!("({})", inlined) } else { inlined })
+    }
+
+    /// Whether `arg` is a literal or a path which can be substituted for a
+    /// parameter without changing its meaning.
+    fn is_trivial(&self, arg: &ast::Expr) -> bool {
+        match arg {
+            ast::Expr::Literal(_) => true,
+            ast::Expr::PathExpr(it) => it.path().map_or(false, |path| {
+                let first_segment = successors(Some(path), |it| it.qualifier()).last();
+                let name = first_segment.and_then(|it| it.segment());
+                name.map_or(false, |it| !self.bound_names.contains(&it.syntax().to_string()))
+            }),
+            _ => false,
+        }
+    }
+
+    /// Whether `arg` is a path or a field of a path, which can be used as the
+    /// receiver of field accesses and method calls in place of `self`.
+    fn is_place(&self, arg: &ast::Expr) -> bool {
+        match arg {
+            ast::Expr::FieldExpr(it) => it.expr().map_or(false, |it| self.is_place(&it)),
+            ast::Expr::PathExpr(_) => self.is_trivial(arg),
+            _ => false,
+        }
+    }
+}
+
+fn self_pattern(self_param: &ast::SelfParam) -> String {
+    let is_mut = self_param.kind() == SelfParamKind::Owned
+        && self_param.syntax().children_with_tokens().any(|it| it.kind() == T![mut]);
+    let pat = if is_mut { "mut this" } else { "this" };
+    pat.to_string()
+}
+
+/// Whether `usage` is the receiver of a field access or of a method call.
+fn is_receiver(usage: &ast::PathExpr) -> bool {
+    let receiver = usage.syntax().parent().and_then(|parent| {
+        match_ast! {
+            match parent {
+                ast::FieldExpr(it) => it.expr(),
+                ast::MethodCallExpr(it) => it.expr(),
+                _ => None,
+            }
+        }
+    });
+    receiver.map_or(false, |it| it.syntax() == usage.syntax())
+}
+
+/// Whether the inlined code replacing `call` has to be parenthesized, unless
+/// it is an atomic expression.
+fn needs_parens(call: &ast::Expr) -> bool {
+    let parent = match call.syntax().parent() {
+        Some(it) => it,
+        None => return false,
+    };
+    !matches!(
+        parent.kind(),
+        SyntaxKind::EXPR_STMT
+            | SyntaxKind::LET_STMT
+            | SyntaxKind::BLOCK
+            | SyntaxKind::ARG_LIST
+            | SyntaxKind::PAREN_EXPR
+            | SyntaxKind::TUPLE_EXPR
+            | SyntaxKind::ARRAY_EXPR
+            | SyntaxKind::RECORD_FIELD
+            | SyntaxKind::MATCH_ARM
+            | SyntaxKind::RETURN_EXPR
+            | SyntaxKind::BREAK_EXPR
+    )
+}
+
+/// Collects the replacements in the body of the inlined function.
+struct BodyRewriter<'a> {
+    /// Replacements of the usages of parameters, by range.
+    substitutions: &'a FxHashMap<TextRange, String>,
+    has_return: bool,
+    replacements: Vec<(TextRange, String)>,
+}
+
+impl BodyRewriter<'_> {
+    fn rewrite(
+        &mut self,
+        scope: &SemanticsScope<RootDatabase>,
+        transform: &dyn AstTransform<'_>,
+        node: &SyntaxNode,
+        in_closure: bool,
+    ) -> Option<()> {
+        if let Some(replacement) = self.substitutions.get(&node.text_range()) {
+            if ast::PathExpr::can_cast(node.kind()) {
+                self.replacements.push((node.text_range(), replacement.clone()));
+                return Some(());
+            }
+        }
+        match_ast! {
+            match node {
+                ast::ReturnExpr(it) => {
+                    if !in_closure {
+                        self.has_return = true;
+                        let return_token = it.syntax().first_token()?;
+                        self.replacements.push((return_token.text_range(), "break 'inlined".to_string()));
+                    }
+                },
+                ast::TryExpr(_it) => {
+                    // `?` would return from the caller.
+                    if !in_closure {
+                        return None;
+                    }
+                },
+                ast::LambdaExpr(_it) => {
+                    for child in node.children() {
+                        self.rewrite(scope, transform, &child, true)?;
+                    }
+                    return Some(());
+                },
+                _ => {
+                    if ast::ModuleItem::can_cast(node.kind()) {
+                        // Items in the body don't depend on the scope they are in.
+                        return Some(());
+                    }
+                },
+            }
+        }
+        if let Some(replacement) = transform.get_substitution(node) {
+            self.replacements.push((node.text_range(), replacement.to_string()));
+            return Some(());
+        }
+        if let Some(path) = ast::Path::cast(node.clone()) {
+            let resolution = match path.qualifier() {
+                None => hir::Path::from_ast(path).and_then(|it| scope.resolve_hir_path(&it)),
+                Some(_) => None,
+            };
+            if let Some(PathResolution::SelfType(impl_def)) = resolution {
+                let ty = impl_def.source(scope.db).value.target_type()?.syntax().to_string();
+                if ty.contains('<') {
+                    return None;
+                }
+                self.replacements.push((node.text_range(), ty));
+                return Some(());
+            }
+        }
+        for child in node.children() {
+            self.rewrite(scope, transform, &child, in_closure)?;
+        }
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ra_db::FileRange;
+    use ra_syntax::TextRange;
+    use test_utils::{add_cursor, assert_eq_text, extract_offset};
+
+    use crate::{
+        helpers::{check_assist, check_assist_not_applicable, with_single_file},
+        resolved_assists,
+    };
+
+    use super::*;
+
+    fn check_inline_all(before: &str, after: &str) {
+        let (offset, before) = extract_offset(before);
+        let (db, file_id) = with_single_file(&before);
+        let frange = FileRange { file_id, range: TextRange::offset_len(offset, 0.into()) };
+        let assist = resolved_assists(&db, frange)
+            .into_iter()
+            .find(|it| it.label.label == "Inline all calls and remove the function")
+            .expect("inlining all calls is not applicable");
+        let actual = assist.action.edit.apply(&before);
+        let actual = add_cursor(&actual, assist.action.cursor_position.unwrap());
+        assert_eq_text!(after, &actual);
+    }
+
+    #[test]
+    fn inline_expression_body() {
+        check_assist(
+            inline_call,
+            r#"
+fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
+
+fn main() {
+    let x = add<|>(1, 2) * 3;
+}
+"#,
+            r#"
+fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
+
+fn main() {
+    let x = <|>(1 + 2) * 3;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn inline_with_let_bindings() {
+        check_assist(
+            inline_call,
+            r#"
+fn area(w: u32, h: u32) -> u32 {
+    let a = w * h;
+    a + w
+}
+
+fn main() {
+    let n = 2;
+    area<|>(n + 1, n);
+}
+"#,
+            r#"
+fn area(w: u32, h: u32) -> u32 {
+    let a = w * h;
+    a + w
+}
+
+fn main() {
+    let n = 2;
+    <|>{
+        let w = n + 1;
+        let a = w * n;
+        a + w
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn inline_binds_arguments_shadowed_by_parameters_at_once() {
+        check_assist(
+            inline_call,
+            r#"
+fn sub(x: u32, y: u32) -> u32 {
+    x - y + x - y
+}
+
+fn main() {
+    let x = 1;
+    let y = 2;
+    let z = sub<|>(y + 1, x + 1);
+}
+"#,
+            r#"
+fn sub(x: u32, y: u32) -> u32 {
+    x - y + x - y
+}
+
+fn main() {
+    let x = 1;
+    let y = 2;
+    let z = <|>{
+        let (x, y) = (y + 1, x + 1);
+        x - y + x - y
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn inline_method_with_early_return() {
+        check_assist(
+            inline_call,
+            r#"
+struct S { x: u32 }
+
+impl S {
+    fn get(&self, n: u32) -> u32 {
+        if n == 0 {
+            return 0;
+        }
+        self.x * n
+    }
+}
+
+fn main() {
+    let s = S { x: 1 };
+    let y = s.get<|>(2 + 3);
+}
+"#,
+            r#"
+struct S { x: u32 }
+
+impl S {
+    fn get(&self, n: u32) -> u32 {
+        if n == 0 {
+            return 0;
+        }
+        self.x * n
+    }
+}
+
+fn main() {
+    let s = S { x: 1 };
+    let y = <|>'inlined: loop {
+        let n = 2 + 3;
+        if n == 0 {
+            break 'inlined 0;
+        }
+        break 'inlined s.x * n
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn inline_method_binds_borrowed_receiver() {
+        check_assist(
+            inline_call,
+            r#"
+struct S;
+
+fn helper(_s: &S, n: u32) -> u32 { n }
+
+impl S {
+    fn twice(&self, n: u32) -> u32 {
+        helper(self, n) + n
+    }
+}
+
+fn main() {
+    let s = S;
+    let m = 1;
+    s.twice<|>(m);
+}
+"#,
+            r#"
+struct S;
+
+fn helper(_s: &S, n: u32) -> u32 { n }
+
+impl S {
+    fn twice(&self, n: u32) -> u32 {
+        helper(self, n) + n
+    }
+}
+
+fn main() {
+    let s = S;
+    let m = 1;
+    <|>{
+        let this = &s;
+        let n = m;
+        helper(this, n) + n
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn inline_replaces_self_type_and_keeps_shorthand_fields() {
+        check_assist(
+            inline_call,
+            r#"
+struct S { x: u32 }
+
+impl S {
+    fn new(x: u32) -> Self {
+        Self { x }
+    }
+}
+
+fn main() {
+    let s = S::new<|>(1);
+}
+"#,
+            r#"
+struct S { x: u32 }
+
+impl S {
+    fn new(x: u32) -> Self {
+        Self { x }
+    }
+}
+
+fn main() {
+    let s = <|>{
+        let x = 1;
+        S { x }
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn inline_substitutes_explicit_type_arguments() {
+        check_assist(
+            inline_call,
+            r#"
+struct Wrapper<T>(T);
+
+fn wrap<T>(x: T) -> Wrapper<T> {
+    let w: Wrapper<T> = Wrapper(x);
+    w
+}
+
+fn main() {
+    let w = wrap<|>::<u32>(1);
+}
+"#,
+            r#"
+struct Wrapper<T>(T);
+
+fn wrap<T>(x: T) -> Wrapper<T> {
+    let w: Wrapper<T> = Wrapper(x);
+    w
+}
+
+fn main() {
+    let w = <|>{
+        let w: Wrapper<u32> = Wrapper(1);
+        w
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn inline_qualifies_paths_from_other_modules() {
+        check_assist(
+            inline_call,
+            r#"
+mod m {
+    pub const LIMIT: u32 = 10;
+
+    pub fn clamp(v: u32) -> u32 {
+        if v > LIMIT { LIMIT } else { v }
+    }
+}
+
+fn main() {
+    let v = m::clamp<|>(12);
+}
+"#,
+            r#"
+mod m {
+    pub const LIMIT: u32 = 10;
+
+    pub fn clamp(v: u32) -> u32 {
+        if v > LIMIT { LIMIT } else { v }
+    }
+}
+
+fn main() {
+    let v = <|>{
+        let v = 12;
+        if v > m::LIMIT { m::LIMIT } else { v }
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn inline_all_calls_and_remove_function() {
+        check_inline_all(
+            r#"
+fn inc(x: u32) -> u32 {
+    x + 1
+}
+
+fn main() {
+    let a = inc(1);
+    let b = inc<|>(2);
+}
+"#,
+            r#"
+fn main() {
+    let a = 1 + 1;
+    let b = <|>2 + 1;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn inline_not_applicable_with_try_operator() {
+        check_assist_not_applicable(
+            inline_call,
+            r#"
+fn first(v: Option<u32>) -> Option<u32> {
+    let x = v?;
+    Some(x)
+}
+
+fn main() {
+    first<|>(None);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn inline_not_applicable_to_trait_methods() {
+        check_assist_not_applicable(
+            inline_call,
+            r#"
+trait Tr {
+    fn frob(&self) -> u32 { 0 }
+}
+
+struct S;
+impl Tr for S {}
+
+fn main() {
+    S.frob<|>();
+}
+"#,
+        );
+    }
+}
@@ -0,0 +1,416 @@
+use std::{iter::successors, mem};
+
+use hir::HirFileId;
+use ra_db::SourceDatabaseExt;
+use ra_syntax::{
+    ast::{self, make, AttrsOwner, VisibilityOwner},
+    AstNode, Direction,
+    SyntaxKind::{COMMENT, WHITESPACE},
+    TextRange,
+};
+use rustc_hash::FxHashSet;
+
+use crate::{
+    handlers::merge_imports::try_merge_trees, utils::line_indent, Assist, AssistCtx, AssistId,
+};
+
+// Assist: organize_imports
+//
+// Removes the unused imports of a block of `use` items, then sorts and merges
+// the remaining ones. Blank lines separate groups of imports, which are kept.
+//
+// ```
+// mod foo { pub struct Bar; pub struct Baz; pub struct Qux; }
+// use foo::<|>Qux;
+// use foo::Baz;
+// use foo::Bar;
+//
+// fn main() { let _ = (Bar, Qux); }
+// ```
+// ->
+// ```
+// mod foo { pub struct Bar; pub struct Baz; pub struct Qux; }
+// use foo::{Bar, Qux};
+//
+// fn main() { let _ = (Bar, Qux); }
+// ```
+pub(crate) fn organize_imports(ctx: AssistCtx) -> Option<Assist> {
+    let use_item: ast::UseItem = ctx.find_node_at_offset()?;
+    if !is_organizable(&use_item) {
+        return None;
+    }
+    let block = import_block(&use_item);
+    let range = TextRange::from_to(
+        block.first()?.syntax().text_range().start(),
+        block.last()?.syntax().text_range().end(),
+    );
+
+    let module = ctx.sema.scope(use_item.syntax()).module()?;
+    let file_id = HirFileId::from(ctx.frange.file_id);
+    let unused: FxHashSet<TextRange> = module
+        .unused_imports(ctx.db)
+        .into_iter()
+        .filter(|it| it.file_id == file_id)
+        .map(|it| it.value.syntax().text_range())
+        .collect();
+
+    let mut groups = Vec::new();
+    let mut group = Vec::new();
+    for (idx, item) in block.iter().enumerate() {
+        if idx > 0 && starts_group(item) {
+            groups.push(mem::take(&mut group));
+        }
+        let visibility = item.visibility().map(|it| format!("{} ", it)).unwrap_or_default();
+        if let Some(use_tree) = item.use_tree().and_then(|it| remove_unused(&it, &unused)) {
+            group.push((visibility, use_tree));
+        }
+    }
+    groups.push(group);
+
+    let text = ctx.db.file_text(ctx.frange.file_id);
+    let indent = line_indent(&text, range.start());
+    let organized = groups
+        .into_iter()
+        .filter(|it| !it.is_empty())
+        .map(|group| {
+            organize_group(group)
+                .into_iter()
+                .map(|(visibility, use_tree)| format!("{}use {};", visibility, use_tree))
+                .collect::<Vec<_>>()
+                .join(&format!("\n{}", indent))
+        })
+        .collect::<Vec<_>>()
+        .join(&format!("\n\n{}", indent));
+    if organized == text[range] {
+        return None;
+    }
+
+    ctx.add_assist(AssistId("organize_imports"), "Organize imports", |edit| {
+        edit.target(range);
+        edit.replace(range, organized);
+        edit.set_cursor(range.start());
+    })
+}
+
+/// Items with attributes or comments are left alone, as we'd lose them when
+/// merging.
+fn is_organizable(use_item: &ast::UseItem) -> bool {
+    use_item.attrs().next().is_none()
+        && use_item.use_tree().is_some()
+        && use_item.syntax().descendants_with_tokens().all(|it| it.kind() != COMMENT)
+}
+
+/// The `use` items around `use_item` which are only separated by whitespace.
+fn import_block(use_item: &ast::UseItem) -> Vec<ast::UseItem> {
+    let mut res: Vec<_> =
+        successors(Some(use_item.clone()), |it| neighbor_use_item(it, Direction::Prev)).collect();
+    res.reverse();
+    res.extend(successors(neighbor_use_item(use_item, Direction::Next), |it| {
+        neighbor_use_item(it, Direction::Next)
+    }));
+    res
+}
+
+fn neighbor_use_item(use_item: &ast::UseItem, dir: Direction) -> Option<ast::UseItem> {
+    let neighbor =
+        use_item.syntax().siblings_with_tokens(dir).skip(1).find(|it| it.kind() != WHITESPACE)?;
+    ast::UseItem::cast(neighbor.into_node()?).filter(is_organizable)
+}
+
+/// Checks whether there is a blank line in front of `use_item`.
+fn starts_group(use_item: &ast::UseItem) -> bool {
+    match use_item.syntax().prev_sibling_or_token() {
+        Some(it) if it.kind() == WHITESPACE => {
+            it.into_token().map_or(false, |ws| ws.text().matches('\n').count() > 1)
+        }
+        _ => false,
+    }
+}
+
+/// Drops the trees in `unused` from `use_tree`, and the `{}` trees which are
+/// left empty.
+fn remove_unused(use_tree: &ast::UseTree, unused: &FxHashSet<TextRange>) -> Option<ast::UseTree> {
+    if unused.contains(&use_tree.syntax().text_range()) {
+        return None;
+    }
+    let use_tree_list = match use_tree.use_tree_list() {
+        Some(it) => it,
+        None => return Some(use_tree.clone()),
+    };
+    let count = use_tree_list.use_trees().count();
+    let use_trees: Vec<_> =
+        use_tree_list.use_trees().filter_map(|it| remove_unused(&it, unused)).collect();
+    if use_trees.is_empty() && count > 0 {
+        return None;
+    }
+    if use_trees.len() == count {
+        return Some(use_tree.clone());
+    }
+    Some(use_tree.with_use_tree_list(make::use_tree_list(use_trees)))
+}
+
+/// Sorts the trees of a group by their text, which puts the trees with the
+/// same first segment next to each other, merges them, and then sorts the
+/// nested trees.
+fn organize_group(mut group: Vec<(String, ast::UseTree)>) -> Vec<(String, ast::UseTree)> {
+    group.sort_by_cached_key(|(visibility, use_tree)| (visibility.clone(), use_tree.to_string()));
+    group.dedup_by(|(lhs_vis, lhs), (rhs_vis, rhs)| {
+        lhs_vis == rhs_vis && lhs.to_string() == rhs.to_string()
+    });
+
+    let mut res: Vec<(String, ast::UseTree)> = Vec::new();
+    for (visibility, use_tree) in group {
+        if let Some((last_visibility, last)) = res.last_mut() {
+            if *last_visibility == visibility {
+                if let Some(merged) = try_merge_trees(last, &use_tree) {
+                    *last = merged;
+                    continue;
+                }
+            }
+        }
+        res.push((visibility, use_tree));
+    }
+    res.into_iter().map(|(visibility, use_tree)| (visibility, sort_use_tree(&use_tree))).collect()
+}
+
+/// Recursively sorts the trees in `{}` by their text, with `self` first.
+fn sort_use_tree(use_tree: &ast::UseTree) -> ast::UseTree {
+    let use_tree_list = match use_tree.use_tree_list() {
+        Some(it) => it,
+        None => return use_tree.clone(),
+    };
+    let mut use_trees: Vec<_> = use_tree_list.use_trees().map(|it| sort_use_tree(&it)).collect();
+    use_trees.sort_by_cached_key(|it| {
+        let text = it.to_string();
+        (text != "self", text)
+    });
+    use_tree.with_use_tree_list(make::use_tree_list(use_trees))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::helpers::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn organize_imports_removes_unused() {
+        check_assist(
+            organize_imports,
+            r"
+mod foo {
+    pub struct Bar;
+    pub struct Baz;
+}
+use foo::<|>Bar;
+use foo::Baz;
+
+fn main() {
+    let _ = Bar;
+}
+",
+            r"
+mod foo {
+    pub struct Bar;
+    pub struct Baz;
+}
+<|>use foo::Bar;
+
+fn main() {
+    let _ = Bar;
+}
+",
+        );
+    }
+
+    #[test]
+    fn organize_imports_sorts_and_merges() {
+        check_assist(
+            organize_imports,
+            r"
+mod foo {
+    pub struct Bar;
+    pub struct Baz;
+    pub mod inner {
+        pub struct Qux;
+    }
+}
+mod quux {
+    pub fn f() {}
+}
+use quux::f;
+use foo::{inner::Qux, <|>Baz};
+use foo::Bar;
+
+fn main() {
+    let _ = (Bar, Baz, Qux);
+    f();
+}
+",
+            r"
+mod foo {
+    pub struct Bar;
+    pub struct Baz;
+    pub mod inner {
+        pub struct Qux;
+    }
+}
+mod quux {
+    pub fn f() {}
+}
+<|>use foo::{Bar, Baz, inner::Qux};
+use quux::f;
+
+fn main() {
+    let _ = (Bar, Baz, Qux);
+    f();
+}
+",
+        );
+    }
+
+    #[test]
+    fn organize_imports_sorts_nested_trees() {
+        check_assist(
+            organize_imports,
+            r"
+mod foo {
+    pub struct Bar;
+    pub struct Baz;
+    pub mod inner {
+        pub struct Qux;
+        pub struct Quux;
+    }
+}
+use foo::{inner::{Quux, Qux, self}, Baz, <|>Bar};
+
+fn main() {
+    let _ = (Bar, Baz, Qux, Quux, inner::Qux);
+}
+",
+            r"
+mod foo {
+    pub struct Bar;
+    pub struct Baz;
+    pub mod inner {
+        pub struct Qux;
+        pub struct Quux;
+    }
+}
+<|>use foo::{Bar, Baz, inner::{self, Quux, Qux}};
+
+fn main() {
+    let _ = (Bar, Baz, Qux, Quux, inner::Qux);
+}
+",
+        );
+    }
+
+    #[test]
+    fn organize_imports_keeps_groups() {
+        check_assist(
+            organize_imports,
+            r"
+mod foo {
+    pub struct Bar;
+    pub struct Baz;
+    pub mod inner {
+        pub struct Qux;
+    }
+}
+use foo::Baz;
+use foo::<|>Bar;
+
+use self::foo::inner::Qux;
+use self::foo::Bar as Unused;
+
+fn main() {
+    let _ = (Bar, Baz, Qux);
+}
+",
+            r"
+mod foo {
+    pub struct Bar;
+    pub struct Baz;
+    pub mod inner {
+        pub struct Qux;
+    }
+}
+<|>use foo::{Bar, Baz};
+
+use self::foo::inner::Qux;
+
+fn main() {
+    let _ = (Bar, Baz, Qux);
+}
+",
+        );
+    }
+
+    #[test]
+    fn organize_imports_in_nested_module() {
+        check_assist(
+            organize_imports,
+            r"
+mod foo {
+    pub struct Bar;
+    pub struct Baz;
+}
+mod bar {
+    use super::foo::Baz;
+    use super::foo::<|>Bar;
+    use super::foo::{Baz as Unused};
+
+    fn f() {
+        let _ = (Bar, Baz);
+    }
+}
+",
+            r"
+mod foo {
+    pub struct Bar;
+    pub struct Baz;
+}
+mod bar {
+    <|>use super::foo::{Bar, Baz};
+
+    fn f() {
+        let _ = (Bar, Baz);
+    }
+}
+",
+        );
+    }
+
+    #[test]
+    fn organize_imports_not_applicable_when_organized() {
+        check_assist_not_applicable(
+            organize_imports,
+            r"
+mod foo {
+    pub struct Bar;
+    pub struct Baz;
+}
+use foo::{<|>Bar, Baz};
+
+fn main() {
+    let _ = (Bar, Baz);
+}
+",
+        );
+    }
+
+    #[test]
+    fn organize_imports_not_applicable_with_attributes() {
+        check_assist_not_applicable(
+            organize_imports,
+            r"
+mod foo {
+    pub struct Bar;
+}
+#[cfg(test)]
+use foo::<|>Bar;
+",
+        );
+    }
+}
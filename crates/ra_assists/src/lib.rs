@@ -18,6 +18,7 @@ use ra_syntax::{TextRange, TextUnit};
 use ra_text_edit::TextEdit;
 
 pub(crate) use crate::assist_ctx::{Assist, AssistCtx, AssistHandler};
+pub use crate::handlers::use_path_candidates;
 use hir::Semantics;
 
 /// Unique identifier of the assist, should not be shown to the user
@@ -103,10 +104,13 @@ mod handlers {
     mod auto_import;
     mod change_visibility;
     mod early_return;
+    mod extract_function;
     mod fill_match_arms;
     mod flip_binexpr;
     mod flip_comma;
     mod flip_trait_bound;
+    mod generate_function;
+    mod inline_call;
     mod inline_local_variable;
     mod introduce_variable;
     mod invert_if;
@@ -114,6 +118,7 @@ mod handlers {
     mod merge_match_arms;
     mod move_bounds;
     mod move_guard;
+    mod organize_imports;
     mod raw_string;
     mod remove_dbg;
     mod remove_mut;
@@ -123,6 +128,8 @@ mod handlers {
     mod replace_unwrap_with_match;
     mod split_import;
 
+    pub use auto_import::use_path_candidates;
+
     pub(crate) fn all() -> &'static [AssistHandler] {
         &[
             add_custom_impl::add_custom_impl,
@@ -136,10 +143,13 @@ mod handlers {
             auto_import::auto_import,
             change_visibility::change_visibility,
             early_return::convert_to_guarded_return,
+            extract_function::extract_function,
             fill_match_arms::fill_match_arms,
             flip_binexpr::flip_binexpr,
             flip_comma::flip_comma,
             flip_trait_bound::flip_trait_bound,
+            generate_function::generate_function,
+            inline_call::inline_call,
             inline_local_variable::inline_local_variable,
             introduce_variable::introduce_variable,
             invert_if::invert_if,
@@ -148,6 +158,7 @@ mod handlers {
             move_bounds::move_bounds_to_where_clause,
             move_guard::move_arm_cond_to_match_guard,
             move_guard::move_guard_to_arm_body,
+            organize_imports::organize_imports,
             raw_string::add_hash,
             raw_string::make_raw_string,
             raw_string::make_usual_string,
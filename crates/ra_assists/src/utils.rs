@@ -2,10 +2,11 @@
 pub(crate) mod insert_use;
 
 use hir::Semantics;
+use itertools::Itertools;
 use ra_ide_db::RootDatabase;
 use ra_syntax::{
     ast::{self, make, NameOwner},
-    AstNode, T,
+    AstNode, TextRange, TextUnit, T,
 };
 use rustc_hash::FxHashSet;
 
@@ -99,3 +100,48 @@ fn invert_special_case(expr: &ast::Expr) -> Option<ast::Expr> {
         _ => None,
     }
 }
+
+/// The indentation of the line containing `offset`.
+pub(crate) fn line_indent(text: &str, offset: TextUnit) -> String {
+    let line_start = text[..offset.to_usize()].rfind('\n').map_or(0, |it| it + 1);
+    text[line_start..].chars().take_while(|it| *it == ' ' || *it == '\t').collect()
+}
+
+/// Returns the text of `range` with the given replacements applied. The
+/// ranges of the replacements are relative to `text_start`.
+pub(crate) fn replace_ranges(
+    text: &str,
+    range: TextRange,
+    text_start: TextUnit,
+    mut replacements: Vec<(TextRange, String)>,
+) -> String {
+    replacements.sort_by_key(|(range, _)| (range.start(), range.end()));
+    let mut buf = String::new();
+    let mut pos = range.start();
+    for (replaced, replacement) in replacements {
+        let start = replaced.start() - text_start;
+        buf.push_str(&text[pos.to_usize()..start.to_usize()]);
+        buf.push_str(&replacement);
+        pos = replaced.end() - text_start;
+    }
+    buf.push_str(&text[pos.to_usize()..range.end().to_usize()]);
+    buf
+}
+
+/// Replaces the indentation `old_indent` of every line of `text` with
+/// `new_indent`, dropping the whitespace of blank lines.
+pub(crate) fn reindent(text: &str, old_indent: &str, new_indent: &str) -> String {
+    text.lines()
+        .map(|line| {
+            if line.trim().is_empty() {
+                return String::new();
+            }
+            let line = if line.starts_with(old_indent) {
+                &line[old_indent.len()..]
+            } else {
+                line.trim_start()
+            };
+            format!("{}{}", new_indent, line)
+        })
+        .join("\n")
+}
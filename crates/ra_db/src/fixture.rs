@@ -88,6 +88,7 @@ fn with_single_file(db: &mut dyn SourceDatabaseExt, ra_fixture: &str) -> FileId
     };
 
     db.set_file_text(file_id, Arc::new(ra_fixture.to_string()));
+    db.set_file_reparse(file_id, None);
     db.set_file_relative_path(file_id, rel_path);
     db.set_file_source_root(file_id, WORKSPACE);
     db.set_source_root(WORKSPACE, Arc::new(source_root));
@@ -154,6 +155,7 @@ fn with_files(db: &mut dyn SourceDatabaseExt, fixture: &str) -> Option<FilePosit
         };
 
         db.set_file_text(file_id, Arc::new(text));
+        db.set_file_reparse(file_id, None);
         db.set_file_relative_path(file_id, meta.path.clone());
         db.set_file_source_root(file_id, source_root_id);
         source_root.insert_file(meta.path, file_id);
@@ -98,6 +98,12 @@ pub trait FileLoader {
         extern_id: ExternSourceId,
         relative_path: &RelativePath,
     ) -> Option<FileId>;
+
+    /// Syntax tree of the text of the file, if it was already computed by
+    /// incrementally reparsing the previous text.
+    fn reparsed_file(&self, _file_id: FileId) -> Option<Parse<ast::SourceFile>> {
+        None
+    }
 }
 
 /// Database which stores all significant input facts: source code and project
@@ -115,6 +121,9 @@ pub trait SourceDatabase: CheckCanceled + FileLoader + std::fmt::Debug {
 
 fn parse_query(db: &impl SourceDatabase, file_id: FileId) -> Parse<ast::SourceFile> {
     let _p = profile("parse_query");
+    if let Some(parse) = db.reparsed_file(file_id) {
+        return parse;
+    }
     let text = db.file_text(file_id);
     SourceFile::parse(&*text)
 }
@@ -125,6 +134,12 @@ fn parse_query(db: &impl SourceDatabase, file_id: FileId) -> Parse<ast::SourceFi
 pub trait SourceDatabaseExt: SourceDatabase {
     #[salsa::input]
     fn file_text(&self, file_id: FileId) -> Arc<String>;
+    /// Syntax tree of `file_text`, if it was computed by incrementally
+    /// reparsing the previous tree of the file. Replaced on every change of
+    /// the text, so that at most one tree per file is kept. Must be set
+    /// whenever `file_text` is.
+    #[salsa::input]
+    fn file_reparse(&self, file_id: FileId) -> Option<Parse<ast::SourceFile>>;
     /// Path to a file, relative to the root of its source root.
     #[salsa::input]
     fn file_relative_path(&self, file_id: FileId) -> RelativePathBuf;
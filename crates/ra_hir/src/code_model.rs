@@ -8,13 +8,15 @@ use hir_def::{
     adt::VariantData,
     builtin_type::BuiltinType,
     docs::Documentation,
-    expr::{BindingAnnotation, Pat, PatId},
+    expr::{BindingAnnotation, LabelId, Pat, PatId},
+    import_usage::UnusedImports,
     per_ns::PerNs,
     resolver::{HasResolver, Resolver},
     type_ref::{Mutability, TypeRef},
-    AdtId, AssocContainerId, ConstId, DefWithBodyId, EnumId, FunctionId, GenericDefId, HasModule,
-    ImplId, LocalEnumVariantId, LocalModuleId, LocalStructFieldId, Lookup, ModuleId, StaticId,
-    StructId, TraitId, TypeAliasId, TypeParamId, UnionId,
+    AdtId, AssocContainerId, ConstId, ConstParamId, DefWithBodyId, EnumId, FunctionId,
+    GenericDefId, HasModule, ImplId, LifetimeParamId, LocalEnumVariantId, LocalModuleId,
+    LocalStructFieldId, Lookup, ModuleDefId, ModuleId, StaticId, StructId, TraitId, TypeAliasId,
+    TypeParamId, UnionId,
 };
 use hir_expand::{
     diagnostics::DiagnosticSink,
@@ -22,19 +24,21 @@ use hir_expand::{
     MacroDefId,
 };
 use hir_ty::{
-    autoderef, display::HirFormatter, expr::ExprValidator, method_resolution, ApplicationTy,
-    Canonical, InEnvironment, Substs, TraitEnvironment, Ty, TyDefId, TypeCtor,
+    autoderef, consteval::ConstValue, display::HirFormatter, expr::ExprValidator,
+    method_resolution, unsafe_validation::UnsafeValidator, ApplicationTy, Canonical,
+    GenericPredicate, InEnvironment, Substs, TraitEnvironment, Ty, TyDefId, TypeCtor,
 };
 use ra_db::{CrateId, Edition, FileId};
 use ra_prof::profile;
 use ra_syntax::{
     ast::{self, AttrsOwner, NameOwner},
-    AstNode,
+    AstNode, AstPtr,
 };
 use rustc_hash::FxHashSet;
 
 use crate::{
     db::{DefDatabase, HirDatabase},
+    diagnostics::UnusedImport,
     has_source::HasSource,
     CallableDef, HirDisplay, InFile, Name,
 };
@@ -245,6 +249,8 @@ impl Module {
         for decl in self.declarations(db) {
             match decl {
                 crate::ModuleDef::Function(f) => f.diagnostics(db, sink),
+                crate::ModuleDef::Const(c) => DefWithBody::from(c).diagnostics(db, sink),
+                crate::ModuleDef::Static(s) => DefWithBody::from(s).diagnostics(db, sink),
                 crate::ModuleDef::Module(m) => {
                     // Only add diagnostics from inline modules
                     if crate_def_map[m.id.local_id].origin.is_inline() {
@@ -257,11 +263,36 @@ impl Module {
 
         for impl_def in self.impl_defs(db) {
             for item in impl_def.items(db) {
-                if let AssocItem::Function(f) = item {
-                    f.diagnostics(db, sink);
+                match item {
+                    AssocItem::Function(f) => f.diagnostics(db, sink),
+                    AssocItem::Const(c) => DefWithBody::from(c).diagnostics(db, sink),
+                    AssocItem::TypeAlias(_) => (),
                 }
             }
         }
+
+        for use_tree in self.unused_imports(db) {
+            sink.push(UnusedImport { file: use_tree.file_id, node: AstPtr::new(&use_tree.value) })
+        }
+    }
+
+    /// Finds the imports of this module which are neither used by a path nor,
+    /// for traits, by a method call or an associated item path in a body.
+    pub fn unused_imports(self, db: &dyn HirDatabase) -> Vec<InFile<ast::UseTree>> {
+        let _p = profile("Module::unused_imports");
+        let unused = UnusedImports::new(db.upcast(), self.id);
+        let mut used_traits = None;
+        unused
+            .imports()
+            .iter()
+            .filter(|import| match import.def.take_types() {
+                Some(ModuleDefId::TraitId(trait_)) => !used_traits
+                    .get_or_insert_with(|| traits_used_in_bodies(db, unused.scopes()))
+                    .contains(&trait_),
+                _ => true,
+            })
+            .filter_map(|import| import.source(db.upcast()))
+            .collect()
     }
 
     pub fn declarations(self, db: &dyn HirDatabase) -> Vec<ModuleDef> {
@@ -289,6 +320,58 @@ impl Module {
     }
 }
 
+fn traits_used_in_bodies(db: &dyn HirDatabase, modules: &[ModuleId]) -> FxHashSet<TraitId> {
+    let mut bodies: Vec<DefWithBody> = Vec::new();
+    for &module in modules {
+        let module = Module::from(module);
+        let mut assoc_items = Vec::new();
+        for decl in module.declarations(db) {
+            match decl {
+                ModuleDef::Function(it) => bodies.push(it.into()),
+                ModuleDef::Const(it) => bodies.push(it.into()),
+                ModuleDef::Static(it) => bodies.push(it.into()),
+                ModuleDef::Trait(it) => assoc_items.extend(it.items(db)),
+                _ => (),
+            }
+        }
+        for impl_def in module.impl_defs(db) {
+            assoc_items.extend(impl_def.items(db));
+        }
+        for item in assoc_items {
+            match item {
+                AssocItem::Function(it) => bodies.push(it.into()),
+                AssocItem::Const(it) => bodies.push(it.into()),
+                AssocItem::TypeAlias(_) => (),
+            }
+        }
+    }
+
+    let mut res = FxHashSet::default();
+    for def in bodies {
+        let def = DefWithBodyId::from(def);
+        let body = db.body(def);
+        let infer = db.infer(def);
+        let resolved = body
+            .exprs
+            .iter()
+            .filter_map(|(expr, _)| {
+                infer
+                    .method_resolution(expr)
+                    .map(AssocItemId::from)
+                    .or_else(|| infer.assoc_resolutions_for_expr(expr))
+            })
+            .chain(body.pats.iter().filter_map(|(pat, _)| infer.assoc_resolutions_for_pat(pat)));
+        for item in resolved {
+            let trait_ = match AssocItem::from(item).container(db) {
+                AssocItemContainer::Trait(it) => Some(it),
+                AssocItemContainer::ImplDef(it) => it.target_trait_def(db),
+            };
+            res.extend(trait_.map(|it| it.id));
+        }
+    }
+    res
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct StructField {
     pub(crate) parent: VariantDef,
@@ -582,6 +665,16 @@ impl DefWithBody {
             DefWithBody::Const(c) => c.name(db),
         }
     }
+
+    pub fn diagnostics(self, db: &dyn HirDatabase, sink: &mut DiagnosticSink) {
+        match self {
+            DefWithBody::Function(f) => f.diagnostics(db, sink),
+            DefWithBody::Const(_) | DefWithBody::Static(_) => {
+                let (_, source_map) = db.body_with_source_map(self.into());
+                source_map.add_diagnostics(sink);
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -610,11 +703,19 @@ impl Function {
         db.function_data(self.id).params.clone()
     }
 
+    pub fn is_unsafe(self, db: &dyn HirDatabase) -> bool {
+        db.function_data(self.id).is_unsafe
+    }
+
     pub fn diagnostics(self, db: &dyn HirDatabase, sink: &mut DiagnosticSink) {
         let _p = profile("Function::diagnostics");
+        let (_, source_map) = db.body_with_source_map(self.id.into());
+        source_map.add_diagnostics(sink);
         let infer = db.infer(self.id.into());
         infer.add_diagnostics(db, self.id, sink);
-        let mut validator = ExprValidator::new(self.id, infer, sink);
+        let mut validator = ExprValidator::new(self.id, infer.clone(), sink);
+        validator.validate_body(db);
+        let mut validator = UnsafeValidator::new(self.id, infer, sink);
         validator.validate_body(db);
     }
 }
@@ -644,6 +745,11 @@ impl Const {
     pub fn name(self, db: &dyn HirDatabase) -> Option<Name> {
         db.const_data(self.id).name.clone()
     }
+
+    /// Evaluates the value of the constant, if it's simple enough.
+    pub fn eval(self, db: &dyn HirDatabase) -> Option<ConstValue> {
+        db.const_eval(self.id).ok()
+    }
 }
 
 impl HasVisibility for Const {
@@ -671,6 +777,10 @@ impl Static {
     pub fn name(self, db: &dyn HirDatabase) -> Option<Name> {
         db.static_data(self.id).name.clone()
     }
+
+    pub fn is_mut(self, db: &dyn HirDatabase) -> bool {
+        db.static_data(self.id).mutable
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -924,6 +1034,28 @@ impl Local {
     }
 }
 
+/// The label of a loop.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Label {
+    pub(crate) parent: DefWithBodyId,
+    pub(crate) label_id: LabelId,
+}
+
+impl Label {
+    pub fn name(self, db: &dyn HirDatabase) -> Name {
+        let body = db.body(self.parent);
+        body[self.label_id].name.clone()
+    }
+
+    pub fn parent(self, _db: &dyn HirDatabase) -> DefWithBody {
+        self.parent.into()
+    }
+
+    pub fn module(self, db: &dyn HirDatabase) -> Module {
+        self.parent(db).module(db)
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct TypeParam {
     pub(crate) id: TypeParamId,
@@ -940,6 +1072,47 @@ impl TypeParam {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LifetimeParam {
+    pub(crate) id: LifetimeParamId,
+}
+
+impl LifetimeParam {
+    pub fn name(self, db: &dyn HirDatabase) -> Name {
+        let params = db.generic_params(self.id.parent);
+        params.lifetimes[self.id.local_id].name.clone()
+    }
+
+    pub fn module(self, db: &dyn HirDatabase) -> Module {
+        self.id.parent.module(db.upcast()).into()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ConstParam {
+    pub(crate) id: ConstParamId,
+}
+
+impl ConstParam {
+    pub fn name(self, db: &dyn HirDatabase) -> Name {
+        let params = db.generic_params(self.id.parent);
+        params.consts[self.id.local_id].name.clone()
+    }
+
+    pub fn module(self, db: &dyn HirDatabase) -> Module {
+        self.id.parent.module(db.upcast()).into()
+    }
+
+    pub fn ty(self, db: &dyn HirDatabase) -> Type {
+        let params = db.generic_params(self.id.parent);
+        let resolver = self.id.parent.resolver(db.upcast());
+        let ctx = hir_ty::TyLoweringContext::new(db, &resolver);
+        let ty = Ty::from_hir(&ctx, &params.consts[self.id.local_id].ty);
+        let krate = self.id.parent.module(db.upcast()).krate;
+        Type::new(db, krate, self.id.parent, ty)
+    }
+}
+
 // FIXME: rename from `ImplDef` to `Impl`
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ImplDef {
@@ -960,6 +1133,10 @@ impl ImplDef {
         db.impl_data(self.id).target_trait.clone()
     }
 
+    pub fn target_trait_def(&self, db: &dyn HirDatabase) -> Option<Trait> {
+        db.impl_trait(self.id).map(|it| it.value.trait_.into())
+    }
+
     pub fn target_type(&self, db: &dyn HirDatabase) -> TypeRef {
         db.impl_data(self.id).target_type.clone()
     }
@@ -1051,6 +1228,21 @@ impl Type {
         matches!(self.ty.value, Ty::Apply(ApplicationTy { ctor: TypeCtor::Bool, .. }))
     }
 
+    pub fn is_unit(&self) -> bool {
+        matches!(
+            self.ty.value,
+            Ty::Apply(ApplicationTy { ctor: TypeCtor::Tuple { cardinality: 0 }, .. })
+        )
+    }
+
+    pub fn is_raw_ptr(&self) -> bool {
+        matches!(self.ty.value, Ty::Apply(ApplicationTy { ctor: TypeCtor::RawPtr(..), .. }))
+    }
+
+    pub fn is_reference(&self) -> bool {
+        matches!(self.ty.value, Ty::Apply(ApplicationTy { ctor: TypeCtor::Ref(..), .. }))
+    }
+
     pub fn is_mutable_reference(&self) -> bool {
         matches!(
             self.ty.value,
@@ -1084,6 +1276,53 @@ impl Type {
         )
     }
 
+    /// Checks that the type implements `trait_`, which is most useful for auto
+    /// traits like `Send` and `Sync`, e.g. to tell whether a future can be sent
+    /// across threads.
+    pub fn impls_trait(&self, db: &dyn HirDatabase, trait_: Trait) -> bool {
+        let canonical_ty = Canonical { value: self.ty.value.clone(), num_vars: 0 };
+        method_resolution::implements_trait(
+            &canonical_ty,
+            db,
+            self.ty.environment.clone(),
+            self.krate,
+            trait_.id,
+        )
+    }
+
+    /// Checks that the type implements `Copy`. Primitive types and shared
+    /// references are always `Copy`, even if we can't find the trait.
+    pub fn is_copy(&self, db: &dyn HirDatabase) -> bool {
+        if let Ty::Apply(a_ty) = &self.ty.value {
+            match a_ty.ctor {
+                TypeCtor::Bool
+                | TypeCtor::Char
+                | TypeCtor::Int(_)
+                | TypeCtor::Float(_)
+                | TypeCtor::Never
+                | TypeCtor::RawPtr(_)
+                | TypeCtor::FnPtr { .. }
+                | TypeCtor::Ref(Mutability::Shared) => return true,
+                _ => {}
+            }
+        }
+
+        let krate = self.krate;
+        let copy_trait = match db.lang_item(krate, "copy".into()).and_then(|it| it.as_trait()) {
+            Some(it) => it,
+            None => return false,
+        };
+
+        let canonical_ty = Canonical { value: self.ty.value.clone(), num_vars: 0 };
+        method_resolution::implements_trait(
+            &canonical_ty,
+            db,
+            self.ty.environment.clone(),
+            krate,
+            copy_trait,
+        )
+    }
+
     // FIXME: this method is broken, as it doesn't take closures into account.
     pub fn as_callable(&self) -> Option<CallableDef> {
         Some(self.ty.value.as_callable()?.0)
@@ -1250,6 +1489,19 @@ impl Type {
         Some(adt.into())
     }
 
+    /// If this is an opaque `impl Trait` type, returns the traits in its bounds.
+    pub fn as_impl_traits(&self, db: &dyn HirDatabase) -> Option<Vec<Trait>> {
+        let bounds = self.ty.value.impl_trait_bounds(db)?;
+        let traits = bounds
+            .into_iter()
+            .filter_map(|pred| match pred {
+                GenericPredicate::Implemented(trait_ref) => Some(trait_ref.trait_.into()),
+                _ => None,
+            })
+            .collect();
+        Some(traits)
+    }
+
     // FIXME: provide required accessors such that it becomes implementable from outside.
     pub fn is_equal_for_find_impls(&self, other: &Type) -> bool {
         match (&self.ty.value, &other.ty.value) {
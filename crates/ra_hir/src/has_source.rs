@@ -9,8 +9,9 @@ use hir_def::{
 use ra_syntax::ast;
 
 use crate::{
-    db::HirDatabase, Const, Enum, EnumVariant, FieldSource, Function, ImplDef, MacroDef, Module,
-    Static, Struct, StructField, Trait, TypeAlias, TypeParam, Union,
+    db::HirDatabase, Const, ConstParam, Enum, EnumVariant, FieldSource, Function, ImplDef, Label,
+    LifetimeParam, MacroDef, Module, Static, Struct, StructField, Trait, TypeAlias, TypeParam,
+    Union,
 };
 
 pub use hir_expand::InFile;
@@ -125,3 +126,29 @@ impl HasSource for TypeParam {
         child_source.map(|it| it[self.id.local_id].clone())
     }
 }
+
+impl HasSource for LifetimeParam {
+    type Ast = ast::LifetimeParam;
+    fn source(self, db: &dyn HirDatabase) -> InFile<Self::Ast> {
+        let child_source = self.id.parent.lifetime_param_source(db.upcast());
+        child_source.map(|it| it[self.id.local_id].clone())
+    }
+}
+
+impl HasSource for ConstParam {
+    type Ast = ast::ConstParam;
+    fn source(self, db: &dyn HirDatabase) -> InFile<Self::Ast> {
+        let child_source = self.id.parent.const_param_source(db.upcast());
+        child_source.map(|it| it[self.id.local_id].clone())
+    }
+}
+
+impl HasSource for Label {
+    type Ast = ast::Label;
+    fn source(self, db: &dyn HirDatabase) -> InFile<Self::Ast> {
+        let (_body, source_map) = db.body_with_source_map(self.parent);
+        let src = source_map.label_syntax(self.label_id);
+        let root = src.file_syntax(db.upcast());
+        src.map(|ast| ast.to_node(&root))
+    }
+}
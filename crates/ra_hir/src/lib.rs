@@ -51,10 +51,11 @@ mod has_source;
 
 pub use crate::{
     code_model::{
-        Adt, AsAssocItem, AssocItem, AssocItemContainer, AttrDef, Const, Crate, CrateDependency,
-        DefWithBody, Docs, Enum, EnumVariant, FieldSource, Function, GenericDef, HasAttrs,
-        HasVisibility, ImplDef, Local, MacroDef, Module, ModuleDef, ScopeDef, Static, Struct,
-        StructField, Trait, Type, TypeAlias, TypeParam, Union, VariantDef, Visibility,
+        Adt, AsAssocItem, AssocItem, AssocItemContainer, AttrDef, Const, ConstParam, Crate,
+        CrateDependency, DefWithBody, Docs, Enum, EnumVariant, FieldSource, Function, GenericDef,
+        HasAttrs, HasVisibility, ImplDef, Label, LifetimeParam, Local, MacroDef, Module, ModuleDef,
+        ScopeDef, Static, Struct, StructField, Trait, Type, TypeAlias, TypeParam, Union,
+        VariantDef, Visibility,
     },
     has_source::HasSource,
     semantics::{original_range, PathResolution, Semantics, SemanticsScope},
@@ -70,6 +71,8 @@ pub use hir_def::{
     type_ref::Mutability,
 };
 pub use hir_expand::{
-    name::Name, HirFileId, InFile, MacroCallId, MacroCallLoc, MacroDefId, MacroFile, Origin,
+    db::{ExpansionTrace, TracedBinding},
+    name::Name,
+    HirFileId, InFile, MacroCallId, MacroCallLoc, MacroDefId, MacroFile, Origin,
 };
-pub use hir_ty::{display::HirDisplay, CallableDef};
+pub use hir_ty::{consteval::ConstValue, display::HirDisplay, CallableDef};
@@ -8,7 +8,7 @@ use hir_def::{
     resolver::{self, HasResolver, Resolver},
     AsMacroCall, TraitId,
 };
-use hir_expand::ExpansionInfo;
+use hir_expand::{db::ExpansionTrace, ExpansionInfo};
 use ra_db::{FileId, FileRange};
 use ra_prof::profile;
 use ra_syntax::{
@@ -21,8 +21,8 @@ use crate::{
     db::HirDatabase,
     semantics::source_to_def::{ChildContainer, SourceToDefCache, SourceToDefCtx},
     source_analyzer::{resolve_hir_path, SourceAnalyzer},
-    AssocItem, Function, HirFileId, ImplDef, InFile, Local, MacroDef, Module, ModuleDef, Name,
-    Origin, Path, ScopeDef, StructField, Trait, Type, TypeParam, VariantDef,
+    AssocItem, Function, HirFileId, ImplDef, InFile, Label, LifetimeParam, Local, MacroDef, Module,
+    ModuleDef, Name, Origin, Path, ScopeDef, StructField, Trait, Type, TypeParam, VariantDef,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -33,6 +33,8 @@ pub enum PathResolution {
     Local(Local),
     /// A generic parameter
     TypeParam(TypeParam),
+    /// A const generic parameter (only value namespace)
+    ConstParam(ConstParam),
     SelfType(ImplDef),
     Macro(MacroDef),
     AssocItem(AssocItem),
@@ -85,6 +87,16 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
         hir_expand::db::expand_hypothetical(self.db, macro_call_id, hypothetical_args, token_to_map)
     }
 
+    /// Records which rule of a `macro_rules!` macro `macro_call` is expanded
+    /// with, and what the metavariables are bound to.
+    pub fn expansion_trace(&self, macro_call: &ast::MacroCall) -> Option<ExpansionTrace> {
+        let macro_call = self.find_file(macro_call.syntax().clone()).with_value(macro_call);
+        let sa = self.analyze2(macro_call.map(|it| it.syntax()), None);
+        let macro_call_id = macro_call
+            .as_call_id(self.db, |path| sa.resolver.resolve_path_as_macro(self.db, &path))?;
+        hir_expand::db::expansion_trace(self.db, macro_call_id)
+    }
+
     pub fn descend_into_macros(&self, token: SyntaxToken) -> SyntaxToken {
         let parent = token.parent();
         let parent = self.find_file(parent);
@@ -176,6 +188,11 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
         self.analyze(call.syntax()).resolve_method_call(self.db, call)
     }
 
+    /// Resolves an overloaded binary operator to the method implementing it.
+    pub fn resolve_bin_expr(&self, bin_expr: &ast::BinExpr) -> Option<Function> {
+        self.analyze(bin_expr.syntax()).resolve_bin_expr(self.db, bin_expr)
+    }
+
     pub fn resolve_field(&self, field: &ast::FieldExpr) -> Option<StructField> {
         self.analyze(field.syntax()).resolve_field(self.db, field)
     }
@@ -205,6 +222,23 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
         self.analyze(path.syntax()).resolve_path(self.db, path)
     }
 
+    /// Resolves the label of a `break` or `continue` to the loop it refers to.
+    pub fn resolve_label(&self, lifetime: &SyntaxToken) -> Option<Label> {
+        let expr = lifetime.parent().ancestors().find_map(ast::Expr::cast)?;
+        match expr {
+            ast::Expr::BreakExpr(_) | ast::Expr::ContinueExpr(_) => {}
+            _ => return None,
+        }
+        self.analyze(expr.syntax()).resolve_label(self.db, &expr)
+    }
+
+    /// Resolves a lifetime to the generic parameter declaring it.
+    pub fn resolve_lifetime_param(&self, lifetime: &SyntaxToken) -> Option<LifetimeParam> {
+        let name = Name::new_lifetime(lifetime);
+        let id = self.scope(&lifetime.parent()).resolver.resolve_lifetime(&name)?;
+        Some(id.into())
+    }
+
     pub fn resolve_bind_pat_to_const(&self, pat: &ast::BindPat) -> Option<ModuleDef> {
         self.analyze(pat.syntax()).resolve_bind_pat_to_const(self.db, pat)
     }
@@ -341,8 +375,11 @@ to_def_impls![
     (crate::StructField, ast::TupleFieldDef, tuple_field_to_def),
     (crate::EnumVariant, ast::EnumVariant, enum_variant_to_def),
     (crate::TypeParam, ast::TypeParam, type_param_to_def),
+    (crate::LifetimeParam, ast::LifetimeParam, lifetime_param_to_def),
+    (crate::ConstParam, ast::ConstParam, const_param_to_def),
     (crate::MacroDef, ast::MacroCall, macro_call_to_def), // this one is dubious, not all calls are macros
     (crate::Local, ast::BindPat, bind_pat_to_def),
+    (crate::Label, ast::Label, label_to_def),
 ];
 
 fn find_root(node: &SyntaxNode) -> SyntaxNode {
@@ -5,27 +5,27 @@
 //!
 //! So, this modules should not be used during hir construction, it exists
 //! purely for "IDE needs".
-use std::{iter::once, sync::Arc};
+use std::sync::Arc;
 
 use hir_def::{
     body::{
         scope::{ExprScopes, ScopeId},
         Body, BodySourceMap,
     },
-    expr::{ExprId, Pat, PatId},
+    expr::{Expr, ExprId, Pat, PatId},
     resolver::{resolver_for_scope, Resolver, TypeNs, ValueNs},
     AsMacroCall, DefWithBodyId,
 };
-use hir_expand::{hygiene::Hygiene, name::AsName, HirFileId, InFile};
-use hir_ty::InferenceResult;
+use hir_expand::{hygiene::Hygiene, HirFileId, InFile};
+use hir_ty::{method_resolution, InferenceResult};
 use ra_syntax::{
     ast::{self, AstNode},
     SyntaxNode, SyntaxNodePtr, TextUnit,
 };
 
 use crate::{
-    db::HirDatabase, semantics::PathResolution, Adt, Const, EnumVariant, Function, Local, MacroDef,
-    ModPath, ModuleDef, Path, PathKind, Static, Struct, Trait, Type, TypeAlias, TypeParam,
+    db::HirDatabase, semantics::PathResolution, Adt, Const, ConstParam, EnumVariant, Function,
+    Label, Local, MacroDef, ModuleDef, Path, Static, Struct, Trait, Type, TypeAlias, TypeParam,
 };
 
 /// `SourceAnalyzer` is a convenience wrapper which exposes HIR API in terms of
@@ -130,6 +130,33 @@ impl SourceAnalyzer {
         self.infer.as_ref()?.method_resolution(expr_id).map(Function::from)
     }
 
+    pub(crate) fn resolve_bin_expr(
+        &self,
+        db: &dyn HirDatabase,
+        bin_expr: &ast::BinExpr,
+    ) -> Option<Function> {
+        let infer = self.infer.as_ref()?;
+        let expr_id = self.expr_id(db, &bin_expr.clone().into())?;
+        let func = infer.method_resolution(expr_id)?;
+        let lhs = self.expr_id(db, &bin_expr.lhs()?)?;
+        let rhs = self.expr_id(db, &bin_expr.rhs()?)?;
+        let krate = self.resolver.krate()?;
+        let trait_params = [infer[lhs].clone(), infer[rhs].clone()];
+        let func =
+            method_resolution::lookup_impl_method(db, krate, func, &trait_params).unwrap_or(func);
+        Some(func.into())
+    }
+
+    pub(crate) fn resolve_label(&self, db: &dyn HirDatabase, expr: &ast::Expr) -> Option<Label> {
+        let expr_id = self.expr_id(db, expr)?;
+        let label_id = match &self.body.as_ref()?[expr_id] {
+            Expr::Break { label, .. } | Expr::Continue { label } => (*label)?,
+            _ => return None,
+        };
+        let parent = self.resolver.body_owner()?;
+        Some(Label { parent, label_id })
+    }
+
     pub(crate) fn resolve_field(
         &self,
         db: &dyn HirDatabase,
@@ -149,9 +176,14 @@ impl SourceAnalyzer {
             None => {
                 let src = InFile { file_id: self.file_id, value: field };
                 let expr_id = self.body_source_map.as_ref()?.field_init_shorthand_expr(src)?;
-                let local_name = field.name_ref()?.as_name();
-                let path = ModPath::from_segments(PathKind::Plain, once(local_name));
-                let local = match self.resolver.resolve_path_in_value_ns_fully(db.upcast(), &path) {
+                let path = match &self.body.as_ref()?[expr_id] {
+                    Expr::Path(path) => path,
+                    _ => return None,
+                };
+                let local = match self
+                    .resolver
+                    .resolve_path_in_value_ns_hygienic_fully(db.upcast(), path)
+                {
                     Some(ValueNs::LocalBinding(pat_id)) => {
                         Some(Local { pat_id, parent: self.resolver.body_owner()? })
                     }
@@ -226,8 +258,8 @@ impl SourceAnalyzer {
                 return Some(PathResolution::AssocItem(assoc.into()));
             }
         }
-        // This must be a normal source file rather than macro file.
-        let hir_path = crate::Path::from_ast(path.clone())?;
+        let hygiene = Hygiene::new(db.upcast(), self.file_id);
+        let hir_path = crate::Path::from_src(path.clone(), &hygiene)?;
         resolve_hir_path(db, &self.resolver, &hir_path)
     }
 
@@ -304,7 +336,7 @@ pub(crate) fn resolve_hir_path(
         });
     let body_owner = resolver.body_owner();
     let values =
-        resolver.resolve_path_in_value_ns_fully(db.upcast(), path.mod_path()).and_then(|val| {
+        resolver.resolve_path_in_value_ns_hygienic_fully(db.upcast(), path).and_then(|val| {
             let res = match val {
                 ValueNs::LocalBinding(pat_id) => {
                     let var = Local { parent: body_owner?.into(), pat_id };
@@ -315,6 +347,7 @@ pub(crate) fn resolve_hir_path(
                 ValueNs::StaticId(it) => PathResolution::Def(Static::from(it).into()),
                 ValueNs::StructId(it) => PathResolution::Def(Struct::from(it).into()),
                 ValueNs::EnumVariantId(it) => PathResolution::Def(EnumVariant::from(it).into()),
+                ValueNs::GenericParam(id) => PathResolution::ConstParam(ConstParam { id }),
             };
             Some(res)
         });
@@ -7,15 +7,22 @@ use std::{mem, ops::Index, sync::Arc};
 
 use drop_bomb::DropBomb;
 use either::Either;
-use hir_expand::{ast_id_map::AstIdMap, hygiene::Hygiene, AstId, HirFileId, InFile, MacroDefId};
+use hir_expand::{
+    ast_id_map::AstIdMap,
+    diagnostics::DiagnosticSink,
+    hygiene::{Hygiene, SyntaxContext},
+    AstId, HirFileId, InFile, MacroDefId,
+};
+use mbe::ExpandResult;
 use ra_arena::{map::ArenaMap, Arena};
 use ra_prof::profile;
-use ra_syntax::{ast, AstNode, AstPtr};
+use ra_syntax::{ast, AstNode, AstPtr, TextRange};
 use rustc_hash::FxHashMap;
 
 use crate::{
     db::DefDatabase,
-    expr::{Expr, ExprId, Pat, PatId},
+    diagnostics::MacroError,
+    expr::{Expr, ExprId, Label, LabelId, Pat, PatId},
     item_scope::BuiltinShadowMode,
     item_scope::ItemScope,
     nameres::CrateDefMap,
@@ -50,44 +57,57 @@ impl Expander {
         db: &dyn DefDatabase,
         local_scope: Option<&ItemScope>,
         macro_call: ast::MacroCall,
-    ) -> Option<(Mark, T)> {
+    ) -> ExpandResult<Option<(Mark, T)>> {
         if self.recursive_limit > 1024 {
-            return None;
+            return ExpandResult::ok(None);
         }
 
         let macro_call = InFile::new(self.current_file_id, &macro_call);
 
-        if let Some(call_id) = macro_call.as_call_id(db, |path| {
-            if let Some(local_scope) = local_scope {
-                if let Some(def) = path.as_ident().and_then(|n| local_scope.get_legacy_macro(n)) {
-                    return Some(def);
-                }
-            }
-            self.resolve_path_as_macro(db, &path)
-        }) {
-            let file_id = call_id.as_file();
-            if let Some(node) = db.parse_or_expand(file_id) {
-                if let Some(expr) = T::cast(node) {
-                    log::debug!("macro expansion {:#?}", expr.syntax());
-
-                    let mark = Mark {
-                        file_id: self.current_file_id,
-                        ast_id_map: mem::take(&mut self.ast_id_map),
-                        bomb: DropBomb::new("expansion mark dropped"),
-                    };
-                    self.hygiene = Hygiene::new(db.upcast(), file_id);
-                    self.current_file_id = file_id;
-                    self.ast_id_map = db.ast_id_map(file_id);
-                    self.recursive_limit += 1;
-
-                    return Some((mark, expr));
+        let mut err = None;
+        let call_id = macro_call.as_call_id_with_errors(
+            db,
+            |path| {
+                if let Some(local_scope) = local_scope {
+                    if let Some(def) = path.as_ident().and_then(|n| local_scope.get_legacy_macro(n))
+                    {
+                        return Some(def);
+                    }
                 }
+                self.resolve_path_as_macro(db, &path)
+            },
+            &mut |e| {
+                err.get_or_insert(e);
+            },
+        );
+        let call_id = match call_id {
+            Some(it) => it,
+            None => return ExpandResult(None, err),
+        };
+        if err.is_none() {
+            err = db.macro_expand_error(call_id);
+        }
+
+        let file_id = call_id.as_file();
+        if let Some(node) = db.parse_or_expand(file_id) {
+            if let Some(expr) = T::cast(node) {
+                log::debug!("macro expansion {:#?}", expr.syntax());
+
+                let mark = Mark {
+                    file_id: self.current_file_id,
+                    ast_id_map: mem::take(&mut self.ast_id_map),
+                    bomb: DropBomb::new("expansion mark dropped"),
+                };
+                self.hygiene = Hygiene::new(db.upcast(), file_id);
+                self.current_file_id = file_id;
+                self.ast_id_map = db.ast_id_map(file_id);
+                self.recursive_limit += 1;
+
+                return ExpandResult(Some((mark, expr)), err);
             }
         }
 
-        // FIXME: Instead of just dropping the error from expansion
-        // report it
-        None
+        ExpandResult(None, err)
     }
 
     pub(crate) fn exit(&mut self, db: &dyn DefDatabase, mut mark: Mark) {
@@ -130,6 +150,7 @@ pub(crate) struct Mark {
 pub struct Body {
     pub exprs: Arena<Expr>,
     pub pats: Arena<Pat>,
+    pub labels: Arena<Label>,
     /// The patterns for the function's parameters. While the parameter types are
     /// part of the function signature, the patterns are not (they don't change
     /// the external type of the function).
@@ -140,6 +161,9 @@ pub struct Body {
     /// The `ExprId` of the actual body expression.
     pub body_expr: ExprId,
     pub item_scope: ItemScope,
+    /// Syntax contexts of bindings introduced by `macro_rules!` definitions;
+    /// all other bindings are in the root context.
+    binding_contexts: FxHashMap<PatId, SyntaxContext>,
 }
 
 pub type ExprPtr = Either<AstPtr<ast::Expr>, AstPtr<ast::RecordField>>;
@@ -148,6 +172,9 @@ pub type ExprSource = InFile<ExprPtr>;
 pub type PatPtr = Either<AstPtr<ast::Pat>, AstPtr<ast::SelfParam>>;
 pub type PatSource = InFile<PatPtr>;
 
+pub type LabelPtr = AstPtr<ast::Label>;
+pub type LabelSource = InFile<LabelPtr>;
+
 /// An item body together with the mapping from syntax nodes to HIR expression
 /// IDs. This is needed to go from e.g. a position in a file to the HIR
 /// expression containing it; but for type inference etc., we want to operate on
@@ -165,8 +192,20 @@ pub struct BodySourceMap {
     expr_map_back: ArenaMap<ExprId, Result<ExprSource, SyntheticSyntax>>,
     pat_map: FxHashMap<PatSource, PatId>,
     pat_map_back: ArenaMap<PatId, Result<PatSource, SyntheticSyntax>>,
+    label_map: FxHashMap<LabelSource, LabelId>,
+    label_map_back: ArenaMap<LabelId, LabelSource>,
     field_map: FxHashMap<(ExprId, usize), AstPtr<ast::RecordField>>,
     expansions: FxHashMap<InFile<AstPtr<ast::MacroCall>>, HirFileId>,
+    diagnostics: Vec<BodyDiagnostic>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum BodyDiagnostic {
+    MacroError {
+        node: InFile<AstPtr<ast::MacroCall>>,
+        message: String,
+        token_range: Option<TextRange>,
+    },
 }
 
 #[derive(Default, Debug, Eq, PartialEq, Clone, Copy)]
@@ -216,6 +255,12 @@ impl Body {
     ) -> (Body, BodySourceMap) {
         lower::lower(db, def, expander, params, body)
     }
+
+    /// Returns the syntax context of the binding introduced by `pat`, which
+    /// determines which paths can refer to it.
+    pub fn binding_context(&self, pat: PatId) -> SyntaxContext {
+        self.binding_contexts.get(&pat).copied().unwrap_or_default()
+    }
 }
 
 impl Index<ExprId> for Body {
@@ -234,6 +279,14 @@ impl Index<PatId> for Body {
     }
 }
 
+impl Index<LabelId> for Body {
+    type Output = Label;
+
+    fn index(&self, label: LabelId) -> &Label {
+        &self.labels[label]
+    }
+}
+
 impl BodySourceMap {
     pub fn expr_syntax(&self, expr: ExprId) -> Result<ExprSource, SyntheticSyntax> {
         self.expr_map_back[expr]
@@ -263,7 +316,31 @@ impl BodySourceMap {
         self.pat_map.get(&src).cloned()
     }
 
+    pub fn label_syntax(&self, label: LabelId) -> LabelSource {
+        self.label_map_back[label]
+    }
+
+    pub fn node_label(&self, node: InFile<&ast::Label>) -> Option<LabelId> {
+        let src = node.map(|it| AstPtr::new(it));
+        self.label_map.get(&src).cloned()
+    }
+
     pub fn field_syntax(&self, expr: ExprId, field: usize) -> AstPtr<ast::RecordField> {
         self.field_map[&(expr, field)]
     }
+
+    pub fn add_diagnostics(&self, sink: &mut DiagnosticSink<'_>) {
+        for diag in &self.diagnostics {
+            match diag {
+                BodyDiagnostic::MacroError { node, message, token_range } => {
+                    sink.push(MacroError {
+                        file: node.file_id,
+                        node: node.value.into(),
+                        message: message.clone(),
+                        token_range: *token_range,
+                    })
+                }
+            }
+        }
+    }
 }
@@ -1,31 +1,38 @@
 //! Transforms `ast::Expr` into an equivalent `hir_def::expr::Expr`
 //! representation.
 
+use std::mem;
+
 use either::Either;
 
 use hir_expand::{
+    hygiene::SyntaxContext,
     name::{name, AsName, Name},
     MacroDefId, MacroDefKind,
 };
+use mbe::ExpandResult;
 use ra_arena::Arena;
 use ra_syntax::{
     ast::{
-        self, ArgListOwner, ArrayExprKind, LiteralKind, LoopBodyOwner, ModuleItemOwner, NameOwner,
-        SlicePatComponents, TypeAscriptionOwner,
+        self, ArgListOwner, ArrayExprKind, AstChildren, LiteralKind, LoopBodyOwner,
+        ModuleItemOwner, NameOwner, SlicePatComponents, TypeAscriptionOwner,
     },
-    AstNode, AstPtr,
+    AstNode, AstPtr, SyntaxToken,
 };
+use rustc_hash::FxHashMap;
 use test_utils::tested_by;
 
-use super::{ExprSource, PatSource};
+use super::{ExprSource, LabelSource, PatSource};
 use crate::{
     adt::StructKind,
-    body::{Body, BodySourceMap, Expander, PatPtr, SyntheticSyntax},
+    body::{Body, BodyDiagnostic, BodySourceMap, Expander, PatPtr, SyntheticSyntax},
     builtin_type::{BuiltinFloat, BuiltinInt},
     db::DefDatabase,
+    diagnostics::MacroError,
     expr::{
-        dummy_expr_id, ArithOp, Array, BinaryOp, BindingAnnotation, CmpOp, Expr, ExprId, Literal,
-        LogicOp, MatchArm, Ordering, Pat, PatId, RecordFieldPat, RecordLitField, Statement,
+        dummy_expr_id, ArithOp, Array, BinaryOp, BindingAnnotation, CmpOp, Expr, ExprId, Label,
+        LabelId, Literal, LogicOp, MatchArm, Ordering, Pat, PatId, RecordFieldPat, RecordLitField,
+        Statement,
     },
     item_scope::BuiltinShadowMode,
     path::GenericArgs,
@@ -50,10 +57,14 @@ pub(super) fn lower(
         body: Body {
             exprs: Arena::default(),
             pats: Arena::default(),
+            labels: Arena::default(),
             params: Vec::new(),
             body_expr: dummy_expr_id(),
             item_scope: Default::default(),
+            binding_contexts: FxHashMap::default(),
         },
+        label_scopes: Vec::new(),
+        label_contexts: FxHashMap::default(),
     }
     .collect(params, body)
 }
@@ -65,6 +76,10 @@ struct ExprCollector<'a> {
 
     body: Body,
     source_map: BodySourceMap,
+    /// The labels of the loops and blocks we are currently in, innermost last.
+    label_scopes: Vec<LabelId>,
+    /// Syntax contexts of labels introduced by `macro_rules!` definitions.
+    label_contexts: FxHashMap<LabelId, SyntaxContext>,
 }
 
 impl ExprCollector<'_> {
@@ -84,6 +99,10 @@ impl ExprCollector<'_> {
                     },
                     Either::Right(ptr),
                 );
+                let ctx = self.expander.hygiene.syntax_context(&self_param.self_kw_token());
+                if ctx != SyntaxContext::Root {
+                    self.body.binding_contexts.insert(param_pat, ctx);
+                }
                 self.body.params.push(param_pat);
             }
 
@@ -121,7 +140,7 @@ impl ExprCollector<'_> {
         id
     }
     fn empty_block(&mut self) -> ExprId {
-        self.alloc_expr_desugared(Expr::Block { statements: Vec::new(), tail: None })
+        self.alloc_expr_desugared(Expr::Block { statements: Vec::new(), tail: None, label: None })
     }
     fn missing_expr(&mut self) -> ExprId {
         self.alloc_expr_desugared(Expr::Missing)
@@ -132,6 +151,48 @@ impl ExprCollector<'_> {
         id
     }
 
+    fn alloc_label(&mut self, label: ast::Label) -> Option<LabelId> {
+        let lifetime = label.lifetime_token()?;
+        let name = Name::new_lifetime(&lifetime);
+        let src: LabelSource = self.expander.to_source(AstPtr::new(&label));
+        let id = self.body.labels.alloc(Label { name });
+        let ctx = self.expander.hygiene.syntax_context(&lifetime);
+        if ctx != SyntaxContext::Root {
+            self.label_contexts.insert(id, ctx);
+        }
+        self.source_map.label_map.insert(src, id);
+        self.source_map.label_map_back.insert(id, src);
+        Some(id)
+    }
+
+    /// Lowers the body of a loop with the given label, so that `break` and
+    /// `continue` inside of it can refer to it.
+    fn collect_loop_body(
+        &mut self,
+        label: Option<LabelId>,
+        body: Option<ast::BlockExpr>,
+    ) -> ExprId {
+        let label_scopes_len = self.label_scopes.len();
+        self.label_scopes.extend(label);
+        let body = self.collect_block_opt(body);
+        self.label_scopes.truncate(label_scopes_len);
+        body
+    }
+
+    /// Resolves the label of a `break` or `continue` against the enclosing
+    /// loops and labeled blocks. Labels are hygienic, so a label written in a
+    /// `macro_rules!` definition can only be referred to from the same
+    /// expansion.
+    fn resolve_label(&self, lifetime: Option<SyntaxToken>) -> Option<LabelId> {
+        let lifetime = lifetime?;
+        let name = Name::new_lifetime(&lifetime);
+        let ctx = self.expander.hygiene.syntax_context(&lifetime);
+        self.label_scopes.iter().rev().copied().find(|&id| {
+            self.body[id].name == name
+                && self.label_contexts.get(&id).copied().unwrap_or_default() == ctx
+        })
+    }
+
     fn alloc_pat(&mut self, pat: Pat, ptr: PatPtr) -> PatId {
         let src = self.expander.to_source(ptr);
         let id = self.make_pat(pat, Ok(src));
@@ -190,13 +251,22 @@ impl ExprCollector<'_> {
                 let body = self.collect_block_opt(e.body());
                 self.alloc_expr(Expr::TryBlock { body }, syntax_ptr)
             }
-            ast::Expr::BlockExpr(e) => self.collect_block(e),
+            ast::Expr::BlockExpr(e) => {
+                if e.unsafe_token().is_some() {
+                    let body = self.collect_block(e);
+                    self.alloc_expr(Expr::Unsafe { body }, syntax_ptr)
+                } else {
+                    self.collect_block(e)
+                }
+            }
             ast::Expr::LoopExpr(e) => {
-                let body = self.collect_block_opt(e.loop_body());
-                self.alloc_expr(Expr::Loop { body }, syntax_ptr)
+                let label = e.label().and_then(|l| self.alloc_label(l));
+                let body = self.collect_loop_body(label, e.loop_body());
+                self.alloc_expr(Expr::Loop { body, label }, syntax_ptr)
             }
             ast::Expr::WhileExpr(e) => {
-                let body = self.collect_block_opt(e.loop_body());
+                let label = e.label().and_then(|l| self.alloc_label(l));
+                let body = self.collect_loop_body(label, e.loop_body());
 
                 let condition = match e.condition() {
                     None => self.missing_expr(),
@@ -208,25 +278,28 @@ impl ExprCollector<'_> {
                             let pat = self.collect_pat(pat);
                             let match_expr = self.collect_expr_opt(condition.expr());
                             let placeholder_pat = self.missing_pat();
-                            let break_ = self.alloc_expr_desugared(Expr::Break { expr: None });
+                            let break_ =
+                                self.alloc_expr_desugared(Expr::Break { expr: None, label });
                             let arms = vec![
                                 MatchArm { pat, expr: body, guard: None },
                                 MatchArm { pat: placeholder_pat, expr: break_, guard: None },
                             ];
                             let match_expr =
                                 self.alloc_expr_desugared(Expr::Match { expr: match_expr, arms });
-                            return self.alloc_expr(Expr::Loop { body: match_expr }, syntax_ptr);
+                            return self
+                                .alloc_expr(Expr::Loop { body: match_expr, label }, syntax_ptr);
                         }
                     },
                 };
 
-                self.alloc_expr(Expr::While { condition, body }, syntax_ptr)
+                self.alloc_expr(Expr::While { condition, body, label }, syntax_ptr)
             }
             ast::Expr::ForExpr(e) => {
+                let label = e.label().and_then(|l| self.alloc_label(l));
                 let iterable = self.collect_expr_opt(e.iterable());
                 let pat = self.collect_pat_opt(e.pat());
-                let body = self.collect_block_opt(e.loop_body());
-                self.alloc_expr(Expr::For { iterable, pat, body }, syntax_ptr)
+                let body = self.collect_loop_body(label, e.loop_body());
+                self.alloc_expr(Expr::For { iterable, pat, body, label }, syntax_ptr)
             }
             ast::Expr::CallExpr(e) => {
                 let callee = self.collect_expr_opt(e.expr());
@@ -278,13 +351,14 @@ impl ExprCollector<'_> {
                     .unwrap_or(Expr::Missing);
                 self.alloc_expr(path, syntax_ptr)
             }
-            ast::Expr::ContinueExpr(_e) => {
-                // FIXME: labels
-                self.alloc_expr(Expr::Continue, syntax_ptr)
+            ast::Expr::ContinueExpr(e) => {
+                let label = self.resolve_label(e.lifetime_token());
+                self.alloc_expr(Expr::Continue { label }, syntax_ptr)
             }
             ast::Expr::BreakExpr(e) => {
+                let label = self.resolve_label(e.lifetime_token());
                 let expr = e.expr().map(|e| self.collect_expr(e));
-                self.alloc_expr(Expr::Break { expr }, syntax_ptr)
+                self.alloc_expr(Expr::Break { expr, label }, syntax_ptr)
             }
             ast::Expr::ParenExpr(e) => {
                 let inner = self.collect_expr_opt(e.expr());
@@ -314,7 +388,7 @@ impl ExprCollector<'_> {
                             } else if let Some(nr) = field.name_ref() {
                                 // field shorthand
                                 self.alloc_expr_field_shorthand(
-                                    Expr::Path(Path::from_name_ref(&nr)),
+                                    Expr::Path(Path::from_name_ref(&nr, &self.expander.hygiene)),
                                     AstPtr::new(&field),
                                 )
                             } else {
@@ -380,7 +454,10 @@ impl ExprCollector<'_> {
                     }
                 }
                 let ret_type = e.ret_type().and_then(|r| r.type_ref()).map(TypeRef::from_ast);
+                // Labels of the enclosing function are not visible inside of closures.
+                let label_scopes = mem::take(&mut self.label_scopes);
                 let body = self.collect_expr_opt(e.body());
+                self.label_scopes = label_scopes;
                 self.alloc_expr(Expr::Lambda { args, arg_types, ret_type, body }, syntax_ptr)
             }
             ast::Expr::BinExpr(e) => {
@@ -417,26 +494,7 @@ impl ExprCollector<'_> {
                 }
             }
 
-            ast::Expr::Literal(e) => {
-                let lit = match e.kind() {
-                    LiteralKind::IntNumber { suffix } => {
-                        let known_name = suffix.and_then(|it| BuiltinInt::from_suffix(&it));
-
-                        Literal::Int(Default::default(), known_name)
-                    }
-                    LiteralKind::FloatNumber { suffix } => {
-                        let known_name = suffix.and_then(|it| BuiltinFloat::from_suffix(&it));
-
-                        Literal::Float(Default::default(), known_name)
-                    }
-                    LiteralKind::ByteString => Literal::ByteString(Default::default()),
-                    LiteralKind::String => Literal::String(Default::default()),
-                    LiteralKind::Byte => Literal::Int(Default::default(), Some(BuiltinInt::U8)),
-                    LiteralKind::Bool => Literal::Bool(Default::default()),
-                    LiteralKind::Char => Literal::Char(Default::default()),
-                };
-                self.alloc_expr(Expr::Literal(lit), syntax_ptr)
-            }
+            ast::Expr::Literal(e) => self.alloc_expr(Expr::Literal(lower_literal(&e)), syntax_ptr),
             ast::Expr::IndexExpr(e) => {
                 let base = self.collect_expr_opt(e.base());
                 let index = self.collect_expr_opt(e.index());
@@ -465,7 +523,21 @@ impl ExprCollector<'_> {
                     self.alloc_expr(Expr::Missing, syntax_ptr)
                 } else {
                     let macro_call = self.expander.to_source(AstPtr::new(&e));
-                    match self.expander.enter_expand(self.db, Some(&self.body.item_scope), e) {
+                    if let Some(path) = e.path().and_then(|path| self.expander.parse_path(path)) {
+                        self.body.item_scope.push_macro_call(path.mod_path().clone());
+                    }
+                    let ExpandResult(expansion, err) =
+                        self.expander.enter_expand(self.db, Some(&self.body.item_scope), e.clone());
+                    if let Some(err) = err {
+                        let token_range =
+                            err.token().and_then(|token| MacroError::token_range(&e, token));
+                        self.source_map.diagnostics.push(BodyDiagnostic::MacroError {
+                            node: macro_call,
+                            message: err.to_string(),
+                            token_range,
+                        });
+                    }
+                    match expansion {
                         Some((mark, expansion)) => {
                             self.source_map
                                 .expansions
@@ -499,6 +571,9 @@ impl ExprCollector<'_> {
             None => return self.alloc_expr(Expr::Missing, syntax_node_ptr),
         };
         self.collect_block_items(&block);
+        let label = expr.label().and_then(|l| self.alloc_label(l));
+        let label_scopes_len = self.label_scopes.len();
+        self.label_scopes.extend(label);
         let statements = block
             .statements()
             .map(|s| match s {
@@ -512,7 +587,8 @@ impl ExprCollector<'_> {
             })
             .collect();
         let tail = block.expr().map(|e| self.collect_expr(e));
-        self.alloc_expr(Expr::Block { statements, tail }, syntax_node_ptr)
+        self.label_scopes.truncate(label_scopes_len);
+        self.alloc_expr(Expr::Block { statements, tail, label }, syntax_node_ptr)
     }
 
     fn collect_block_items(&mut self, block: &ast::Block) {
@@ -624,8 +700,8 @@ impl ExprCollector<'_> {
             }
             ast::Pat::TupleStructPat(p) => {
                 let path = p.path().and_then(|path| self.expander.parse_path(path));
-                let args = p.args().map(|p| self.collect_pat(p)).collect();
-                Pat::TupleStruct { path, args }
+                let (args, ellipsis) = self.collect_tuple_pat(p.args());
+                Pat::TupleStruct { path, args, ellipsis }
             }
             ast::Pat::RefPat(p) => {
                 let pat = self.collect_pat_opt(p.pat());
@@ -642,8 +718,8 @@ impl ExprCollector<'_> {
             }
             ast::Pat::ParenPat(p) => return self.collect_pat_opt(p.pat()),
             ast::Pat::TuplePat(p) => {
-                let args = p.args().map(|p| self.collect_pat(p)).collect();
-                Pat::Tuple(args)
+                let (args, ellipsis) = self.collect_tuple_pat(p.args());
+                Pat::Tuple { args, ellipsis }
             }
             ast::Pat::PlaceholderPat(_) | ast::Pat::DotDotPat(_) => Pat::Wild,
             ast::Pat::RecordPat(p) => {
@@ -680,13 +756,31 @@ impl ExprCollector<'_> {
                 }
             }
 
+            ast::Pat::LiteralPat(lit) => match lit.literal() {
+                // The literal has no syntax of its own, so that its type is
+                // only recorded for the pattern.
+                Some(lit) => {
+                    Pat::Lit(self.alloc_expr_desugared(Expr::Literal(lower_literal(&lit))))
+                }
+                None => Pat::Missing,
+            },
+
             // FIXME: implement
             ast::Pat::BoxPat(_) => Pat::Missing,
-            ast::Pat::LiteralPat(_) => Pat::Missing,
             ast::Pat::RangePat(_) => Pat::Missing,
         };
         let ptr = AstPtr::new(&pat);
-        self.alloc_pat(pattern, Either::Left(ptr))
+        let id = self.alloc_pat(pattern, Either::Left(ptr));
+        if let ast::Pat::BindPat(bp) = &pat {
+            let token = bp.name().and_then(|name| name.syntax().first_token());
+            if let Some(token) = token.filter(|_| matches!(self.body[id], Pat::Bind { .. })) {
+                let ctx = self.expander.hygiene.syntax_context(&token);
+                if ctx != SyntaxContext::Root {
+                    self.body.binding_contexts.insert(id, ctx);
+                }
+            }
+        }
+        id
     }
 
     fn collect_pat_opt(&mut self, pat: Option<ast::Pat>) -> PatId {
@@ -696,6 +790,20 @@ impl ExprCollector<'_> {
             self.missing_pat()
         }
     }
+
+    /// Collects the subpatterns of a tuple or tuple struct pattern, returning
+    /// the position of the `..` rest pattern separately.
+    fn collect_tuple_pat(&mut self, args: AstChildren<ast::Pat>) -> (Vec<PatId>, Option<usize>) {
+        let mut ellipsis = None;
+        let mut pats = Vec::new();
+        for arg in args {
+            match arg {
+                ast::Pat::DotDotPat(_) if ellipsis.is_none() => ellipsis = Some(pats.len()),
+                _ => pats.push(self.collect_pat(arg)),
+            }
+        }
+        (pats, ellipsis)
+    }
 }
 
 impl From<ast::BinOp> for BinaryOp {
@@ -741,3 +849,23 @@ impl From<ast::BinOp> for BinaryOp {
         }
     }
 }
+
+fn lower_literal(e: &ast::Literal) -> Literal {
+    match e.kind() {
+        LiteralKind::IntNumber { suffix } => {
+            let known_name = suffix.and_then(|it| BuiltinInt::from_suffix(&it));
+
+            Literal::Int(e.int_value().unwrap_or_default(), known_name)
+        }
+        LiteralKind::FloatNumber { suffix } => {
+            let known_name = suffix.and_then(|it| BuiltinFloat::from_suffix(&it));
+
+            Literal::Float(Default::default(), known_name)
+        }
+        LiteralKind::ByteString => Literal::ByteString(Default::default()),
+        LiteralKind::String => Literal::String(Default::default()),
+        LiteralKind::Byte => Literal::Int(Default::default(), Some(BuiltinInt::U8)),
+        LiteralKind::Bool(val) => Literal::Bool(val),
+        LiteralKind::Char => Literal::Char(Default::default()),
+    }
+}
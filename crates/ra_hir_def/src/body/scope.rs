@@ -1,7 +1,7 @@
 //! Name resolution for expressions.
 use std::sync::Arc;
 
-use hir_expand::name::Name;
+use hir_expand::{hygiene::SyntaxContext, name::Name};
 use ra_arena::{Arena, Idx};
 use rustc_hash::FxHashMap;
 
@@ -24,6 +24,7 @@ pub struct ExprScopes {
 pub struct ScopeEntry {
     name: Name,
     pat: PatId,
+    syntax_context: SyntaxContext,
 }
 
 impl ScopeEntry {
@@ -31,6 +32,10 @@ impl ScopeEntry {
         &self.name
     }
 
+    pub fn syntax_context(&self) -> SyntaxContext {
+        self.syntax_context
+    }
+
     pub fn pat(&self) -> PatId {
         self.pat
     }
@@ -91,7 +96,8 @@ impl ExprScopes {
             Pat::Bind { name, .. } => {
                 // bind can have a sub pattern, but it's actually not allowed
                 // to bind to things in there
-                let entry = ScopeEntry { name: name.clone(), pat };
+                let syntax_context = body.binding_context(pat);
+                let entry = ScopeEntry { name: name.clone(), pat, syntax_context };
                 self.scopes[scope].entries.push(entry)
             }
             p => p.walk_child_pats(|pat| self.add_bindings(body, scope, pat)),
@@ -138,10 +144,10 @@ fn compute_block_scopes(
 fn compute_expr_scopes(expr: ExprId, body: &Body, scopes: &mut ExprScopes, scope: ScopeId) {
     scopes.set_scope(expr, scope);
     match &body[expr] {
-        Expr::Block { statements, tail } => {
+        Expr::Block { statements, tail, .. } => {
             compute_block_scopes(&statements, *tail, body, scopes, scope);
         }
-        Expr::For { iterable, pat, body: body_expr } => {
+        Expr::For { iterable, pat, body: body_expr, .. } => {
             compute_expr_scopes(*iterable, body, scopes, scope);
             let scope = scopes.new_scope(scope);
             scopes.add_bindings(body, scope, *pat);
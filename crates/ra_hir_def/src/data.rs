@@ -8,14 +8,15 @@ use hir_expand::{
 };
 use ra_prof::profile;
 use ra_syntax::ast::{
-    self, AstNode, ImplItem, ModuleItemOwner, NameOwner, TypeAscriptionOwner, VisibilityOwner,
+    self, AstNode, ImplItem, ModuleItemOwner, NameOwner, TypeAscriptionOwner, TypeBoundsOwner,
+    VisibilityOwner,
 };
 
 use crate::{
     db::DefDatabase,
     path::{path, GenericArgs, Path},
     src::HasSource,
-    type_ref::{Mutability, TypeBound, TypeRef},
+    type_ref::{type_bounds_from_ast, Mutability, TypeBound, TypeRef},
     visibility::RawVisibility,
     AssocContainerId, AssocItemId, ConstId, ConstLoc, Expander, FunctionId, FunctionLoc, HasModule,
     ImplId, Intern, Lookup, ModuleId, StaticId, TraitId, TypeAliasId, TypeAliasLoc,
@@ -26,9 +27,13 @@ pub struct FunctionData {
     pub name: Name,
     pub params: Vec<TypeRef>,
     pub ret_type: TypeRef,
+    /// For an `async fn`, `ret_type` is the desugared `impl Future<Output = T>`;
+    /// this is the `T` that the body evaluates to.
+    pub async_ret_type: Option<TypeRef>,
     /// True if the first param is `self`. This is relevant to decide whether this
     /// can be called as a method.
     pub has_self_param: bool,
+    pub is_unsafe: bool,
     pub visibility: RawVisibility,
 }
 
@@ -69,19 +74,29 @@ impl FunctionData {
             TypeRef::unit()
         };
 
-        let ret_type = if src.value.is_async() {
-            let future_impl = desugar_future_path(ret_type);
+        let (ret_type, async_ret_type) = if src.value.is_async() {
+            let future_impl = desugar_future_path(ret_type.clone());
             let ty_bound = TypeBound::Path(future_impl);
-            TypeRef::ImplTrait(vec![ty_bound])
+            (TypeRef::ImplTrait(vec![ty_bound]), Some(ret_type))
         } else {
-            ret_type
+            (ret_type, None)
         };
 
         let vis_default = RawVisibility::default_for_container(loc.container);
         let visibility =
             RawVisibility::from_ast_with_default(db, vis_default, src.map(|s| s.visibility()));
 
-        let sig = FunctionData { name, params, ret_type, has_self_param, visibility };
+        let is_unsafe = src.value.is_unsafe();
+
+        let sig = FunctionData {
+            name,
+            params,
+            ret_type,
+            async_ret_type,
+            has_self_param,
+            is_unsafe,
+            visibility,
+        };
         Arc::new(sig)
     }
 }
@@ -101,6 +116,8 @@ pub struct TypeAliasData {
     pub name: Name,
     pub type_ref: Option<TypeRef>,
     pub visibility: RawVisibility,
+    /// Bounds of an associated type, e.g. `Debug` in `type Item: Debug;`.
+    pub bounds: Vec<TypeBound>,
 }
 
 impl TypeAliasData {
@@ -115,7 +132,8 @@ impl TypeAliasData {
         let vis_default = RawVisibility::default_for_container(loc.container);
         let visibility =
             RawVisibility::from_ast_with_default(db, vis_default, node.map(|n| n.visibility()));
-        Arc::new(TypeAliasData { name, type_ref, visibility })
+        let bounds = type_bounds_from_ast(node.value.type_bound_list());
+        Arc::new(TypeAliasData { name, type_ref, visibility, bounds })
     }
 }
 
@@ -189,6 +207,13 @@ impl TraitData {
             _ => None,
         })
     }
+
+    pub fn method_by_name(&self, name: &Name) -> Option<FunctionId> {
+        self.items.iter().find_map(|(item_name, item)| match item {
+            AssocItemId::FunctionId(t) if item_name == name => Some(*t),
+            _ => None,
+        })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -242,11 +267,6 @@ impl ConstData {
         Arc::new(ConstData::new(db, vis_default, node))
     }
 
-    pub(crate) fn static_data_query(db: &dyn DefDatabase, konst: StaticId) -> Arc<ConstData> {
-        let node = konst.lookup(db).source(db);
-        Arc::new(ConstData::new(db, RawVisibility::private(), node))
-    }
-
     fn new<N: NameOwner + TypeAscriptionOwner + VisibilityOwner>(
         db: &dyn DefDatabase,
         vis_default: RawVisibility,
@@ -260,6 +280,29 @@ impl ConstData {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaticData {
+    pub name: Option<Name>,
+    pub type_ref: TypeRef,
+    pub visibility: RawVisibility,
+    pub mutable: bool,
+}
+
+impl StaticData {
+    pub(crate) fn static_data_query(db: &dyn DefDatabase, konst: StaticId) -> Arc<StaticData> {
+        let node = konst.lookup(db).source(db);
+        let name = node.value.name().map(|n| n.as_name());
+        let type_ref = TypeRef::from_ast_opt(node.value.ascribed_type());
+        let mutable = node.value.is_mut();
+        let visibility = RawVisibility::from_ast_with_default(
+            db,
+            RawVisibility::private(),
+            node.map(|n| n.visibility()),
+        );
+        Arc::new(StaticData { name, type_ref, visibility, mutable })
+    }
+}
+
 fn collect_impl_items_in_macros(
     db: &dyn DefDatabase,
     module_id: ModuleId,
@@ -290,7 +333,7 @@ fn collect_impl_items_in_macro(
         return Vec::new();
     }
 
-    if let Some((mark, items)) = expander.enter_expand(db, None, m) {
+    if let Some((mark, items)) = expander.enter_expand(db, None, m).0 {
         let items: InFile<ast::MacroItems> = expander.to_source(items);
         let mut res = collect_impl_items(
             db,
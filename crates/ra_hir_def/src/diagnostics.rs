@@ -2,9 +2,12 @@
 
 use std::any::Any;
 
-use hir_expand::diagnostics::Diagnostic;
+use hir_expand::{
+    db::AstDatabase,
+    diagnostics::{AstDiagnostic, Diagnostic},
+};
 use ra_db::RelativePathBuf;
-use ra_syntax::{ast, AstPtr, SyntaxNodePtr};
+use ra_syntax::{ast, AstNode, AstPtr, SyntaxNodePtr, TextRange};
 
 use hir_expand::{HirFileId, InFile};
 
@@ -26,3 +29,99 @@ impl Diagnostic for UnresolvedModule {
         self
     }
 }
+
+#[derive(Debug)]
+pub struct UnresolvedImport {
+    pub file: HirFileId,
+    pub node: AstPtr<ast::UseTree>,
+}
+
+impl Diagnostic for UnresolvedImport {
+    fn message(&self) -> String {
+        "unresolved import".to_string()
+    }
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.node.into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+#[derive(Debug)]
+pub struct UnusedImport {
+    pub file: HirFileId,
+    pub node: AstPtr<ast::UseTree>,
+}
+
+impl Diagnostic for UnusedImport {
+    fn message(&self) -> String {
+        "unused import".to_string()
+    }
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.node.into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+#[derive(Debug)]
+pub struct MacroError {
+    pub file: HirFileId,
+    pub node: SyntaxNodePtr,
+    pub message: String,
+    /// The range of the input token at which the expansion failed, if known.
+    pub token_range: Option<TextRange>,
+}
+
+impl MacroError {
+    /// Finds the range of `token`, an input token of a failed expansion of
+    /// `macro_call`.
+    pub(crate) fn token_range(
+        macro_call: &ast::MacroCall,
+        token: tt::TokenId,
+    ) -> Option<TextRange> {
+        let token_tree = macro_call.token_tree()?;
+        let (_, token_map) = mbe::ast_to_token_tree(&token_tree)?;
+        let range = match token_map.range_by_token(token)? {
+            mbe::TokenTextRange::Token(it) | mbe::TokenTextRange::Delimiter(it, _) => it,
+        };
+        Some(range + token_tree.syntax().text_range().start())
+    }
+}
+
+impl Diagnostic for MacroError {
+    fn message(&self) -> String {
+        self.message.clone()
+    }
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.node }
+    }
+    fn highlight_range(&self) -> TextRange {
+        self.token_range.unwrap_or_else(|| self.node.range())
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+impl AstDiagnostic for UnresolvedImport {
+    type AST = ast::UseTree;
+
+    fn ast(&self, db: &impl AstDatabase) -> Self::AST {
+        let root = db.parse_or_expand(self.file).unwrap();
+        let node = self.source().value.to_node(&root);
+        ast::UseTree::cast(node).unwrap()
+    }
+}
+
+impl AstDiagnostic for UnusedImport {
+    type AST = ast::UseTree;
+
+    fn ast(&self, db: &impl AstDatabase) -> Self::AST {
+        let root = db.parse_or_expand(self.file).unwrap();
+        let node = self.source().value.to_node(&root);
+        ast::UseTree::cast(node).unwrap()
+    }
+}
@@ -29,13 +29,21 @@ pub(crate) fn dummy_expr_id() -> ExprId {
 
 pub type PatId = Idx<Pat>;
 
+/// The label of a loop or block, like `'outer` in `'outer: loop {}`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Label {
+    pub name: Name,
+}
+
+pub type LabelId = Idx<Label>;
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Literal {
     String(String),
     ByteString(Vec<u8>),
     Char(char),
     Bool(bool),
-    Int(u64, Option<BuiltinInt>),
+    Int(u128, Option<BuiltinInt>),
     Float(u64, Option<BuiltinFloat>), // FIXME: f64 is not Eq
 }
 
@@ -52,18 +60,22 @@ pub enum Expr {
     Block {
         statements: Vec<Statement>,
         tail: Option<ExprId>,
+        label: Option<LabelId>,
     },
     Loop {
         body: ExprId,
+        label: Option<LabelId>,
     },
     While {
         condition: ExprId,
         body: ExprId,
+        label: Option<LabelId>,
     },
     For {
         iterable: ExprId,
         pat: PatId,
         body: ExprId,
+        label: Option<LabelId>,
     },
     Call {
         callee: ExprId,
@@ -79,9 +91,14 @@ pub enum Expr {
         expr: ExprId,
         arms: Vec<MatchArm>,
     },
-    Continue,
+    /// `label` is the label of the loop we continue, if it is named
+    /// explicitly and could be resolved.
+    Continue {
+        label: Option<LabelId>,
+    },
     Break {
         expr: Option<ExprId>,
+        label: Option<LabelId>,
     },
     Return {
         expr: Option<ExprId>,
@@ -104,6 +121,9 @@ pub enum Expr {
     TryBlock {
         body: ExprId,
     },
+    Unsafe {
+        body: ExprId,
+    },
     Cast {
         expr: ExprId,
         type_ref: TypeRef,
@@ -224,7 +244,7 @@ impl Expr {
                     f(*else_branch);
                 }
             }
-            Expr::Block { statements, tail } => {
+            Expr::Block { statements, tail, .. } => {
                 for stmt in statements {
                     match stmt {
                         Statement::Let { initializer, .. } => {
@@ -239,9 +259,9 @@ impl Expr {
                     f(*expr);
                 }
             }
-            Expr::TryBlock { body } => f(*body),
-            Expr::Loop { body } => f(*body),
-            Expr::While { condition, body } => {
+            Expr::TryBlock { body } | Expr::Unsafe { body } => f(*body),
+            Expr::Loop { body, .. } => f(*body),
+            Expr::While { condition, body, .. } => {
                 f(*condition);
                 f(*body);
             }
@@ -267,8 +287,8 @@ impl Expr {
                     f(arm.expr);
                 }
             }
-            Expr::Continue => {}
-            Expr::Break { expr } | Expr::Return { expr } => {
+            Expr::Continue { .. } => {}
+            Expr::Break { expr, .. } | Expr::Return { expr } => {
                 if let Some(expr) = expr {
                     f(*expr);
                 }
@@ -374,7 +394,11 @@ pub struct RecordFieldPat {
 pub enum Pat {
     Missing,
     Wild,
-    Tuple(Vec<PatId>),
+    Tuple {
+        args: Vec<PatId>,
+        /// Position of the `..` rest pattern, if any.
+        ellipsis: Option<usize>,
+    },
     Or(Vec<PatId>),
     Record {
         path: Option<Path>,
@@ -400,6 +424,8 @@ pub enum Pat {
     TupleStruct {
         path: Option<Path>,
         args: Vec<PatId>,
+        /// Position of the `..` rest pattern, if any.
+        ellipsis: Option<usize>,
     },
     Ref {
         pat: PatId,
@@ -414,7 +440,7 @@ impl Pat {
             Pat::Bind { subpat, .. } => {
                 subpat.iter().copied().for_each(f);
             }
-            Pat::Or(args) | Pat::Tuple(args) | Pat::TupleStruct { args, .. } => {
+            Pat::Or(args) | Pat::Tuple { args, .. } | Pat::TupleStruct { args, .. } => {
                 args.iter().copied().for_each(f);
             }
             Pat::Ref { pat, .. } => f(*pat),
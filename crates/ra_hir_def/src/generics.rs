@@ -12,7 +12,7 @@ use hir_expand::{
 use ra_arena::{map::ArenaMap, Arena};
 use ra_db::FileId;
 use ra_prof::profile;
-use ra_syntax::ast::{self, NameOwner, TypeBoundsOwner, TypeParamsOwner};
+use ra_syntax::ast::{self, NameOwner, TypeAscriptionOwner, TypeBoundsOwner, TypeParamsOwner};
 
 use crate::{
     child_by_source::ChildBySource,
@@ -22,7 +22,8 @@ use crate::{
     src::HasChildSource,
     src::HasSource,
     type_ref::{TypeBound, TypeRef},
-    AdtId, GenericDefId, LocalTypeParamId, Lookup, TypeParamId,
+    AdtId, ConstParamId, GenericDefId, LifetimeParamId, LocalConstParamId, LocalLifetimeParamId,
+    LocalTypeParamId, Lookup, TypeParamId,
 };
 
 /// Data about a generic parameter (to a function, struct, impl, ...).
@@ -33,6 +34,20 @@ pub struct TypeParamData {
     pub provenance: TypeParamProvenance,
 }
 
+/// Data about a lifetime parameter, like `'a` in `fn foo<'a>()`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LifetimeParamData {
+    /// The name includes the leading `'`.
+    pub name: Name,
+}
+
+/// Data about a const parameter, like `N` in `struct Foo<const N: usize>`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ConstParamData {
+    pub name: Name,
+    pub ty: TypeRef,
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum TypeParamProvenance {
     TypeParamList,
@@ -44,7 +59,8 @@ pub enum TypeParamProvenance {
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct GenericParams {
     pub types: Arena<TypeParamData>,
-    // lifetimes: Arena<LocalLifetimeParamId, LifetimeParamData>,
+    pub lifetimes: Arena<LifetimeParamData>,
+    pub consts: Arena<ConstParamData>,
     pub where_predicates: Vec<WherePredicate>,
 }
 
@@ -66,6 +82,15 @@ pub enum WherePredicateTarget {
 }
 
 type SourceMap = ArenaMap<LocalTypeParamId, Either<ast::TraitDef, ast::TypeParam>>;
+type LifetimeSourceMap = ArenaMap<LocalLifetimeParamId, ast::LifetimeParam>;
+type ConstSourceMap = ArenaMap<LocalConstParamId, ast::ConstParam>;
+
+#[derive(Default)]
+struct SourceMaps {
+    types: SourceMap,
+    lifetimes: LifetimeSourceMap,
+    consts: ConstSourceMap,
+}
 
 impl GenericParams {
     pub(crate) fn generic_params_query(
@@ -73,13 +98,18 @@ impl GenericParams {
         def: GenericDefId,
     ) -> Arc<GenericParams> {
         let _p = profile("generic_params_query");
-        let (params, _source_map) = GenericParams::new(db, def);
+        let (params, _source_maps) = GenericParams::new(db, def);
         Arc::new(params)
     }
 
-    fn new(db: &dyn DefDatabase, def: GenericDefId) -> (GenericParams, InFile<SourceMap>) {
-        let mut generics = GenericParams { types: Arena::default(), where_predicates: Vec::new() };
-        let mut sm = ArenaMap::default();
+    fn new(db: &dyn DefDatabase, def: GenericDefId) -> (GenericParams, InFile<SourceMaps>) {
+        let mut generics = GenericParams {
+            types: Arena::default(),
+            lifetimes: Arena::default(),
+            consts: Arena::default(),
+            where_predicates: Vec::new(),
+        };
+        let mut sm = SourceMaps::default();
         // FIXME: add `: Sized` bound for everything except for `Self` in traits
         let file_id = match def {
             GenericDefId::FunctionId(it) => {
@@ -116,7 +146,7 @@ impl GenericParams {
                     default: None,
                     provenance: TypeParamProvenance::TraitSelf,
                 });
-                sm.insert(self_param_id, Either::Left(src.value.clone()));
+                sm.types.insert(self_param_id, Either::Left(src.value.clone()));
                 // add super traits as bounds on Self
                 // i.e., trait Foo: Bar is equivalent to trait Foo where Self: Bar
                 let self_param = TypeRef::Path(name![Self].into());
@@ -145,7 +175,7 @@ impl GenericParams {
         (generics, InFile::new(file_id, sm))
     }
 
-    fn fill(&mut self, sm: &mut SourceMap, node: &dyn TypeParamsOwner) {
+    fn fill(&mut self, sm: &mut SourceMaps, node: &dyn TypeParamsOwner) {
         if let Some(params) = node.type_param_list() {
             self.fill_params(sm, params)
         }
@@ -162,7 +192,16 @@ impl GenericParams {
         }
     }
 
-    fn fill_params(&mut self, sm: &mut SourceMap, params: ast::TypeParamList) {
+    fn fill_params(&mut self, sm: &mut SourceMaps, params: ast::TypeParamList) {
+        for lifetime_param in params.lifetime_params() {
+            let lifetime = match lifetime_param.lifetime_token() {
+                Some(it) => it,
+                None => continue,
+            };
+            let param = LifetimeParamData { name: Name::new_lifetime(&lifetime) };
+            let param_id = self.lifetimes.alloc(param);
+            sm.lifetimes.insert(param_id, lifetime_param.clone());
+        }
         for type_param in params.type_params() {
             let name = type_param.name().map_or_else(Name::missing, |it| it.as_name());
             // FIXME: Use `Path::from_src`
@@ -173,11 +212,18 @@ impl GenericParams {
                 provenance: TypeParamProvenance::TypeParamList,
             };
             let param_id = self.types.alloc(param);
-            sm.insert(param_id, Either::Right(type_param.clone()));
+            sm.types.insert(param_id, Either::Right(type_param.clone()));
 
             let type_ref = TypeRef::Path(name.into());
             self.fill_bounds(&type_param, type_ref);
         }
+        for const_param in params.const_params() {
+            let name = const_param.name().map_or_else(Name::missing, |it| it.as_name());
+            let ty = TypeRef::from_ast_opt(const_param.ascribed_type());
+            let param = ConstParamData { name, ty };
+            let param_id = self.consts.alloc(param);
+            sm.consts.insert(param_id, const_param.clone());
+        }
     }
 
     fn fill_where_predicates(&mut self, where_clause: ast::WhereClause) {
@@ -228,6 +274,14 @@ impl GenericParams {
             .find_map(|(id, p)| if p.name.as_ref() == Some(name) { Some(id) } else { None })
     }
 
+    pub fn find_lifetime_by_name(&self, name: &Name) -> Option<LocalLifetimeParamId> {
+        self.lifetimes.iter().find_map(|(id, p)| if &p.name == name { Some(id) } else { None })
+    }
+
+    pub fn find_const_by_name(&self, name: &Name) -> Option<LocalConstParamId> {
+        self.consts.iter().find_map(|(id, p)| if &p.name == name { Some(id) } else { None })
+    }
+
     pub fn find_trait_self_param(&self) -> Option<LocalTypeParamId> {
         self.types.iter().find_map(|(id, p)| {
             if p.provenance == TypeParamProvenance::TraitSelf {
@@ -244,7 +298,19 @@ impl HasChildSource for GenericDefId {
     type Value = Either<ast::TraitDef, ast::TypeParam>;
     fn child_source(&self, db: &dyn DefDatabase) -> InFile<SourceMap> {
         let (_, sm) = GenericParams::new(db, *self);
-        sm
+        sm.map(|it| it.types)
+    }
+}
+
+impl GenericDefId {
+    pub fn lifetime_param_source(&self, db: &dyn DefDatabase) -> InFile<LifetimeSourceMap> {
+        let (_, sm) = GenericParams::new(db, *self);
+        sm.map(|it| it.lifetimes)
+    }
+
+    pub fn const_param_source(&self, db: &dyn DefDatabase) -> InFile<ConstSourceMap> {
+        let (_, sm) = GenericParams::new(db, *self);
+        sm.map(|it| it.consts)
     }
 }
 
@@ -259,6 +325,18 @@ impl ChildBySource for GenericDefId {
                 res[keys::TYPE_PARAM].insert(arena_map.with_value(type_param.clone()), id)
             }
         }
+        let arena_map = self.lifetime_param_source(db);
+        let arena_map = arena_map.as_ref();
+        for (local_id, lifetime_param) in arena_map.value.iter() {
+            let id = LifetimeParamId { parent: *self, local_id };
+            res[keys::LIFETIME_PARAM].insert(arena_map.with_value(lifetime_param.clone()), id)
+        }
+        let arena_map = self.const_param_source(db);
+        let arena_map = arena_map.as_ref();
+        for (local_id, const_param) in arena_map.value.iter() {
+            let id = ConstParamId { parent: *self, local_id };
+            res[keys::CONST_PARAM].insert(arena_map.with_value(const_param.clone()), id)
+        }
         res
     }
 }
@@ -0,0 +1,407 @@
+//! Tracks which imports are used.
+//!
+//! Name resolution happens in queries, so we can't record the imports it goes
+//! through as a side effect. Instead, `module_import_usage` collects the
+//! lookups into module scopes which resolving the paths of a module does: the
+//! paths in the signatures and bodies of its items, in its imports and in its
+//! macro calls and derives. Items and bodies produced by macro expansions are
+//! lowered like any other, so the paths in them are covered as well.
+//!
+//! Only the lookups which might go through an import are recorded: the first
+//! segment of a path, and every segment following a prefix which resolves to a
+//! module of the same crate. This errs on the side of considering imports
+//! used, e.g. a local shadowing an imported name still counts as a use of the
+//! import.
+
+use std::sync::Arc;
+
+use hir_expand::name::Name;
+use ra_prof::profile;
+use rustc_hash::FxHashSet;
+
+use crate::{
+    adt::VariantData,
+    body::Body,
+    db::DefDatabase,
+    expr::{Expr, Pat, Statement},
+    generics::WherePredicateTarget,
+    item_scope::{BuiltinShadowMode, ImportEntry, ItemScope},
+    nameres::CrateDefMap,
+    path::{GenericArg, GenericArgs, ModPath, Path, PathKind},
+    type_ref::{TypeBound, TypeRef},
+    visibility::Visibility,
+    AdtId, AssocItemId, AttrDefId, DefWithBodyId, GenericDefId, LocalModuleId, ModuleDefId,
+    ModuleId,
+};
+
+/// The names looked up in module scopes by the paths of a module.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportUsage {
+    lookups: FxHashSet<(LocalModuleId, Name)>,
+}
+
+impl ImportUsage {
+    pub(crate) fn module_import_usage_query(
+        db: &dyn DefDatabase,
+        module: ModuleId,
+    ) -> Arc<ImportUsage> {
+        let _p = profile("module_import_usage_query");
+        let def_map = db.crate_def_map(module.krate);
+        let mut collector =
+            Collector { db, def_map: &def_map, module: module.local_id, res: Default::default() };
+        let scope = &def_map[module.local_id].scope;
+        for import in scope.imports() {
+            // `use foo;` would only look itself up.
+            if import.path.kind == PathKind::Plain && import.path.segments.len() == 1 {
+                continue;
+            }
+            collector.record_mod_path(&import.path);
+        }
+        collector.collect_scope(scope);
+        Arc::new(collector.res)
+    }
+
+    fn is_looked_up(&self, module: LocalModuleId, name: &Name) -> bool {
+        self.lookups.contains(&(module, name.clone()))
+    }
+}
+
+/// The private imports of a module that aren't used by any path.
+#[derive(Debug, Default)]
+pub struct UnusedImports {
+    imports: Vec<ImportEntry>,
+    scopes: Vec<ModuleId>,
+}
+
+impl UnusedImports {
+    pub fn new(db: &dyn DefDatabase, module: ModuleId) -> UnusedImports {
+        let _p = profile("UnusedImports::new");
+        let def_map = db.crate_def_map(module.krate);
+        // Unexpanded proc macros might use any name.
+        if def_map.has_unexpanded_proc_macros() {
+            return UnusedImports::default();
+        }
+        let mut candidates: Vec<&ImportEntry> = def_map[module.local_id]
+            .scope
+            .imports()
+            .filter(|it| {
+                // Other imports might be used outside of this crate or by
+                // modules we don't look at. Imports produced by macros can't
+                // be removed by the user.
+                let file_id = it.use_tree.0.file_id;
+                it.visibility == Visibility::Module(module)
+                    && !it.is_glob
+                    && it.name.is_some()
+                    && !it.def.is_none()
+                    && file_id == file_id.original_file(db.upcast()).into()
+            })
+            .collect();
+        if candidates.is_empty() {
+            return UnusedImports::default();
+        }
+
+        // Private imports are visible in the module and its descendants, and
+        // the latter can refer to them with `super::` paths.
+        let mut modules = vec![module.local_id];
+        let mut idx = 0;
+        while idx < modules.len() {
+            modules.extend(def_map[modules[idx]].children.values().copied());
+            idx += 1;
+        }
+        let usages: Vec<Arc<ImportUsage>> = modules
+            .iter()
+            .map(|&local_id| db.module_import_usage(ModuleId { krate: module.krate, local_id }))
+            .collect();
+
+        // Descendants which glob import the module see the imports in their own
+        // scope.
+        let mut scopes = vec![module.local_id];
+        loop {
+            let new_scopes: Vec<LocalModuleId> = modules
+                .iter()
+                .copied()
+                .filter(|it| !scopes.contains(it))
+                .filter(|&it| {
+                    def_map[it].scope.imports().any(|import| {
+                        import.is_glob
+                            && match import.def.take_types() {
+                                Some(ModuleDefId::ModuleId(m)) => {
+                                    m.krate == module.krate && scopes.contains(&m.local_id)
+                                }
+                                _ => false,
+                            }
+                    })
+                })
+                .collect();
+            if new_scopes.is_empty() {
+                break;
+            }
+            scopes.extend(new_scopes);
+        }
+
+        candidates.retain(|import| {
+            let name = match &import.name {
+                Some(it) => it,
+                None => return false,
+            };
+            !scopes.iter().any(|&scope| usages.iter().any(|it| it.is_looked_up(scope, name)))
+        });
+        UnusedImports {
+            imports: candidates.into_iter().cloned().collect(),
+            scopes: scopes
+                .into_iter()
+                .map(|local_id| ModuleId { krate: module.krate, local_id })
+                .collect(),
+        }
+    }
+
+    pub fn imports(&self) -> &[ImportEntry] {
+        &self.imports
+    }
+
+    /// The modules whose scopes contain the imports. Besides paths, imported
+    /// traits are also used by method calls and associated item paths in these
+    /// modules, which we can only see after type inference.
+    pub fn scopes(&self) -> &[ModuleId] {
+        &self.scopes
+    }
+}
+
+struct Collector<'a> {
+    db: &'a dyn DefDatabase,
+    def_map: &'a CrateDefMap,
+    module: LocalModuleId,
+    res: ImportUsage,
+}
+
+impl Collector<'_> {
+    fn collect_scope(&mut self, scope: &ItemScope) {
+        for path in scope.macro_calls() {
+            self.record_mod_path(path);
+        }
+        for def in scope.declarations() {
+            self.collect_def(def);
+        }
+        for impl_id in scope.impls() {
+            let data = self.db.impl_data(impl_id);
+            self.collect_attrs(impl_id.into());
+            self.collect_generics(impl_id.into());
+            if let Some(target_trait) = &data.target_trait {
+                self.record_type_ref(target_trait);
+            }
+            self.record_type_ref(&data.target_type);
+            for &item in data.items.iter() {
+                self.collect_assoc_item(item);
+            }
+        }
+    }
+
+    fn collect_def(&mut self, def: ModuleDefId) {
+        let db = self.db;
+        match def {
+            ModuleDefId::FunctionId(it) => self.collect_assoc_item(it.into()),
+            ModuleDefId::ConstId(it) => self.collect_assoc_item(it.into()),
+            ModuleDefId::TypeAliasId(it) => self.collect_assoc_item(it.into()),
+            ModuleDefId::AdtId(it) => {
+                self.collect_attrs(it.into());
+                self.collect_generics(it.into());
+                match it {
+                    AdtId::StructId(it) => self.collect_variant(&db.struct_data(it).variant_data),
+                    AdtId::UnionId(it) => self.collect_variant(&db.union_data(it).variant_data),
+                    AdtId::EnumId(it) => {
+                        for (_, variant) in db.enum_data(it).variants.iter() {
+                            self.collect_variant(&variant.variant_data);
+                        }
+                    }
+                }
+            }
+            ModuleDefId::StaticId(it) => {
+                self.collect_attrs(it.into());
+                self.record_type_ref(&db.static_data(it).type_ref);
+                self.collect_body(it.into());
+            }
+            ModuleDefId::TraitId(it) => {
+                self.collect_attrs(it.into());
+                self.collect_generics(it.into());
+                for &(_, item) in db.trait_data(it).items.iter() {
+                    self.collect_assoc_item(item);
+                }
+            }
+            ModuleDefId::ModuleId(_)
+            | ModuleDefId::EnumVariantId(_)
+            | ModuleDefId::BuiltinType(_) => (),
+        }
+    }
+
+    fn collect_assoc_item(&mut self, item: AssocItemId) {
+        let db = self.db;
+        match item {
+            AssocItemId::FunctionId(it) => {
+                let data = db.function_data(it);
+                self.collect_attrs(it.into());
+                self.collect_generics(it.into());
+                for param in data.params.iter() {
+                    self.record_type_ref(param);
+                }
+                self.record_type_ref(&data.ret_type);
+                self.collect_body(it.into());
+            }
+            AssocItemId::ConstId(it) => {
+                self.collect_attrs(it.into());
+                self.record_type_ref(&db.const_data(it).type_ref);
+                self.collect_body(it.into());
+            }
+            AssocItemId::TypeAliasId(it) => {
+                self.collect_attrs(it.into());
+                self.collect_generics(it.into());
+                let data = db.type_alias_data(it);
+                if let Some(type_ref) = &data.type_ref {
+                    self.record_type_ref(type_ref);
+                }
+                for bound in data.bounds.iter() {
+                    if let TypeBound::Path(path) = bound {
+                        self.record_path(path);
+                    }
+                }
+            }
+        }
+    }
+
+    fn collect_variant(&mut self, variant_data: &VariantData) {
+        for (_, field) in variant_data.fields().iter() {
+            self.record_type_ref(&field.type_ref);
+        }
+    }
+
+    fn collect_attrs(&mut self, def: AttrDefId) {
+        for attr in self.db.attrs(def).iter() {
+            self.record_mod_path(&attr.path);
+        }
+    }
+
+    fn collect_generics(&mut self, def: GenericDefId) {
+        let params = self.db.generic_params(def);
+        for (_, param) in params.types.iter() {
+            if let Some(default) = &param.default {
+                self.record_type_ref(default);
+            }
+        }
+        for (_, param) in params.consts.iter() {
+            self.record_type_ref(&param.ty);
+        }
+        for pred in params.where_predicates.iter() {
+            if let WherePredicateTarget::TypeRef(type_ref) = &pred.target {
+                self.record_type_ref(type_ref);
+            }
+            if let TypeBound::Path(path) = &pred.bound {
+                self.record_path(path);
+            }
+        }
+    }
+
+    fn collect_body(&mut self, def: DefWithBodyId) {
+        let body: Arc<Body> = self.db.body(def);
+        for (_, expr) in body.exprs.iter() {
+            match expr {
+                Expr::Path(path) => self.record_path(path),
+                Expr::RecordLit { path: Some(path), .. } => self.record_path(path),
+                Expr::MethodCall { generic_args: Some(generic_args), .. } => {
+                    self.record_generic_args(generic_args)
+                }
+                Expr::Cast { type_ref, .. } => self.record_type_ref(type_ref),
+                Expr::Lambda { arg_types, ret_type, .. } => {
+                    for type_ref in arg_types.iter().chain(Some(ret_type)).flatten() {
+                        self.record_type_ref(type_ref);
+                    }
+                }
+                Expr::Block { statements, .. } => {
+                    for statement in statements {
+                        if let Statement::Let { type_ref: Some(type_ref), .. } = statement {
+                            self.record_type_ref(type_ref);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        for (_, pat) in body.pats.iter() {
+            match pat {
+                Pat::Path(path)
+                | Pat::Record { path: Some(path), .. }
+                | Pat::TupleStruct { path: Some(path), .. } => self.record_path(path),
+                _ => (),
+            }
+        }
+        // Items and macro calls in blocks.
+        self.collect_scope(&body.item_scope);
+    }
+
+    fn record_type_ref(&mut self, type_ref: &TypeRef) {
+        type_ref.walk(&mut |type_ref| match type_ref {
+            TypeRef::Path(path) => self.record_mod_path(path.mod_path()),
+            TypeRef::ImplTrait(bounds) | TypeRef::DynTrait(bounds) => {
+                for bound in bounds {
+                    if let TypeBound::Path(path) = bound {
+                        self.record_mod_path(path.mod_path());
+                    }
+                }
+            }
+            _ => (),
+        });
+    }
+
+    fn record_path(&mut self, path: &Path) {
+        if let Some(type_anchor) = path.type_anchor() {
+            self.record_type_ref(type_anchor);
+        }
+        self.record_mod_path(path.mod_path());
+        for segment in path.segments().iter() {
+            if let Some(generic_args) = segment.args_and_bindings {
+                self.record_generic_args(generic_args);
+            }
+        }
+    }
+
+    fn record_generic_args(&mut self, generic_args: &GenericArgs) {
+        for arg in generic_args.args.iter() {
+            if let GenericArg::Type(type_ref) = arg {
+                self.record_type_ref(type_ref);
+            }
+        }
+        for (_, type_ref) in generic_args.bindings.iter() {
+            self.record_type_ref(type_ref);
+        }
+    }
+
+    fn record_mod_path(&mut self, path: &ModPath) {
+        let mut module = match path.kind {
+            PathKind::Plain => self.module,
+            PathKind::Super(n) => {
+                let mut module = self.module;
+                for _ in 0..n {
+                    module = match self.def_map[module].parent {
+                        Some(it) => it,
+                        None => return,
+                    };
+                }
+                module
+            }
+            PathKind::Crate => self.def_map.root,
+            PathKind::Abs | PathKind::DollarCrate(_) => return,
+        };
+        for (idx, name) in path.segments.iter().enumerate() {
+            self.res.lookups.insert((module, name.clone()));
+            if idx + 1 == path.segments.len() {
+                break;
+            }
+            let prefix =
+                ModPath { kind: path.kind.clone(), segments: path.segments[..=idx].to_vec() };
+            let (res, _) =
+                self.def_map.resolve_path(self.db, self.module, &prefix, BuiltinShadowMode::Module);
+            module = match res.take_types() {
+                Some(ModuleDefId::ModuleId(it)) if it.krate == self.def_map.krate => it.local_id,
+                _ => return,
+            };
+        }
+    }
+}
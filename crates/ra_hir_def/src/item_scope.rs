@@ -1,13 +1,14 @@
 //! Describes items defined or visible (ie, imported) in a certain scope.
 //! This is shared between modules and blocks.
 
-use hir_expand::name::Name;
+use hir_expand::{name::Name, InFile};
 use once_cell::sync::Lazy;
+use ra_syntax::ast;
 use rustc_hash::FxHashMap;
 
 use crate::{
-    per_ns::PerNs, visibility::Visibility, AdtId, BuiltinType, ImplId, MacroDefId, ModuleDefId,
-    TraitId,
+    db::DefDatabase, nameres::use_tree_source, path::ModPath, per_ns::PerNs,
+    visibility::Visibility, AdtId, AstId, BuiltinType, ImplId, MacroDefId, ModuleDefId, TraitId,
 };
 
 #[derive(Debug, Default, PartialEq, Eq)]
@@ -28,6 +29,26 @@ pub struct ItemScope {
     // FIXME: Macro shadowing in one module is not properly handled. Non-item place macros will
     // be all resolved to the last one defined if shadowing happens.
     legacy_macros: FxHashMap<Name, MacroDefId>,
+    /// Resolved imports, by their use tree.
+    imports: FxHashMap<(AstId<ast::UseItem>, usize), ImportEntry>,
+    /// Paths of the macro calls and derives in this scope, used to tell
+    /// whether an import is used.
+    macro_calls: Vec<ModPath>,
+}
+
+/// An import, together with what it brought into scope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportEntry {
+    /// The `use` item and the index of the use tree in it, see
+    /// `raw::ImportData::use_tree`.
+    pub use_tree: (AstId<ast::UseItem>, usize),
+    pub path: ModPath,
+    /// The name the import is bound to, `None` for glob imports and for
+    /// `use Trait as _`.
+    pub name: Option<Name>,
+    pub is_glob: bool,
+    pub def: PerNs,
+    pub visibility: Visibility,
 }
 
 pub(crate) static BUILTIN_SCOPE: Lazy<FxHashMap<Name, PerNs>> = Lazy::new(|| {
@@ -37,6 +58,13 @@ pub(crate) static BUILTIN_SCOPE: Lazy<FxHashMap<Name, PerNs>> = Lazy::new(|| {
         .collect()
 });
 
+impl ImportEntry {
+    pub fn source(&self, db: &dyn DefDatabase) -> Option<InFile<ast::UseTree>> {
+        let (use_item, index) = self.use_tree;
+        use_tree_source(db, use_item, index)
+    }
+}
+
 /// Shadow mode for builtin type which can be shadowed by module.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub(crate) enum BuiltinShadowMode {
@@ -68,6 +96,10 @@ impl ItemScope {
         self.impls.iter().copied()
     }
 
+    pub fn imports(&self) -> impl Iterator<Item = &ImportEntry> + '_ {
+        self.imports.values()
+    }
+
     pub fn visibility_of(&self, def: ModuleDefId) -> Option<Visibility> {
         self.name_of(ItemInNs::Types(def))
             .or_else(|| self.name_of(ItemInNs::Values(def)))
@@ -145,6 +177,19 @@ impl ItemScope {
         self.visible.iter().map(|(name, res)| (name.clone(), *res))
     }
 
+    pub(crate) fn push_import(&mut self, import: ImportEntry) {
+        // Imports are recorded again once they're resolved in more namespaces.
+        self.imports.insert(import.use_tree, import);
+    }
+
+    pub(crate) fn macro_calls(&self) -> impl Iterator<Item = &ModPath> + '_ {
+        self.macro_calls.iter()
+    }
+
+    pub(crate) fn push_macro_call(&mut self, path: ModPath) {
+        self.macro_calls.push(path);
+    }
+
     pub(crate) fn collect_legacy_macros(&self) -> FxHashMap<Name, MacroDefId> {
         self.legacy_macros.clone()
     }
@@ -57,7 +57,7 @@ mod tests;
 
 use std::sync::Arc;
 
-use hir_expand::{diagnostics::DiagnosticSink, name::Name, InFile};
+use hir_expand::{diagnostics::DiagnosticSink, hygiene::Hygiene, name::Name, InFile};
 use ra_arena::Arena;
 use ra_db::{CrateId, Edition, FileId};
 use ra_prof::profile;
@@ -88,6 +88,8 @@ pub struct CrateDefMap {
 
     edition: Edition,
     diagnostics: Vec<DefDiagnostic>,
+    /// Whether the crate uses proc macros which we couldn't expand.
+    has_unexpanded_proc_macros: bool,
 }
 
 impl std::ops::Index<LocalModuleId> for CrateDefMap {
@@ -198,6 +200,7 @@ impl CrateDefMap {
                 root,
                 modules,
                 diagnostics: Vec::new(),
+                has_unexpanded_proc_macros: false,
             }
         };
         let def_map = collector::collect_defs(db, def_map);
@@ -210,7 +213,17 @@ impl CrateDefMap {
         module: LocalModuleId,
         sink: &mut DiagnosticSink,
     ) {
-        self.diagnostics.iter().for_each(|it| it.add_to(db, module, sink))
+        self.diagnostics
+            .iter()
+            .filter(|it| !(it.is_unresolved_name() && self.has_unexpanded_proc_macros))
+            .for_each(|it| it.add_to(db, module, sink))
+    }
+
+    /// Whether the crate uses proc macros which we couldn't expand. As these
+    /// might define anything, unresolved names in such a crate are not
+    /// necessarily errors.
+    pub fn has_unexpanded_proc_macros(&self) -> bool {
+        self.has_unexpanded_proc_macros
     }
 
     pub fn modules_for_file(&self, file_id: FileId) -> impl Iterator<Item = LocalModuleId> + '_ {
@@ -288,6 +301,29 @@ impl ModuleData {
     }
 }
 
+/// Finds the use tree with the given index in `use_item`, see
+/// `raw::ImportData::use_tree`.
+pub(crate) fn use_tree_source(
+    db: &dyn DefDatabase,
+    use_item: AstId<ast::UseItem>,
+    index: usize,
+) -> Option<InFile<ast::UseTree>> {
+    let hygiene = Hygiene::new(db.upcast(), use_item.file_id);
+    let mut use_tree = None;
+    let mut current = 0;
+    ModPath::expand_use_item(
+        InFile::new(use_item.file_id, use_item.to_node(db.upcast())),
+        &hygiene,
+        |_path, tree, _is_glob, _alias| {
+            if current == index {
+                use_tree = Some(tree.clone());
+            }
+            current += 1;
+        },
+    );
+    use_tree.map(|it| InFile::new(use_item.file_id, it))
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ModuleSource {
     SourceFile(ast::SourceFile),
@@ -299,7 +335,12 @@ mod diagnostics {
     use ra_db::RelativePathBuf;
     use ra_syntax::{ast, AstPtr};
 
-    use crate::{db::DefDatabase, diagnostics::UnresolvedModule, nameres::LocalModuleId, AstId};
+    use crate::{
+        db::DefDatabase,
+        diagnostics::{MacroError, UnresolvedImport, UnresolvedModule},
+        nameres::{use_tree_source, LocalModuleId},
+        AstId,
+    };
 
     #[derive(Debug, PartialEq, Eq)]
     pub(super) enum DefDiagnostic {
@@ -308,9 +349,29 @@ mod diagnostics {
             declaration: AstId<ast::Module>,
             candidate: RelativePathBuf,
         },
+        UnresolvedImport {
+            module: LocalModuleId,
+            use_item: AstId<ast::UseItem>,
+            /// Index of the use tree, see `raw::ImportData::use_tree`.
+            index: usize,
+        },
+        MacroError {
+            module: LocalModuleId,
+            ast: AstId<ast::MacroCall>,
+            message: String,
+            /// The input token at which the expansion failed, if known.
+            token: Option<tt::TokenId>,
+        },
     }
 
     impl DefDiagnostic {
+        pub(super) fn is_unresolved_name(&self) -> bool {
+            match self {
+                DefDiagnostic::UnresolvedImport { .. } => true,
+                DefDiagnostic::UnresolvedModule { .. } | DefDiagnostic::MacroError { .. } => false,
+            }
+        }
+
         pub(super) fn add_to(
             &self,
             db: &dyn DefDatabase,
@@ -329,6 +390,27 @@ mod diagnostics {
                         candidate: candidate.clone(),
                     })
                 }
+                DefDiagnostic::UnresolvedImport { module, use_item, index } => {
+                    if *module != target_module {
+                        return;
+                    }
+                    if let Some(use_tree) = use_tree_source(db, *use_item, *index) {
+                        let node = AstPtr::new(&use_tree.value);
+                        sink.push(UnresolvedImport { file: use_tree.file_id, node })
+                    }
+                }
+                DefDiagnostic::MacroError { module, ast, message, token } => {
+                    if *module != target_module {
+                        return;
+                    }
+                    let node = ast.to_node(db.upcast());
+                    sink.push(MacroError {
+                        file: ast.file_id,
+                        node: AstPtr::new(&node).into(),
+                        message: message.clone(),
+                        token_range: token.and_then(|it| MacroError::token_range(&node, it)),
+                    })
+                }
             }
         }
     }
@@ -8,7 +8,7 @@ use hir_expand::{
     builtin_macro::find_builtin_macro,
     name::{name, AsName, Name},
     proc_macro::ProcMacroExpander,
-    HirFileId, MacroCallId, MacroDefId, MacroDefKind,
+    HirFileId, MacroCallId, MacroCallKind, MacroDefId, MacroDefKind,
 };
 use ra_cfg::CfgOptions;
 use ra_db::{CrateId, FileId, ProcMacroId};
@@ -19,6 +19,7 @@ use test_utils::tested_by;
 use crate::{
     attr::Attrs,
     db::DefDatabase,
+    item_scope::ImportEntry,
     nameres::{
         diagnostics::DefDiagnostic, mod_resolution::ModDir, path_resolution::ReachedFixedPoint,
         raw, BuiltinShadowMode, CrateDefMap, ModuleData, ModuleOrigin, ResolveMode,
@@ -188,7 +189,40 @@ impl DefCollector<'_> {
         let unresolved_imports = std::mem::replace(&mut self.unresolved_imports, Vec::new());
         // show unresolved imports in completion, etc
         for directive in unresolved_imports {
-            self.record_resolved_import(&directive)
+            self.record_resolved_import(&directive);
+            if let Some((use_item, index)) = directive.import.use_tree {
+                self.def_map.diagnostics.push(DefDiagnostic::UnresolvedImport {
+                    module: directive.module_id,
+                    use_item,
+                    index,
+                });
+            }
+        }
+
+        // Eager macros are expanded while resolving them, so the ones that failed
+        // to expand are still here; expand them once more to report the errors.
+        let unexpanded_macros = std::mem::replace(&mut self.unexpanded_macros, Vec::new());
+        for directive in unexpanded_macros {
+            let mut errors = Vec::new();
+            directive.ast_id.as_call_id_with_errors(
+                self.db,
+                |path| self.resolve_macro_path(directive.module_id, &path),
+                &mut |err| errors.push(err),
+            );
+            for err in errors {
+                self.def_map.diagnostics.push(DefDiagnostic::MacroError {
+                    module: directive.module_id,
+                    ast: directive.ast_id.ast_id,
+                    message: err.to_string(),
+                    token: err.token(),
+                });
+            }
+        }
+
+        // Derives which are not built in come from proc macros, so the ones we
+        // couldn't resolve might define items we don't know about.
+        if !self.unexpanded_attribute_macros.is_empty() {
+            self.def_map.has_unexpanded_proc_macros = true;
         }
 
         // Record proc-macros
@@ -406,6 +440,23 @@ impl DefCollector<'_> {
             .resolve_visibility(self.db, module_id, &directive.import.visibility)
             .unwrap_or(Visibility::Public);
 
+        if let Some(use_tree) = import.use_tree {
+            let name = match &import.alias {
+                _ if import.is_glob => None,
+                Some(ImportAlias::Alias(name)) => Some(name.clone()),
+                Some(ImportAlias::Underscore) => None,
+                None => import.path.segments.last().cloned(),
+            };
+            self.def_map.modules[module_id].scope.push_import(ImportEntry {
+                use_tree,
+                path: import.path.clone(),
+                name,
+                is_glob: import.is_glob,
+                def,
+                visibility: vis,
+            });
+        }
+
         if import.is_glob {
             log::debug!("glob import: {:?}", import);
             match def.take_types() {
@@ -562,16 +613,10 @@ impl DefCollector<'_> {
                 return false;
             }
 
-            if let Some(call_id) = directive.ast_id.as_call_id(self.db, |path| {
-                let resolved_res = self.def_map.resolve_path_fp_with_macro(
-                    self.db,
-                    ResolveMode::Other,
-                    directive.module_id,
-                    &path,
-                    BuiltinShadowMode::Module,
-                );
-                resolved_res.resolved_def.take_macros()
-            }) {
+            if let Some(call_id) = directive
+                .ast_id
+                .as_call_id(self.db, |path| self.resolve_macro_path(directive.module_id, &path))
+            {
                 resolved.push((directive.module_id, call_id, directive.depth));
                 res = ReachedFixedPoint::No;
                 return false;
@@ -606,6 +651,17 @@ impl DefCollector<'_> {
         res
     }
 
+    fn resolve_macro_path(&self, module_id: LocalModuleId, path: &ModPath) -> Option<MacroDefId> {
+        let resolved_res = self.def_map.resolve_path_fp_with_macro(
+            self.db,
+            ResolveMode::Other,
+            module_id,
+            path,
+            BuiltinShadowMode::Module,
+        );
+        resolved_res.resolved_def.take_macros()
+    }
+
     fn resolve_attribute_macro(
         &self,
         directive: &DeriveDirective,
@@ -637,6 +693,21 @@ impl DefCollector<'_> {
         depth: usize,
     ) {
         let file_id: HirFileId = macro_call_id.as_file();
+        if self.is_proc_macro_call(macro_call_id) && self.db.parse_or_expand(file_id).is_none() {
+            self.def_map.has_unexpanded_proc_macros = true;
+        }
+        if let MacroCallId::LazyMacro(id) = macro_call_id {
+            if let MacroCallKind::FnLike(ast) = self.db.lookup_intern_macro(id).kind {
+                if let Some(err) = self.db.macro_expand_error(macro_call_id) {
+                    self.def_map.diagnostics.push(DefDiagnostic::MacroError {
+                        module: module_id,
+                        ast,
+                        message: err.to_string(),
+                        token: err.token(),
+                    });
+                }
+            }
+        }
         let raw_items = self.db.raw_items(file_id);
         let mod_dir = self.mod_dirs[&module_id].clone();
         ModCollector {
@@ -650,6 +721,16 @@ impl DefCollector<'_> {
         .collect(raw_items.items());
     }
 
+    fn is_proc_macro_call(&self, macro_call_id: MacroCallId) -> bool {
+        match macro_call_id {
+            MacroCallId::LazyMacro(id) => {
+                let loc = self.db.lookup_intern_macro(id);
+                matches!(loc.def.kind, MacroDefKind::CustomDerive(_))
+            }
+            MacroCallId::EagerMacro(_) => false,
+        }
+    }
+
     fn finish(self) -> CrateDefMap {
         self.def_map
     }
@@ -898,6 +979,9 @@ impl ModCollector<'_, '_> {
                     _ => continue, // anything else would be an error (which we currently ignore)
                 };
                 let path = ModPath::from_tt_ident(ident);
+                self.def_collector.def_map.modules[self.module_id]
+                    .scope
+                    .push_macro_call(path.clone());
 
                 let ast_id = AstIdWithPath::new(self.file_id, def.kind.ast_id(), path);
                 self.def_collector
@@ -939,6 +1023,8 @@ impl ModCollector<'_, '_> {
             return;
         }
 
+        self.def_collector.def_map.modules[self.module_id].scope.push_macro_call(mac.path.clone());
+
         // Case 2: try to resolve in legacy scope and expand macro_rules
         if let Some(macro_call_id) = ast_id.as_call_id(self.def_collector.db, |path| {
             path.as_ident().and_then(|name| {
@@ -1030,6 +1116,7 @@ mod tests {
                 root,
                 modules,
                 diagnostics: Vec::new(),
+                has_unexpanded_proc_macros: false,
             }
         };
         do_collect_defs(&db, def_map)
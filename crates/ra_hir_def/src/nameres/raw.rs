@@ -25,7 +25,7 @@ use crate::{
     db::DefDatabase,
     path::{ImportAlias, ModPath},
     visibility::RawVisibility,
-    FileAstId, HirFileId, InFile,
+    AstId, FileAstId, HirFileId, InFile,
 };
 
 /// `RawItems` is a set of top-level items in a file (except for impls).
@@ -144,6 +144,10 @@ pub struct ImportData {
     pub(super) is_extern_crate: bool,
     pub(super) is_macro_use: bool,
     pub(super) visibility: RawVisibility,
+    /// The `use` item this import comes from, together with the index of its
+    /// use tree in the order of `ModPath::expand_use_item`. `None` for
+    /// `extern crate`.
+    pub(super) use_tree: Option<(AstId<ast::UseItem>, usize)>,
 }
 
 // type Def = Idx<DefData>;
@@ -309,6 +313,7 @@ impl RawItemsCollector {
         let is_prelude = use_item.has_atom_attr("prelude_import");
         let attrs = self.parse_attrs(&use_item);
         let visibility = RawVisibility::from_ast_with_hygiene(use_item.visibility(), &self.hygiene);
+        let ast_id = InFile::new(self.file_id, self.source_ast_id_map.ast_id(&use_item));
 
         let mut buf = Vec::new();
         ModPath::expand_use_item(
@@ -323,6 +328,7 @@ impl RawItemsCollector {
                     is_extern_crate: false,
                     is_macro_use: false,
                     visibility: visibility.clone(),
+                    use_tree: Some((ast_id, buf.len())),
                 };
                 buf.push(import_data);
             },
@@ -355,6 +361,7 @@ impl RawItemsCollector {
                 is_extern_crate: true,
                 is_macro_use,
                 visibility,
+                use_tree: None,
             };
             self.push_import(current_module, attrs, import_data);
         }
@@ -604,3 +604,79 @@ mod b {
     ⋮T: t v
 "###);
 }
+
+#[test]
+fn unresolved_import_diagnostics() {
+    let crate_def_map = compute_crate_def_map(
+        r"
+        //- /lib.rs
+        use foo::Bar;
+        use crate::baz::{Qux, Quux};
+        mod baz { pub struct Quux; }
+        ",
+    );
+
+    insta::assert_debug_snapshot!(
+        crate_def_map.diagnostics,
+        @r###"
+    [
+        UnresolvedImport {
+            module: Idx::<ModuleData>(0),
+            use_item: InFile {
+                file_id: HirFileId(
+                    FileId(
+                        FileId(
+                            0,
+                        ),
+                    ),
+                ),
+                value: FileAstId {
+                    raw: Idx::<SyntaxNodePtr>(0),
+                    _ty: PhantomData,
+                },
+            },
+            index: 0,
+        },
+        UnresolvedImport {
+            module: Idx::<ModuleData>(0),
+            use_item: InFile {
+                file_id: HirFileId(
+                    FileId(
+                        FileId(
+                            0,
+                        ),
+                    ),
+                ),
+                value: FileAstId {
+                    raw: Idx::<SyntaxNodePtr>(1),
+                    _ty: PhantomData,
+                },
+            },
+            index: 0,
+        },
+    ]
+    "###
+    );
+}
+
+#[test]
+fn unresolved_derive_is_an_unexpanded_proc_macro() {
+    let crate_def_map = compute_crate_def_map(
+        r"
+        //- /lib.rs
+        use foo::Bar;
+        #[derive(Baz)]
+        struct S;
+        ",
+    );
+    assert!(crate_def_map.has_unexpanded_proc_macros());
+
+    let crate_def_map = compute_crate_def_map(
+        r"
+        //- /lib.rs
+        use foo::Bar;
+        struct S;
+        ",
+    );
+    assert!(!crate_def_map.has_unexpanded_proc_macros());
+}
@@ -14,6 +14,7 @@ fn check_def_map_is_not_recomputed(ra_fixture_initial: &str, ra_fixture_change:
         assert!(format!("{:?}", events).contains("crate_def_map"), "{:#?}", events)
     }
     db.set_file_text(pos.file_id, Arc::new(ra_fixture_change.to_string()));
+    db.set_file_reparse(pos.file_id, None);
 
     {
         let events = db.log_executed(|| {
@@ -121,6 +122,7 @@ fn typing_inside_a_macro_should_not_invalidate_def_map() {
         assert!(format!("{:?}", events).contains("crate_def_map"), "{:#?}", events)
     }
     db.set_file_text(pos.file_id, Arc::new("m!(Y);".to_string()));
+    db.set_file_reparse(pos.file_id, None);
 
     {
         let events = db.log_executed(|| {
@@ -8,13 +8,16 @@ use std::{
 };
 
 use hir_expand::{
-    hygiene::Hygiene,
+    hygiene::{Hygiene, SyntaxContext},
     name::{AsName, Name},
 };
 use ra_db::CrateId;
-use ra_syntax::ast;
+use ra_syntax::{ast, AstNode};
 
-use crate::{type_ref::TypeRef, InFile};
+use crate::{
+    type_ref::{ConstRef, TypeRef},
+    InFile,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ModPath {
@@ -97,6 +100,9 @@ pub struct Path {
     mod_path: ModPath,
     /// Invariant: the same len as `self.mod_path.segments`
     generic_args: Vec<Option<Arc<GenericArgs>>>,
+    /// The syntax context of the first segment, used to resolve local
+    /// variables hygienically.
+    syntax_context: SyntaxContext,
 }
 
 /// Generic arguments to a path segment (e.g. the `i32` in `Option<i32>`). This
@@ -118,6 +124,10 @@ pub struct GenericArgs {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum GenericArg {
     Type(TypeRef),
+    /// A const argument like the `3` in `Foo<3>`. Note that a const parameter
+    /// used as an argument, like in `Foo<N>`, is parsed as a type argument;
+    /// it's only distinguished when lowering the path.
+    Const(ConstRef),
     // or lifetime...
 }
 
@@ -135,8 +145,17 @@ impl Path {
     }
 
     /// Converts an `ast::NameRef` into a single-identifier `Path`.
-    pub(crate) fn from_name_ref(name_ref: &ast::NameRef) -> Path {
-        Path { type_anchor: None, mod_path: name_ref.as_name().into(), generic_args: vec![None] }
+    pub(crate) fn from_name_ref(name_ref: &ast::NameRef, hygiene: &Hygiene) -> Path {
+        let syntax_context = match name_ref.syntax().first_token() {
+            Some(token) => hygiene.syntax_context(&token),
+            None => SyntaxContext::Root,
+        };
+        Path {
+            type_anchor: None,
+            mod_path: name_ref.as_name().into(),
+            generic_args: vec![None],
+            syntax_context,
+        }
     }
 
     /// Converts a known mod path to `Path`.
@@ -144,7 +163,12 @@ impl Path {
         path: ModPath,
         generic_args: Vec<Option<Arc<GenericArgs>>>,
     ) -> Path {
-        Path { type_anchor: None, mod_path: path, generic_args }
+        Path {
+            type_anchor: None,
+            mod_path: path,
+            generic_args,
+            syntax_context: SyntaxContext::Root,
+        }
     }
 
     pub fn kind(&self) -> &PathKind {
@@ -166,6 +190,10 @@ impl Path {
         &self.mod_path
     }
 
+    pub fn syntax_context(&self) -> SyntaxContext {
+        self.syntax_context
+    }
+
     pub fn qualifier(&self) -> Option<Path> {
         if self.mod_path.is_ident() {
             return None;
@@ -177,6 +205,7 @@ impl Path {
                 segments: self.mod_path.segments[..self.mod_path.segments.len() - 1].to_vec(),
             },
             generic_args: self.generic_args[..self.generic_args.len() - 1].to_vec(),
+            syntax_context: self.syntax_context,
         };
         Some(res)
     }
@@ -247,6 +276,7 @@ impl From<Name> for Path {
             type_anchor: None,
             mod_path: ModPath::from_segments(PathKind::Plain, iter::once(name)),
             generic_args: vec![None],
+            syntax_context: SyntaxContext::Root,
         }
     }
 }
@@ -6,14 +6,14 @@ use std::sync::Arc;
 
 use either::Either;
 use hir_expand::{
-    hygiene::Hygiene,
+    hygiene::{Hygiene, SyntaxContext},
     name::{name, AsName},
 };
 use ra_syntax::ast::{self, AstNode, TypeAscriptionOwner};
 
 use crate::{
     path::{GenericArg, GenericArgs, ModPath, Path, PathKind},
-    type_ref::TypeRef,
+    type_ref::{ConstRef, TypeRef},
 };
 
 pub(super) use lower_use::lower_use_tree;
@@ -25,6 +25,7 @@ pub(super) fn lower_path(mut path: ast::Path, hygiene: &Hygiene) -> Option<Path>
     let mut type_anchor = None;
     let mut segments = Vec::new();
     let mut generic_args = Vec::new();
+    let mut first_token = None;
     loop {
         let segment = path.segment()?;
 
@@ -34,6 +35,9 @@ pub(super) fn lower_path(mut path: ast::Path, hygiene: &Hygiene) -> Option<Path>
 
         match segment.kind()? {
             ast::PathSegmentKind::Name(name_ref) => {
+                // Segments are visited last to first, so this ends up being
+                // the first segment.
+                first_token = name_ref.syntax().first_token();
                 // FIXME: this should just return name
                 match hygiene.name_ref_to_name(name_ref) {
                     Either::Left(name) => {
@@ -97,6 +101,7 @@ pub(super) fn lower_path(mut path: ast::Path, hygiene: &Hygiene) -> Option<Path>
                 break;
             }
             ast::PathSegmentKind::SelfKw => {
+                first_token = segment.syntax().first_token();
                 kind = PathKind::Super(0);
                 break;
             }
@@ -113,7 +118,14 @@ pub(super) fn lower_path(mut path: ast::Path, hygiene: &Hygiene) -> Option<Path>
     segments.reverse();
     generic_args.reverse();
     let mod_path = ModPath { kind, segments };
-    return Some(Path { type_anchor, mod_path, generic_args });
+    // Only paths which can refer to local variables need a syntax context.
+    let syntax_context = match first_token {
+        Some(token) if type_anchor.is_none() && (mod_path.is_ident() || mod_path.is_self()) => {
+            hygiene.syntax_context(&token)
+        }
+        _ => SyntaxContext::Root,
+    };
+    return Some(Path { type_anchor, mod_path, generic_args, syntax_context });
 
     fn qualifier(path: &ast::Path) -> Option<ast::Path> {
         if let Some(q) = path.qualifier() {
@@ -133,6 +145,10 @@ pub(super) fn lower_generic_args(node: ast::TypeArgList) -> Option<GenericArgs>
         let type_ref = TypeRef::from_ast_opt(type_arg.type_ref());
         args.push(GenericArg::Type(type_ref));
     }
+    // const arguments always come after the type arguments
+    for const_arg in node.const_arg() {
+        args.push(GenericArg::Const(ConstRef::from_const_arg(const_arg)));
+    }
     // lifetimes ignored for now
     let mut bindings = Vec::new();
     for assoc_type_arg in node.assoc_type_args() {
@@ -2,6 +2,7 @@
 use std::sync::Arc;
 
 use hir_expand::{
+    hygiene::SyntaxContext,
     name::{name, Name},
     MacroDefId,
 };
@@ -17,12 +18,13 @@ use crate::{
     generics::GenericParams,
     item_scope::{BuiltinShadowMode, BUILTIN_SCOPE},
     nameres::CrateDefMap,
-    path::{ModPath, PathKind},
+    path::{ModPath, Path, PathKind},
     per_ns::PerNs,
     visibility::{RawVisibility, Visibility},
-    AdtId, AssocContainerId, ConstId, ContainerId, DefWithBodyId, EnumId, EnumVariantId,
-    FunctionId, GenericDefId, HasModule, ImplId, LocalModuleId, Lookup, ModuleDefId, ModuleId,
-    StaticId, StructId, TraitId, TypeAliasId, TypeParamId, VariantId,
+    AdtId, AssocContainerId, ConstId, ConstParamId, ContainerId, DefWithBodyId, EnumId,
+    EnumVariantId, FunctionId, GenericDefId, HasModule, ImplId, LifetimeParamId, LocalModuleId,
+    Lookup, ModuleDefId, ModuleId, StaticId, StructId, TraitId, TypeAliasId, TypeParamId,
+    VariantId,
 };
 
 #[derive(Debug, Clone, Default)]
@@ -92,6 +94,7 @@ pub enum ValueNs {
     StaticId(StaticId),
     StructId(StructId),
     EnumVariantId(EnumVariantId),
+    GenericParam(ConstParamId),
 }
 
 impl Resolver {
@@ -253,6 +256,26 @@ impl Resolver {
         &self,
         db: &dyn DefDatabase,
         path: &ModPath,
+    ) -> Option<ResolveValueResult> {
+        self.resolve_value_path(db, path, None)
+    }
+
+    /// Like `resolve_path_in_value_ns`, but only lets `path` refer to local
+    /// bindings from the same syntax context, following `macro_rules!`
+    /// hygiene.
+    pub fn resolve_path_in_value_ns_hygienic(
+        &self,
+        db: &dyn DefDatabase,
+        path: &Path,
+    ) -> Option<ResolveValueResult> {
+        self.resolve_value_path(db, path.mod_path(), Some(path.syntax_context()))
+    }
+
+    fn resolve_value_path(
+        &self,
+        db: &dyn DefDatabase,
+        path: &ModPath,
+        ctx: Option<SyntaxContext>,
     ) -> Option<ResolveValueResult> {
         let n_segments = path.segments.len();
         let tmp = name![self];
@@ -271,11 +294,10 @@ impl Resolver {
                 }
 
                 Scope::ExprScope(scope) if n_segments <= 1 => {
-                    let entry = scope
-                        .expr_scopes
-                        .entries(scope.scope_id)
-                        .iter()
-                        .find(|entry| entry.name() == first_name);
+                    let entry = scope.expr_scopes.entries(scope.scope_id).iter().find(|entry| {
+                        entry.name() == first_name
+                            && ctx.map_or(true, |ctx| entry.syntax_context() == ctx)
+                    });
 
                     if let Some(e) = entry {
                         return Some(ResolveValueResult::ValueNs(ValueNs::LocalBinding(e.pat())));
@@ -289,7 +311,12 @@ impl Resolver {
                         return Some(ResolveValueResult::Partial(ty, 1));
                     }
                 }
-                Scope::GenericParams { .. } => continue,
+                Scope::GenericParams { params, def } => {
+                    if let Some(local_id) = params.find_const_by_name(first_name) {
+                        let val = ValueNs::GenericParam(ConstParamId { local_id, parent: *def });
+                        return Some(ResolveValueResult::ValueNs(val));
+                    }
+                }
 
                 Scope::ImplDefScope(impl_) if n_segments > 1 => {
                     if first_name == &name![Self] {
@@ -376,6 +403,17 @@ impl Resolver {
         }
     }
 
+    pub fn resolve_path_in_value_ns_hygienic_fully(
+        &self,
+        db: &dyn DefDatabase,
+        path: &Path,
+    ) -> Option<ValueNs> {
+        match self.resolve_path_in_value_ns_hygienic(db, path)? {
+            ResolveValueResult::ValueNs(it) => Some(it),
+            ResolveValueResult::Partial(..) => None,
+        }
+    }
+
     pub fn resolve_path_as_macro(
         &self,
         db: &dyn DefDatabase,
@@ -457,6 +495,16 @@ impl Resolver {
         })
     }
 
+    /// Resolves a lifetime like `'a` to the generic parameter declaring it.
+    pub fn resolve_lifetime(&self, name: &Name) -> Option<LifetimeParamId> {
+        self.scopes.iter().rev().find_map(|scope| match scope {
+            Scope::GenericParams { params, def } => params
+                .find_lifetime_by_name(name)
+                .map(|local_id| LifetimeParamId { parent: *def, local_id }),
+            _ => None,
+        })
+    }
+
     pub fn body_owner(&self) -> Option<DefWithBodyId> {
         self.scopes.iter().rev().find_map(|scope| match scope {
             Scope::ExprScope(it) => Some(it.owner),
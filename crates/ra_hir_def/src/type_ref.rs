@@ -44,7 +44,7 @@ pub enum TypeRef {
     Path(Path),
     RawPtr(Box<TypeRef>, Mutability),
     Reference(Box<TypeRef>, Mutability),
-    Array(Box<TypeRef> /*, Expr*/),
+    Array(Box<TypeRef>, ConstRef),
     Slice(Box<TypeRef>),
     /// A fn pointer. Last element of the vector is the return type.
     Fn(Vec<TypeRef>),
@@ -54,6 +54,15 @@ pub enum TypeRef {
     Error,
 }
 
+/// A constant in a type, like the length of an array type. We only keep the
+/// forms which can be evaluated without a body.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ConstRef {
+    Literal(u128),
+    Path(Path),
+    Unknown,
+}
+
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub enum TypeBound {
     Path(Path),
@@ -81,7 +90,8 @@ impl TypeRef {
                 TypeRef::RawPtr(Box::new(inner_ty), mutability)
             }
             ast::TypeRef::ArrayType(inner) => {
-                TypeRef::Array(Box::new(TypeRef::from_ast_opt(inner.type_ref())))
+                let len = ConstRef::from_ast_opt(inner.expr());
+                TypeRef::Array(Box::new(TypeRef::from_ast_opt(inner.type_ref())), len)
             }
             ast::TypeRef::SliceType(inner) => {
                 TypeRef::Slice(Box::new(TypeRef::from_ast_opt(inner.type_ref())))
@@ -134,7 +144,7 @@ impl TypeRef {
                 TypeRef::Fn(types) | TypeRef::Tuple(types) => types.iter().for_each(|t| go(t, f)),
                 TypeRef::RawPtr(type_ref, _)
                 | TypeRef::Reference(type_ref, _)
-                | TypeRef::Array(type_ref)
+                | TypeRef::Array(type_ref, _)
                 | TypeRef::Slice(type_ref) => go(&type_ref, f),
                 TypeRef::ImplTrait(bounds) | TypeRef::DynTrait(bounds) => {
                     for bound in bounds {
@@ -156,8 +166,9 @@ impl TypeRef {
             for segment in path.segments().iter() {
                 if let Some(args_and_bindings) = segment.args_and_bindings {
                     for arg in &args_and_bindings.args {
-                        let crate::path::GenericArg::Type(type_ref) = arg;
-                        go(type_ref, f);
+                        if let crate::path::GenericArg::Type(type_ref) = arg {
+                            go(type_ref, f);
+                        }
                     }
                     for (_, type_ref) in &args_and_bindings.bindings {
                         go(type_ref, f);
@@ -176,6 +187,34 @@ pub(crate) fn type_bounds_from_ast(type_bounds_opt: Option<ast::TypeBoundList>)
     }
 }
 
+impl ConstRef {
+    pub(crate) fn from_ast_opt(node: Option<ast::Expr>) -> Self {
+        match node {
+            Some(ast::Expr::Literal(lit)) => {
+                lit.int_value().map(ConstRef::Literal).unwrap_or(ConstRef::Unknown)
+            }
+            Some(ast::Expr::PathExpr(it)) => {
+                it.path().and_then(Path::from_ast).map(ConstRef::Path).unwrap_or(ConstRef::Unknown)
+            }
+            Some(ast::Expr::ParenExpr(it)) => ConstRef::from_ast_opt(it.expr()),
+            Some(ast::Expr::BlockExpr(it)) => match it.block() {
+                Some(block) if block.statements().next().is_none() => {
+                    ConstRef::from_ast_opt(block.expr())
+                }
+                _ => ConstRef::Unknown,
+            },
+            _ => ConstRef::Unknown,
+        }
+    }
+
+    /// Converts a const generic argument, like the `3` in `Foo<3>`.
+    pub(crate) fn from_const_arg(node: ast::ConstArg) -> Self {
+        let expr =
+            node.literal().map(ast::Expr::from).or_else(|| node.block_expr().map(Into::into));
+        ConstRef::from_ast_opt(expr)
+    }
+}
+
 impl TypeBound {
     pub(crate) fn from_ast(node: ast::TypeBound) -> Self {
         match node.kind() {
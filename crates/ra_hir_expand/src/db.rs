@@ -2,16 +2,21 @@
 
 use std::sync::Arc;
 
-use mbe::{ExpandResult, MacroRules};
+use mbe::{ExpandError, ExpandResult, MacroRules};
 use ra_db::{salsa, SourceDatabase};
 use ra_parser::FragmentKind;
 use ra_prof::profile;
-use ra_syntax::{algo::diff, AstNode, Parse, SyntaxKind::*, SyntaxNode};
+use ra_syntax::{
+    algo::{diff, find_covering_element},
+    AstNode, Parse, SmolStr,
+    SyntaxKind::*,
+    SyntaxNode, TextRange,
+};
 
 use crate::{
     ast_id_map::AstIdMap, BuiltinDeriveExpander, BuiltinFnLikeExpander, EagerCallLoc, EagerMacroId,
-    HirFileId, HirFileIdRepr, LazyMacroId, MacroCallId, MacroCallLoc, MacroDefId, MacroDefKind,
-    MacroFile, ProcMacroExpander,
+    HirFileId, HirFileIdRepr, InFile, LazyMacroId, MacroCallId, MacroCallLoc, MacroDefId,
+    MacroDefKind, MacroFile, ProcMacroExpander,
 };
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -71,7 +76,13 @@ pub trait AstDatabase: SourceDatabase {
     fn macro_def(&self, id: MacroDefId) -> Option<Arc<(TokenExpander, mbe::TokenMap)>>;
     fn parse_macro(&self, macro_file: MacroFile)
         -> Option<(Parse<SyntaxNode>, Arc<mbe::TokenMap>)>;
-    fn macro_expand(&self, macro_call: MacroCallId) -> (Option<Arc<tt::Subtree>>, Option<String>);
+    fn macro_expand(
+        &self,
+        macro_call: MacroCallId,
+    ) -> (Option<Arc<tt::Subtree>>, Option<ExpandError>);
+    /// Returns the error that occurred when expanding the given macro call, if
+    /// any. The expansion itself may still have produced (partial) output.
+    fn macro_expand_error(&self, macro_call: MacroCallId) -> Option<ExpandError>;
 
     #[salsa::interned]
     fn intern_eager_expansion(&self, eager: EagerCallLoc) -> EagerMacroId;
@@ -101,6 +112,124 @@ pub fn expand_hypothetical(
     Some((node.syntax_node(), token))
 }
 
+/// How a `macro_rules!` call was expanded: which rule was used, and what its
+/// metavariables were bound to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpansionTrace {
+    pub rule: Option<usize>,
+    pub bindings: Vec<TracedBinding>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TracedBinding {
+    pub name: SmolStr,
+    /// The iteration of each enclosing repetition, see `mbe::MetaVarBinding`.
+    pub nesting: Vec<usize>,
+    pub text: String,
+    /// The range of the bound tokens in the file they were written in, which
+    /// is an outer macro call for tokens passed through several macros.
+    pub range: Option<InFile<TextRange>>,
+}
+
+/// Expands the given macro call once more, recording how it was matched. Only
+/// `macro_rules!` macros can be traced.
+pub fn expansion_trace(db: &dyn AstDatabase, id: MacroCallId) -> Option<ExpansionTrace> {
+    let lazy_id = match id {
+        MacroCallId::LazyMacro(id) => id,
+        MacroCallId::EagerMacro(_) => return None,
+    };
+    let loc = db.lookup_intern_macro(lazy_id);
+    let macro_def = db.macro_def(loc.def)?;
+    let rules = match &macro_def.0 {
+        TokenExpander::MacroRules(it) => it,
+        _ => return None,
+    };
+    let macro_arg = db.macro_arg(id)?;
+    let arg_start = loc.kind.arg(db)?.text_range().start();
+    let file_id = loc.kind.file_id();
+
+    let (_, trace) = rules.expand_with_trace(&macro_arg.0);
+    let bindings = trace
+        .bindings
+        .into_iter()
+        .map(|binding| {
+            let mut ranges = Vec::new();
+            collect_token_ranges(&mut ranges, rules, &macro_arg.1, &binding.fragment);
+            let ranges = ranges
+                .into_iter()
+                .map(|it| original_token_range(db, InFile::new(file_id, it + arg_start)))
+                .collect::<Option<Vec<_>>>()
+                .unwrap_or_default();
+            let range = match ranges.first() {
+                Some(first) if ranges.iter().all(|it| it.file_id == first.file_id) => {
+                    let start = ranges.iter().map(|it| it.value.start()).min().unwrap();
+                    let end = ranges.iter().map(|it| it.value.end()).max().unwrap();
+                    Some(InFile::new(first.file_id, TextRange::from_to(start, end)))
+                }
+                _ => None,
+            };
+            TracedBinding {
+                name: binding.name,
+                nesting: binding.nesting,
+                text: binding.fragment.to_string(),
+                range,
+            }
+        })
+        .collect();
+    return Some(ExpansionTrace { rule: trace.rule, bindings });
+
+    fn collect_token_ranges(
+        acc: &mut Vec<TextRange>,
+        rules: &MacroRules,
+        token_map: &mbe::TokenMap,
+        tt: &tt::TokenTree,
+    ) {
+        let mut add = |id: tt::TokenId| {
+            if let (id, mbe::Origin::Call) = rules.map_id_up(id) {
+                match token_map.range_by_token(id) {
+                    Some(mbe::TokenTextRange::Token(it)) => acc.push(it),
+                    Some(mbe::TokenTextRange::Delimiter(open, close)) => {
+                        acc.push(open);
+                        acc.push(close);
+                    }
+                    None => (),
+                }
+            }
+        };
+        match tt {
+            tt::TokenTree::Leaf(tt::Leaf::Ident(it)) => add(it.id),
+            tt::TokenTree::Leaf(tt::Leaf::Punct(it)) => add(it.id),
+            tt::TokenTree::Leaf(tt::Leaf::Literal(it)) => add(it.id),
+            tt::TokenTree::Subtree(subtree) => {
+                if let Some(delimiter) = subtree.delimiter {
+                    add(delimiter.id);
+                }
+                for tt in subtree.token_trees.iter() {
+                    collect_token_ranges(acc, rules, token_map, tt);
+                }
+            }
+        }
+    }
+
+    /// Maps the range of a token up through the macro calls it was passed to,
+    /// to where it was written.
+    fn original_token_range(
+        db: &dyn AstDatabase,
+        mut range: InFile<TextRange>,
+    ) -> Option<InFile<TextRange>> {
+        while let Some(info) = range.file_id.expansion_info(db) {
+            let root = db.parse_or_expand(range.file_id)?;
+            let token = find_covering_element(&root, range.value).into_token()?;
+            let (token, origin) = info.map_token_up(InFile::new(range.file_id, &token))?;
+            if origin != mbe::Origin::Call {
+                return None;
+            }
+            range = InFile::new(token.file_id, token.value.text_range());
+        }
+        Some(range)
+    }
+}
+
 pub(crate) fn ast_id_map(db: &dyn AstDatabase, file_id: HirFileId) -> Arc<AstIdMap> {
     let map =
         db.parse_or_expand(file_id).map_or_else(AstIdMap::default, |it| AstIdMap::from_source(&it));
@@ -161,10 +290,14 @@ pub(crate) fn macro_arg(
 pub(crate) fn macro_expand(
     db: &dyn AstDatabase,
     id: MacroCallId,
-) -> (Option<Arc<tt::Subtree>>, Option<String>) {
+) -> (Option<Arc<tt::Subtree>>, Option<ExpandError>) {
     macro_expand_with_arg(db, id, None)
 }
 
+pub(crate) fn macro_expand_error(db: &dyn AstDatabase, id: MacroCallId) -> Option<ExpandError> {
+    db.macro_expand(id).1
+}
+
 fn expander(db: &dyn AstDatabase, id: MacroCallId) -> Option<Arc<(TokenExpander, mbe::TokenMap)>> {
     let lazy_id = match id {
         MacroCallId::LazyMacro(id) => id,
@@ -182,14 +315,16 @@ fn macro_expand_with_arg(
     db: &dyn AstDatabase,
     id: MacroCallId,
     arg: Option<Arc<(tt::Subtree, mbe::TokenMap)>>,
-) -> (Option<Arc<tt::Subtree>>, Option<String>) {
+) -> (Option<Arc<tt::Subtree>>, Option<ExpandError>) {
     let lazy_id = match id {
         MacroCallId::LazyMacro(id) => id,
         MacroCallId::EagerMacro(id) => {
             if arg.is_some() {
                 return (
                     None,
-                    Some("hypothetical macro expansion not implemented for eager macro".to_owned()),
+                    Some(ExpandError::Other(
+                        "hypothetical macro expansion not implemented for eager macro".to_owned(),
+                    )),
                 );
             } else {
                 return (Some(db.lookup_intern_eager_expansion(id).subtree), None);
@@ -200,20 +335,26 @@ fn macro_expand_with_arg(
     let loc = db.lookup_intern_macro(lazy_id);
     let macro_arg = match arg.or_else(|| db.macro_arg(id)) {
         Some(it) => it,
-        None => return (None, Some("Fail to args in to tt::TokenTree".into())),
+        None => return (None, Some(ExpandError::Other("Fail to args in to tt::TokenTree".into()))),
     };
 
     let macro_rules = match db.macro_def(loc.def) {
         Some(it) => it,
-        None => return (None, Some("Fail to find macro definition".into())),
+        None => return (None, Some(ExpandError::Other("Fail to find macro definition".into()))),
     };
     let ExpandResult(tt, err) = macro_rules.0.expand(db, lazy_id, &macro_arg.0);
     // Set a hard limit for the expanded tt
     let count = tt.count();
     if count > 65536 {
-        return (None, Some(format!("Total tokens count exceed limit : count = {}", count)));
+        return (
+            None,
+            Some(ExpandError::Other(format!(
+                "Total tokens count exceed limit : count = {}",
+                count
+            ))),
+        );
     }
-    (Some(Arc::new(tt)), err.map(|e| format!("{:?}", e)))
+    (Some(Arc::new(tt)), err)
 }
 
 pub(crate) fn parse_or_expand(db: &dyn AstDatabase, file_id: HirFileId) -> Option<SyntaxNode> {
@@ -14,7 +14,10 @@
 //! subsystem provides a separate, non-query-based API which can walk all stored
 //! values and transform them into instances of `Diagnostic`.
 
-use std::{any::Any, fmt};
+use std::{
+    any::{Any, TypeId},
+    fmt,
+};
 
 use ra_syntax::{SyntaxNode, SyntaxNodePtr, TextRange};
 
@@ -48,12 +51,28 @@ impl dyn Diagnostic {
 pub struct DiagnosticSink<'a> {
     callbacks: Vec<Box<dyn FnMut(&dyn Diagnostic) -> Result<(), ()> + 'a>>,
     default_callback: Box<dyn FnMut(&dyn Diagnostic) + 'a>,
+    disabled: Vec<TypeId>,
 }
 
 impl<'a> DiagnosticSink<'a> {
     /// FIXME: split `new` and `on` into a separate builder type
     pub fn new(cb: impl FnMut(&dyn Diagnostic) + 'a) -> DiagnosticSink<'a> {
-        DiagnosticSink { callbacks: Vec::new(), default_callback: Box::new(cb) }
+        DiagnosticSink {
+            callbacks: Vec::new(),
+            default_callback: Box::new(cb),
+            disabled: Vec::new(),
+        }
+    }
+
+    /// Drops the diagnostics of type `D`. Producers of expensive diagnostics
+    /// should check `is_enabled` to skip the work of computing them.
+    pub fn disable<D: Diagnostic>(mut self) -> DiagnosticSink<'a> {
+        self.disabled.push(TypeId::of::<D>());
+        self
+    }
+
+    pub fn is_enabled<D: Diagnostic>(&self) -> bool {
+        !self.disabled.contains(&TypeId::of::<D>())
     }
 
     pub fn on<D: Diagnostic, F: FnMut(&D) + 'a>(mut self, mut cb: F) -> DiagnosticSink<'a> {
@@ -74,6 +93,9 @@ impl<'a> DiagnosticSink<'a> {
     }
 
     fn _push(&mut self, d: &dyn Diagnostic) {
+        if self.disabled.contains(&d.as_any().type_id()) {
+            return;
+        }
         for cb in self.callbacks.iter_mut() {
             match cb(d) {
                 Ok(()) => return,
@@ -29,11 +29,15 @@ use ra_parser::FragmentKind;
 use ra_syntax::{algo::SyntaxRewriter, SyntaxNode};
 use std::sync::Arc;
 
+/// Eagerly expands `macro_call`, including the macro calls in its arguments.
+/// Expansion errors are passed to `diagnostic_sink`, since a failed eager
+/// expansion does not produce a macro call id they could be recorded for.
 pub fn expand_eager_macro(
     db: &dyn AstDatabase,
     macro_call: InFile<ast::MacroCall>,
     def: MacroDefId,
     resolver: &dyn Fn(ast::Path) -> Option<MacroDefId>,
+    diagnostic_sink: &mut dyn FnMut(mbe::ExpandError),
 ) -> Option<EagerMacroId> {
     let args = macro_call.value.token_tree()?;
     let parsed_args = mbe::ast_to_token_tree(&args)?.0;
@@ -57,11 +61,18 @@ pub fn expand_eager_macro(
         db,
         InFile::new(arg_file_id.as_file(), parsed_args.syntax_node()),
         resolver,
+        diagnostic_sink,
     )?;
     let subtree = to_subtree(&result)?;
 
     if let MacroDefKind::BuiltInEager(eager) = def.kind {
-        let (subtree, fragment) = eager.expand(db, arg_id, &subtree).ok()?;
+        let (subtree, fragment) = match eager.expand(db, arg_id, &subtree) {
+            Ok(it) => it,
+            Err(err) => {
+                diagnostic_sink(err);
+                return None;
+            }
+        };
         let eager =
             EagerCallLoc { def, fragment, subtree: Arc::new(subtree), file_id: macro_call.file_id };
 
@@ -81,11 +92,15 @@ fn lazy_expand(
     db: &dyn AstDatabase,
     def: &MacroDefId,
     macro_call: InFile<ast::MacroCall>,
+    diagnostic_sink: &mut dyn FnMut(mbe::ExpandError),
 ) -> Option<InFile<SyntaxNode>> {
     let ast_id = db.ast_id_map(macro_call.file_id).ast_id(&macro_call.value);
 
     let id: MacroCallId =
         def.as_lazy_macro(db, MacroCallKind::FnLike(macro_call.with_value(ast_id))).into();
+    if let Some(err) = db.macro_expand_error(id) {
+        diagnostic_sink(err);
+    }
 
     db.parse_or_expand(id.as_file()).map(|node| InFile::new(id.as_file(), node))
 }
@@ -94,6 +109,7 @@ fn eager_macro_recur(
     db: &dyn AstDatabase,
     curr: InFile<SyntaxNode>,
     macro_resolver: &dyn Fn(ast::Path) -> Option<MacroDefId>,
+    diagnostic_sink: &mut dyn FnMut(mbe::ExpandError),
 ) -> Option<SyntaxNode> {
     let original = curr.value.clone();
 
@@ -105,18 +121,24 @@ fn eager_macro_recur(
         let def: MacroDefId = macro_resolver(child.path()?)?;
         let insert = match def.kind {
             MacroDefKind::BuiltInEager(_) => {
-                let id: MacroCallId =
-                    expand_eager_macro(db, curr.with_value(child.clone()), def, macro_resolver)?
-                        .into();
+                let id: MacroCallId = expand_eager_macro(
+                    db,
+                    curr.with_value(child.clone()),
+                    def,
+                    macro_resolver,
+                    diagnostic_sink,
+                )?
+                .into();
                 db.parse_or_expand(id.as_file())?
             }
             MacroDefKind::Declarative
             | MacroDefKind::BuiltIn(_)
             | MacroDefKind::BuiltInDerive(_)
             | MacroDefKind::CustomDerive(_) => {
-                let expanded = lazy_expand(db, &def, curr.with_value(child.clone()))?;
+                let expanded =
+                    lazy_expand(db, &def, curr.with_value(child.clone()), diagnostic_sink)?;
                 // replace macro inside
-                eager_macro_recur(db, expanded, macro_resolver)?
+                eager_macro_recur(db, expanded, macro_resolver, diagnostic_sink)?
             }
         };
 
@@ -1,21 +1,50 @@
 //! This modules handles hygiene information.
 //!
 //! Specifically, `ast` + `Hygiene` allows you to create a `Name`. Note that, at
-//! this moment, this is horribly incomplete and handles only `$crate`.
+//! this moment, this is horribly incomplete and handles only `$crate` and the
+//! syntax contexts of identifiers introduced by `macro_rules!` definitions.
 use either::Either;
 use ra_db::CrateId;
-use ra_syntax::ast;
+use ra_syntax::{ast, SyntaxToken};
 
 use crate::{
     db::AstDatabase,
     name::{AsName, Name},
-    HirFileId, HirFileIdRepr, MacroCallId, MacroDefKind,
+    ExpansionInfo, HirFileId, HirFileIdRepr, InFile, MacroCallId, MacroDefKind, Origin,
 };
 
+/// The syntax context of an identifier, which determines which local
+/// variables and labels it can refer to.
+///
+/// This is a simplified model of `macro_rules!` hygiene: an identifier which
+/// is written in the body of a `macro_rules!` definition belongs to the macro
+/// call that transcribed it, everything else (including the tokens passed to
+/// the macro as arguments) belongs to the surrounding code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxContext {
+    Root,
+    Macro(MacroCallId),
+}
+
+impl Default for SyntaxContext {
+    fn default() -> Self {
+        SyntaxContext::Root
+    }
+}
+
 #[derive(Debug)]
 pub struct Hygiene {
     // This is what `$crate` expands to
     def_crate: Option<CrateId>,
+    // The chain of expansions this file is the result of, innermost first
+    frames: Vec<HygieneFrame>,
+}
+
+#[derive(Debug)]
+struct HygieneFrame {
+    call_id: MacroCallId,
+    is_declarative: bool,
+    info: ExpansionInfo,
 }
 
 impl Hygiene {
@@ -36,11 +65,11 @@ impl Hygiene {
                 MacroCallId::EagerMacro(_id) => None,
             },
         };
-        Hygiene { def_crate }
+        Hygiene { def_crate, frames: hygiene_frames(db, file_id) }
     }
 
     pub fn new_unhygienic() -> Hygiene {
-        Hygiene { def_crate: None }
+        Hygiene { def_crate: None, frames: Vec::new() }
     }
 
     // FIXME: this should just return name
@@ -52,4 +81,40 @@ impl Hygiene {
         }
         Either::Left(name_ref.as_name())
     }
+
+    /// Computes the syntax context of `token`, which must belong to the file
+    /// this `Hygiene` was created for.
+    pub fn syntax_context(&self, token: &SyntaxToken) -> SyntaxContext {
+        let mut token = token.clone();
+        for frame in self.frames.iter() {
+            match frame.info.map_token_up(InFile::new(frame.info.expanded.file_id, &token)) {
+                Some((up, Origin::Call)) => token = up.value,
+                Some((_, Origin::Def)) => return SyntaxContext::Macro(frame.call_id),
+                // Tokens made up by built-in macros behave as if written at the call site
+                None if !frame.is_declarative => break,
+                None => return SyntaxContext::Macro(frame.call_id),
+            }
+        }
+        SyntaxContext::Root
+    }
+}
+
+fn hygiene_frames(db: &dyn AstDatabase, file_id: HirFileId) -> Vec<HygieneFrame> {
+    let mut frames = Vec::new();
+    let mut current = file_id;
+    while let HirFileIdRepr::MacroFile(macro_file) = current.0 {
+        let info = match current.expansion_info(db) {
+            Some(it) => it,
+            None => break,
+        };
+        let is_declarative = match macro_file.macro_call_id {
+            MacroCallId::LazyMacro(id) => {
+                db.lookup_intern_macro(id).def.kind == MacroDefKind::Declarative
+            }
+            MacroCallId::EagerMacro(_id) => false,
+        };
+        current = info.arg.file_id;
+        frames.push(HygieneFrame { call_id: macro_file.macro_call_id, is_declarative, info });
+    }
+    frames
 }
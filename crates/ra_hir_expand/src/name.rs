@@ -2,7 +2,7 @@
 
 use std::fmt;
 
-use ra_syntax::{ast, SmolStr};
+use ra_syntax::{ast, SmolStr, SyntaxToken};
 
 /// `Name` is a wrapper around string, which is used in hir for both references
 /// and declarations. In theory, names should also carry hygiene info, but we are
@@ -52,6 +52,12 @@ impl Name {
         }
     }
 
+    /// Creates the name of a lifetime or a label from its `LIFETIME` token.
+    /// The name includes the leading `'`.
+    pub fn new_lifetime(lifetime: &SyntaxToken) -> Name {
+        Name::new_text(lifetime.text().clone())
+    }
+
     pub fn missing() -> Name {
         Name::new_text("[missing name]".into())
     }
@@ -167,6 +173,35 @@ pub mod known {
         Neg,
         Not,
         Index,
+        // Operator methods
+        add,
+        sub,
+        mul,
+        div,
+        rem,
+        shl,
+        shr,
+        bitxor,
+        bitor,
+        bitand,
+        add_assign,
+        sub_assign,
+        mul_assign,
+        div_assign,
+        rem_assign,
+        shl_assign,
+        shr_assign,
+        bitxor_assign,
+        bitor_assign,
+        bitand_assign,
+        eq,
+        ne,
+        lt,
+        le,
+        gt,
+        ge,
+        index,
+        index_mut,
         // Builtin macros
         file,
         column,
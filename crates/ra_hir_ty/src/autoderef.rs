@@ -12,9 +12,10 @@ use ra_db::CrateId;
 
 use crate::{
     db::HirDatabase,
+    method_resolution,
     traits::{InEnvironment, Solution},
     utils::generics,
-    Canonical, Substs, Ty, TypeWalk,
+    ApplicationTy, Canonical, Mutability, Substs, Ty, TypeCtor, TypeWalk,
 };
 
 const AUTODEREF_RECURSION_LIMIT: usize = 10;
@@ -43,6 +44,34 @@ pub(crate) fn deref(
     }
 }
 
+/// Checks whether `ty` can be dereferenced mutably, i.e. whether it is a
+/// mutable reference or raw pointer or implements `DerefMut`.
+pub(crate) fn can_deref_mut(
+    db: &dyn HirDatabase,
+    krate: CrateId,
+    ty: InEnvironment<&Canonical<Ty>>,
+) -> bool {
+    match &ty.value.value {
+        Ty::Apply(ApplicationTy { ctor: TypeCtor::Ref(mutability), .. })
+        | Ty::Apply(ApplicationTy { ctor: TypeCtor::RawPtr(mutability), .. }) => {
+            *mutability == Mutability::Mut
+        }
+        _ => {
+            let deref_mut_trait = match db.lang_item(krate, "deref_mut".into()) {
+                Some(LangItemTarget::TraitId(it)) => it,
+                _ => return false,
+            };
+            method_resolution::implements_trait(
+                ty.value,
+                db,
+                ty.environment,
+                krate,
+                deref_mut_trait,
+            )
+        }
+    }
+}
+
 fn deref_by_trait(
     db: &dyn HirDatabase,
     krate: CrateId,
@@ -0,0 +1,281 @@
+//! Evaluation of constant expressions.
+//!
+//! We only evaluate what is needed to know the lengths of arrays and to show
+//! the values of `const` items: integer and boolean arithmetic, casts between
+//! integers, and references to other `const` items.
+
+use std::{cmp, convert::TryFrom, fmt};
+
+use hir_def::{
+    body::Body,
+    expr::{ArithOp, BinaryOp, CmpOp, Expr, ExprId, Literal, LogicOp, Ordering, UnaryOp},
+    resolver::{resolver_for_expr, Resolver, ValueNs},
+    type_ref::ConstRef,
+    ConstId, DefWithBodyId,
+};
+
+use crate::{
+    db::HirDatabase,
+    primitive::{IntBitness, IntTy, Signedness, Uncertain},
+    ApplicationTy, Ty, TypeCtor,
+};
+
+/// The value of a constant expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConstValue {
+    Bool(bool),
+    Int(i128),
+}
+
+impl fmt::Display for ConstValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConstValue::Bool(it) => write!(f, "{}", it),
+            ConstValue::Int(it) => write!(f, "{}", it),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConstEvalError {
+    /// The expression contains something we can't evaluate (yet).
+    NotSupported,
+    /// The value doesn't fit into its type.
+    Overflow,
+    DivisionByZero,
+    /// The value of a constant depends on itself.
+    Cycle,
+}
+
+pub(crate) struct ConstEvalCtx<'a> {
+    pub(crate) db: &'a dyn HirDatabase,
+    pub(crate) owner: DefWithBodyId,
+    pub(crate) body: &'a Body,
+    /// Returns the type of an expression of `body`, as far as it is known.
+    pub(crate) expr_ty: &'a mut dyn FnMut(ExprId) -> Ty,
+}
+
+impl ConstEvalCtx<'_> {
+    pub(crate) fn eval(&mut self, expr: ExprId) -> Result<ConstValue, ConstEvalError> {
+        let value = match &self.body[expr] {
+            Expr::Literal(Literal::Bool(it)) => ConstValue::Bool(*it),
+            Expr::Literal(Literal::Int(it, _)) => {
+                ConstValue::Int(i128::try_from(*it).map_err(|_| ConstEvalError::Overflow)?)
+            }
+            Expr::Block { statements, tail: Some(tail), .. } if statements.is_empty() => {
+                return self.eval(*tail);
+            }
+            Expr::Path(path) => {
+                let resolver = resolver_for_expr(self.db.upcast(), self.owner, expr);
+                match resolver.resolve_path_in_value_ns_hygienic_fully(self.db.upcast(), path) {
+                    Some(ValueNs::ConstId(it)) => self.db.const_eval(it)?,
+                    _ => return Err(ConstEvalError::NotSupported),
+                }
+            }
+            Expr::UnaryOp { expr: operand, op } => match (op, self.eval(*operand)?) {
+                (UnaryOp::Not, ConstValue::Bool(it)) => ConstValue::Bool(!it),
+                (UnaryOp::Not, ConstValue::Int(it)) => {
+                    let ty = self.int_ty(expr).ok_or(ConstEvalError::NotSupported)?;
+                    ConstValue::Int(match ty.signedness {
+                        Signedness::Signed => !it,
+                        Signedness::Unsigned => int_range(ty).1 - it,
+                    })
+                }
+                (UnaryOp::Neg, ConstValue::Int(it)) => {
+                    ConstValue::Int(it.checked_neg().ok_or(ConstEvalError::Overflow)?)
+                }
+                _ => return Err(ConstEvalError::NotSupported),
+            },
+            Expr::BinaryOp { lhs, rhs, op: Some(BinaryOp::LogicOp(op)) } => {
+                let lhs = self.eval_bool(*lhs)?;
+                match op {
+                    LogicOp::And if !lhs => ConstValue::Bool(false),
+                    LogicOp::Or if lhs => ConstValue::Bool(true),
+                    _ => ConstValue::Bool(self.eval_bool(*rhs)?),
+                }
+            }
+            Expr::BinaryOp { lhs, rhs, op: Some(BinaryOp::CmpOp(op)) } => {
+                let (lhs, rhs) = (self.eval(*lhs)?, self.eval(*rhs)?);
+                let ordering = match (lhs, rhs) {
+                    (ConstValue::Int(lhs), ConstValue::Int(rhs)) => lhs.cmp(&rhs),
+                    (ConstValue::Bool(lhs), ConstValue::Bool(rhs)) => lhs.cmp(&rhs),
+                    _ => return Err(ConstEvalError::NotSupported),
+                };
+                ConstValue::Bool(match op {
+                    CmpOp::Eq { negated } => (ordering == cmp::Ordering::Equal) != *negated,
+                    CmpOp::Ord { ordering: expected, strict } => {
+                        let expected = match expected {
+                            Ordering::Less => cmp::Ordering::Less,
+                            Ordering::Greater => cmp::Ordering::Greater,
+                        };
+                        ordering == expected || (!strict && ordering == cmp::Ordering::Equal)
+                    }
+                })
+            }
+            Expr::BinaryOp { lhs, rhs, op: Some(BinaryOp::ArithOp(op)) } => {
+                match (self.eval(*lhs)?, self.eval(*rhs)?) {
+                    (ConstValue::Int(lhs), ConstValue::Int(rhs)) => {
+                        ConstValue::Int(self.eval_arith_op(expr, *op, lhs, rhs)?)
+                    }
+                    (ConstValue::Bool(lhs), ConstValue::Bool(rhs)) => ConstValue::Bool(match op {
+                        ArithOp::BitAnd => lhs & rhs,
+                        ArithOp::BitOr => lhs | rhs,
+                        ArithOp::BitXor => lhs ^ rhs,
+                        _ => return Err(ConstEvalError::NotSupported),
+                    }),
+                    _ => return Err(ConstEvalError::NotSupported),
+                }
+            }
+            Expr::Cast { expr: operand, .. } => {
+                let ty = self.int_ty(expr).ok_or(ConstEvalError::NotSupported)?;
+                let value = match self.eval(*operand)? {
+                    ConstValue::Int(it) => it,
+                    ConstValue::Bool(it) => it as i128,
+                };
+                ConstValue::Int(truncate(value, ty))
+            }
+            _ => return Err(ConstEvalError::NotSupported),
+        };
+
+        if let (ConstValue::Int(value), Some(ty)) = (value, self.int_ty(expr)) {
+            let (min, max) = int_range(ty);
+            if value < min || value > max {
+                return Err(ConstEvalError::Overflow);
+            }
+        }
+        Ok(value)
+    }
+
+    fn eval_bool(&mut self, expr: ExprId) -> Result<bool, ConstEvalError> {
+        match self.eval(expr)? {
+            ConstValue::Bool(it) => Ok(it),
+            ConstValue::Int(_) => Err(ConstEvalError::NotSupported),
+        }
+    }
+
+    fn eval_arith_op(
+        &mut self,
+        expr: ExprId,
+        op: ArithOp,
+        lhs: i128,
+        rhs: i128,
+    ) -> Result<i128, ConstEvalError> {
+        let res = match op {
+            ArithOp::Add => lhs.checked_add(rhs),
+            ArithOp::Sub => lhs.checked_sub(rhs),
+            ArithOp::Mul => lhs.checked_mul(rhs),
+            ArithOp::Div | ArithOp::Rem if rhs == 0 => return Err(ConstEvalError::DivisionByZero),
+            ArithOp::Div => lhs.checked_div(rhs),
+            ArithOp::Rem => lhs.checked_rem(rhs),
+            ArithOp::Shl | ArithOp::Shr => {
+                // Shifting by at least the bit width is an error, but bits
+                // shifted out to the left are silently lost.
+                let ty = self.int_ty(expr).ok_or(ConstEvalError::NotSupported)?;
+                let shift = u32::try_from(rhs).map_err(|_| ConstEvalError::Overflow)?;
+                if shift >= bits(ty) {
+                    return Err(ConstEvalError::Overflow);
+                }
+                Some(match op {
+                    ArithOp::Shl => truncate(lhs << shift, ty),
+                    _ => lhs >> shift,
+                })
+            }
+            ArithOp::BitAnd => Some(lhs & rhs),
+            ArithOp::BitOr => Some(lhs | rhs),
+            ArithOp::BitXor => Some(lhs ^ rhs),
+        };
+        res.ok_or(ConstEvalError::Overflow)
+    }
+
+    fn int_ty(&mut self, expr: ExprId) -> Option<IntTy> {
+        match (self.expr_ty)(expr) {
+            Ty::Apply(ApplicationTy { ctor: TypeCtor::Int(Uncertain::Known(ty)), .. }) => Some(ty),
+            _ => None,
+        }
+    }
+}
+
+fn bits(ty: IntTy) -> u32 {
+    match ty.bitness {
+        IntBitness::X8 => 8,
+        IntBitness::X16 => 16,
+        IntBitness::X32 => 32,
+        // FIXME: this should depend on the target
+        IntBitness::X64 | IntBitness::Xsize => 64,
+        IntBitness::X128 => 128,
+    }
+}
+
+/// The smallest and largest values of `ty`. Values of `u128` which don't fit
+/// into an `i128` are not supported.
+fn int_range(ty: IntTy) -> (i128, i128) {
+    let bits = bits(ty);
+    match ty.signedness {
+        Signedness::Signed => {
+            let min = i128::min_value() >> (128 - bits);
+            (min, !min)
+        }
+        Signedness::Unsigned if bits == 128 => (0, i128::max_value()),
+        Signedness::Unsigned => (0, (1 << bits) - 1),
+    }
+}
+
+/// Converts `value` to `ty` the way `as` does, by dropping the high bits.
+fn truncate(value: i128, ty: IntTy) -> i128 {
+    let bits = bits(ty);
+    if bits == 128 {
+        return value;
+    }
+    let value = value & ((1 << bits) - 1);
+    match ty.signedness {
+        Signedness::Signed if value >= 1 << (bits - 1) => value - (1 << bits),
+        _ => value,
+    }
+}
+
+/// Evaluates the length of an array type.
+pub(crate) fn eval_array_len(
+    db: &dyn HirDatabase,
+    resolver: &Resolver,
+    len: &ConstRef,
+) -> Uncertain<u64> {
+    let value = match len {
+        ConstRef::Literal(it) => {
+            return u64::try_from(*it).map_or(Uncertain::Unknown, Uncertain::Known)
+        }
+        ConstRef::Path(path) => {
+            match resolver.resolve_path_in_value_ns_fully(db.upcast(), path.mod_path()) {
+                Some(ValueNs::ConstId(it)) => db.const_eval(it),
+                _ => return Uncertain::Unknown,
+            }
+        }
+        ConstRef::Unknown => return Uncertain::Unknown,
+    };
+    array_len(value)
+}
+
+/// Converts the result of evaluating the length of an array.
+pub(crate) fn array_len(value: Result<ConstValue, ConstEvalError>) -> Uncertain<u64> {
+    match value {
+        Ok(ConstValue::Int(it)) => u64::try_from(it).map_or(Uncertain::Unknown, Uncertain::Known),
+        _ => Uncertain::Unknown,
+    }
+}
+
+pub(crate) fn const_eval_query(
+    db: &dyn HirDatabase,
+    def: ConstId,
+) -> Result<ConstValue, ConstEvalError> {
+    let body = db.body(def.into());
+    let infer = db.infer(def.into());
+    let mut expr_ty = |expr: ExprId| infer[expr].clone();
+    ConstEvalCtx { db, owner: def.into(), body: &body, expr_ty: &mut expr_ty }.eval(body.body_expr)
+}
+
+pub(crate) fn const_eval_recover(
+    _db: &dyn HirDatabase,
+    _cycle: &[String],
+    _def: &ConstId,
+) -> Result<ConstValue, ConstEvalError> {
+    Err(ConstEvalError::Cycle)
+}
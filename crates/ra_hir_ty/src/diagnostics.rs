@@ -2,11 +2,12 @@
 
 use std::any::Any;
 
+use hir_def::type_ref::Mutability;
 use hir_expand::{db::AstDatabase, name::Name, HirFileId, InFile};
 use ra_syntax::{ast, AstNode, AstPtr, SyntaxNodePtr};
 use stdx::format_to;
 
-pub use hir_def::diagnostics::UnresolvedModule;
+pub use hir_def::diagnostics::{UnresolvedImport, UnresolvedModule};
 pub use hir_expand::diagnostics::{AstDiagnostic, Diagnostic, DiagnosticSink};
 
 #[derive(Debug)]
@@ -62,6 +63,35 @@ impl AstDiagnostic for MissingFields {
     }
 }
 
+#[derive(Debug)]
+pub struct MissingMatchArms {
+    pub file: HirFileId,
+    pub match_expr: AstPtr<ast::Expr>,
+    pub arms: AstPtr<ast::MatchArmList>,
+    /// Some of the patterns no arm covers, as source code.
+    pub uncovered_patterns: Vec<String>,
+}
+
+impl Diagnostic for MissingMatchArms {
+    fn message(&self) -> String {
+        let mut buf = String::from("Missing match arms: ");
+        for (i, pat) in self.uncovered_patterns.iter().enumerate() {
+            if i > 0 {
+                buf.push_str(", ");
+            }
+            format_to!(buf, "`{}`", pat);
+        }
+        buf.push_str(" not covered");
+        buf
+    }
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.match_expr.into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
 #[derive(Debug)]
 pub struct MissingOkInTailExpr {
     pub file: HirFileId,
@@ -89,3 +119,121 @@ impl AstDiagnostic for MissingOkInTailExpr {
         ast::Expr::cast(node).unwrap()
     }
 }
+
+#[derive(Debug)]
+pub struct MissingUnsafe {
+    pub file: HirFileId,
+    pub expr: AstPtr<ast::Expr>,
+}
+
+impl Diagnostic for MissingUnsafe {
+    fn message(&self) -> String {
+        "this operation is unsafe and requires an unsafe function or block".to_string()
+    }
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.expr.into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+impl AstDiagnostic for MissingUnsafe {
+    type AST = ast::Expr;
+
+    fn ast(&self, db: &impl AstDatabase) -> Self::AST {
+        let root = db.parse_or_expand(self.file).unwrap();
+        let node = self.source().value.to_node(&root);
+        ast::Expr::cast(node).unwrap()
+    }
+}
+
+#[derive(Debug)]
+pub struct UnresolvedPath {
+    pub file: HirFileId,
+    pub path: AstPtr<ast::Path>,
+}
+
+impl Diagnostic for UnresolvedPath {
+    fn message(&self) -> String {
+        "unresolved path".to_string()
+    }
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.path.into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+impl AstDiagnostic for UnresolvedPath {
+    type AST = ast::Path;
+
+    fn ast(&self, db: &impl AstDatabase) -> Self::AST {
+        let root = db.parse_or_expand(self.file).unwrap();
+        let node = self.source().value.to_node(&root);
+        ast::Path::cast(node).unwrap()
+    }
+}
+
+#[derive(Debug)]
+pub struct TypeMismatch {
+    pub file: HirFileId,
+    pub expr: AstPtr<ast::Expr>,
+    pub expected: String,
+    pub actual: String,
+    pub fix: Option<TypeMismatchFix>,
+}
+
+/// A simple change to the expression which makes its type match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeMismatchFix {
+    /// `&expr` or `&mut expr`.
+    Borrow(Mutability),
+    /// `expr.into()`
+    Into,
+    /// `expr.to_string()`
+    ToString,
+}
+
+impl Diagnostic for TypeMismatch {
+    fn message(&self) -> String {
+        format!("expected {}, found {}", self.expected, self.actual)
+    }
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.expr.into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+impl AstDiagnostic for TypeMismatch {
+    type AST = ast::Expr;
+
+    fn ast(&self, db: &impl AstDatabase) -> Self::AST {
+        let root = db.parse_or_expand(self.file).unwrap();
+        let node = self.source().value.to_node(&root);
+        ast::Expr::cast(node).unwrap()
+    }
+}
+
+#[derive(Debug)]
+pub struct PatTypeMismatch {
+    pub file: HirFileId,
+    pub pat: AstPtr<ast::Pat>,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl Diagnostic for PatTypeMismatch {
+    fn message(&self) -> String {
+        format!("expected {}, found {}", self.expected, self.actual)
+    }
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.pat.into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
@@ -3,8 +3,8 @@
 use std::fmt;
 
 use crate::{
-    db::HirDatabase, utils::generics, ApplicationTy, CallableDef, FnSig, GenericPredicate,
-    Obligation, ProjectionTy, Substs, TraitRef, Ty, TypeCtor,
+    db::HirDatabase, primitive::Uncertain, utils::generics, ApplicationTy, CallableDef,
+    ConstScalar, FnSig, GenericPredicate, Obligation, ProjectionTy, Substs, TraitRef, Ty, TypeCtor,
 };
 use hir_def::{generics::TypeParamProvenance, AdtId, AssocContainerId, Lookup};
 use hir_expand::name::Name;
@@ -123,9 +123,12 @@ impl HirDisplay for ApplicationTy {
                 let t = self.parameters.as_single();
                 write!(f, "[{}]", t.display(f.db))?;
             }
-            TypeCtor::Array => {
+            TypeCtor::Array(len) => {
                 let t = self.parameters.as_single();
-                write!(f, "[{}; _]", t.display(f.db))?;
+                match len {
+                    Uncertain::Known(len) => write!(f, "[{}; {}]", t.display(f.db), len)?,
+                    Uncertain::Unknown => write!(f, "[{}; _]", t.display(f.db))?,
+                }
             }
             TypeCtor::RawPtr(m) => {
                 let t = self.parameters.as_single();
@@ -168,13 +171,17 @@ impl HirDisplay for ApplicationTy {
                 };
                 if self.parameters.len() > 0 {
                     let generics = generics(f.db.upcast(), def.into());
-                    let (parent_params, self_param, type_params, _impl_trait_params) =
+                    let (parent_params, self_param, type_params, impl_trait_params, _const_params) =
                         generics.provenance_split();
                     let total_len = parent_params + self_param + type_params;
                     // We print all params except implicit impl Trait params. Still a bit weird; should we leave out parent and self?
-                    if total_len > 0 {
+                    let params_to_write = self.parameters.0[..total_len]
+                        .iter()
+                        .chain(&self.parameters.0[total_len + impl_trait_params..])
+                        .collect::<Vec<_>>();
+                    if !params_to_write.is_empty() {
                         write!(f, "<")?;
-                        f.write_joined(&self.parameters.0[..total_len], ", ")?;
+                        f.write_joined(params_to_write, ", ")?;
                         write!(f, ">")?;
                     }
                 }
@@ -240,6 +247,11 @@ impl HirDisplay for ApplicationTy {
                     write!(f, ">")?;
                 }
             }
+            TypeCtor::OpaqueType(..) => {
+                let bounds = Ty::Apply(self.clone()).impl_trait_bounds(f.db).unwrap_or_default();
+                write!(f, "impl ")?;
+                write_bounds_like_dyn_trait(&bounds, f)?;
+            }
             TypeCtor::Closure { .. } => {
                 let sig = self.parameters[0]
                     .callable_sig(f.db)
@@ -255,6 +267,11 @@ impl HirDisplay for ApplicationTy {
                     write!(f, "| -> {}", return_type_hint)?;
                 };
             }
+            TypeCtor::Const(ConstScalar::Value(value)) => write!(f, "{}", value)?,
+            TypeCtor::Const(ConstScalar::Param(id)) => {
+                let params = f.db.generic_params(id.parent);
+                write!(f, "{}", params.consts[id.local_id].name)?
+            }
         }
         Ok(())
     }
@@ -306,12 +323,8 @@ impl HirDisplay for Ty {
                 }
             }
             Ty::Bound(idx) => write!(f, "?{}", idx)?,
-            Ty::Dyn(predicates) | Ty::Opaque(predicates) => {
-                match self {
-                    Ty::Dyn(_) => write!(f, "dyn ")?,
-                    Ty::Opaque(_) => write!(f, "impl ")?,
-                    _ => unreachable!(),
-                };
+            Ty::Dyn(predicates) => {
+                write!(f, "dyn ")?;
                 write_bounds_like_dyn_trait(&predicates, f)?;
             }
             Ty::Unknown => write!(f, "{{unknown}}")?,
@@ -4,7 +4,8 @@ use std::sync::Arc;
 
 use hir_def::{
     path::{path, Path},
-    resolver::HasResolver,
+    resolver::{HasResolver, Resolver},
+    type_ref::Mutability,
     AdtId, FunctionId,
 };
 use hir_expand::{diagnostics::DiagnosticSink, name::Name};
@@ -14,9 +15,16 @@ use rustc_hash::FxHashSet;
 
 use crate::{
     db::HirDatabase,
-    diagnostics::{MissingFields, MissingOkInTailExpr},
+    diagnostics::{
+        MissingFields, MissingMatchArms, MissingOkInTailExpr, PatTypeMismatch, TypeMismatch,
+        TypeMismatchFix,
+    },
+    display::HirDisplay,
+    infer,
+    match_check::MatchCheckCtx,
     utils::variant_data,
-    ApplicationTy, InferenceResult, Ty, TypeCtor,
+    ApplicationTy, Canonical, InEnvironment, InferenceResult, Obligation, Substs, TraitEnvironment,
+    TraitRef, Ty, TypeCtor, TypeWalk,
 };
 
 pub use hir_def::{
@@ -49,15 +57,116 @@ impl<'a, 'b> ExprValidator<'a, 'b> {
     pub fn validate_body(&mut self, db: &dyn HirDatabase) {
         let body = db.body(self.func.into());
 
-        for e in body.exprs.iter() {
-            if let (id, Expr::RecordLit { path, fields, spread }) = e {
-                self.validate_record_literal(id, path, fields, *spread, db);
+        for (id, expr) in body.exprs.iter() {
+            match expr {
+                Expr::RecordLit { path, fields, spread } => {
+                    self.validate_record_literal(id, path, fields, *spread, db);
+                }
+                Expr::Match { expr, arms } => {
+                    self.validate_match(id, *expr, arms, body.clone(), db);
+                }
+                _ => {}
             }
         }
 
+        let mut missing_ok = false;
         let body_expr = &body[body.body_expr];
-        if let Expr::Block { statements: _, tail: Some(t) } = body_expr {
-            self.validate_results_in_tail_expr(body.body_expr, *t, db);
+        if let Expr::Block { statements: _, tail: Some(t), .. } = body_expr {
+            missing_ok = self.validate_results_in_tail_expr(body.body_expr, *t, db);
+        }
+
+        // Rendering the types and looking for fixes, which needs the trait
+        // solver, is wasted work when nobody listens.
+        if self.sink.is_enabled::<TypeMismatch>() {
+            self.validate_type_mismatches(&body, missing_ok, db);
+        }
+        if self.sink.is_enabled::<PatTypeMismatch>() {
+            self.validate_pat_type_mismatches(&body, db);
+        }
+    }
+
+    /// Reports all type mismatches, except for the one of the body expression
+    /// if it's already reported as `MissingOkInTailExpr`.
+    fn validate_type_mismatches(
+        &mut self,
+        body: &Body,
+        skip_body_expr: bool,
+        db: &dyn HirDatabase,
+    ) {
+        let (_, source_map) = db.body_with_source_map(self.func.into());
+        let resolver = self.func.resolver(db.upcast());
+        let infer = self.infer.clone();
+
+        for (id, _) in body.exprs.iter() {
+            let mismatch = match infer.type_mismatch_for_expr(id) {
+                Some(it) => it,
+                None => continue,
+            };
+            // Types we couldn't infer fully produce lots of bogus mismatches.
+            if contains_unknown(&mismatch.expected) || contains_unknown(&mismatch.actual) {
+                continue;
+            }
+
+            if skip_body_expr && is_tail_of(body, body.body_expr, id) {
+                continue;
+            }
+
+            // The mismatch of a block is caused by its tail expression. Coercion
+            // usually records it on the tail as well, so we report it there.
+            if let Expr::Block { tail: Some(tail), .. } = &body[id] {
+                if infer.type_mismatch_for_expr(*tail).is_some() {
+                    continue;
+                }
+            }
+            let mut expr_id = id;
+            while let Expr::Block { tail: Some(tail), .. } = &body[expr_id] {
+                expr_id = *tail;
+            }
+
+            let source_ptr = match source_map.expr_syntax(expr_id) {
+                Ok(it) => it,
+                Err(_) => continue,
+            };
+            let expr = match source_ptr.value.left() {
+                Some(it) => it,
+                None => continue,
+            };
+            self.sink.push(TypeMismatch {
+                file: source_ptr.file_id,
+                expr,
+                expected: mismatch.expected.display(db).to_string(),
+                actual: mismatch.actual.display(db).to_string(),
+                fix: type_mismatch_fix(db, &resolver, mismatch),
+            });
+        }
+    }
+
+    fn validate_pat_type_mismatches(&mut self, body: &Body, db: &dyn HirDatabase) {
+        let (_, source_map) = db.body_with_source_map(self.func.into());
+        let infer = self.infer.clone();
+
+        for (id, _) in body.pats.iter() {
+            let mismatch = match infer.type_mismatch_for_pat(id) {
+                Some(it) => it,
+                None => continue,
+            };
+            if contains_unknown(&mismatch.expected) || contains_unknown(&mismatch.actual) {
+                continue;
+            }
+            let source_ptr = match source_map.pat_syntax(id) {
+                Ok(it) => it,
+                Err(_) => continue,
+            };
+            let pat = match source_ptr.value.left() {
+                Some(it) => it,
+                None => continue,
+            };
+            self.sink.push(PatTypeMismatch {
+                file: source_ptr.file_id,
+                pat,
+                expected: mismatch.expected.display(db).to_string(),
+                actual: mismatch.actual.display(db).to_string(),
+            });
         }
     }
 
@@ -116,11 +225,51 @@ impl<'a, 'b> ExprValidator<'a, 'b> {
         }
     }
 
-    fn validate_results_in_tail_expr(&mut self, body_id: ExprId, id: ExprId, db: &dyn HirDatabase) {
+    fn validate_match(
+        &mut self,
+        id: ExprId,
+        scrutinee: ExprId,
+        arms: &[MatchArm],
+        body: Arc<Body>,
+        db: &dyn HirDatabase,
+    ) {
+        let cx = MatchCheckCtx { db, body, infer: self.infer.clone() };
+        let uncovered_patterns = cx.uncovered_patterns(scrutinee, arms);
+        if uncovered_patterns.is_empty() {
+            return;
+        }
+        let (_, source_map) = db.body_with_source_map(self.func.into());
+
+        if let Ok(source_ptr) = source_map.expr_syntax(id) {
+            if let Some(expr) = source_ptr.value.left() {
+                let root = source_ptr.file_syntax(db.upcast());
+                if let ast::Expr::MatchExpr(match_expr) = expr.to_node(&root) {
+                    if let (Some(match_expr), Some(arms)) =
+                        (match_expr.expr(), match_expr.match_arm_list())
+                    {
+                        self.sink.push(MissingMatchArms {
+                            file: source_ptr.file_id,
+                            match_expr: AstPtr::new(&match_expr),
+                            arms: AstPtr::new(&arms),
+                            uncovered_patterns,
+                        })
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns `true` if the mismatch of the tail expression was reported.
+    fn validate_results_in_tail_expr(
+        &mut self,
+        body_id: ExprId,
+        id: ExprId,
+        db: &dyn HirDatabase,
+    ) -> bool {
         // the mismatch will be on the whole block currently
         let mismatch = match self.infer.type_mismatch_for_expr(body_id) {
             Some(m) => m,
-            None => return,
+            None => return false,
         };
 
         let std_result_path = path![std::result::Result];
@@ -128,13 +277,13 @@ impl<'a, 'b> ExprValidator<'a, 'b> {
         let resolver = self.func.resolver(db.upcast());
         let std_result_enum = match resolver.resolve_known_enum(db.upcast(), &std_result_path) {
             Some(it) => it,
-            _ => return,
+            _ => return false,
         };
 
         let std_result_ctor = TypeCtor::Adt(AdtId::EnumId(std_result_enum));
         let params = match &mismatch.expected {
             Ty::Apply(ApplicationTy { ctor, parameters }) if ctor == &std_result_ctor => parameters,
-            _ => return,
+            _ => return false,
         };
 
         if params.len() == 2 && params[0] == mismatch.actual {
@@ -143,8 +292,82 @@ impl<'a, 'b> ExprValidator<'a, 'b> {
             if let Ok(source_ptr) = source_map.expr_syntax(id) {
                 if let Some(expr) = source_ptr.value.left() {
                     self.sink.push(MissingOkInTailExpr { file: source_ptr.file_id, expr });
+                    return true;
                 }
             }
         }
+        false
+    }
+}
+
+/// Checks whether `expr` is `block` or (recursively) its tail expression.
+fn is_tail_of(body: &Body, block: ExprId, expr: ExprId) -> bool {
+    let mut current = block;
+    loop {
+        if current == expr {
+            return true;
+        }
+        match &body[current] {
+            Expr::Block { tail: Some(tail), .. } => current = *tail,
+            _ => return false,
+        }
+    }
+}
+
+fn contains_unknown(ty: &Ty) -> bool {
+    let mut res = false;
+    ty.walk(&mut |ty| {
+        if let Ty::Unknown = ty {
+            res = true;
+        }
+    });
+    res
+}
+
+/// Finds a simple change to the expression that makes it have the expected
+/// type: borrowing it, or converting it with `.to_string()` or `.into()`.
+fn type_mismatch_fix(
+    db: &dyn HirDatabase,
+    resolver: &Resolver,
+    mismatch: &infer::TypeMismatch,
+) -> Option<TypeMismatchFix> {
+    if let Some((inner, mutability)) = mismatch.expected.as_reference() {
+        if *inner == mismatch.actual {
+            return Some(TypeMismatchFix::Borrow(mutability));
+        }
+    }
+
+    let std_string_path = path![std::string::String];
+    if let Some(std_string) = resolver.resolve_known_struct(db.upcast(), &std_string_path) {
+        let std_string_ctor = TypeCtor::Adt(AdtId::StructId(std_string));
+        let expects_string = match &mismatch.expected {
+            Ty::Apply(ApplicationTy { ctor, .. }) => *ctor == std_string_ctor,
+            _ => false,
+        };
+        let is_str = match mismatch.actual.as_reference() {
+            Some((Ty::Apply(ApplicationTy { ctor: TypeCtor::Str, .. }), Mutability::Shared)) => {
+                true
+            }
+            _ => false,
+        };
+        if expects_string && is_str {
+            return Some(TypeMismatchFix::ToString);
+        }
+    }
+
+    let std_into_path = path![std::convert::Into];
+    let into_trait = resolver.resolve_known_trait(db.upcast(), &std_into_path)?;
+    let krate = resolver.krate()?;
+    let substs = Substs::build_for_def(db, into_trait)
+        .push(mismatch.actual.clone())
+        .push(mismatch.expected.clone())
+        .build();
+    let trait_ref = TraitRef { trait_: into_trait, substs };
+    let env = TraitEnvironment::lower(db, resolver);
+    let goal =
+        Canonical { value: InEnvironment::new(env, Obligation::Trait(trait_ref)), num_vars: 0 };
+    if db.trait_solve(krate, goal).is_some() {
+        return Some(TypeMismatchFix::Into);
     }
+    None
 }
@@ -18,12 +18,12 @@ use std::mem;
 use std::ops::Index;
 use std::sync::Arc;
 
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 use hir_def::{
     body::Body,
-    data::{ConstData, FunctionData},
-    expr::{BindingAnnotation, ExprId, PatId},
+    data::{ConstData, FunctionData, StaticData},
+    expr::{BinaryOp, BindingAnnotation, ExprId, LabelId, PatId},
     lang_item::LangItemTarget,
     path::{path, Path},
     resolver::{HasResolver, Resolver, TypeNs},
@@ -37,12 +37,15 @@ use ra_syntax::SmolStr;
 
 use super::{
     primitive::{FloatTy, IntTy},
-    traits::{Guidance, Obligation, ProjectionPredicate, Solution},
-    ApplicationTy, GenericPredicate, InEnvironment, ProjectionTy, Substs, TraitEnvironment,
-    TraitRef, Ty, TypeCtor, TypeWalk, Uncertain,
+    traits::{FnTrait, Guidance, Obligation, ProjectionPredicate, Solution},
+    ApplicationTy, InEnvironment, ProjectionTy, Substs, TraitEnvironment, TraitRef, Ty, TypeCtor,
+    TypeWalk, Uncertain,
 };
 use crate::{
-    db::HirDatabase, infer::diagnostics::InferenceDiagnostic, lower::ImplTraitLoweringMode,
+    db::HirDatabase,
+    infer::diagnostics::{InferenceDiagnostic, TypeSource},
+    lower::ImplTraitLoweringMode,
+    op,
 };
 
 pub(crate) use unify::unify;
@@ -71,7 +74,7 @@ pub(crate) fn infer_query(db: &dyn HirDatabase, def: DefWithBodyId) -> Arc<Infer
     match def {
         DefWithBodyId::ConstId(c) => ctx.collect_const(&db.const_data(c)),
         DefWithBodyId::FunctionId(f) => ctx.collect_fn(&db.function_data(f)),
-        DefWithBodyId::StaticId(s) => ctx.collect_const(&db.static_data(s)),
+        DefWithBodyId::StaticId(s) => ctx.collect_static(&db.static_data(s)),
     }
 
     ctx.infer_body();
@@ -111,6 +114,68 @@ impl Default for BindingMode {
     }
 }
 
+/// Whether control flow may continue past the expression currently being
+/// inferred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Diverges {
+    Maybe,
+    Always,
+}
+
+impl Diverges {
+    fn is_always(self) -> bool {
+        self == Diverges::Always
+    }
+}
+
+impl std::ops::BitAnd for Diverges {
+    type Output = Self;
+    fn bitand(self, other: Self) -> Self {
+        std::cmp::min(self, other)
+    }
+}
+
+impl std::ops::BitOr for Diverges {
+    type Output = Self;
+    fn bitor(self, other: Self) -> Self {
+        std::cmp::max(self, other)
+    }
+}
+
+/// A loop or labeled block that a `break` inside of it can target.
+#[derive(Clone, Debug)]
+struct BreakableContext {
+    /// Whether there is a `break` targeting this context.
+    may_break: bool,
+    /// The type that `break` values have to coerce to.
+    break_ty: Ty,
+    label: Option<LabelId>,
+    /// Unlabeled `break`s only target loops, never labeled blocks.
+    is_loop: bool,
+}
+
+/// An implicit conversion applied to an expression, like the autoderef and
+/// autoref of a method call receiver.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Adjustment {
+    pub kind: Adjust,
+    /// The type of the expression after this adjustment.
+    pub target: Ty,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Adjust {
+    /// Dereferencing a reference or raw pointer.
+    Deref,
+    /// Dereferencing through `Deref::deref`, or `DerefMut::deref_mut` if the
+    /// result is used mutably.
+    OverloadedDeref(Mutability),
+    /// Taking a reference.
+    Borrow(Mutability),
+    /// Unsizing an array to a slice.
+    Unsize,
+}
+
 /// A mismatch between an expected and an inferred type.
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct TypeMismatch {
@@ -134,7 +199,12 @@ pub struct InferenceResult {
     diagnostics: Vec<InferenceDiagnostic>,
     pub type_of_expr: ArenaMap<ExprId, Ty>,
     pub type_of_pat: ArenaMap<PatId, Ty>,
-    pub(super) type_mismatches: ArenaMap<ExprId, TypeMismatch>,
+    type_mismatches: FxHashMap<ExprOrPatId, TypeMismatch>,
+    /// Expressions after which control flow can't continue, either because
+    /// they are `!`-typed or because they are unreachable.
+    diverging_exprs: FxHashSet<ExprId>,
+    /// The adjustments applied to expressions, in order.
+    adjustments: FxHashMap<ExprId, Vec<Adjustment>>,
 }
 
 impl InferenceResult {
@@ -159,8 +229,19 @@ impl InferenceResult {
     pub fn assoc_resolutions_for_pat(&self, id: PatId) -> Option<AssocItemId> {
         self.assoc_resolutions.get(&id.into()).copied()
     }
+    /// Whether control flow can never continue past `expr`, e.g. because it is
+    /// a `return`, a `loop` without `break` or comes after one of those.
+    pub fn expr_diverges(&self, expr: ExprId) -> bool {
+        self.diverging_exprs.contains(&expr)
+    }
+    pub fn expr_adjustments(&self, expr: ExprId) -> &[Adjustment] {
+        self.adjustments.get(&expr).map_or(&[], |it| it.as_slice())
+    }
     pub fn type_mismatch_for_expr(&self, expr: ExprId) -> Option<&TypeMismatch> {
-        self.type_mismatches.get(expr)
+        self.type_mismatches.get(&expr.into())
+    }
+    pub fn type_mismatch_for_pat(&self, pat: PatId) -> Option<&TypeMismatch> {
+        self.type_mismatches.get(&pat.into())
     }
     pub fn add_diagnostics(
         &self,
@@ -206,6 +287,8 @@ struct InferenceContext<'a> {
     /// closures, but currently this is the only field that will change there,
     /// so it doesn't make sense.
     return_ty: Ty,
+    diverges: Diverges,
+    breakables: Vec<BreakableContext>,
 }
 
 impl<'a> InferenceContext<'a> {
@@ -215,6 +298,8 @@ impl<'a> InferenceContext<'a> {
             table: unify::InferenceTable::new(),
             obligations: Vec::default(),
             return_ty: Ty::Unknown, // set in collect_fn_signature
+            diverges: Diverges::Maybe,
+            breakables: Vec::new(),
             trait_env: TraitEnvironment::lower(db, &resolver),
             db,
             owner,
@@ -234,6 +319,13 @@ impl<'a> InferenceContext<'a> {
             let resolved = self.table.resolve_ty_completely(mem::replace(ty, Ty::Unknown));
             *ty = resolved;
         }
+        for adjustment in result.adjustments.values_mut().flat_map(|it| it.iter_mut()) {
+            adjustment.target = self.table.resolve_ty_completely(adjustment.target.clone());
+        }
+        for mismatch in result.type_mismatches.values_mut() {
+            mismatch.expected = self.table.resolve_ty_completely(mismatch.expected.clone());
+            mismatch.actual = self.table.resolve_ty_completely(mismatch.actual.clone());
+        }
         result
     }
 
@@ -241,6 +333,10 @@ impl<'a> InferenceContext<'a> {
         self.result.type_of_expr.insert(expr, ty);
     }
 
+    fn write_type_mismatch(&mut self, id: ExprOrPatId, expected: Ty, actual: Ty) {
+        self.result.type_mismatches.insert(id, TypeMismatch { expected, actual });
+    }
+
     fn write_method_resolution(&mut self, expr: ExprId, func: FunctionId) {
         self.result.method_resolutions.insert(expr, func);
     }
@@ -265,21 +361,34 @@ impl<'a> InferenceContext<'a> {
         self.result.diagnostics.push(diagnostic);
     }
 
+    fn push_unresolved_type_paths(&mut self, source: TypeSource, paths: Vec<Path>) {
+        for path in paths {
+            let diagnostic = InferenceDiagnostic::UnresolvedTypePath { source, path };
+            // The diagnostic points at every occurrence of the path in
+            // `source` anyway.
+            if !self.result.diagnostics.contains(&diagnostic) {
+                self.push_diagnostic(diagnostic);
+            }
+        }
+    }
+
     fn make_ty_with_mode(
         &mut self,
         type_ref: &TypeRef,
+        source: TypeSource,
         impl_trait_mode: ImplTraitLoweringMode,
     ) -> Ty {
         // FIXME use right resolver for block
         let ctx = crate::lower::TyLoweringContext::new(self.db, &self.resolver)
             .with_impl_trait_mode(impl_trait_mode);
         let ty = Ty::from_hir(&ctx, type_ref);
+        self.push_unresolved_type_paths(source, ctx.unresolved_paths.into_inner());
         let ty = self.insert_type_vars(ty);
         self.normalize_associated_types_in(ty)
     }
 
-    fn make_ty(&mut self, type_ref: &TypeRef) -> Ty {
-        self.make_ty_with_mode(type_ref, ImplTraitLoweringMode::Disallowed)
+    fn make_ty(&mut self, type_ref: &TypeRef, source: TypeSource) -> Ty {
+        self.make_ty_with_mode(type_ref, source, ImplTraitLoweringMode::Disallowed)
     }
 
     /// Replaces Ty::Unknown by a new type var, so we can maybe still infer it.
@@ -357,25 +466,6 @@ impl<'a> InferenceContext<'a> {
     ) -> Ty {
         match assoc_ty {
             Some(res_assoc_ty) => {
-                // FIXME:
-                // Check if inner_ty is is `impl Trait` and contained input TypeAlias id
-                // this is a workaround while Chalk assoc type projection doesn't always work yet,
-                // but once that is fixed I don't think we should keep this
-                // (we'll probably change how associated types are resolved anyway)
-                if let Ty::Opaque(ref predicates) = inner_ty {
-                    for p in predicates.iter() {
-                        if let GenericPredicate::Projection(projection) = p {
-                            if projection.projection_ty.associated_ty == res_assoc_ty {
-                                if let ty_app!(_, params) = &projection.ty {
-                                    if params.len() == 0 {
-                                        return projection.ty.clone();
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-
                 let ty = self.table.new_type_var();
                 let builder = Substs::build_for_def(self.db, res_assoc_ty)
                     .push(inner_ty)
@@ -416,11 +506,15 @@ impl<'a> InferenceContext<'a> {
         var
     }
 
-    fn resolve_variant(&mut self, path: Option<&Path>) -> (Ty, Option<VariantId>) {
+    fn resolve_variant(&mut self, path: Option<&Path>, id: ExprOrPatId) -> (Ty, Option<VariantId>) {
         let path = match path {
             Some(path) => path,
             None => return (Ty::Unknown, None),
         };
+        if self.resolver.resolve_path_in_type_ns(self.db.upcast(), path.mod_path()).is_none() {
+            self.push_diagnostic(InferenceDiagnostic::UnresolvedPath { id });
+            return (Ty::Unknown, None);
+        }
         let resolver = &self.resolver;
         let ctx = crate::lower::TyLoweringContext::new(self.db, &self.resolver);
         // FIXME: this should resolve assoc items as well, see this example:
@@ -469,23 +563,85 @@ impl<'a> InferenceContext<'a> {
     }
 
     fn collect_const(&mut self, data: &ConstData) {
-        self.return_ty = self.make_ty(&data.type_ref);
+        self.return_ty = self.make_ty(&data.type_ref, TypeSource::RetType);
+    }
+
+    fn collect_static(&mut self, data: &StaticData) {
+        self.return_ty = self.make_ty(&data.type_ref, TypeSource::RetType);
     }
 
     fn collect_fn(&mut self, data: &FunctionData) {
         let body = Arc::clone(&self.body); // avoid borrow checker problem
         let ctx = crate::lower::TyLoweringContext::new(self.db, &self.resolver)
             .with_impl_trait_mode(ImplTraitLoweringMode::Param);
-        let param_tys =
-            data.params.iter().map(|type_ref| Ty::from_hir(&ctx, type_ref)).collect::<Vec<_>>();
+        let mut unresolved_paths = Vec::new();
+        let param_tys = data
+            .params
+            .iter()
+            .enumerate()
+            .map(|(idx, type_ref)| {
+                let ty = Ty::from_hir(&ctx, type_ref);
+                let paths = ctx.unresolved_paths.replace(Vec::new());
+                unresolved_paths.push((TypeSource::Param(idx), paths));
+                ty
+            })
+            .collect::<Vec<_>>();
+        for (source, paths) in unresolved_paths {
+            self.push_unresolved_type_paths(source, paths);
+        }
         for (ty, pat) in param_tys.into_iter().zip(body.params.iter()) {
             let ty = self.insert_type_vars(ty);
             let ty = self.normalize_associated_types_in(ty);
 
             self.infer_pat(*pat, &ty, BindingMode::default());
         }
-        let return_ty = self.make_ty_with_mode(&data.ret_type, ImplTraitLoweringMode::Disallowed); // FIXME implement RPIT
-        self.return_ty = return_ty;
+        // The body of an `async fn` evaluates to the output of the future.
+        let ret_type = data.async_ret_type.as_ref().unwrap_or(&data.ret_type);
+        let return_ty =
+            self.make_ty_with_mode(ret_type, TypeSource::RetType, ImplTraitLoweringMode::Opaque);
+        self.return_ty = self.insert_vars_for_impl_trait(return_ty);
+    }
+
+    /// Inside the function that defines them, the return position `impl Trait`
+    /// types stand for their hidden types, which we infer from the body. So we
+    /// replace them by type variables that have to fulfill the bounds.
+    fn insert_vars_for_impl_trait(&mut self, ty: Ty) -> Ty {
+        let func = match self.owner {
+            DefWithBodyId::FunctionId(it) => it,
+            _ => return ty,
+        };
+        let mut opaque_tys = Vec::new();
+        let table = &mut self.table;
+        let ty = ty.fold(&mut |ty| {
+            let is_own_opaque_ty = match &ty {
+                Ty::Apply(ApplicationTy { ctor: TypeCtor::OpaqueType(opaque_ty_id), .. }) => {
+                    opaque_ty_id.function() == func
+                }
+                _ => false,
+            };
+            if !is_own_opaque_ty {
+                return ty;
+            }
+            let var = table.new_type_var();
+            opaque_tys.push((ty, var.clone()));
+            var
+        });
+        for (opaque_ty, _) in &opaque_tys {
+            let bounds = opaque_ty.impl_trait_bounds(self.db).unwrap_or_default();
+            for bound in bounds {
+                let bound = bound.fold(&mut |ty| match opaque_tys
+                    .iter()
+                    .find(|(opaque_ty, _)| *opaque_ty == ty)
+                {
+                    Some((_, var)) => var.clone(),
+                    None => ty,
+                });
+                if let Some(obligation) = Obligation::from_predicate(bound) {
+                    self.obligations.push(obligation);
+                }
+            }
+        }
+        ty
     }
 
     fn infer_body(&mut self) {
@@ -520,11 +676,39 @@ impl<'a> InferenceContext<'a> {
         self.db.trait_data(trait_).associated_type_by_name(&name![Output])
     }
 
+    fn resolve_binary_op_trait(&self, op: BinaryOp) -> Option<TraitId> {
+        let (lang_item, _) = op::binary_op_lang_item(op)?;
+        self.resolve_lang_item(lang_item)?.as_trait()
+    }
+
+    fn resolve_binary_op_method(&self, op: BinaryOp) -> Option<FunctionId> {
+        let trait_ = self.resolve_binary_op_trait(op)?;
+        let (_, method) = op::binary_op_lang_item(op)?;
+        self.db.trait_data(trait_).method_by_name(&method)
+    }
+
+    fn resolve_binary_op_output(&self, op: BinaryOp) -> Option<TypeAliasId> {
+        let trait_ = self.resolve_binary_op_trait(op)?;
+        self.db.trait_data(trait_).associated_type_by_name(&name![Output])
+    }
+
     fn resolve_future_future_output(&self) -> Option<TypeAliasId> {
         let trait_ = self.resolve_lang_item("future_trait")?.as_trait()?;
         self.db.trait_data(trait_).associated_type_by_name(&name![Output])
     }
 
+    fn is_fn_trait(&self, trait_: TraitId) -> bool {
+        [FnTrait::FnOnce, FnTrait::FnMut, FnTrait::Fn].iter().any(|fn_trait| {
+            self.resolve_lang_item(fn_trait.lang_item_name()).and_then(|it| it.as_trait())
+                == Some(trait_)
+        })
+    }
+
+    fn resolve_fn_once_output(&self) -> Option<TypeAliasId> {
+        let trait_ = self.resolve_lang_item(FnTrait::FnOnce.lang_item_name())?.as_trait()?;
+        self.db.trait_data(trait_).associated_type_by_name(&name![Output])
+    }
+
     fn resolve_boxed_box(&self) -> Option<AdtId> {
         let struct_ = self.resolve_lang_item("owned_box")?.as_struct()?;
         Some(struct_.into())
@@ -574,6 +758,16 @@ impl<'a> InferenceContext<'a> {
         let trait_ = self.resolve_ops_index()?;
         self.db.trait_data(trait_).associated_type_by_name(&name![Output])
     }
+
+    fn resolve_ops_index_method(&self) -> Option<FunctionId> {
+        let trait_ = self.resolve_ops_index()?;
+        self.db.trait_data(trait_).method_by_name(&name![index])
+    }
+
+    fn resolve_ops_index_mut_method(&self) -> Option<FunctionId> {
+        let trait_ = self.resolve_lang_item("index_mut")?.as_trait()?;
+        self.db.trait_data(trait_).method_by_name(&name![index_mut])
+    }
 }
 
 /// The kinds of placeholders we need during type inference. There's separate
@@ -663,14 +857,46 @@ impl Expectation {
 }
 
 mod diagnostics {
-    use hir_def::{expr::ExprId, src::HasSource, FunctionId, Lookup};
-    use hir_expand::diagnostics::DiagnosticSink;
+    use hir_def::{expr::ExprId, path::Path, src::HasSource, FunctionId, HasModule, Lookup};
+    use hir_expand::{diagnostics::DiagnosticSink, hygiene::Hygiene, InFile};
+    use ra_syntax::{ast, AstNode, AstPtr, SyntaxNode, SyntaxNodePtr};
+
+    use super::ExprOrPatId;
+    use crate::{
+        db::HirDatabase,
+        diagnostics::{NoSuchField, UnresolvedPath},
+    };
 
-    use crate::{db::HirDatabase, diagnostics::NoSuchField};
+    /// Where a type that is lowered during inference is written.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub(super) enum TypeSource {
+        /// The `n`th parameter of the function, counting `self`.
+        Param(usize),
+        /// The return type of the function, or the type of a const or static.
+        RetType,
+        /// The signature of a closure, a cast, or the generic arguments of a
+        /// method call or path written in the expression, or the type of the
+        /// `let` binding the pattern.
+        Body(ExprOrPatId),
+    }
 
     #[derive(Debug, PartialEq, Eq, Clone)]
     pub(super) enum InferenceDiagnostic {
-        NoSuchField { expr: ExprId, field: usize },
+        NoSuchField {
+            expr: ExprId,
+            field: usize,
+        },
+        /// The path of a path expression, record literal or pattern couldn't be
+        /// resolved.
+        UnresolvedPath {
+            id: ExprOrPatId,
+        },
+        /// A path in a type couldn't be resolved. Types have no source map,
+        /// so this is mapped back to the matching paths in `source`.
+        UnresolvedTypePath {
+            source: TypeSource,
+            path: Path,
+        },
     }
 
     impl InferenceDiagnostic {
@@ -687,7 +913,101 @@ mod diagnostics {
                     let field = source_map.field_syntax(*expr, *field);
                     sink.push(NoSuchField { file, field })
                 }
+                InferenceDiagnostic::UnresolvedPath { id } => {
+                    if has_unexpanded_proc_macros(db, owner) {
+                        return;
+                    }
+                    let file = owner.lookup(db.upcast()).source(db.upcast()).file_id;
+                    // Paths produced by macros can't be fixed at the call
+                    // site, and record field shorthands have no path.
+                    let node = match body_syntax(db, owner, *id) {
+                        Some(node) if node.file_id == file => node,
+                        _ => return,
+                    };
+                    let root = db.parse_or_expand(file).unwrap();
+                    let path = node.value.to_node(&root).children().find_map(ast::Path::cast);
+                    if let Some(path) = path {
+                        sink.push(UnresolvedPath { file, path: AstPtr::new(&path) })
+                    }
+                }
+                InferenceDiagnostic::UnresolvedTypePath { source, path } => {
+                    if has_unexpanded_proc_macros(db, owner) {
+                        return;
+                    }
+                    let fn_src = owner.lookup(db.upcast()).source(db.upcast());
+                    let file = fn_src.file_id;
+                    let node = match source {
+                        TypeSource::Param(idx) => fn_src.value.param_list().and_then(|it| {
+                            let self_param = it.self_param().map(|it| it.syntax().clone());
+                            let params = it.params().map(|it| it.syntax().clone());
+                            self_param.into_iter().chain(params).nth(*idx)
+                        }),
+                        TypeSource::RetType => {
+                            fn_src.value.ret_type().map(|it| it.syntax().clone())
+                        }
+                        TypeSource::Body(id) => {
+                            let node = match body_syntax(db, owner, *id) {
+                                Some(node) if node.file_id == file => node,
+                                _ => return,
+                            };
+                            let root = db.parse_or_expand(file).unwrap();
+                            let node = node.value.to_node(&root);
+                            match id {
+                                // The type of a `let` is a sibling of its pattern.
+                                ExprOrPatId::PatId(_) => node.parent(),
+                                ExprOrPatId::ExprId(_) => Some(node),
+                            }
+                        }
+                    };
+                    let node = match node {
+                        Some(it) => it,
+                        None => return,
+                    };
+                    let hygiene = Hygiene::new(db.upcast(), file);
+                    for it in type_paths(&node) {
+                        if Path::from_src(it.clone(), &hygiene).as_ref() == Some(path) {
+                            sink.push(UnresolvedPath { file, path: AstPtr::new(&it) })
+                        }
+                    }
+                }
             }
         }
     }
+
+    fn body_syntax(
+        db: &dyn HirDatabase,
+        owner: FunctionId,
+        id: ExprOrPatId,
+    ) -> Option<InFile<SyntaxNodePtr>> {
+        let (_, source_map) = db.body_with_source_map(owner.into());
+        match id {
+            ExprOrPatId::ExprId(expr) => source_map
+                .expr_syntax(expr)
+                .ok()
+                .and_then(|it| it.map(|ptr| ptr.left().map(|it| it.syntax_node_ptr())).transpose()),
+            ExprOrPatId::PatId(pat) => source_map
+                .pat_syntax(pat)
+                .ok()
+                .and_then(|it| it.map(|ptr| ptr.left().map(|it| it.syntax_node_ptr())).transpose()),
+        }
+    }
+
+    /// The paths of the types written directly in `node`, leaving out the
+    /// ones in nested expressions and items, which are lowered on their own.
+    fn type_paths(node: &SyntaxNode) -> Vec<ast::Path> {
+        let mut res = Vec::new();
+        for child in node.children() {
+            if ast::Expr::can_cast(child.kind()) || ast::ModuleItem::can_cast(child.kind()) {
+                continue;
+            }
+            res.extend(ast::PathType::cast(child.clone()).and_then(|it| it.path()));
+            res.extend(type_paths(&child));
+        }
+        res
+    }
+
+    fn has_unexpanded_proc_macros(db: &dyn HirDatabase, owner: FunctionId) -> bool {
+        let krate = owner.lookup(db.upcast()).module(db.upcast()).krate;
+        db.crate_def_map(krate).has_unexpanded_proc_macros()
+    }
 }
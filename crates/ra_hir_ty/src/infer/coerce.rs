@@ -7,7 +7,7 @@
 use hir_def::{lang_item::LangItemTarget, type_ref::Mutability};
 use test_utils::tested_by;
 
-use crate::{autoderef, traits::Solution, Obligation, Substs, TraitRef, Ty, TypeCtor};
+use crate::{autoderef, traits::Solution, Canonical, Obligation, Substs, TraitRef, Ty, TypeCtor};
 
 use super::{unify::TypeVarValue, InEnvironment, InferTy, InferenceContext};
 
@@ -104,9 +104,9 @@ impl<'a> InferenceContext<'a> {
 
         // Auto Deref if cannot coerce
         match (&from_ty, to_ty) {
-            // FIXME: DerefMut
-            (ty_app!(TypeCtor::Ref(_), st1), ty_app!(TypeCtor::Ref(_), st2)) => {
-                self.unify_autoderef_behind_ref(&st1[0], &st2[0])
+            // After the weakening above, both references have the same mutability
+            (ty_app!(TypeCtor::Ref(mutability), st1), ty_app!(TypeCtor::Ref(_), st2)) => {
+                self.unify_autoderef_behind_ref(&st1[0], &st2[0], *mutability)
             }
 
             // Otherwise, normal unify
@@ -152,13 +152,19 @@ impl<'a> InferenceContext<'a> {
         Some(true)
     }
 
-    /// Unify `from_ty` to `to_ty` with optional auto Deref
+    /// Unify `from_ty` to `to_ty` with optional auto Deref, or auto DerefMut
+    /// if the references are mutable.
     ///
     /// Note that the parameters are already stripped the outer reference.
-    fn unify_autoderef_behind_ref(&mut self, from_ty: &Ty, to_ty: &Ty) -> bool {
+    fn unify_autoderef_behind_ref(
+        &mut self,
+        from_ty: &Ty,
+        to_ty: &Ty,
+        mutability: Mutability,
+    ) -> bool {
         let canonicalized = self.canonicalizer().canonicalize_ty(from_ty.clone());
         let to_ty = self.resolve_ty_shallow(&to_ty);
-        // FIXME: Auto DerefMut
+        let mut prev_ty: Option<Canonical<Ty>> = None;
         for derefed_ty in autoderef::autoderef(
             self.db,
             self.resolver.krate(),
@@ -167,10 +173,25 @@ impl<'a> InferenceContext<'a> {
                 environment: self.trait_env.clone(),
             },
         ) {
+            if let (Some(prev_ty), Some(krate)) = (&prev_ty, self.resolver.krate()) {
+                let env = self.trait_env.clone();
+                if mutability == Mutability::Mut
+                    && !autoderef::can_deref_mut(
+                        self.db,
+                        krate,
+                        InEnvironment { value: prev_ty, environment: env },
+                    )
+                {
+                    break;
+                }
+            }
+            prev_ty = Some(derefed_ty.clone());
             let derefed_ty = canonicalized.decanonicalize_ty(derefed_ty.value);
             match (&*self.resolve_ty_shallow(&derefed_ty), &*to_ty) {
                 // Stop when constructor matches.
-                (ty_app!(from_ctor, st1), ty_app!(to_ctor, st2)) if from_ctor == to_ctor => {
+                (ty_app!(from_ctor, st1), ty_app!(to_ctor, st2))
+                    if from_ctor.unifies_with(*to_ctor) =>
+                {
                     // It will not recurse to `coerce`.
                     return self.table.unify_substs(st1, st2, 0);
                 }
@@ -1,37 +1,42 @@
 //! Type inference for expressions.
 
 use std::iter::{repeat, repeat_with};
-use std::sync::Arc;
+use std::{mem, sync::Arc};
 
 use hir_def::{
     builtin_type::Signedness,
-    expr::{Array, BinaryOp, Expr, ExprId, Literal, Statement, UnaryOp},
+    expr::{Array, BinaryOp, Expr, ExprId, LabelId, Literal, PatId, Statement, UnaryOp},
     path::{GenericArg, GenericArgs},
-    resolver::resolver_for_expr,
+    resolver::{resolver_for_expr, ValueNs},
+    type_ref::{ConstRef, TypeRef},
     AdtId, AssocContainerId, Lookup, StructFieldId,
 };
 use hir_expand::name::Name;
 use ra_syntax::ast::RangeOp;
+use rustc_hash::FxHashSet;
 
 use crate::{
-    autoderef, method_resolution, op,
+    autoderef,
+    consteval::{self, ConstEvalCtx},
+    method_resolution::{self, ReceiverAdjustments},
+    op,
     traits::InEnvironment,
     utils::{generics, variant_data, Generics},
     ApplicationTy, Binders, CallableDef, InferTy, IntTy, Mutability, Obligation, Substs, TraitRef,
-    Ty, TypeCtor, Uncertain,
+    Ty, TyLoweringContext, TypeCtor, Uncertain,
 };
 
-use super::{BindingMode, Expectation, InferenceContext, InferenceDiagnostic, TypeMismatch};
+use super::{
+    diagnostics::TypeSource, Adjust, Adjustment, BindingMode, BreakableContext, Diverges,
+    Expectation, InferenceContext, InferenceDiagnostic,
+};
 
 impl<'a> InferenceContext<'a> {
     pub(super) fn infer_expr(&mut self, tgt_expr: ExprId, expected: &Expectation) -> Ty {
         let ty = self.infer_expr_inner(tgt_expr, expected);
         let could_unify = self.unify(&ty, &expected.ty);
         if !could_unify {
-            self.result.type_mismatches.insert(
-                tgt_expr,
-                TypeMismatch { expected: expected.ty.clone(), actual: ty.clone() },
-            );
+            self.write_type_mismatch(tgt_expr.into(), expected.ty.clone(), ty.clone());
         }
         self.resolve_ty_as_possible(ty)
     }
@@ -41,9 +46,7 @@ impl<'a> InferenceContext<'a> {
     pub(super) fn infer_expr_coerce(&mut self, expr: ExprId, expected: &Expectation) -> Ty {
         let ty = self.infer_expr_inner(expr, &expected);
         let ty = if !self.coerce(&ty, &expected.coercion_target()) {
-            self.result
-                .type_mismatches
-                .insert(expr, TypeMismatch { expected: expected.ty.clone(), actual: ty.clone() });
+            self.write_type_mismatch(expr.into(), expected.ty.clone(), ty.clone());
             // Return actual type when type mismatch.
             // This is needed for diagnostic when return type mismatch.
             ty
@@ -64,79 +67,137 @@ impl<'a> InferenceContext<'a> {
                 // if let is desugared to match, so this is always simple if
                 self.infer_expr(*condition, &Expectation::has_type(Ty::simple(TypeCtor::Bool)));
 
+                let condition_diverges = mem::replace(&mut self.diverges, Diverges::Maybe);
                 let then_ty = self.infer_expr_inner(*then_branch, &expected);
+                let then_diverges = mem::replace(&mut self.diverges, Diverges::Maybe);
                 let else_ty = match else_branch {
                     Some(else_branch) => self.infer_expr_inner(*else_branch, &expected),
                     None => Ty::unit(),
                 };
+                self.diverges = condition_diverges | (then_diverges & self.diverges);
 
                 self.coerce_merge_branch(&then_ty, &else_ty)
             }
-            Expr::Block { statements, tail } => self.infer_block(statements, *tail, expected),
+            Expr::Block { statements, tail, label: None } => {
+                self.infer_block(statements, *tail, expected)
+            }
+            Expr::Block { statements, tail, label: Some(label) } => {
+                let diverges = self.diverges;
+                let break_ty = self.table.new_type_var();
+                self.breakables.push(BreakableContext {
+                    may_break: false,
+                    break_ty: break_ty.clone(),
+                    label: Some(*label),
+                    is_loop: false,
+                });
+                let ty =
+                    self.infer_block(statements, *tail, &Expectation::has_type(break_ty.clone()));
+                let ctxt = self.breakables.pop().expect("breakable stack broken");
+                if ctxt.may_break {
+                    self.diverges = diverges;
+                    ctxt.break_ty
+                } else {
+                    ty
+                }
+            }
             Expr::TryBlock { body } => {
                 let _inner = self.infer_expr(*body, expected);
                 // FIXME should be std::result::Result<{inner}, _>
                 Ty::Unknown
             }
-            Expr::Loop { body } => {
-                self.infer_expr(*body, &Expectation::has_type(Ty::unit()));
-                // FIXME handle break with value
-                Ty::simple(TypeCtor::Never)
+            Expr::Unsafe { body } => self.infer_expr(*body, expected),
+            Expr::Loop { body, label } => {
+                let diverges = self.diverges;
+                let break_ty = self.table.new_type_var();
+                let ctxt = self.infer_loop_body(*body, break_ty, *label);
+                if ctxt.may_break {
+                    self.diverges = diverges;
+                    ctxt.break_ty
+                } else {
+                    self.diverges = Diverges::Always;
+                    Ty::simple(TypeCtor::Never)
+                }
             }
-            Expr::While { condition, body } => {
+            Expr::While { condition, body, label } => {
                 // while let is desugared to a match loop, so this is always simple while
                 self.infer_expr(*condition, &Expectation::has_type(Ty::simple(TypeCtor::Bool)));
-                self.infer_expr(*body, &Expectation::has_type(Ty::unit()));
+                let diverges = self.diverges;
+                self.infer_loop_body(*body, Ty::unit(), *label);
+                // the body might never run
+                self.diverges = diverges;
                 Ty::unit()
             }
-            Expr::For { iterable, body, pat } => {
+            Expr::For { iterable, body, pat, label } => {
                 let iterable_ty = self.infer_expr(*iterable, &Expectation::none());
 
                 let pat_ty =
                     self.resolve_associated_type(iterable_ty, self.resolve_into_iter_item());
 
                 self.infer_pat(*pat, &pat_ty, BindingMode::default());
-                self.infer_expr(*body, &Expectation::has_type(Ty::unit()));
+                let diverges = self.diverges;
+                self.infer_loop_body(*body, Ty::unit(), *label);
+                // the body might never run
+                self.diverges = diverges;
                 Ty::unit()
             }
             Expr::Lambda { body, args, ret_type, arg_types } => {
                 assert_eq!(args.len(), arg_types.len());
 
-                let mut sig_tys = Vec::new();
-
-                for (arg_pat, arg_type) in args.iter().zip(arg_types.iter()) {
-                    let expected = if let Some(type_ref) = arg_type {
-                        self.make_ty(type_ref)
-                    } else {
-                        Ty::Unknown
-                    };
-                    let arg_ty = self.infer_pat(*arg_pat, &expected, BindingMode::default());
-                    sig_tys.push(arg_ty);
-                }
+                let mut sig_tys: Vec<Ty> = arg_types
+                    .iter()
+                    .map(|arg_type| match arg_type {
+                        Some(type_ref) => self.make_ty(type_ref, TypeSource::Body(tgt_expr.into())),
+                        None => self.table.new_type_var(),
+                    })
+                    .collect();
 
                 // add return type
                 let ret_ty = match ret_type {
-                    Some(type_ref) => self.make_ty(type_ref),
+                    Some(type_ref) => self.make_ty(type_ref, TypeSource::Body(tgt_expr.into())),
                     None => self.table.new_type_var(),
                 };
                 sig_tys.push(ret_ty.clone());
                 let sig_ty = Ty::apply(
                     TypeCtor::FnPtr { num_args: sig_tys.len() as u16 - 1 },
-                    Substs(sig_tys.into()),
+                    Substs(sig_tys.clone().into()),
+                );
+                // The captured types are only known after inferring the body.
+                let captures_ty = self.table.new_type_var();
+                let closure_ty = Ty::apply(
+                    TypeCtor::Closure { def: self.owner, expr: tgt_expr },
+                    Substs(vec![sig_ty, captures_ty.clone()].into()),
                 );
-                let closure_ty =
-                    Ty::apply_one(TypeCtor::Closure { def: self.owner, expr: tgt_expr }, sig_ty);
 
                 // Eagerly try to relate the closure type with the expected
                 // type, otherwise we often won't have enough information to
                 // infer the body.
                 self.coerce(&closure_ty, &expected.ty);
+                self.deduce_closure_signature(tgt_expr, &sig_tys[..args.len()], &ret_ty);
 
-                let prev_ret_ty = std::mem::replace(&mut self.return_ty, ret_ty.clone());
+                for (arg_pat, arg_ty) in args.iter().zip(sig_tys.iter()) {
+                    let arg_ty = self.resolve_ty_as_possible(arg_ty.clone());
+                    self.infer_pat(*arg_pat, &arg_ty, BindingMode::default());
+                }
+
+                let prev_ret_ty = mem::replace(&mut self.return_ty, ret_ty.clone());
+                let prev_diverges = mem::replace(&mut self.diverges, Diverges::Maybe);
+                let prev_breakables = mem::replace(&mut self.breakables, Vec::new());
 
                 self.infer_expr_coerce(*body, &Expectation::has_type(ret_ty));
 
                 self.return_ty = prev_ret_ty;
+                self.diverges = prev_diverges;
+                self.breakables = prev_breakables;
+
+                let captured_tys: Vec<_> = self
+                    .closure_captures(tgt_expr, *body)
+                    .into_iter()
+                    .map(|pat| self.result.type_of_pat.get(pat).cloned().unwrap_or(Ty::Unknown))
+                    .collect();
+                let cardinality = captured_tys.len() as u16;
+                let captures =
+                    Ty::apply(TypeCtor::Tuple { cardinality }, Substs(captured_tys.into()));
+                self.unify(&captures_ty, &captures);
 
                 closure_ty
             }
@@ -165,7 +226,10 @@ impl<'a> InferenceContext<'a> {
                     self.table.new_type_var()
                 };
 
+                let input_diverges = self.diverges;
+                let mut all_arms_diverge = Diverges::Always;
                 for arm in arms {
+                    self.diverges = Diverges::Maybe;
                     let _pat_ty = self.infer_pat(arm.pat, &input_ty, BindingMode::default());
                     if let Some(guard_expr) = arm.guard {
                         self.infer_expr(
@@ -175,8 +239,10 @@ impl<'a> InferenceContext<'a> {
                     }
 
                     let arm_ty = self.infer_expr_inner(arm.expr, &expected);
+                    all_arms_diverge = all_arms_diverge & self.diverges;
                     result_ty = self.coerce_merge_branch(&result_ty, &arm_ty);
                 }
+                self.diverges = input_diverges | all_arms_diverge;
 
                 result_ty
             }
@@ -185,11 +251,22 @@ impl<'a> InferenceContext<'a> {
                 let resolver = resolver_for_expr(self.db.upcast(), self.owner, tgt_expr);
                 self.infer_path(&resolver, p, tgt_expr.into()).unwrap_or(Ty::Unknown)
             }
-            Expr::Continue => Ty::simple(TypeCtor::Never),
-            Expr::Break { expr } => {
-                if let Some(expr) = expr {
-                    // FIXME handle break with value
-                    self.infer_expr(*expr, &Expectation::none());
+            Expr::Continue { .. } => Ty::simple(TypeCtor::Never),
+            Expr::Break { expr, label } => {
+                let break_ty = match self.find_breakable(*label) {
+                    Some(ctxt) => ctxt.break_ty.clone(),
+                    None => Ty::Unknown,
+                };
+                match expr {
+                    Some(expr) => {
+                        self.infer_expr_coerce(*expr, &Expectation::has_type(break_ty));
+                    }
+                    None => {
+                        self.coerce(&Ty::unit(), &break_ty);
+                    }
+                }
+                if let Some(ctxt) = self.find_breakable(*label) {
+                    ctxt.may_break = true;
                 }
                 Ty::simple(TypeCtor::Never)
             }
@@ -203,7 +280,7 @@ impl<'a> InferenceContext<'a> {
                 Ty::simple(TypeCtor::Never)
             }
             Expr::RecordLit { path, fields, spread } => {
-                let (ty, def_id) = self.resolve_variant(path.as_ref());
+                let (ty, def_id) = self.resolve_variant(path.as_ref(), tgt_expr.into());
                 if let Some(variant) = def_id {
                     self.write_variant_resolution(tgt_expr.into(), variant);
                 }
@@ -241,8 +318,8 @@ impl<'a> InferenceContext<'a> {
             }
             Expr::Field { expr, name } => {
                 let receiver_ty = self.infer_expr_inner(*expr, &Expectation::none());
-                let canonicalized = self.canonicalizer().canonicalize_ty(receiver_ty);
-                let ty = autoderef::autoderef(
+                let canonicalized = self.canonicalizer().canonicalize_ty(receiver_ty.clone());
+                let field_ty = autoderef::autoderef(
                     self.db,
                     self.resolver.krate(),
                     InEnvironment {
@@ -250,27 +327,38 @@ impl<'a> InferenceContext<'a> {
                         environment: self.trait_env.clone(),
                     },
                 )
-                .find_map(|derefed_ty| match canonicalized.decanonicalize_ty(derefed_ty.value) {
-                    Ty::Apply(a_ty) => match a_ty.ctor {
-                        TypeCtor::Tuple { .. } => name
-                            .as_tuple_index()
-                            .and_then(|idx| a_ty.parameters.0.get(idx).cloned()),
-                        TypeCtor::Adt(AdtId::StructId(s)) => {
-                            self.db.struct_data(s).variant_data.field(name).map(|local_id| {
-                                let field = StructFieldId { parent: s.into(), local_id };
-                                self.write_field_resolution(tgt_expr, field);
-                                self.db.field_types(s.into())[field.local_id]
-                                    .clone()
-                                    .subst(&a_ty.parameters)
-                            })
-                        }
-                        // FIXME:
-                        TypeCtor::Adt(AdtId::UnionId(_)) => None,
+                .enumerate()
+                .find_map(|(autoderefs, derefed_ty)| {
+                    let field_ty = match canonicalized.decanonicalize_ty(derefed_ty.value) {
+                        Ty::Apply(a_ty) => match a_ty.ctor {
+                            TypeCtor::Tuple { .. } => name
+                                .as_tuple_index()
+                                .and_then(|idx| a_ty.parameters.0.get(idx).cloned()),
+                            TypeCtor::Adt(AdtId::StructId(s)) => {
+                                self.db.struct_data(s).variant_data.field(name).map(|local_id| {
+                                    let field = StructFieldId { parent: s.into(), local_id };
+                                    self.write_field_resolution(tgt_expr, field);
+                                    self.db.field_types(s.into())[field.local_id]
+                                        .clone()
+                                        .subst(&a_ty.parameters)
+                                })
+                            }
+                            // FIXME:
+                            TypeCtor::Adt(AdtId::UnionId(_)) => None,
+                            _ => None,
+                        },
                         _ => None,
-                    },
-                    _ => None,
-                })
-                .unwrap_or(Ty::Unknown);
+                    };
+                    field_ty.map(|ty| (ty, autoderefs))
+                });
+                let ty = match field_ty {
+                    Some((ty, autoderefs)) => {
+                        let adjustments = ReceiverAdjustments { autoderefs, autoref: None };
+                        self.write_autoderef_adjustments(*expr, &receiver_ty, adjustments);
+                        ty
+                    }
+                    None => Ty::Unknown,
+                };
                 let ty = self.insert_type_vars(ty);
                 self.normalize_associated_types_in(ty)
             }
@@ -284,7 +372,7 @@ impl<'a> InferenceContext<'a> {
             }
             Expr::Cast { expr, type_ref } => {
                 let _inner_ty = self.infer_expr_inner(*expr, &Expectation::none());
-                let cast_ty = self.make_ty(type_ref);
+                let cast_ty = self.make_ty(type_ref, TypeSource::Body(tgt_expr.into()));
                 // FIXME check the cast...
                 cast_ty
             }
@@ -300,6 +388,9 @@ impl<'a> InferenceContext<'a> {
                         Expectation::none()
                     };
                 let inner_ty = self.infer_expr_inner(*expr, &expectation);
+                if *mutability == Mutability::Mut {
+                    self.convert_place_to_mut(*expr);
+                }
                 Ty::apply_one(TypeCtor::Ref(*mutability), inner_ty)
             }
             Expr::Box { expr } => {
@@ -375,13 +466,27 @@ impl<'a> InferenceContext<'a> {
                         _ => Expectation::none(),
                     };
                     let lhs_ty = self.infer_expr(*lhs, &lhs_expectation);
-                    // FIXME: find implementation of trait corresponding to operation
-                    // symbol and resolve associated `Output` type
+                    if let BinaryOp::Assignment { .. } = op {
+                        self.convert_place_to_mut(*lhs);
+                    }
                     let rhs_expectation = op::binary_op_rhs_expectation(*op, lhs_ty.clone());
                     let rhs_ty = self.infer_expr(*rhs, &Expectation::has_type(rhs_expectation));
 
-                    // FIXME: similar as above, return ty is often associated trait type
-                    op::binary_op_return_ty(*op, lhs_ty, rhs_ty)
+                    if let Some(method) = self.resolve_binary_op_method(*op) {
+                        self.write_method_resolution(tgt_expr, method);
+                    }
+
+                    match (op, op::binary_op_return_ty(*op, lhs_ty.clone(), rhs_ty.clone())) {
+                        // Not a builtin operation, so the result is the `Output` of the
+                        // corresponding `std::ops` trait
+                        (BinaryOp::ArithOp(_), Ty::Unknown) => self
+                            .resolve_associated_type_with_params(
+                                lhs_ty,
+                                self.resolve_binary_op_output(*op),
+                                &[rhs_ty],
+                            ),
+                        (_, ty) => ty,
+                    }
                 }
                 _ => Ty::Unknown,
             },
@@ -430,16 +535,24 @@ impl<'a> InferenceContext<'a> {
                 if let (Some(index_trait), Some(krate)) =
                     (self.resolve_ops_index(), self.resolver.krate())
                 {
-                    let canonicalized = self.canonicalizer().canonicalize_ty(base_ty);
-                    let self_ty = method_resolution::resolve_indexing_op(
+                    let canonicalized = self.canonicalizer().canonicalize_ty(base_ty.clone());
+                    let resolved = method_resolution::resolve_indexing_op(
                         self.db,
                         &canonicalized.value,
                         self.trait_env.clone(),
                         krate,
                         index_trait,
                     );
-                    let self_ty =
-                        self_ty.map_or(Ty::Unknown, |t| canonicalized.decanonicalize_ty(t.value));
+                    let self_ty = match resolved {
+                        Some((self_ty, adjustments)) => {
+                            if let Some(index_method) = self.resolve_ops_index_method() {
+                                self.write_method_resolution(tgt_expr, index_method);
+                            }
+                            self.write_autoderef_adjustments(*base, &base_ty, adjustments);
+                            canonicalized.decanonicalize_ty(self_ty.value)
+                        }
+                        None => Ty::Unknown,
+                    };
                     self.resolve_associated_type_with_params(
                         self_ty,
                         self.resolve_ops_index_output(),
@@ -468,17 +581,18 @@ impl<'a> InferenceContext<'a> {
             }
             Expr::Array(array) => {
                 let elem_ty = match &expected.ty {
-                    ty_app!(TypeCtor::Array, st) | ty_app!(TypeCtor::Slice, st) => {
+                    ty_app!(TypeCtor::Array(_), st) | ty_app!(TypeCtor::Slice, st) => {
                         st.as_single().clone()
                     }
                     _ => self.table.new_type_var(),
                 };
 
-                match array {
+                let len = match array {
                     Array::ElementList(items) => {
                         for expr in items.iter() {
                             self.infer_expr_coerce(*expr, &Expectation::has_type(elem_ty.clone()));
                         }
+                        Uncertain::Known(items.len() as u64)
                     }
                     Array::Repeat { initializer, repeat } => {
                         self.infer_expr_coerce(
@@ -491,10 +605,11 @@ impl<'a> InferenceContext<'a> {
                                 IntTy::usize(),
                             )))),
                         );
+                        self.eval_array_len(*repeat)
                     }
-                }
+                };
 
-                Ty::apply_one(TypeCtor::Array, elem_ty)
+                Ty::apply_one(TypeCtor::Array(len), elem_ty)
             }
             Expr::Literal(lit) => match lit {
                 Literal::Bool(..) => Ty::simple(TypeCtor::Bool),
@@ -514,22 +629,40 @@ impl<'a> InferenceContext<'a> {
         // use a new type variable if we got Ty::Unknown here
         let ty = self.insert_type_vars_shallow(ty);
         let ty = self.resolve_ty_as_possible(ty);
+        if let ty_app!(TypeCtor::Never) = ty {
+            self.diverges = Diverges::Always;
+        }
+        if self.diverges.is_always() {
+            self.result.diverging_exprs.insert(tgt_expr);
+        }
         self.write_expr_ty(tgt_expr, ty.clone());
         ty
     }
 
+    fn eval_array_len(&mut self, expr: ExprId) -> Uncertain<u64> {
+        let body = Arc::clone(&self.body);
+        let (db, owner) = (self.db, self.owner);
+        let mut expr_ty = |expr: ExprId| {
+            let ty = self.result.type_of_expr.get(expr).cloned().unwrap_or(Ty::Unknown);
+            self.resolve_ty_as_possible(ty)
+        };
+        let value = ConstEvalCtx { db, owner, body: &body, expr_ty: &mut expr_ty }.eval(expr);
+        consteval::array_len(value)
+    }
+
     fn infer_block(
         &mut self,
         statements: &[Statement],
         tail: Option<ExprId>,
         expected: &Expectation,
     ) -> Ty {
-        let mut diverges = false;
         for stmt in statements {
             match stmt {
                 Statement::Let { pat, type_ref, initializer } => {
-                    let decl_ty =
-                        type_ref.as_ref().map(|tr| self.make_ty(tr)).unwrap_or(Ty::Unknown);
+                    let decl_ty = type_ref
+                        .as_ref()
+                        .map(|tr| self.make_ty(tr, TypeSource::Body((*pat).into())))
+                        .unwrap_or(Ty::Unknown);
 
                     // Always use the declared type when specified
                     let mut ty = decl_ty.clone();
@@ -546,23 +679,19 @@ impl<'a> InferenceContext<'a> {
                     self.infer_pat(*pat, &ty, BindingMode::default());
                 }
                 Statement::Expr(expr) => {
-                    if let ty_app!(TypeCtor::Never) = self.infer_expr(*expr, &Expectation::none()) {
-                        diverges = true;
-                    }
+                    self.infer_expr(*expr, &Expectation::none());
                 }
             }
         }
 
-        let ty = if let Some(expr) = tail {
+        if let Some(expr) = tail {
             self.infer_expr_coerce(expr, expected)
+        } else if self.diverges.is_always() {
+            // A block that never reaches its end can have any type.
+            Ty::simple(TypeCtor::Never)
         } else {
             self.coerce(&Ty::unit(), expected.coercion_target());
             Ty::unit()
-        };
-        if diverges {
-            Ty::simple(TypeCtor::Never)
-        } else {
-            ty
         }
     }
 
@@ -589,15 +718,17 @@ impl<'a> InferenceContext<'a> {
                 method_name,
             )
         });
-        let (derefed_receiver_ty, method_ty, def_generics) = match resolved {
-            Some((ty, func)) => {
+        let (derefed_receiver_ty, method_ty, def_generics, adjustments) = match resolved {
+            Some((ty, func, adjustments)) => {
                 let ty = canonicalized_receiver.decanonicalize_ty(ty);
                 self.write_method_resolution(tgt_expr, func);
-                (ty, self.db.value_ty(func.into()), Some(generics(self.db.upcast(), func.into())))
+                let method_ty = self.db.value_ty(func.into());
+                (ty, method_ty, Some(generics(self.db.upcast(), func.into())), Some(adjustments))
             }
-            None => (receiver_ty, Binders::new(0, Ty::Unknown), None),
+            None => (receiver_ty.clone(), Binders::new(0, Ty::Unknown), None, None),
         };
-        let substs = self.substs_for_method_call(def_generics, generic_args, &derefed_receiver_ty);
+        let substs =
+            self.substs_for_method_call(tgt_expr, def_generics, generic_args, &derefed_receiver_ty);
         let method_ty = method_ty.subst(&substs);
         let method_ty = self.insert_type_vars(method_ty);
         self.register_obligations_for_call(&method_ty);
@@ -611,11 +742,11 @@ impl<'a> InferenceContext<'a> {
             }
             None => (Ty::Unknown, Vec::new(), Ty::Unknown),
         };
-        // Apply autoref so the below unification works correctly
-        // FIXME: return correct autorefs from lookup_method
-        let actual_receiver_ty = match expected_receiver_ty.as_reference() {
-            Some((_, mutability)) => Ty::apply_one(TypeCtor::Ref(mutability), derefed_receiver_ty),
-            _ => derefed_receiver_ty,
+        let actual_receiver_ty = match adjustments {
+            Some(adjustments) => {
+                self.write_autoderef_adjustments(receiver, &receiver_ty, adjustments)
+            }
+            None => derefed_receiver_ty,
         };
         self.unify(&expected_receiver_ty, &actual_receiver_ty);
 
@@ -647,17 +778,191 @@ impl<'a> InferenceContext<'a> {
         }
     }
 
+    /// Records the adjustments that take `expr` of type `ty` `autoderefs`
+    /// steps down its method receiver deref chain and then apply the
+    /// autoref, returning the adjusted type.
+    fn write_autoderef_adjustments(
+        &mut self,
+        expr: ExprId,
+        ty: &Ty,
+        adjustments: ReceiverAdjustments,
+    ) -> Ty {
+        let canonicalized = self.canonicalizer().canonicalize_ty(ty.clone());
+        let deref_chain = match self.resolver.krate() {
+            Some(krate) => method_resolution::autoderef_method_receiver(
+                self.db,
+                krate,
+                InEnvironment {
+                    value: canonicalized.value.clone(),
+                    environment: self.trait_env.clone(),
+                },
+            ),
+            None => Vec::new(),
+        };
+
+        let mut result = Vec::new();
+        let mut ty = ty.clone();
+        for step in deref_chain.windows(2).take(adjustments.autoderefs) {
+            let kind = match (&step[0].value, &step[1].value) {
+                (ty_app!(TypeCtor::Array(_)), ty_app!(TypeCtor::Slice)) => Adjust::Unsize,
+                (from, _) if from.builtin_deref().is_some() => Adjust::Deref,
+                _ => Adjust::OverloadedDeref(Mutability::Shared),
+            };
+            ty = canonicalized.decanonicalize_ty(step[1].value.clone());
+            result.push(Adjustment { kind, target: ty.clone() });
+        }
+        if let Some(mutability) = adjustments.autoref {
+            ty = Ty::apply_one(TypeCtor::Ref(mutability), ty);
+            result.push(Adjustment { kind: Adjust::Borrow(mutability), target: ty.clone() });
+        }
+        if !result.is_empty() {
+            self.result.adjustments.insert(expr, result);
+        }
+        if adjustments.autoref == Some(Mutability::Mut) {
+            self.convert_place_to_mut(expr);
+        }
+        ty
+    }
+
+    /// Called when the place `expr` is used mutably, e.g. assigned to or
+    /// borrowed with `&mut`: the overloaded derefs and indexing it consists
+    /// of then have to go through `DerefMut` and `IndexMut`.
+    fn convert_place_to_mut(&mut self, expr: ExprId) {
+        if let Some(adjustments) = self.result.adjustments.get_mut(&expr) {
+            for adjustment in adjustments.iter_mut() {
+                match &mut adjustment.kind {
+                    Adjust::OverloadedDeref(mutability) => *mutability = Mutability::Mut,
+                    // anything after a shared autoref is not used mutably
+                    Adjust::Borrow(Mutability::Shared) => break,
+                    _ => {}
+                }
+            }
+        }
+
+        let body = Arc::clone(&self.body);
+        match &body[expr] {
+            Expr::Field { expr: base, .. } | Expr::UnaryOp { expr: base, op: UnaryOp::Deref } => {
+                self.convert_place_to_mut(*base)
+            }
+            Expr::Index { base, .. } => {
+                if self.result.method_resolution(expr).is_some() {
+                    if let Some(index_mut) = self.resolve_ops_index_mut_method() {
+                        self.write_method_resolution(expr, index_mut);
+                    }
+                }
+                self.convert_place_to_mut(*base)
+            }
+            _ => {}
+        }
+    }
+
+    fn infer_loop_body(
+        &mut self,
+        body: ExprId,
+        break_ty: Ty,
+        label: Option<LabelId>,
+    ) -> BreakableContext {
+        self.breakables.push(BreakableContext { may_break: false, break_ty, label, is_loop: true });
+        self.infer_expr(body, &Expectation::has_type(Ty::unit()));
+        self.breakables.pop().expect("breakable stack broken")
+    }
+
+    /// Finds the loop or labeled block a `break` with the given label targets.
+    fn find_breakable(&mut self, label: Option<LabelId>) -> Option<&mut BreakableContext> {
+        match label {
+            Some(label) => self.breakables.iter_mut().rev().find(|ctx| ctx.label == Some(label)),
+            None => self.breakables.iter_mut().rev().find(|ctx| ctx.is_loop),
+        }
+    }
+
+    /// Uses the pending `Fn*` obligations on the closure `closure_expr`, e.g.
+    /// from an `F: FnMut(Self::Item) -> B` bound on the function it is passed
+    /// to, to fix its parameter and return types before the body is inferred.
+    fn deduce_closure_signature(&mut self, closure_expr: ExprId, arg_tys: &[Ty], ret_ty: &Ty) {
+        let fn_once_output = self.resolve_fn_once_output();
+        for obligation in self.obligations.clone() {
+            match obligation {
+                Obligation::Trait(trait_ref) => {
+                    if !self.is_fn_trait(trait_ref.trait_)
+                        || !self.is_closure(trait_ref.self_ty(), closure_expr)
+                    {
+                        continue;
+                    }
+                    let params = match trait_ref.substs.get(1) {
+                        Some(Ty::Apply(ApplicationTy {
+                            ctor: TypeCtor::Tuple { .. },
+                            parameters,
+                        })) if parameters.len() == arg_tys.len() => parameters.clone(),
+                        _ => continue,
+                    };
+                    for (param, arg_ty) in params.iter().zip(arg_tys.iter()) {
+                        let param = self.normalize_associated_types_in(param.clone());
+                        self.unify(&param, arg_ty);
+                    }
+                }
+                Obligation::Projection(pred) => {
+                    if Some(pred.projection_ty.associated_ty) != fn_once_output
+                        || !self.is_closure(&pred.projection_ty.parameters[0], closure_expr)
+                    {
+                        continue;
+                    }
+                    let ty = self.normalize_associated_types_in(pred.ty);
+                    self.unify(&ty, ret_ty);
+                }
+            }
+        }
+    }
+
+    /// The bindings of the enclosing body which are used in the closure. We
+    /// treat them as captured by value, regardless of the capture mode.
+    fn closure_captures(&self, closure_expr: ExprId, body: ExprId) -> Vec<PatId> {
+        let scopes = self.db.expr_scopes(self.owner);
+        let outer_bindings: FxHashSet<PatId> = scopes
+            .scope_chain(scopes.scope_for(closure_expr))
+            .flat_map(|scope| scopes.entries(scope))
+            .map(|entry| entry.pat())
+            .collect();
+        let mut captures = Vec::new();
+        let mut exprs = vec![body];
+        while let Some(expr) = exprs.pop() {
+            if let Expr::Path(path) = &self.body[expr] {
+                let resolver = resolver_for_expr(self.db.upcast(), self.owner, expr);
+                let value =
+                    resolver.resolve_path_in_value_ns_hygienic_fully(self.db.upcast(), path);
+                if let Some(ValueNs::LocalBinding(pat)) = value {
+                    if outer_bindings.contains(&pat) && !captures.contains(&pat) {
+                        captures.push(pat);
+                    }
+                }
+            }
+            self.body[expr].walk_child_exprs(|child| exprs.push(child));
+        }
+        captures
+    }
+
+    fn is_closure(&mut self, ty: &Ty, closure_expr: ExprId) -> bool {
+        match &*self.resolve_ty_shallow(ty) {
+            Ty::Apply(ApplicationTy { ctor: TypeCtor::Closure { def, expr }, .. }) => {
+                *def == self.owner && *expr == closure_expr
+            }
+            _ => false,
+        }
+    }
+
     fn substs_for_method_call(
         &mut self,
+        tgt_expr: ExprId,
         def_generics: Option<Generics>,
         generic_args: Option<&GenericArgs>,
         receiver_ty: &Ty,
     ) -> Substs {
-        let (parent_params, self_params, type_params, impl_trait_params) =
-            def_generics.as_ref().map_or((0, 0, 0, 0), |g| g.provenance_split());
+        let (parent_params, self_params, type_params, impl_trait_params, const_params) =
+            def_generics.as_ref().map_or((0, 0, 0, 0, 0), |g| g.provenance_split());
         assert_eq!(self_params, 0); // method shouldn't have another Self param
-        let total_len = parent_params + type_params + impl_trait_params;
+        let type_len = parent_params + type_params + impl_trait_params;
+        let total_len = type_len + const_params;
         let mut substs = Vec::with_capacity(total_len);
+        let mut const_substs = Vec::new();
         // Parent arguments are unknown, except for the receiver type
         if let Some(parent_generics) = def_generics.as_ref().map(|p| p.iter_parent()) {
             for (_id, param) in parent_generics {
@@ -668,20 +973,36 @@ impl<'a> InferenceContext<'a> {
                 }
             }
         }
+        // the parent's const params come after its type params
+        for _ in substs.len()..parent_params {
+            substs.push(Ty::Unknown);
+        }
         // handle provided type arguments
         if let Some(generic_args) = generic_args {
             // if args are provided, it should be all of them, but we can't rely on that
-            for arg in generic_args.args.iter().take(type_params) {
-                match arg {
-                    GenericArg::Type(type_ref) => {
-                        let ty = self.make_ty(type_ref);
+            let mut type_args = 0;
+            for arg in generic_args.args.iter() {
+                let const_ref = match arg {
+                    GenericArg::Type(type_ref) if type_args < type_params => {
+                        let ty = self.make_ty(type_ref, TypeSource::Body(tgt_expr.into()));
                         substs.push(ty);
+                        type_args += 1;
+                        continue;
                     }
-                }
+                    // a const parameter used as an argument is parsed as a type
+                    GenericArg::Type(TypeRef::Path(path)) => ConstRef::Path(path.clone()),
+                    GenericArg::Type(_) => ConstRef::Unknown,
+                    GenericArg::Const(const_ref) => const_ref.clone(),
+                };
+                let ctx = TyLoweringContext::new(self.db, &self.resolver);
+                const_substs.push(Ty::from_hir_const(&ctx, &const_ref));
             }
         };
-        let supplied_params = substs.len();
-        for _ in supplied_params..total_len {
+        for _ in substs.len()..type_len {
+            substs.push(Ty::Unknown);
+        }
+        substs.extend(const_substs.into_iter().take(const_params));
+        for _ in substs.len()..total_len {
             substs.push(Ty::Unknown);
         }
         assert_eq!(substs.len(), total_len);
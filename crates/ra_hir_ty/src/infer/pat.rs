@@ -4,15 +4,17 @@ use std::iter::repeat;
 use std::sync::Arc;
 
 use hir_def::{
-    expr::{BindingAnnotation, Pat, PatId, RecordFieldPat},
+    body::Body,
+    expr::{BindingAnnotation, Expr, Literal, Pat, PatId, RecordFieldPat},
     path::Path,
+    resolver::ValueNs,
     type_ref::Mutability,
 };
 use hir_expand::name::Name;
 use test_utils::tested_by;
 
-use super::{BindingMode, InferenceContext};
-use crate::{utils::variant_data, Substs, Ty, TypeCtor};
+use super::{BindingMode, Expectation, InferenceContext};
+use crate::{primitive::Uncertain, utils::variant_data, Substs, Ty, TypeCtor};
 
 impl<'a> InferenceContext<'a> {
     fn infer_tuple_struct_pat(
@@ -21,16 +23,29 @@ impl<'a> InferenceContext<'a> {
         subpats: &[PatId],
         expected: &Ty,
         default_bm: BindingMode,
+        id: PatId,
+        ellipsis: Option<usize>,
     ) -> Ty {
-        let (ty, def) = self.resolve_variant(path);
+        let (ty, def) = self.resolve_variant(path, id.into());
         let var_data = def.map(|it| variant_data(self.db.upcast(), it));
+        if let Some(variant) = def {
+            self.write_variant_resolution(id.into(), variant);
+        }
         self.unify(&ty, expected);
 
         let substs = ty.substs().unwrap_or_else(Substs::empty);
 
         let field_tys = def.map(|it| self.db.field_types(it)).unwrap_or_default();
+        let (pre, post) = match ellipsis {
+            Some(idx) => subpats.split_at(idx),
+            None => (&subpats[..], &[][..]),
+        };
+        let n_fields = var_data.as_ref().map_or(0, |d| d.fields().len());
+        let post_idx_offset = n_fields.saturating_sub(post.len()).max(pre.len());
 
-        for (i, &subpat) in subpats.iter().enumerate() {
+        let pre_iter = pre.iter().enumerate();
+        let post_iter = (post_idx_offset..).zip(post.iter());
+        for (i, &subpat) in pre_iter.chain(post_iter) {
             let expected_ty = var_data
                 .as_ref()
                 .and_then(|d| d.field(&Name::new_tuple_field(i)))
@@ -50,7 +65,7 @@ impl<'a> InferenceContext<'a> {
         default_bm: BindingMode,
         id: PatId,
     ) -> Ty {
-        let (ty, def) = self.resolve_variant(path);
+        let (ty, def) = self.resolve_variant(path, id.into());
         let var_data = def.map(|it| variant_data(self.db.upcast(), it));
         if let Some(variant) = def {
             self.write_variant_resolution(id.into(), variant);
@@ -80,18 +95,7 @@ impl<'a> InferenceContext<'a> {
     ) -> Ty {
         let body = Arc::clone(&self.body); // avoid borrow checker problem
 
-        let is_non_ref_pat = match &body[pat] {
-            Pat::Tuple(..)
-            | Pat::Or(..)
-            | Pat::TupleStruct { .. }
-            | Pat::Record { .. }
-            | Pat::Range { .. }
-            | Pat::Slice { .. } => true,
-            // FIXME: Path/Lit might actually evaluate to ref, but inference is unimplemented.
-            Pat::Path(..) | Pat::Lit(..) => true,
-            Pat::Wild | Pat::Bind { .. } | Pat::Ref { .. } | Pat::Missing => false,
-        };
-        if is_non_ref_pat {
+        if self.is_non_ref_pat(&body, pat) {
             while let Some((inner, mutability)) = expected.as_reference() {
                 expected = inner;
                 default_bm = match default_bm {
@@ -112,48 +116,62 @@ impl<'a> InferenceContext<'a> {
         let expected = expected;
 
         let ty = match &body[pat] {
-            Pat::Tuple(ref args) => {
+            Pat::Tuple { ref args, ellipsis } => {
                 let expectations = match expected.as_tuple() {
                     Some(parameters) => &*parameters.0,
                     _ => &[],
                 };
-                let expectations_iter = expectations.iter().chain(repeat(&Ty::Unknown));
+                let (pre, post) = match ellipsis {
+                    Some(idx) => args.split_at(*idx),
+                    None => (&args[..], &[][..]),
+                };
+                let n_uncovered_patterns = match ellipsis {
+                    Some(_) => expectations.len().saturating_sub(args.len()),
+                    None => 0,
+                };
+                let mut expectations_iter = expectations.iter().chain(repeat(&Ty::Unknown));
 
-                let inner_tys = args
-                    .iter()
-                    .zip(expectations_iter)
-                    .map(|(&pat, ty)| self.infer_pat(pat, ty, default_bm))
-                    .collect();
+                let mut inner_tys = Vec::with_capacity(n_uncovered_patterns + args.len());
+                for &pat in pre {
+                    let ty = expectations_iter.next().unwrap();
+                    inner_tys.push(self.infer_pat(pat, ty, default_bm));
+                }
+                inner_tys.extend(expectations_iter.by_ref().take(n_uncovered_patterns).cloned());
+                for &pat in post {
+                    let ty = expectations_iter.next().unwrap();
+                    inner_tys.push(self.infer_pat(pat, ty, default_bm));
+                }
 
-                Ty::apply(TypeCtor::Tuple { cardinality: args.len() as u16 }, Substs(inner_tys))
+                Ty::apply(
+                    TypeCtor::Tuple { cardinality: inner_tys.len() as u16 },
+                    Substs(inner_tys.into()),
+                )
             }
             Pat::Or(ref pats) => {
-                if let Some((first_pat, rest)) = pats.split_first() {
-                    let ty = self.infer_pat(*first_pat, expected, default_bm);
-                    for pat in rest {
-                        self.infer_pat(*pat, expected, default_bm);
-                    }
-                    ty
-                } else {
-                    Ty::Unknown
+                for pat in pats {
+                    self.infer_pat(*pat, expected, default_bm);
                 }
+                expected.clone()
             }
+            Pat::Lit(expr) => self.infer_expr(*expr, &Expectation::has_type(expected.clone())),
             Pat::Ref { pat, mutability } => {
+                // A mutability mismatch is recorded when the resulting
+                // reference type is unified with `expected` below.
                 let expectation = match expected.as_reference() {
-                    Some((inner_ty, exp_mut)) => {
-                        if *mutability != exp_mut {
-                            // FIXME: emit type error?
-                        }
-                        inner_ty
-                    }
+                    Some((inner_ty, _exp_mut)) => inner_ty,
                     _ => &Ty::Unknown,
                 };
                 let subty = self.infer_pat(*pat, expectation, default_bm);
                 Ty::apply_one(TypeCtor::Ref(*mutability), subty)
             }
-            Pat::TupleStruct { path: p, args: subpats } => {
-                self.infer_tuple_struct_pat(p.as_ref(), subpats, expected, default_bm)
-            }
+            Pat::TupleStruct { path: p, args: subpats, ellipsis } => self.infer_tuple_struct_pat(
+                p.as_ref(),
+                subpats,
+                expected,
+                default_bm,
+                pat,
+                *ellipsis,
+            ),
             Pat::Record { path: p, args: fields } => {
                 self.infer_record_pat(p.as_ref(), fields, expected, default_bm, pat)
             }
@@ -185,9 +203,11 @@ impl<'a> InferenceContext<'a> {
                 self.write_pat_ty(pat, bound_ty);
                 return inner_ty;
             }
-            Pat::Slice { prefix, slice: _slice, suffix } => {
+            Pat::Slice { prefix, slice, suffix } => {
                 let (container_ty, elem_ty) = match &expected {
-                    ty_app!(TypeCtor::Array, st) => (TypeCtor::Array, st.as_single().clone()),
+                    ty_app!(TypeCtor::Array(len), st) => {
+                        (TypeCtor::Array(*len), st.as_single().clone())
+                    }
                     ty_app!(TypeCtor::Slice, st) => (TypeCtor::Slice, st.as_single().clone()),
                     _ => (TypeCtor::Slice, Ty::Unknown),
                 };
@@ -196,6 +216,21 @@ impl<'a> InferenceContext<'a> {
                     self.infer_pat(*pat_id, &elem_ty, default_bm);
                 }
 
+                if let Some(slice_pat) = slice {
+                    // The rest pattern of `[a, rest @ .., b]` is a slice, or
+                    // a shorter array if the length of the matched one is known.
+                    let rest_ctor = match container_ty {
+                        TypeCtor::Array(Uncertain::Known(len)) => {
+                            let taken = (prefix.len() + suffix.len()) as u64;
+                            TypeCtor::Array(Uncertain::Known(len.saturating_sub(taken)))
+                        }
+                        TypeCtor::Array(Uncertain::Unknown) => TypeCtor::Array(Uncertain::Unknown),
+                        _ => TypeCtor::Slice,
+                    };
+                    let rest_ty = Ty::apply_one(rest_ctor, elem_ty.clone());
+                    self.infer_pat(*slice_pat, &rest_ty, default_bm);
+                }
+
                 Ty::apply_one(container_ty, elem_ty)
             }
             _ => Ty::Unknown,
@@ -203,10 +238,43 @@ impl<'a> InferenceContext<'a> {
         // use a new type variable if we got Ty::Unknown here
         let ty = self.insert_type_vars_shallow(ty);
         if !self.unify(&ty, expected) {
-            // FIXME record mismatch, we need to change the type of self.type_mismatches for that
+            // Literal patterns already record the mismatch on their expression.
+            if !matches!(&body[pat], Pat::Lit(_)) {
+                self.write_type_mismatch(pat.into(), expected.clone(), ty.clone());
+            }
         }
         let ty = self.resolve_ty_as_possible(ty);
         self.write_pat_ty(pat, ty.clone());
         ty
     }
+
+    /// Whether `pat` can never match a reference, so that matching it against
+    /// one dereferences the scrutinee and switches the default binding mode.
+    fn is_non_ref_pat(&self, body: &Body, pat: PatId) -> bool {
+        match &body[pat] {
+            Pat::Tuple { .. }
+            | Pat::TupleStruct { .. }
+            | Pat::Record { .. }
+            | Pat::Range { .. }
+            | Pat::Slice { .. } => true,
+            // String and byte string literals have reference types.
+            Pat::Lit(expr) => match &body[*expr] {
+                Expr::Literal(Literal::String(..)) | Expr::Literal(Literal::ByteString(..)) => {
+                    false
+                }
+                _ => true,
+            },
+            // Constants and statics may have reference types as well.
+            Pat::Path(path) => {
+                let resolved =
+                    self.resolver.resolve_path_in_value_ns_fully(self.db.upcast(), path.mod_path());
+                match resolved {
+                    Some(ValueNs::ConstId(_)) | Some(ValueNs::StaticId(_)) => false,
+                    _ => true,
+                }
+            }
+            // Each alternative of an or-pattern adjusts the binding mode on its own.
+            Pat::Or(..) | Pat::Wild | Pat::Bind { .. } | Pat::Ref { .. } | Pat::Missing => false,
+        }
+    }
 }
@@ -11,7 +11,9 @@ use hir_expand::name::Name;
 
 use crate::{method_resolution, Substs, Ty, ValueTyDefId};
 
-use super::{ExprOrPatId, InferenceContext, TraitRef};
+use super::{
+    diagnostics::TypeSource, ExprOrPatId, InferenceContext, InferenceDiagnostic, TraitRef,
+};
 
 impl<'a> InferenceContext<'a> {
     pub(super) fn infer_path(
@@ -37,7 +39,7 @@ impl<'a> InferenceContext<'a> {
                 // This can't actually happen syntax-wise
                 return None;
             }
-            let ty = self.make_ty(type_ref);
+            let ty = self.make_ty(type_ref, TypeSource::Body(id));
             let remaining_segments_for_ty = path.segments().take(path.segments().len() - 1);
             let ctx = crate::lower::TyLoweringContext::new(self.db, &resolver);
             let (ty, _) = Ty::from_type_relative_path(&ctx, ty, None, remaining_segments_for_ty);
@@ -48,7 +50,13 @@ impl<'a> InferenceContext<'a> {
             )?
         } else {
             let value_or_partial =
-                resolver.resolve_path_in_value_ns(self.db.upcast(), path.mod_path())?;
+                match resolver.resolve_path_in_value_ns_hygienic(self.db.upcast(), path) {
+                    Some(it) => it,
+                    None => {
+                        self.push_diagnostic(InferenceDiagnostic::UnresolvedPath { id });
+                        return None;
+                    }
+                };
 
             match value_or_partial {
                 ResolveValueResult::ValueNs(it) => (it, None),
@@ -67,8 +75,19 @@ impl<'a> InferenceContext<'a> {
             ValueNs::FunctionId(it) => it.into(),
             ValueNs::ConstId(it) => it.into(),
             ValueNs::StaticId(it) => it.into(),
-            ValueNs::StructId(it) => it.into(),
-            ValueNs::EnumVariantId(it) => it.into(),
+            ValueNs::StructId(it) => {
+                self.write_variant_resolution(id, it.into());
+                it.into()
+            }
+            ValueNs::EnumVariantId(it) => {
+                self.write_variant_resolution(id, it.into());
+                it.into()
+            }
+            ValueNs::GenericParam(it) => {
+                let params = self.db.generic_params(it.parent);
+                let ctx = crate::lower::TyLoweringContext::new(self.db, &self.resolver);
+                return Some(Ty::from_hir(&ctx, &params.consts[it.local_id].ty));
+            }
         };
 
         let ty = self.db.value_ty(typable);
@@ -32,6 +32,9 @@ pub(crate) mod utils;
 pub mod db;
 pub mod diagnostics;
 pub mod expr;
+pub mod consteval;
+pub mod unsafe_validation;
+mod match_check;
 
 #[cfg(test)]
 mod tests;
@@ -44,20 +47,21 @@ use std::sync::Arc;
 use std::{iter, mem};
 
 use hir_def::{
-    expr::ExprId, type_ref::Mutability, AdtId, AssocContainerId, DefWithBodyId, GenericDefId,
-    HasModule, Lookup, TraitId, TypeAliasId, TypeParamId,
+    expr::ExprId, type_ref::Mutability, AdtId, AssocContainerId, ConstParamId, DefWithBodyId,
+    FunctionId, GenericDefId, HasModule, Lookup, TraitId, TypeAliasId, TypeParamId,
 };
 use ra_db::{impl_intern_key, salsa, CrateId};
 
 use crate::{
+    consteval::ConstValue,
     db::HirDatabase,
     primitive::{FloatTy, IntTy, Uncertain},
-    utils::{generics, make_mut_slice, Generics},
+    utils::{generics, make_mut_slice, GenericParamId, Generics},
 };
 use display::HirDisplay;
 
 pub use autoderef::autoderef;
-pub use infer::{InferTy, InferenceResult};
+pub use infer::{Adjust, Adjustment, InferTy, InferenceResult};
 pub use lower::CallableDef;
 pub use lower::{
     callable_item_sig, ImplTraitLoweringMode, TyDefId, TyLoweringContext, ValueTyDefId,
@@ -92,7 +96,7 @@ pub enum TypeCtor {
     Slice,
 
     /// An array with the given length. Written as `[T; n]`.
-    Array,
+    Array(Uncertain<u64>),
 
     /// A raw pointer. Written as `*mut T` or `*const T`
     RawPtr(Mutability),
@@ -140,8 +144,46 @@ pub enum TypeCtor {
     /// The type of a specific closure.
     ///
     /// The closure signature is stored in a `FnPtr` type in the first type
-    /// parameter.
+    /// parameter, and the types of the captured bindings in a tuple in the
+    /// second one.
     Closure { def: DefWithBodyId, expr: ExprId },
+
+    /// An opaque type (`impl Trait`) in the return type of a function. The
+    /// type parameters are the generic parameters of that function.
+    ///
+    /// Outside of the function, this only unifies with itself; which traits it
+    /// implements is determined by its bounds.
+    OpaqueType(OpaqueTyId),
+
+    /// A constant used as a generic argument, like the `3` in `Foo<3>`. These
+    /// are not really types, but representing them as type constructors
+    /// without parameters lets `Substs` carry them.
+    Const(ConstScalar),
+}
+
+/// A constant generic argument.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum ConstScalar {
+    /// A known value.
+    Value(ConstValue),
+    /// A const parameter, inside the item that declares it; like
+    /// `Ty::Placeholder` for type parameters.
+    Param(ConstParamId),
+}
+
+/// Identifies an `impl Trait` type that is not a type parameter.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum OpaqueTyId {
+    /// The `impl Trait` with the given index in the return type of a function.
+    ReturnTypeImplTrait(FunctionId, u16),
+}
+
+impl OpaqueTyId {
+    fn function(self) -> FunctionId {
+        match self {
+            OpaqueTyId::ReturnTypeImplTrait(func, _) => func,
+        }
+    }
 }
 
 /// This exists just for Chalk, because Chalk just has a single `StructId` where
@@ -159,13 +201,11 @@ impl TypeCtor {
             | TypeCtor::Int(_)
             | TypeCtor::Float(_)
             | TypeCtor::Str
-            | TypeCtor::Never => 0,
-            TypeCtor::Slice
-            | TypeCtor::Array
-            | TypeCtor::RawPtr(_)
-            | TypeCtor::Ref(_)
-            | TypeCtor::Closure { .. } // 1 param representing the signature of the closure
-            => 1,
+            | TypeCtor::Never
+            | TypeCtor::Const(_) => 0,
+            TypeCtor::Slice | TypeCtor::Array(_) | TypeCtor::RawPtr(_) | TypeCtor::Ref(_) => 1,
+            // The signature of the closure, and the tuple of its captured types
+            TypeCtor::Closure { .. } => 2,
             TypeCtor::Adt(adt) => {
                 let generic_params = generics(db.upcast(), adt.into());
                 generic_params.len()
@@ -178,6 +218,10 @@ impl TypeCtor {
                 let generic_params = generics(db.upcast(), type_alias.into());
                 generic_params.len()
             }
+            TypeCtor::OpaqueType(opaque_ty_id) => {
+                let generic_params = generics(db.upcast(), opaque_ty_id.function().into());
+                generic_params.len()
+            }
             TypeCtor::FnPtr { num_args } => num_args as usize + 1,
             TypeCtor::Tuple { cardinality } => cardinality as usize,
         }
@@ -192,11 +236,12 @@ impl TypeCtor {
             | TypeCtor::Str
             | TypeCtor::Never
             | TypeCtor::Slice
-            | TypeCtor::Array
+            | TypeCtor::Array(_)
             | TypeCtor::RawPtr(_)
             | TypeCtor::Ref(_)
             | TypeCtor::FnPtr { .. }
-            | TypeCtor::Tuple { .. } => None,
+            | TypeCtor::Tuple { .. }
+            | TypeCtor::Const(_) => None,
             // Closure's krate is irrelevant for coherence I would think?
             TypeCtor::Closure { .. } => None,
             TypeCtor::Adt(adt) => Some(adt.module(db.upcast()).krate),
@@ -204,6 +249,30 @@ impl TypeCtor {
             TypeCtor::AssociatedType(type_alias) => {
                 Some(type_alias.lookup(db.upcast()).module(db.upcast()).krate)
             }
+            TypeCtor::OpaqueType(opaque_ty_id) => {
+                Some(opaque_ty_id.function().lookup(db.upcast()).module(db.upcast()).krate)
+            }
+        }
+    }
+
+    /// Whether types built with these constructors can be unified. This is
+    /// equality, except that an array of unknown length unifies with arrays of
+    /// any length.
+    pub fn unifies_with(self, other: TypeCtor) -> bool {
+        match (self, other) {
+            (TypeCtor::Array(Uncertain::Unknown), TypeCtor::Array(_))
+            | (TypeCtor::Array(_), TypeCtor::Array(Uncertain::Unknown)) => true,
+            _ => self == other,
+        }
+    }
+
+    /// The constructor with the length of arrays erased. Impls can be generic
+    /// over the length, so the trait solver and impl lookup must not tell
+    /// arrays of different lengths apart.
+    pub(crate) fn without_array_len(self) -> TypeCtor {
+        match self {
+            TypeCtor::Array(_) => TypeCtor::Array(Uncertain::Unknown),
+            _ => self,
         }
     }
 
@@ -216,15 +285,17 @@ impl TypeCtor {
             | TypeCtor::Str
             | TypeCtor::Never
             | TypeCtor::Slice
-            | TypeCtor::Array
+            | TypeCtor::Array(_)
             | TypeCtor::RawPtr(_)
             | TypeCtor::Ref(_)
             | TypeCtor::FnPtr { .. }
             | TypeCtor::Tuple { .. }
-            | TypeCtor::Closure { .. } => None,
+            | TypeCtor::Closure { .. }
+            | TypeCtor::Const(_) => None,
             TypeCtor::Adt(adt) => Some(adt.into()),
             TypeCtor::FnDef(callable) => Some(callable.into()),
             TypeCtor::AssociatedType(type_alias) => Some(type_alias.into()),
+            TypeCtor::OpaqueType(opaque_ty_id) => Some(opaque_ty_id.function().into()),
         }
     }
 }
@@ -312,12 +383,6 @@ pub enum Ty {
     /// didn't seem worth the overhead yet.
     Dyn(Arc<[GenericPredicate]>),
 
-    /// An opaque type (`impl Trait`).
-    ///
-    /// The predicates are quantified over the `Self` type; see `Ty::Dyn` for
-    /// more.
-    Opaque(Arc<[GenericPredicate]>),
-
     /// A placeholder for a type which could not be computed; this is propagated
     /// to avoid useless error messages. Doubles as a placeholder where type
     /// variables are inserted before type checking, since we want to try to
@@ -370,7 +435,17 @@ impl Substs {
 
     /// Return Substs that replace each parameter by itself (i.e. `Ty::Param`).
     pub(crate) fn type_params_for_generics(generic_params: &Generics) -> Substs {
-        Substs(generic_params.iter().map(|(id, _)| Ty::Placeholder(id)).collect())
+        Substs(
+            generic_params
+                .iter_all()
+                .map(|id| match id {
+                    GenericParamId::Type(id) => Ty::Placeholder(id),
+                    GenericParamId::Const(id) => {
+                        Ty::apply(TypeCtor::Const(ConstScalar::Param(id)), Substs::empty())
+                    }
+                })
+                .collect(),
+        )
     }
 
     /// Return Substs that replace each parameter by itself (i.e. `Ty::Param`).
@@ -381,7 +456,7 @@ impl Substs {
 
     /// Return Substs that replace each parameter by a bound variable.
     pub(crate) fn bound_vars(generic_params: &Generics) -> Substs {
-        Substs(generic_params.iter().enumerate().map(|(idx, _)| Ty::Bound(idx as u32)).collect())
+        Substs((0..generic_params.len()).map(|idx| Ty::Bound(idx as u32)).collect())
     }
 
     pub fn build_for_def(db: &dyn HirDatabase, def: impl Into<GenericDefId>) -> SubstsBuilder {
@@ -623,6 +698,21 @@ impl TypeWalk for FnSig {
     }
 }
 
+/// The `impl Trait` types in the return type of a function, in the order in
+/// which they appear. This is quantified over the generic parameters of the
+/// function.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ReturnTypeImplTraits {
+    pub(crate) impl_traits: Vec<ReturnTypeImplTrait>,
+}
+
+/// The bounds of one return position `impl Trait`. Their self type is the
+/// corresponding `TypeCtor::OpaqueType`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) struct ReturnTypeImplTrait {
+    pub(crate) bounds: Vec<GenericPredicate>,
+}
+
 impl Ty {
     pub fn simple(ctor: TypeCtor) -> Ty {
         Ty::Apply(ApplicationTy { ctor, parameters: Substs::empty() })
@@ -736,14 +826,30 @@ impl Ty {
         }
     }
 
-    /// If this is an `impl Trait` or `dyn Trait`, returns that trait.
+    /// If this is a `dyn Trait`, returns that trait.
     pub fn inherent_trait(&self) -> Option<TraitId> {
         match self {
-            Ty::Dyn(predicates) | Ty::Opaque(predicates) => {
-                predicates.iter().find_map(|pred| match pred {
-                    GenericPredicate::Implemented(tr) => Some(tr.trait_),
-                    _ => None,
-                })
+            Ty::Dyn(predicates) => predicates.iter().find_map(|pred| match pred {
+                GenericPredicate::Implemented(tr) => Some(tr.trait_),
+                _ => None,
+            }),
+            _ => None,
+        }
+    }
+
+    /// If this is an opaque `impl Trait` type, returns its bounds.
+    pub fn impl_trait_bounds(&self, db: &dyn HirDatabase) -> Option<Vec<GenericPredicate>> {
+        match self {
+            Ty::Apply(ApplicationTy { ctor: TypeCtor::OpaqueType(opaque_ty_id), parameters }) => {
+                let OpaqueTyId::ReturnTypeImplTrait(func, idx) = *opaque_ty_id;
+                let impl_traits = db.return_type_impl_traits(func)?;
+                let impl_trait = impl_traits.value.impl_traits.get(idx as usize)?;
+                let bounds = impl_trait
+                    .bounds
+                    .iter()
+                    .map(|pred| pred.clone().subst_bound_vars(parameters))
+                    .collect();
+                Some(bounds)
             }
             _ => None,
         }
@@ -765,8 +871,7 @@ pub trait TypeWalk {
     /// and blindly replace each instance of a certain index; when we 'enter'
     /// things that introduce new bound variables, we have to keep track of
     /// that. Currently, the only thing that introduces bound variables on our
-    /// side are `Ty::Dyn` and `Ty::Opaque`, which each introduce a bound
-    /// variable for the self type.
+    /// side is `Ty::Dyn`, which introduces a bound variable for the self type.
     fn walk_mut_binders(&mut self, f: &mut impl FnMut(&mut Ty, usize), binders: usize);
 
     fn fold_binders(mut self, f: &mut impl FnMut(Ty, usize) -> Ty, binders: usize) -> Self
@@ -846,7 +951,7 @@ impl TypeWalk for Ty {
                     t.walk(f);
                 }
             }
-            Ty::Dyn(predicates) | Ty::Opaque(predicates) => {
+            Ty::Dyn(predicates) => {
                 for p in predicates.iter() {
                     p.walk(f);
                 }
@@ -864,7 +969,7 @@ impl TypeWalk for Ty {
             Ty::Projection(p_ty) => {
                 p_ty.parameters.walk_mut_binders(f, binders);
             }
-            Ty::Dyn(predicates) | Ty::Opaque(predicates) => {
+            Ty::Dyn(predicates) => {
                 for p in make_mut_slice(predicates) {
                     p.walk_mut_binders(f, binders + 1);
                 }
@@ -5,6 +5,7 @@
 //!  - Building the type for an item: This happens through the `type_for_def` query.
 //!
 //! This usually involves resolving names, collecting generic arguments etc.
+use std::convert::TryFrom;
 use std::iter;
 use std::sync::Arc;
 
@@ -13,8 +14,8 @@ use hir_def::{
     builtin_type::BuiltinType,
     generics::{TypeParamProvenance, WherePredicate, WherePredicateTarget},
     path::{GenericArg, Path, PathSegment, PathSegments},
-    resolver::{HasResolver, Resolver, TypeNs},
-    type_ref::{TypeBound, TypeRef},
+    resolver::{HasResolver, Resolver, TypeNs, ValueNs},
+    type_ref::{ConstRef, TypeBound, TypeRef},
     AdtId, AssocContainerId, ConstId, EnumId, EnumVariantId, FunctionId, GenericDefId, HasModule,
     ImplId, LocalStructFieldId, Lookup, StaticId, StructId, TraitId, TypeAliasId, TypeParamId,
     UnionId, VariantId,
@@ -23,14 +24,16 @@ use ra_arena::map::ArenaMap;
 use ra_db::CrateId;
 
 use crate::{
+    consteval::{self, ConstValue},
     db::HirDatabase,
     primitive::{FloatTy, IntTy},
     utils::{
         all_super_traits, associated_type_by_name_including_super_traits, generics, make_mut_slice,
-        variant_data,
+        variant_data, GenericParamId,
     },
-    Binders, FnSig, GenericPredicate, PolyFnSig, ProjectionPredicate, ProjectionTy, Substs,
-    TraitEnvironment, TraitRef, Ty, TypeCtor,
+    Binders, ConstScalar, FnSig, GenericPredicate, OpaqueTyId, PolyFnSig, ProjectionPredicate,
+    ProjectionTy, ReturnTypeImplTrait, ReturnTypeImplTraits, Substs, TraitEnvironment, TraitRef,
+    Ty, TypeCtor,
 };
 
 #[derive(Debug)]
@@ -44,6 +47,11 @@ pub struct TyLoweringContext<'a> {
     pub type_param_mode: TypeParamLoweringMode,
     pub impl_trait_mode: ImplTraitLoweringMode,
     pub impl_trait_counter: std::cell::Cell<u16>,
+    /// The bounds of the opaque types lowered so far, indexed like
+    /// `impl_trait_counter`. Only filled in `ImplTraitLoweringMode::Opaque`.
+    pub(crate) opaque_type_data: std::cell::RefCell<Vec<ReturnTypeImplTrait>>,
+    /// The type paths which couldn't be resolved, for diagnostics.
+    pub(crate) unresolved_paths: std::cell::RefCell<Vec<Path>>,
 }
 
 impl<'a> TyLoweringContext<'a> {
@@ -51,7 +59,17 @@ impl<'a> TyLoweringContext<'a> {
         let impl_trait_counter = std::cell::Cell::new(0);
         let impl_trait_mode = ImplTraitLoweringMode::Disallowed;
         let type_param_mode = TypeParamLoweringMode::Placeholder;
-        Self { db, resolver, impl_trait_mode, impl_trait_counter, type_param_mode }
+        let opaque_type_data = std::cell::RefCell::new(Vec::new());
+        let unresolved_paths = std::cell::RefCell::new(Vec::new());
+        Self {
+            db,
+            resolver,
+            impl_trait_mode,
+            impl_trait_counter,
+            type_param_mode,
+            opaque_type_data,
+            unresolved_paths,
+        }
     }
 
     pub fn with_impl_trait_mode(self, impl_trait_mode: ImplTraitLoweringMode) -> Self {
@@ -66,9 +84,9 @@ impl<'a> TyLoweringContext<'a> {
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ImplTraitLoweringMode {
     /// `impl Trait` gets lowered into an opaque type that doesn't unify with
-    /// anything except itself. This is used in places where values flow 'out',
-    /// i.e. for arguments of the function we're currently checking, and return
-    /// types of functions we're calling.
+    /// anything except itself. This is used for the return types of functions;
+    /// the opaque type is identified by the function and the position of the
+    /// `impl Trait` in its return type.
     Opaque,
     /// `impl Trait` gets lowered into a type variable. Used for argument
     /// position impl Trait when inside the respective function, since it allows
@@ -113,9 +131,10 @@ impl Ty {
                 let inner_ty = Ty::from_hir(ctx, inner);
                 Ty::apply_one(TypeCtor::RawPtr(*mutability), inner_ty)
             }
-            TypeRef::Array(inner) => {
+            TypeRef::Array(inner, len) => {
                 let inner_ty = Ty::from_hir(ctx, inner);
-                Ty::apply_one(TypeCtor::Array, inner_ty)
+                let len = consteval::eval_array_len(ctx.db, ctx.resolver, len);
+                Ty::apply_one(TypeCtor::Array(len), inner_ty)
             }
             TypeRef::Slice(inner) => {
                 let inner_ty = Ty::from_hir(ctx, inner);
@@ -141,14 +160,39 @@ impl Ty {
             TypeRef::ImplTrait(bounds) => {
                 match ctx.impl_trait_mode {
                     ImplTraitLoweringMode::Opaque => {
-                        let self_ty = Ty::Bound(0);
+                        let func = match ctx.resolver.generic_def() {
+                            Some(GenericDefId::FunctionId(func)) => func,
+                            _ => return (Ty::Unknown, None),
+                        };
+                        let idx = ctx.impl_trait_counter.get();
+                        ctx.impl_trait_counter.set(idx + 1);
+                        let generics = generics(ctx.db.upcast(), func.into());
+                        let parameters = match ctx.type_param_mode {
+                            TypeParamLoweringMode::Placeholder => {
+                                Substs::type_params_for_generics(&generics)
+                            }
+                            TypeParamLoweringMode::Variable => Substs::bound_vars(&generics),
+                        };
+                        let opaque_ty = Ty::apply(
+                            TypeCtor::OpaqueType(OpaqueTyId::ReturnTypeImplTrait(func, idx)),
+                            parameters,
+                        );
+                        // Reserve the slot first, since the bounds may contain
+                        // nested `impl Trait`s which get the following indices.
+                        ctx.opaque_type_data
+                            .borrow_mut()
+                            .push(ReturnTypeImplTrait { bounds: Vec::new() });
                         let predicates = bounds
                             .iter()
                             .flat_map(|b| {
-                                GenericPredicate::from_type_bound(ctx, b, self_ty.clone())
+                                GenericPredicate::from_type_bound(ctx, b, opaque_ty.clone())
                             })
-                            .collect();
-                        Ty::Opaque(predicates)
+                            .collect::<Vec<_>>();
+                        if let Some(data) = ctx.opaque_type_data.borrow_mut().get_mut(idx as usize)
+                        {
+                            data.bounds = predicates;
+                        }
+                        opaque_ty
                     }
                     ImplTraitLoweringMode::Param => {
                         let idx = ctx.impl_trait_counter.get();
@@ -170,12 +214,12 @@ impl Ty {
                     ImplTraitLoweringMode::Variable => {
                         let idx = ctx.impl_trait_counter.get();
                         ctx.impl_trait_counter.set(idx + 1);
-                        let (parent_params, self_params, list_params, _impl_trait_params) =
+                        let (parent_params, self_params, list_params, _impl_trait_params, _consts) =
                             if let Some(def) = ctx.resolver.generic_def() {
                                 let generics = generics(ctx.db.upcast(), def);
                                 generics.provenance_split()
                             } else {
-                                (0, 0, 0, 0)
+                                (0, 0, 0, 0, 0)
                             };
                         Ty::Bound(
                             idx as u32
@@ -221,6 +265,45 @@ impl Ty {
         }
     }
 
+    /// Lowers a const generic argument, like the `3` in `Foo<3>`.
+    pub(crate) fn from_hir_const(ctx: &TyLoweringContext<'_>, const_ref: &ConstRef) -> Ty {
+        let value = match const_ref {
+            ConstRef::Literal(it) => match i128::try_from(*it) {
+                Ok(it) => ConstValue::Int(it),
+                Err(_) => return Ty::Unknown,
+            },
+            ConstRef::Path(path) => {
+                let resolution =
+                    ctx.resolver.resolve_path_in_value_ns_fully(ctx.db.upcast(), path.mod_path());
+                match resolution {
+                    Some(ValueNs::ConstId(it)) => match ctx.db.const_eval(it) {
+                        Ok(it) => it,
+                        Err(_) => return Ty::Unknown,
+                    },
+                    Some(ValueNs::GenericParam(param_id)) => {
+                        return match ctx.type_param_mode {
+                            TypeParamLoweringMode::Placeholder => {
+                                Ty::simple(TypeCtor::Const(ConstScalar::Param(param_id)))
+                            }
+                            TypeParamLoweringMode::Variable => {
+                                let generics = generics(
+                                    ctx.db.upcast(),
+                                    ctx.resolver.generic_def().expect("generics in scope"),
+                                );
+                                let idx =
+                                    generics.const_param_idx(param_id).expect("matching generics");
+                                Ty::Bound(idx)
+                            }
+                        };
+                    }
+                    _ => return Ty::Unknown,
+                }
+            }
+            ConstRef::Unknown => return Ty::Unknown,
+        };
+        Ty::simple(TypeCtor::Const(ConstScalar::Value(value)))
+    }
+
     pub(crate) fn from_type_relative_path(
         ctx: &TyLoweringContext<'_>,
         ty: Ty,
@@ -333,7 +416,10 @@ impl Ty {
         let (resolution, remaining_index) =
             match ctx.resolver.resolve_path_in_type_ns(ctx.db.upcast(), path.mod_path()) {
                 Some(it) => it,
-                None => return (Ty::Unknown, None),
+                None => {
+                    ctx.unresolved_paths.borrow_mut().push(path.clone());
+                    return (Ty::Unknown, None);
+                }
             };
         let (resolved_segment, remaining_segments) = match remaining_index {
             None => (
@@ -439,8 +525,9 @@ pub(super) fn substs_from_path_segment(
     let mut substs = Vec::new();
     let def_generics = def_generic.map(|def| generics(ctx.db.upcast(), def));
 
-    let (parent_params, self_params, type_params, impl_trait_params) =
-        def_generics.map_or((0, 0, 0, 0), |g| g.provenance_split());
+    let (parent_params, self_params, type_params, impl_trait_params, const_params) =
+        def_generics.map_or((0, 0, 0, 0, 0), |g| g.provenance_split());
+    let mut const_substs = Vec::new();
     substs.extend(iter::repeat(Ty::Unknown).take(parent_params));
     if let Some(generic_args) = &segment.args_and_bindings {
         if !generic_args.has_self_type {
@@ -450,17 +537,30 @@ pub(super) fn substs_from_path_segment(
             if generic_args.has_self_type { self_params + type_params } else { type_params };
         let skip = if generic_args.has_self_type && self_params == 0 { 1 } else { 0 };
         // if args are provided, it should be all of them, but we can't rely on that
-        for arg in generic_args.args.iter().skip(skip).take(expected_num) {
-            match arg {
-                GenericArg::Type(type_ref) => {
+        let mut type_args = 0;
+        for arg in generic_args.args.iter().skip(skip) {
+            let const_ref = match arg {
+                GenericArg::Type(type_ref) if type_args < expected_num => {
                     let ty = Ty::from_hir(ctx, type_ref);
                     substs.push(ty);
+                    type_args += 1;
+                    continue;
                 }
-            }
+                // a const parameter used as an argument is parsed as a type
+                GenericArg::Type(TypeRef::Path(path)) => ConstRef::Path(path.clone()),
+                GenericArg::Type(_) => ConstRef::Unknown,
+                GenericArg::Const(const_ref) => const_ref.clone(),
+            };
+            const_substs.push(Ty::from_hir_const(ctx, &const_ref));
         }
     }
-    let total_len = parent_params + self_params + type_params + impl_trait_params;
+    let type_len = parent_params + self_params + type_params + impl_trait_params;
+    let total_len = type_len + const_params;
     // add placeholders for args that were not provided
+    for _ in substs.len()..type_len {
+        substs.push(Ty::Unknown);
+    }
+    substs.extend(const_substs.into_iter().take(const_params));
     for _ in substs.len()..total_len {
         substs.push(Ty::Unknown);
     }
@@ -744,8 +844,14 @@ pub(crate) fn generic_defaults_query(db: &dyn HirDatabase, def: GenericDefId) ->
     let generic_params = generics(db.upcast(), def);
 
     let defaults = generic_params
-        .iter()
-        .map(|(_idx, p)| p.default.as_ref().map_or(Ty::Unknown, |t| Ty::from_hir(&ctx, t)))
+        .iter_all()
+        .map(|id| match id {
+            GenericParamId::Type(id) => {
+                let data = &db.generic_params(id.parent).types[id.local_id];
+                data.default.as_ref().map_or(Ty::Unknown, |t| Ty::from_hir(&ctx, t))
+            }
+            GenericParamId::Const(_) => Ty::Unknown,
+        })
         .collect();
 
     Substs(defaults)
@@ -758,13 +864,35 @@ fn fn_sig_for_fn(db: &dyn HirDatabase, def: FunctionId) -> PolyFnSig {
         .with_impl_trait_mode(ImplTraitLoweringMode::Variable)
         .with_type_param_mode(TypeParamLoweringMode::Variable);
     let params = data.params.iter().map(|tr| Ty::from_hir(&ctx_params, tr)).collect::<Vec<_>>();
-    let ctx_ret = ctx_params.with_impl_trait_mode(ImplTraitLoweringMode::Opaque);
+    let ctx_ret = TyLoweringContext::new(db, &resolver)
+        .with_impl_trait_mode(ImplTraitLoweringMode::Opaque)
+        .with_type_param_mode(TypeParamLoweringMode::Variable);
     let ret = Ty::from_hir(&ctx_ret, &data.ret_type);
     let generics = generics(db.upcast(), def.into());
     let num_binders = generics.len();
     Binders::new(num_binders, FnSig::from_params_and_return(params, ret))
 }
 
+/// Collects the bounds of the `impl Trait` types in the return type of a
+/// function, or `None` if there are none.
+pub(crate) fn return_type_impl_traits_query(
+    db: &dyn HirDatabase,
+    def: FunctionId,
+) -> Option<Arc<Binders<ReturnTypeImplTraits>>> {
+    let data = db.function_data(def);
+    let resolver = def.resolver(db.upcast());
+    let ctx_ret = TyLoweringContext::new(db, &resolver)
+        .with_impl_trait_mode(ImplTraitLoweringMode::Opaque)
+        .with_type_param_mode(TypeParamLoweringMode::Variable);
+    let _ret = Ty::from_hir(&ctx_ret, &data.ret_type);
+    let impl_traits = ctx_ret.opaque_type_data.into_inner();
+    if impl_traits.is_empty() {
+        return None;
+    }
+    let generics = generics(db.upcast(), def.into());
+    Some(Arc::new(Binders::new(generics.len(), ReturnTypeImplTraits { impl_traits })))
+}
+
 /// Build the declared type of a function. This should not need to look at the
 /// function body.
 fn type_for_fn(db: &dyn HirDatabase, def: FunctionId) -> Binders<Ty> {
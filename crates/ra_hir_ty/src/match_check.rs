@@ -0,0 +1,848 @@
+//! Exhaustiveness checking for `match` expressions.
+//!
+//! This is the usefulness algorithm from "Warnings for pattern matching" by Luc
+//! Maranget, which is also what rustc uses. We only ever ask whether the
+//! wildcard pattern is useful after all the arms, and instead of a yes/no answer
+//! we compute *witnesses*: patterns describing values no arm matches.
+//!
+//! Patterns are first lowered from `hir_def::expr::Pat` into
+//! `DeconstructedPat`s, which resolves paths and makes the implicit
+//! dereferences of match ergonomics explicit. If we meet anything we do not
+//! understand (range patterns, mismatched or unknown types, unions), we give up
+//! on the whole `match`: a missing diagnostic is much better than a bogus one.
+
+use std::{convert::TryFrom, iter, sync::Arc};
+
+use hir_def::{
+    adt::StructKind,
+    body::Body,
+    expr::{Expr, ExprId, Literal, MatchArm, Pat, PatId},
+    AdtId, EnumVariantId, VariantId,
+};
+use stdx::format_to;
+
+use crate::{
+    db::HirDatabase, primitive::Uncertain, utils::variant_data, ApplicationTy, InferenceResult,
+    Substs, Ty, TypeCtor,
+};
+
+/// We stop looking for uncovered patterns after finding this many of them.
+const MAX_WITNESSES: usize = 3;
+
+/// Constructors are the building blocks of values (and patterns): a value is
+/// a constructor applied to the values of its fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Constructor {
+    Bool(bool),
+    /// The only constructor of tuples and structs.
+    Single,
+    Variant(EnumVariantId),
+    Ref,
+    Slice(SliceKind),
+    /// Literals of types with (practically) infinitely many values, and
+    /// constants. These never cover a type completely.
+    Opaque,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SliceKind {
+    /// `[a, b, c]`
+    Fixed(usize),
+    /// `[a, .., z]`, with the lengths of the prefix and of the suffix.
+    VarLen(usize, usize),
+}
+
+impl SliceKind {
+    fn arity(self) -> usize {
+        match self {
+            SliceKind::Fixed(len) => len,
+            SliceKind::VarLen(prefix, suffix) => prefix + suffix,
+        }
+    }
+}
+
+impl Constructor {
+    /// Whether a pattern with this constructor matches all the values built
+    /// with `other`.
+    fn covers(self, other: Constructor) -> bool {
+        match (self, other) {
+            (Constructor::Opaque, _) => false,
+            (Constructor::Slice(SliceKind::VarLen(prefix, suffix)), Constructor::Slice(other)) => {
+                match other {
+                    SliceKind::Fixed(len) => prefix + suffix <= len,
+                    SliceKind::VarLen(other_prefix, other_suffix) => {
+                        prefix <= other_prefix && suffix <= other_suffix
+                    }
+                }
+            }
+            _ => self == other,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum DeconstructedPat {
+    Wild,
+    /// For `SliceKind::VarLen`, the fields are the prefix followed by the suffix.
+    Ctor(Constructor, Vec<DeconstructedPat>),
+    Or(Vec<DeconstructedPat>),
+}
+
+impl DeconstructedPat {
+    fn ctor(&self) -> Option<Constructor> {
+        match self {
+            DeconstructedPat::Ctor(ctor, _) => Some(*ctor),
+            DeconstructedPat::Wild | DeconstructedPat::Or(_) => None,
+        }
+    }
+}
+
+type Row = Vec<DeconstructedPat>;
+
+#[derive(Debug, Clone)]
+enum WitnessPat {
+    Wild,
+    Ctor(Constructor, Vec<WitnessPat>),
+}
+
+/// A list of patterns, one per column of the matrix, which no row matches.
+type Witness = Vec<WitnessPat>;
+
+pub(crate) struct MatchCheckCtx<'a> {
+    pub(crate) db: &'a dyn HirDatabase,
+    pub(crate) body: Arc<Body>,
+    pub(crate) infer: Arc<InferenceResult>,
+}
+
+impl MatchCheckCtx<'_> {
+    /// Returns (some of) the patterns which are not covered by the arms of the
+    /// `match`, rendered as source code. An empty result means the match is
+    /// exhaustive, or that we were not able to tell.
+    pub(crate) fn uncovered_patterns(&self, scrutinee: ExprId, arms: &[MatchArm]) -> Vec<String> {
+        let scrutinee_ty = &self.infer[scrutinee];
+        if let Ty::Unknown = scrutinee_ty {
+            return Vec::new();
+        }
+
+        // An arm with a guard might not match, so it doesn't cover anything.
+        let rows = arms
+            .iter()
+            .filter(|arm| arm.guard.is_none())
+            .map(|arm| self.lower_pat(arm.pat, scrutinee_ty).map(|pat| vec![pat]))
+            .collect::<Option<Vec<Row>>>();
+        let rows = match rows {
+            Some(it) => it,
+            None => return Vec::new(),
+        };
+
+        let tys = [scrutinee_ty.clone()];
+        self.compute_witnesses(rows, &tys)
+            .into_iter()
+            .map(|witness| {
+                let mut buf = String::new();
+                self.fmt_witness(&witness[0], scrutinee_ty, &mut buf);
+                buf
+            })
+            .collect()
+    }
+
+    fn lower_pat(&self, pat: PatId, ty: &Ty) -> Option<DeconstructedPat> {
+        let res = match &self.body[pat] {
+            Pat::Wild | Pat::Bind { subpat: None, .. } => DeconstructedPat::Wild,
+            Pat::Bind { subpat: Some(subpat), .. } => return self.lower_pat(*subpat, ty),
+            Pat::Or(pats) => DeconstructedPat::Or(
+                pats.iter().map(|&pat| self.lower_pat(pat, ty)).collect::<Option<_>>()?,
+            ),
+            Pat::Ref { pat, .. } => {
+                let (inner_ty, _) = ty.as_reference()?;
+                DeconstructedPat::Ctor(Constructor::Ref, vec![self.lower_pat(*pat, inner_ty)?])
+            }
+            Pat::Lit(expr) => match &self.body[*expr] {
+                Expr::Literal(Literal::Bool(value)) => {
+                    return self.lower_bool_pat(*value, ty);
+                }
+                _ => DeconstructedPat::Ctor(Constructor::Opaque, Vec::new()),
+            },
+            Pat::Missing | Pat::Range { .. } => return None,
+            // Match ergonomics: non-reference patterns auto-dereference.
+            _ if ty.as_reference().is_some() => {
+                let (inner_ty, _) = ty.as_reference()?;
+                DeconstructedPat::Ctor(Constructor::Ref, vec![self.lower_pat(pat, inner_ty)?])
+            }
+            Pat::Tuple { args, ellipsis } => {
+                let field_tys = ty.as_tuple()?;
+                let fields = self.lower_tuple_fields(args, *ellipsis, field_tys)?;
+                DeconstructedPat::Ctor(Constructor::Single, fields)
+            }
+            Pat::TupleStruct { args, ellipsis, .. } => {
+                let ctor = self.variant_ctor(self.infer.variant_resolution_for_pat(pat)?, ty)?;
+                let field_tys = self.ctor_field_tys(ctor, ty);
+                let fields = self.lower_tuple_fields(args, *ellipsis, &field_tys)?;
+                DeconstructedPat::Ctor(ctor, fields)
+            }
+            Pat::Record { args, .. } => {
+                let variant = self.infer.variant_resolution_for_pat(pat)?;
+                let ctor = self.variant_ctor(variant, ty)?;
+                let field_tys = self.ctor_field_tys(ctor, ty);
+                let variant_data = variant_data(self.db.upcast(), variant);
+
+                let mut fields = vec![DeconstructedPat::Wild; field_tys.len()];
+                for arg in args {
+                    let idx =
+                        variant_data.fields().iter().position(|(_, data)| data.name == arg.name)?;
+                    fields[idx] = self.lower_pat(arg.pat, &field_tys[idx])?;
+                }
+                DeconstructedPat::Ctor(ctor, fields)
+            }
+            Pat::Path(_) => match self.infer.variant_resolution_for_pat(pat) {
+                Some(variant) => {
+                    let ctor = self.variant_ctor(variant, ty)?;
+                    if !self.ctor_field_tys(ctor, ty).is_empty() {
+                        return None;
+                    }
+                    DeconstructedPat::Ctor(ctor, Vec::new())
+                }
+                // Most likely a constant.
+                None if self.infer[pat] != Ty::Unknown => {
+                    DeconstructedPat::Ctor(Constructor::Opaque, Vec::new())
+                }
+                None => return None,
+            },
+            Pat::Slice { prefix, slice, suffix } => {
+                let elem_ty = match ty {
+                    Ty::Apply(ApplicationTy { ctor: TypeCtor::Slice, parameters })
+                    | Ty::Apply(ApplicationTy { ctor: TypeCtor::Array(_), parameters }) => {
+                        parameters.as_single()
+                    }
+                    _ => return None,
+                };
+                let kind = match slice {
+                    Some(_) => SliceKind::VarLen(prefix.len(), suffix.len()),
+                    None => SliceKind::Fixed(prefix.len() + suffix.len()),
+                };
+                let fields = prefix
+                    .iter()
+                    .chain(suffix)
+                    .map(|&pat| self.lower_pat(pat, elem_ty))
+                    .collect::<Option<_>>()?;
+                DeconstructedPat::Ctor(Constructor::Slice(kind), fields)
+            }
+        };
+        Some(res)
+    }
+
+    fn lower_bool_pat(&self, value: bool, ty: &Ty) -> Option<DeconstructedPat> {
+        match ty {
+            Ty::Apply(ApplicationTy { ctor: TypeCtor::Bool, .. }) => {
+                Some(DeconstructedPat::Ctor(Constructor::Bool(value), Vec::new()))
+            }
+            _ => {
+                let (inner_ty, _) = ty.as_reference()?;
+                let inner = self.lower_bool_pat(value, inner_ty)?;
+                Some(DeconstructedPat::Ctor(Constructor::Ref, vec![inner]))
+            }
+        }
+    }
+
+    fn lower_tuple_fields(
+        &self,
+        args: &[PatId],
+        ellipsis: Option<usize>,
+        field_tys: &[Ty],
+    ) -> Option<Vec<DeconstructedPat>> {
+        let (pre, post) = match ellipsis {
+            Some(idx) => args.split_at(idx),
+            None => {
+                if args.len() != field_tys.len() {
+                    return None;
+                }
+                (args, &[][..])
+            }
+        };
+        let n_wild = field_tys.len().checked_sub(args.len())?;
+        let mut fields = Vec::with_capacity(field_tys.len());
+        for (&pat, ty) in pre.iter().zip(field_tys) {
+            fields.push(self.lower_pat(pat, ty)?);
+        }
+        fields.extend(iter::repeat(DeconstructedPat::Wild).take(n_wild));
+        for (&pat, ty) in post.iter().zip(&field_tys[pre.len() + n_wild..]) {
+            fields.push(self.lower_pat(pat, ty)?);
+        }
+        Some(fields)
+    }
+
+    /// Checks that `variant` is actually a variant of `ty`.
+    fn variant_ctor(&self, variant: VariantId, ty: &Ty) -> Option<Constructor> {
+        let (adt, _) = ty.as_adt()?;
+        match (variant, adt) {
+            (VariantId::EnumVariantId(it), AdtId::EnumId(e)) if it.parent == e => {
+                Some(Constructor::Variant(it))
+            }
+            (VariantId::StructId(it), AdtId::StructId(s)) if it == s => Some(Constructor::Single),
+            _ => None,
+        }
+    }
+
+    fn ctor_field_tys(&self, ctor: Constructor, ty: &Ty) -> Vec<Ty> {
+        let variant: VariantId = match ctor {
+            Constructor::Bool(_) | Constructor::Opaque => return Vec::new(),
+            Constructor::Ref => {
+                return ty.as_reference().map(|(it, _)| vec![it.clone()]).unwrap_or_default()
+            }
+            Constructor::Slice(kind) => {
+                return match ty {
+                    Ty::Apply(ApplicationTy { parameters, .. }) => {
+                        vec![parameters.as_single().clone(); kind.arity()]
+                    }
+                    _ => Vec::new(),
+                }
+            }
+            Constructor::Single => match ty.as_adt() {
+                Some((AdtId::StructId(it), _)) => it.into(),
+                _ => return ty.as_tuple().map(|it| it.to_vec()).unwrap_or_default(),
+            },
+            Constructor::Variant(it) => it.into(),
+        };
+        let substs = ty.substs().unwrap_or_else(Substs::empty);
+        let field_tys = self.db.field_types(variant);
+        variant_data(self.db.upcast(), variant)
+            .fields()
+            .iter()
+            .map(|(id, _)| field_tys[id].clone().subst(&substs))
+            .collect()
+    }
+
+    /// Returns all constructors of `ty`, or `None` if there are too many of
+    /// them to list. `heads` are the patterns in the current column, which
+    /// determine how we split slices into a finite number of constructors.
+    fn all_ctors(&self, ty: &Ty, heads: &[&DeconstructedPat]) -> Option<Vec<Constructor>> {
+        let slice_kinds = heads.iter().filter_map(|pat| match pat.ctor() {
+            Some(Constructor::Slice(kind)) => Some(kind),
+            _ => None,
+        });
+        let res = match ty {
+            Ty::Apply(ApplicationTy { ctor, .. }) => match ctor {
+                TypeCtor::Bool => vec![Constructor::Bool(false), Constructor::Bool(true)],
+                TypeCtor::Tuple { .. } => vec![Constructor::Single],
+                TypeCtor::Ref(_) => vec![Constructor::Ref],
+                TypeCtor::Never => Vec::new(),
+                TypeCtor::Adt(AdtId::StructId(_)) => vec![Constructor::Single],
+                TypeCtor::Adt(AdtId::EnumId(it)) => self
+                    .db
+                    .enum_data(*it)
+                    .variants
+                    .iter()
+                    .map(|(local_id, _)| {
+                        Constructor::Variant(EnumVariantId { parent: *it, local_id })
+                    })
+                    .collect(),
+                TypeCtor::Slice => {
+                    let mut max_fixed_len = 0;
+                    let (mut max_prefix_len, mut max_suffix_len) = (0, 0);
+                    for kind in slice_kinds {
+                        match kind {
+                            SliceKind::Fixed(len) => max_fixed_len = max_fixed_len.max(len),
+                            SliceKind::VarLen(prefix, suffix) => {
+                                max_prefix_len = max_prefix_len.max(prefix);
+                                max_suffix_len = max_suffix_len.max(suffix);
+                            }
+                        }
+                    }
+                    // Slices longer than any fixed-length pattern all behave the
+                    // same, so we represent them with a single `VarLen`.
+                    if max_fixed_len + 1 >= max_prefix_len + max_suffix_len {
+                        max_prefix_len = max_fixed_len + 1 - max_suffix_len;
+                    }
+                    (0..max_prefix_len + max_suffix_len)
+                        .map(|len| Constructor::Slice(SliceKind::Fixed(len)))
+                        .chain(iter::once(Constructor::Slice(SliceKind::VarLen(
+                            max_prefix_len,
+                            max_suffix_len,
+                        ))))
+                        .collect()
+                }
+                TypeCtor::Array(Uncertain::Known(len)) => {
+                    vec![Constructor::Slice(SliceKind::Fixed(usize::try_from(*len).ok()?))]
+                }
+                TypeCtor::Array(Uncertain::Unknown) => {
+                    // Take the length from the patterns, which rustc checks
+                    // against the type anyway.
+                    let mut len = None;
+                    for kind in slice_kinds {
+                        match kind {
+                            SliceKind::Fixed(_) => return Some(vec![Constructor::Slice(kind)]),
+                            SliceKind::VarLen(..) => len = Some(len.unwrap_or(0).max(kind.arity())),
+                        }
+                    }
+                    vec![Constructor::Slice(SliceKind::Fixed(len?))]
+                }
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(res)
+    }
+
+    /// Computes (at most `MAX_WITNESSES`) lists of patterns of types `tys` not
+    /// covered by any of the `rows`.
+    fn compute_witnesses(&self, rows: Vec<Row>, tys: &[Ty]) -> Vec<Witness> {
+        let ty = match tys.first() {
+            Some(it) => it,
+            None => return if rows.is_empty() { vec![Vec::new()] } else { Vec::new() },
+        };
+        let rows = expand_or_pats(rows);
+        let heads: Vec<&DeconstructedPat> = rows.iter().map(|row| &row[0]).collect();
+
+        let all_ctors = self.all_ctors(ty, &heads);
+        let missing_ctors = all_ctors.as_ref().map(|all_ctors| {
+            all_ctors
+                .iter()
+                .copied()
+                .filter(|&ctor| {
+                    !heads.iter().any(|head| head.ctor().map_or(false, |it| it.covers(ctor)))
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let mut res = Vec::new();
+        match (all_ctors, missing_ctors) {
+            (Some(all_ctors), Some(missing_ctors)) if missing_ctors.is_empty() => {
+                // Every constructor appears in the column, so a value is
+                // covered iff its fields are covered by the rows which
+                // start with its constructor.
+                for ctor in all_ctors {
+                    let field_tys = self.ctor_field_tys(ctor, ty);
+                    let arity = field_tys.len();
+                    let specialized =
+                        rows.iter().filter_map(|row| specialize(row, ctor, arity)).collect();
+                    let tys =
+                        field_tys.into_iter().chain(tys[1..].iter().cloned()).collect::<Vec<_>>();
+
+                    for mut witness in self.compute_witnesses(specialized, &tys) {
+                        let rest = witness.split_off(arity);
+                        let head = WitnessPat::Ctor(ctor, witness);
+                        res.push(iter::once(head).chain(rest).collect());
+                        if res.len() >= MAX_WITNESSES {
+                            return res;
+                        }
+                    }
+                }
+            }
+            (_, missing_ctors) => {
+                // Some constructor is missing from the column, so only the
+                // rows starting with a wildcard can cover its values.
+                let default_rows =
+                    rows.iter().filter(|row| row[0].ctor().is_none()).map(|row| row[1..].to_vec());
+                let witnesses = self.compute_witnesses(default_rows.collect(), &tys[1..]);
+                if witnesses.is_empty() {
+                    return res;
+                }
+
+                let heads_are_wild = heads.iter().all(|head| head.ctor().is_none());
+                let head_witnesses = match missing_ctors {
+                    Some(missing_ctors) if !heads_are_wild => missing_ctors
+                        .into_iter()
+                        .map(|ctor| {
+                            let arity = self.ctor_field_tys(ctor, ty).len();
+                            WitnessPat::Ctor(ctor, vec![WitnessPat::Wild; arity])
+                        })
+                        .collect(),
+                    _ => vec![WitnessPat::Wild],
+                };
+                for head in head_witnesses {
+                    for witness in &witnesses {
+                        res.push(iter::once(head.clone()).chain(witness.iter().cloned()).collect());
+                        if res.len() >= MAX_WITNESSES {
+                            return res;
+                        }
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    fn fmt_witness(&self, pat: &WitnessPat, ty: &Ty, buf: &mut String) {
+        let (ctor, fields) = match pat {
+            WitnessPat::Wild => return buf.push('_'),
+            WitnessPat::Ctor(ctor, fields) => (*ctor, fields),
+        };
+        let field_tys = self.ctor_field_tys(ctor, ty);
+        let fmt_fields = |buf: &mut String, fields: &[WitnessPat], field_tys: &[Ty]| {
+            for (i, (field, field_ty)) in fields.iter().zip(field_tys).enumerate() {
+                if i > 0 {
+                    buf.push_str(", ");
+                }
+                self.fmt_witness(field, field_ty, buf);
+            }
+        };
+
+        let variant: VariantId = match ctor {
+            Constructor::Bool(value) => return format_to!(buf, "{}", value),
+            Constructor::Opaque => return buf.push('_'),
+            Constructor::Ref => {
+                buf.push('&');
+                return self.fmt_witness(&fields[0], &field_tys[0], buf);
+            }
+            Constructor::Slice(kind) => {
+                buf.push('[');
+                match kind {
+                    SliceKind::Fixed(_) => fmt_fields(buf, fields, &field_tys),
+                    SliceKind::VarLen(prefix, _) => {
+                        fmt_fields(buf, &fields[..prefix], &field_tys[..prefix]);
+                        buf.push_str(if prefix > 0 { ", .." } else { ".." });
+                        if fields.len() > prefix {
+                            buf.push_str(", ");
+                            fmt_fields(buf, &fields[prefix..], &field_tys[prefix..]);
+                        }
+                    }
+                }
+                return buf.push(']');
+            }
+            Constructor::Single if ty.as_tuple().is_some() => {
+                buf.push('(');
+                fmt_fields(buf, fields, &field_tys);
+                return buf.push_str(if fields.len() == 1 { ",)" } else { ")" });
+            }
+            Constructor::Single => match ty.as_adt() {
+                Some((AdtId::StructId(it), _)) => {
+                    format_to!(buf, "{}", self.db.struct_data(it).name);
+                    it.into()
+                }
+                _ => return buf.push('_'),
+            },
+            Constructor::Variant(it) => {
+                let enum_data = self.db.enum_data(it.parent);
+                format_to!(buf, "{}::{}", enum_data.name, enum_data.variants[it.local_id].name);
+                it.into()
+            }
+        };
+
+        let variant_data = variant_data(self.db.upcast(), variant);
+        match variant_data.kind() {
+            StructKind::Unit => {}
+            StructKind::Tuple => {
+                buf.push('(');
+                fmt_fields(buf, fields, &field_tys);
+                buf.push(')');
+            }
+            StructKind::Record => {
+                buf.push_str(" {");
+                let mut has_wild = false;
+                let mut first = true;
+                for ((_, data), (field, field_ty)) in
+                    variant_data.fields().iter().zip(fields.iter().zip(&field_tys))
+                {
+                    if let WitnessPat::Wild = field {
+                        has_wild = true;
+                        continue;
+                    }
+                    buf.push_str(if first { " " } else { ", " });
+                    first = false;
+                    format_to!(buf, "{}: ", data.name);
+                    self.fmt_witness(field, field_ty, buf);
+                }
+                if has_wild {
+                    buf.push_str(if first { " .." } else { ", .." });
+                }
+                buf.push_str(" }");
+            }
+        }
+    }
+}
+
+/// Replaces rows starting with an or-pattern by one row per alternative.
+fn expand_or_pats(rows: Vec<Row>) -> Vec<Row> {
+    let mut res = Vec::with_capacity(rows.len());
+    let mut stack = rows;
+    stack.reverse();
+    while let Some(row) = stack.pop() {
+        match &row[0] {
+            DeconstructedPat::Or(alts) => {
+                for alt in alts.iter().rev() {
+                    let mut row = row.clone();
+                    row[0] = alt.clone();
+                    stack.push(row);
+                }
+            }
+            _ => res.push(row),
+        }
+    }
+    res
+}
+
+/// Specializes `row` to the values built with `ctor`: returns its fields,
+/// followed by the rest of the row, or `None` if the row doesn't match such
+/// values.
+fn specialize(row: &[DeconstructedPat], ctor: Constructor, arity: usize) -> Option<Row> {
+    let mut res: Row = match &row[0] {
+        DeconstructedPat::Wild => vec![DeconstructedPat::Wild; arity],
+        DeconstructedPat::Ctor(head, fields) if head.covers(ctor) => match head {
+            Constructor::Slice(SliceKind::VarLen(prefix, suffix)) => fields[..*prefix]
+                .iter()
+                .cloned()
+                .chain(iter::repeat(DeconstructedPat::Wild).take(arity - prefix - suffix))
+                .chain(fields[*prefix..].iter().cloned())
+                .collect(),
+            _ => fields.clone(),
+        },
+        DeconstructedPat::Ctor(..) | DeconstructedPat::Or(_) => return None,
+    };
+    res.extend(row[1..].iter().cloned());
+    Some(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+    use ra_db::fixture::WithFixture;
+
+    use crate::test_db::TestDB;
+
+    fn diagnostics(content: &str) -> String {
+        TestDB::with_single_file(content).0.diagnostics()
+    }
+
+    fn check_no_diagnostic(content: &str) {
+        assert_eq!(diagnostics(content), "");
+    }
+
+    #[test]
+    fn enum_missing_variants() {
+        let diagnostics = diagnostics(
+            r"
+            enum Either { A, B, C }
+            fn test_fn() {
+                match Either::B {
+                    Either::A => (),
+                }
+            }
+            ",
+        );
+        assert_snapshot!(diagnostics, @r###"
+        "Either::B": Missing match arms: `Either::B`, `Either::C` not covered
+        "###
+        );
+    }
+
+    #[test]
+    fn enum_exhaustive() {
+        check_no_diagnostic(
+            r"
+            enum Either { A, B }
+            fn test_fn() {
+                match Either::B {
+                    Either::A => (),
+                    Either::B => (),
+                }
+                match Either::B {
+                    Either::A | Either::B => (),
+                }
+                match Either::B {
+                    _ => (),
+                }
+            }
+            ",
+        );
+    }
+
+    #[test]
+    fn nested_tuple_variant() {
+        let diagnostics = diagnostics(
+            r"
+            enum Option<T> { Some(T), None }
+            fn test_fn() {
+                match Option::Some(true) {
+                    Option::Some(true) => (),
+                    Option::None => (),
+                }
+            }
+            ",
+        );
+        assert_snapshot!(diagnostics, @r###"
+        "Option::Some(true)": Missing match arms: `Option::Some(false)` not covered
+        "###
+        );
+    }
+
+    #[test]
+    fn record_variant() {
+        let diagnostics = diagnostics(
+            r"
+            enum Either { A { foo: bool }, B }
+            fn test_fn() {
+                let a = Either::B;
+                match a {
+                    Either::A { foo: true } => (),
+                    Either::B => (),
+                }
+            }
+            ",
+        );
+        assert_snapshot!(diagnostics, @r###"
+        "a": Missing match arms: `Either::A { foo: false }` not covered
+        "###
+        );
+    }
+
+    #[test]
+    fn tuple_of_bools() {
+        let diagnostics = diagnostics(
+            r"
+            fn test_fn() {
+                match (false, true) {
+                    (true, true) => (),
+                    (false, _) => (),
+                }
+            }
+            ",
+        );
+        assert_snapshot!(diagnostics, @r###"
+        "(false, true)": Missing match arms: `(true, false)` not covered
+        "###
+        );
+    }
+
+    #[test]
+    fn tuple_with_ellipsis() {
+        let diagnostics = diagnostics(
+            r"
+            fn test_fn() {
+                match (true, false, true) {
+                    (true, ..) => (),
+                    (.., true) => (),
+                }
+                match (true, false, true) {
+                    (true, ..) => (),
+                    (false, ..) => (),
+                }
+            }
+            ",
+        );
+        assert_snapshot!(diagnostics, @r###"
+        "(true, false, true)": Missing match arms: `(false, _, false)` not covered
+        "###
+        );
+    }
+
+    #[test]
+    fn match_ergonomics() {
+        let diagnostics = diagnostics(
+            r"
+            enum Either { A, B }
+            fn test_fn() {
+                match &Either::A {
+                    Either::A => (),
+                }
+                match &Either::A {
+                    &Either::A => (),
+                    Either::B => (),
+                }
+            }
+            ",
+        );
+        assert_snapshot!(diagnostics, @r###"
+        "&Either::A": Missing match arms: `&Either::B` not covered
+        "###
+        );
+    }
+
+    #[test]
+    fn slices() {
+        let diagnostics = diagnostics(
+            r"
+            fn test_fn(s: &[bool]) {
+                match s {
+                    [] => (),
+                    [true, ..] => (),
+                }
+                match s {
+                    [] => (),
+                    [_] => (),
+                    [_, .., _] => (),
+                }
+            }
+            ",
+        );
+        assert_snapshot!(diagnostics, @r###"
+        "s": Missing match arms: `&[false, ..]` not covered
+        "###
+        );
+    }
+
+    #[test]
+    fn guarded_arm_does_not_cover() {
+        let diagnostics = diagnostics(
+            r"
+            enum Either { A, B }
+            fn test_fn(cond: bool) {
+                match Either::A {
+                    Either::A if cond => (),
+                    Either::B => (),
+                }
+            }
+            ",
+        );
+        assert_snapshot!(diagnostics, @r###"
+        "Either::A": Missing match arms: `Either::A` not covered
+        "###
+        );
+    }
+
+    #[test]
+    fn integers_need_a_wildcard() {
+        let diagnostics = diagnostics(
+            r"
+            fn test_fn() {
+                match 5 {
+                    1 => (),
+                }
+                match 5 {
+                    1 => (),
+                    _ => (),
+                }
+            }
+            ",
+        );
+        assert_snapshot!(diagnostics, @r###"
+        "5": Missing match arms: `_` not covered
+        "###
+        );
+    }
+
+    #[test]
+    fn empty_enum() {
+        check_no_diagnostic(
+            r"
+            enum Void {}
+            fn test_fn(v: Void) {
+                match v {}
+            }
+            ",
+        );
+    }
+
+    #[test]
+    fn unresolved_types_are_skipped() {
+        let diagnostics = diagnostics(
+            r"
+            fn test_fn() {
+                match Unknown::A {
+                    Unknown::A => (),
+                }
+                match (false, Unknown::A) {
+                    (true, _) => (),
+                    (false, Unknown::A) => (),
+                }
+            }
+            ",
+        );
+        assert_snapshot!(diagnostics, @r###"
+        "Unknown::A": unresolved path
+        "Unknown::A": unresolved path
+        "Unknown::A": unresolved path
+        "Unknown::A": unresolved path
+        "###
+        );
+    }
+}
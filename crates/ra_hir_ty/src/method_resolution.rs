@@ -20,7 +20,8 @@ use crate::{
     db::HirDatabase,
     primitive::{FloatBitness, Uncertain},
     utils::all_super_traits,
-    ApplicationTy, Canonical, InEnvironment, TraitEnvironment, TraitRef, Ty, TypeCtor, TypeWalk,
+    ApplicationTy, Canonical, GenericPredicate, InEnvironment, TraitEnvironment, TraitRef, Ty,
+    TypeCtor, TypeWalk,
 };
 
 /// This is used as a key for indexing impls.
@@ -35,7 +36,7 @@ impl TyFingerprint {
     /// `impl &S`. Hence, this will return `None` for reference types and such.
     fn for_impl(ty: &Ty) -> Option<TyFingerprint> {
         match ty {
-            Ty::Apply(a_ty) => Some(TyFingerprint::Apply(a_ty.ctor)),
+            Ty::Apply(a_ty) => Some(TyFingerprint::Apply(a_ty.ctor.without_array_len())),
             _ => None,
         }
     }
@@ -136,8 +137,18 @@ impl Ty {
         Some(res)
     }
 }
-/// Look up the method with the given name, returning the actual autoderefed
-/// receiver type (but without autoref applied yet).
+/// The adjustments that turn the receiver of a method call into its `self`
+/// argument: the receiver is first autoderefed `autoderefs` times (following
+/// `autoderef_method_receiver`, so the last step may be an array unsizing),
+/// then an autoref is applied.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReceiverAdjustments {
+    pub autoderefs: usize,
+    pub autoref: Option<Mutability>,
+}
+
+/// Look up the method with the given name, returning the self type of the
+/// method's impl and the adjustments that need to be applied to the receiver.
 pub(crate) fn lookup_method(
     ty: &Canonical<Ty>,
     db: &dyn HirDatabase,
@@ -145,8 +156,8 @@ pub(crate) fn lookup_method(
     krate: CrateId,
     traits_in_scope: &FxHashSet<TraitId>,
     name: &Name,
-) -> Option<(Ty, FunctionId)> {
-    iterate_method_candidates(
+) -> Option<(Ty, FunctionId, ReceiverAdjustments)> {
+    iterate_method_candidates_impl(
         ty,
         db,
         env,
@@ -154,8 +165,8 @@ pub(crate) fn lookup_method(
         &traits_in_scope,
         Some(name),
         LookupMode::MethodCall,
-        |ty, f| match f {
-            AssocItemId::FunctionId(f) => Some((ty.clone(), f)),
+        |ty, f, adjustments| match f {
+            AssocItemId::FunctionId(f) => Some((ty.clone(), f, adjustments)),
             _ => None,
         },
     )
@@ -185,13 +196,35 @@ pub fn iterate_method_candidates<T>(
     name: Option<&Name>,
     mode: LookupMode,
     mut callback: impl FnMut(&Ty, AssocItemId) -> Option<T>,
+) -> Option<T> {
+    iterate_method_candidates_impl(
+        ty,
+        db,
+        env,
+        krate,
+        traits_in_scope,
+        name,
+        mode,
+        |ty, item, _adjustments| callback(ty, item),
+    )
+}
+
+fn iterate_method_candidates_impl<T>(
+    ty: &Canonical<Ty>,
+    db: &dyn HirDatabase,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    traits_in_scope: &FxHashSet<TraitId>,
+    name: Option<&Name>,
+    mode: LookupMode,
+    mut callback: impl FnMut(&Ty, AssocItemId, ReceiverAdjustments) -> Option<T>,
 ) -> Option<T> {
     match mode {
         LookupMode::MethodCall => {
             // For method calls, rust first does any number of autoderef, and then one
-            // autoref (i.e. when the method takes &self or &mut self). We just ignore
-            // the autoref currently -- when we find a method matching the given name,
-            // we assume it fits.
+            // autoref (i.e. when the method takes &self or &mut self). The
+            // number of autoderefs and the autoref are passed to the callback,
+            // so that they can be recorded as adjustments of the receiver.
 
             // Also note that when we've got a receiver like &S, even if the method we
             // find in the end takes &self, we still do the autoderef step (just as
@@ -212,15 +245,17 @@ pub fn iterate_method_candidates<T>(
             // types*.
 
             let deref_chain = autoderef_method_receiver(db, krate, ty);
-            for i in 0..deref_chain.len() {
+            for autoderefs in 0..deref_chain.len() {
                 if let Some(result) = iterate_method_candidates_with_autoref(
-                    &deref_chain[i..],
+                    &deref_chain[autoderefs..],
                     db,
                     env.clone(),
                     krate,
                     traits_in_scope,
                     name,
-                    &mut callback,
+                    |ty, item, autoref| {
+                        callback(ty, item, ReceiverAdjustments { autoderefs, autoref })
+                    },
                 ) {
                     return Some(result);
                 }
@@ -236,7 +271,7 @@ pub fn iterate_method_candidates<T>(
                 krate,
                 traits_in_scope,
                 name,
-                &mut callback,
+                |ty, item| callback(ty, item, ReceiverAdjustments::default()),
             )
         }
     }
@@ -249,7 +284,7 @@ fn iterate_method_candidates_with_autoref<T>(
     krate: CrateId,
     traits_in_scope: &FxHashSet<TraitId>,
     name: Option<&Name>,
-    mut callback: impl FnMut(&Ty, AssocItemId) -> Option<T>,
+    mut callback: impl FnMut(&Ty, AssocItemId, Option<Mutability>) -> Option<T>,
 ) -> Option<T> {
     if let Some(result) = iterate_method_candidates_by_receiver(
         &deref_chain[0],
@@ -259,7 +294,7 @@ fn iterate_method_candidates_with_autoref<T>(
         krate,
         &traits_in_scope,
         name,
-        &mut callback,
+        |ty, item| callback(ty, item, None),
     ) {
         return Some(result);
     }
@@ -275,7 +310,7 @@ fn iterate_method_candidates_with_autoref<T>(
         krate,
         &traits_in_scope,
         name,
-        &mut callback,
+        |ty, item| callback(ty, item, Some(Mutability::Shared)),
     ) {
         return Some(result);
     }
@@ -291,7 +326,7 @@ fn iterate_method_candidates_with_autoref<T>(
         krate,
         &traits_in_scope,
         name,
-        &mut callback,
+        |ty, item| callback(ty, item, Some(Mutability::Mut)),
     ) {
         return Some(result);
     }
@@ -372,7 +407,7 @@ fn iterate_trait_method_candidates<T>(
     receiver_ty: Option<&Canonical<Ty>>,
     mut callback: impl FnMut(&Ty, AssocItemId) -> Option<T>,
 ) -> Option<T> {
-    // if ty is `impl Trait` or `dyn Trait`, the trait doesn't need to be in scope
+    // if ty is `dyn Trait`, the trait doesn't need to be in scope
     let inherent_trait = self_ty.value.inherent_trait().into_iter();
     let env_traits = if let Ty::Placeholder(_) = self_ty.value {
         // if we have `T: Trait` in the param env, the trait doesn't need to be in scope
@@ -380,6 +415,16 @@ fn iterate_trait_method_candidates<T>(
             .map(|tr| tr.trait_)
             .flat_map(|t| all_super_traits(db.upcast(), t))
             .collect()
+    } else if let Some(bounds) = self_ty.value.impl_trait_bounds(db) {
+        // neither do the traits in the bounds of an opaque `impl Trait` type
+        bounds
+            .into_iter()
+            .filter_map(|pred| match pred {
+                GenericPredicate::Implemented(tr) => Some(tr.trait_),
+                _ => None,
+            })
+            .flat_map(|t| all_super_traits(db.upcast(), t))
+            .collect()
     } else {
         Vec::new()
     };
@@ -444,25 +489,64 @@ fn iterate_inherent_methods<T>(
     None
 }
 
-/// Returns the self type for the index trait call.
+/// Returns the self type for the index trait call, and the adjustments that
+/// turn the indexed expression into it.
 pub fn resolve_indexing_op(
     db: &dyn HirDatabase,
     ty: &Canonical<Ty>,
     env: Arc<TraitEnvironment>,
     krate: CrateId,
     index_trait: TraitId,
-) -> Option<Canonical<Ty>> {
+) -> Option<(Canonical<Ty>, ReceiverAdjustments)> {
     let ty = InEnvironment { value: ty.clone(), environment: env.clone() };
     let deref_chain = autoderef_method_receiver(db, krate, ty);
-    for ty in deref_chain {
+    for (autoderefs, ty) in deref_chain.into_iter().enumerate() {
         let goal = generic_implements_goal(db, env.clone(), index_trait, ty.clone());
         if db.trait_solve(krate, goal).is_some() {
-            return Some(ty);
+            return Some((ty, ReceiverAdjustments { autoderefs, autoref: None }));
         }
     }
     None
 }
 
+/// Finds the method which implements the trait method `func` for the given
+/// trait parameters, the first of which is the `Self` type. Returns `None` if
+/// there is no matching impl, or if the impl relies on the default method.
+pub fn lookup_impl_method(
+    db: &dyn HirDatabase,
+    krate: CrateId,
+    func: FunctionId,
+    trait_params: &[Ty],
+) -> Option<FunctionId> {
+    let trait_ = match func.lookup(db.upcast()).container {
+        AssocContainerId::TraitId(it) => it,
+        _ => return None,
+    };
+    let (self_ty, params) = trait_params.split_first()?;
+    let self_ty = Canonical { num_vars: 0, value: self_ty.clone() };
+    let impl_id = db.impls_for_trait(krate, trait_).iter().copied().find(|&impl_id| {
+        let substs = match inherent_impl_substs(db, impl_id, &self_ty) {
+            Some(it) => it,
+            None => return false,
+        };
+        let trait_ref = match db.impl_trait(impl_id) {
+            Some(it) => it.subst(&substs),
+            None => return false,
+        };
+        trait_ref.substs.iter().skip(1).zip(params).all(|(impl_param, param)| {
+            let impl_param = Canonical { num_vars: 0, value: impl_param.clone() };
+            let param = Canonical { num_vars: 0, value: param.clone() };
+            super::infer::unify(&impl_param, &param).is_some()
+        })
+    })?;
+
+    let name = &db.function_data(func).name;
+    db.impl_data(impl_id).items.iter().find_map(|item| match *item {
+        AssocItemId::FunctionId(it) if db.function_data(it).name == *name => Some(it),
+        _ => None,
+    })
+}
+
 fn is_valid_candidate(
     db: &dyn HirDatabase,
     name: Option<&Name>,
@@ -566,7 +650,7 @@ pub fn implements_trait(
 ) -> bool {
     if ty.value.inherent_trait() == Some(trait_) {
         // FIXME this is a bit of a hack, since Chalk should say the same thing
-        // anyway, but currently Chalk doesn't implement `dyn/impl Trait` yet
+        // anyway, but currently Chalk doesn't implement `dyn Trait` yet
         return true;
     }
     let goal = generic_implements_goal(db, env, trait_, ty.clone());
@@ -594,14 +678,14 @@ fn generic_implements_goal(
     Canonical { num_vars, value: InEnvironment::new(env, obligation) }
 }
 
-fn autoderef_method_receiver(
+pub(crate) fn autoderef_method_receiver(
     db: &dyn HirDatabase,
     krate: CrateId,
     ty: InEnvironment<Canonical<Ty>>,
 ) -> Vec<Canonical<Ty>> {
     let mut deref_chain: Vec<_> = autoderef::autoderef(db, Some(krate), ty).collect();
     // As a last step, we can do array unsizing (that's the only unsizing that rustc does for method receivers!)
-    if let Some(Ty::Apply(ApplicationTy { ctor: TypeCtor::Array, parameters })) =
+    if let Some(Ty::Apply(ApplicationTy { ctor: TypeCtor::Array(_), parameters })) =
         deref_chain.last().map(|ty| &ty.value)
     {
         let num_vars = deref_chain.last().unwrap().num_vars;
@@ -1,5 +1,6 @@
 //! Helper functions for binary operator type inference.
-use hir_def::expr::{ArithOp, BinaryOp, CmpOp};
+use hir_def::expr::{ArithOp, BinaryOp, CmpOp, Ordering};
+use hir_expand::name::{name, Name};
 
 use super::{InferTy, Ty, TypeCtor};
 use crate::ApplicationTy;
@@ -55,3 +56,48 @@ pub(super) fn binary_op_rhs_expectation(op: BinaryOp, lhs_ty: Ty) -> Ty {
         },
     }
 }
+
+/// Returns the lang item of the trait which overloads `op`, together with the
+/// name of the trait method the operator desugars to.
+pub(super) fn binary_op_lang_item(op: BinaryOp) -> Option<(&'static str, Name)> {
+    let res = match op {
+        BinaryOp::LogicOp(_) => return None,
+        BinaryOp::ArithOp(op) => match op {
+            ArithOp::Add => ("add", name![add]),
+            ArithOp::Mul => ("mul", name![mul]),
+            ArithOp::Sub => ("sub", name![sub]),
+            ArithOp::Div => ("div", name![div]),
+            ArithOp::Rem => ("rem", name![rem]),
+            ArithOp::Shl => ("shl", name![shl]),
+            ArithOp::Shr => ("shr", name![shr]),
+            ArithOp::BitXor => ("bitxor", name![bitxor]),
+            ArithOp::BitOr => ("bitor", name![bitor]),
+            ArithOp::BitAnd => ("bitand", name![bitand]),
+        },
+        BinaryOp::Assignment { op: None } => return None,
+        BinaryOp::Assignment { op: Some(op) } => match op {
+            ArithOp::Add => ("add_assign", name![add_assign]),
+            ArithOp::Mul => ("mul_assign", name![mul_assign]),
+            ArithOp::Sub => ("sub_assign", name![sub_assign]),
+            ArithOp::Div => ("div_assign", name![div_assign]),
+            ArithOp::Rem => ("rem_assign", name![rem_assign]),
+            ArithOp::Shl => ("shl_assign", name![shl_assign]),
+            ArithOp::Shr => ("shr_assign", name![shr_assign]),
+            ArithOp::BitXor => ("bitxor_assign", name![bitxor_assign]),
+            ArithOp::BitOr => ("bitor_assign", name![bitor_assign]),
+            ArithOp::BitAnd => ("bitand_assign", name![bitand_assign]),
+        },
+        BinaryOp::CmpOp(CmpOp::Eq { negated: false }) => ("eq", name![eq]),
+        BinaryOp::CmpOp(CmpOp::Eq { negated: true }) => ("eq", name![ne]),
+        BinaryOp::CmpOp(CmpOp::Ord { ordering, strict }) => {
+            let method = match (ordering, strict) {
+                (Ordering::Less, true) => name![lt],
+                (Ordering::Less, false) => name![le],
+                (Ordering::Greater, true) => name![gt],
+                (Ordering::Greater, false) => name![ge],
+            };
+            ("partial_ord", method)
+        }
+    };
+    Some(res)
+}
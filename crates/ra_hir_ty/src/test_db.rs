@@ -12,7 +12,7 @@ use ra_db::{
 };
 use stdx::format_to;
 
-use crate::{db::HirDatabase, expr::ExprValidator};
+use crate::{db::HirDatabase, expr::ExprValidator, unsafe_validation::UnsafeValidator};
 
 #[salsa::database(
     ra_db::SourceDatabaseExtStorage,
@@ -135,7 +135,9 @@ impl TestDB {
                     format_to!(buf, "{:?}: {}\n", d.syntax_node(self).text(), d.message());
                 });
                 infer.add_diagnostics(self, f, &mut sink);
-                let mut validator = ExprValidator::new(f, infer, &mut sink);
+                let mut validator = ExprValidator::new(f, infer.clone(), &mut sink);
+                validator.validate_body(self);
+                let mut validator = UnsafeValidator::new(f, infer, &mut sink);
                 validator.validate_body(self);
             }
         }
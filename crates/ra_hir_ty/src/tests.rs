@@ -13,6 +13,7 @@ use hir_def::{
     body::{BodySourceMap, SyntheticSyntax},
     child_by_source::ChildBySource,
     db::DefDatabase,
+    expr::ExprId,
     item_scope::ItemScope,
     keys,
     nameres::CrateDefMap,
@@ -34,6 +35,23 @@ use crate::{db::HirDatabase, display::HirDisplay, test_db::TestDB, InferenceResu
 // update the snapshots.
 
 fn type_at_pos(db: &TestDB, pos: FilePosition) -> String {
+    let (infer, expr_id) = infer_expr_at_pos(db, pos);
+    infer[expr_id].display(db).to_string()
+}
+
+/// Renders the adjustments of the expression at `pos`, like
+/// `OverloadedDeref(Shared) -> S, Borrow(Shared) -> &S`.
+fn adjustments_at_pos(db: &TestDB, pos: FilePosition) -> String {
+    let (infer, expr_id) = infer_expr_at_pos(db, pos);
+    infer
+        .expr_adjustments(expr_id)
+        .iter()
+        .map(|it| format!("{:?} -> {}", it.kind, it.target.display(db)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn infer_expr_at_pos(db: &TestDB, pos: FilePosition) -> (Arc<InferenceResult>, ExprId) {
     let file = db.parse(pos.file_id).ok().unwrap();
     let expr = algo::find_node_at_offset::<ast::Expr>(file.syntax(), pos.offset).unwrap();
     let fn_def = expr.syntax().ancestors().find_map(ast::FnDef::cast).unwrap();
@@ -44,9 +62,7 @@ fn type_at_pos(db: &TestDB, pos: FilePosition) -> String {
 
     let (_body, source_map) = db.body_with_source_map(func.into());
     if let Some(expr_id) = source_map.node_expr(InFile::new(pos.file_id.into(), &expr)) {
-        let infer = db.infer(func.into());
-        let ty = &infer[expr_id];
-        return ty.display(db).to_string();
+        return (db.infer(func.into()), expr_id);
     }
     panic!("Can't find expression")
 }
@@ -78,6 +94,9 @@ fn infer_with_mismatches(content: &str, include_mismatches: bool) -> String {
                 Err(SyntheticSyntax) => continue,
             };
             types.push((syntax_ptr, ty));
+            if let Some(mismatch) = inference_result.type_mismatch_for_pat(pat) {
+                mismatches.push((syntax_ptr, mismatch));
+            }
         }
 
         for (expr, ty) in inference_result.type_of_expr.iter() {
@@ -318,3 +337,92 @@ fn no_such_field_diagnostics() {
     "###
     );
 }
+
+#[test]
+fn unresolved_type_path_diagnostics() {
+    let diagnostics = TestDB::with_files(
+        r"
+        //- /lib.rs
+        struct S;
+        fn f<T>(s: S, t: T, u: Unknown) {
+            let x: Vec<Unknown> = loop {};
+            let z: (Unknown, Unknown) = loop {};
+            let w = 0 as Unknown;
+            let y: S = s;
+        }
+        ",
+    )
+    .diagnostics();
+
+    assert_snapshot!(diagnostics, @r###"
+    "Unknown": unresolved path
+    "Vec<Unknown>": unresolved path
+    "Unknown": unresolved path
+    "Unknown": unresolved path
+    "Unknown": unresolved path
+    "###
+    );
+}
+
+#[test]
+fn type_mismatch_diagnostics() {
+    let diagnostics = TestDB::with_files(
+        r"
+        //- /lib.rs
+        struct S;
+        fn takes_ref(s: &S) {}
+        fn returns_u32() -> u32 {
+            let x: bool = 1;
+            takes_ref(S);
+            {
+                true
+            }
+        }
+        ",
+    )
+    .diagnostics();
+
+    assert_snapshot!(diagnostics, @r###"
+    "1": expected bool, found i32
+    "S": expected &S, found S
+    "true": expected u32, found bool
+    "###
+    );
+}
+
+#[test]
+fn missing_unsafe_diagnostics() {
+    let diagnostics = TestDB::with_files(
+        r"
+        //- /lib.rs
+        unsafe fn unsafe_fn() {}
+        static mut GLOBAL: u32 = 0;
+        struct S;
+        impl S {
+            unsafe fn unsafe_method(&self) {}
+        }
+        fn safe(x: *const u32) {
+            unsafe_fn();
+            S.unsafe_method();
+            let _ = *x;
+            GLOBAL = 1;
+            unsafe {
+                unsafe_fn();
+                let _ = *x;
+            }
+        }
+        unsafe fn unsafe_body(x: *const u32) {
+            let _ = *x;
+        }
+        ",
+    )
+    .diagnostics();
+
+    assert_snapshot!(diagnostics, @r###"
+    "unsafe_fn()": this operation is unsafe and requires an unsafe function or block
+    "S.unsafe_method()": this operation is unsafe and requires an unsafe function or block
+    "*x": this operation is unsafe and requires an unsafe function or block
+    "GLOBAL": this operation is unsafe and requires an unsafe function or block
+    "###
+    );
+}
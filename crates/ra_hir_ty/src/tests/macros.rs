@@ -263,6 +263,61 @@ fn test() { S.foo()<|>; }
     assert_eq!(t, "u128");
 }
 
+#[test]
+fn macro_rules_locals_are_hygienic() {
+    let t = type_at(
+        r#"
+//- /main.rs
+macro_rules! shadow {
+    ($e:expr) => {{ let x = 0u8; $e }};
+}
+fn test() {
+    let x = 0u128;
+    let z = shadow!(x);
+    z<|>;
+}
+"#,
+    );
+    assert_eq!(t, "u128");
+}
+
+#[test]
+fn macro_rules_locals_are_visible_in_their_expansion() {
+    let t = type_at(
+        r#"
+//- /main.rs
+macro_rules! local {
+    () => {{ let x = 0u8; x }};
+}
+fn test() {
+    let x = 0u128;
+    let z = local!();
+    z<|>;
+}
+"#,
+    );
+    assert_eq!(t, "u8");
+}
+
+#[test]
+fn macro_rules_labels_are_hygienic() {
+    let t = type_at(
+        r#"
+//- /main.rs
+macro_rules! inner {
+    ($e:expr) => { 'a: loop { $e; break 'a 0u8; } };
+}
+fn test() {
+    let z = 'a: loop {
+        let _ = inner!(break 'a 0u128);
+    };
+    z<|>;
+}
+"#,
+    );
+    assert_eq!(t, "u128");
+}
+
 #[test]
 fn infer_impl_items_generated_by_macros() {
     let t = type_at(
@@ -1,4 +1,4 @@
-use super::{infer, type_at, type_at_pos};
+use super::{adjustments_at_pos, infer, type_at, type_at_pos};
 use crate::test_db::TestDB;
 use insta::assert_snapshot;
 use ra_db::fixture::WithFixture;
@@ -1095,3 +1095,118 @@ fn test() { (S {}).method()<|>; }
     );
     assert_eq!(t, "()");
 }
+
+#[test]
+fn method_receiver_adjustments_through_deref_mut() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs
+#[lang = "deref"]
+trait Deref {
+    type Target;
+    fn deref(&self) -> &Self::Target;
+}
+#[lang = "deref_mut"]
+trait DerefMut: Deref {
+    fn deref_mut(&mut self) -> &mut Self::Target;
+}
+
+struct Guard<T>;
+impl<T> Deref for Guard<T> {
+    type Target = T;
+}
+impl<T> DerefMut for Guard<T> {}
+
+struct S;
+impl S {
+    fn set(&mut self) {}
+}
+
+fn test(mut g: Guard<S>) {
+    g<|>.set();
+}
+"#,
+    );
+    assert_eq!("OverloadedDeref(Mut) -> S, Borrow(Mut) -> &mut S", adjustments_at_pos(&db, pos));
+}
+
+#[test]
+fn method_receiver_adjustments_through_deref() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs
+#[lang = "deref"]
+trait Deref {
+    type Target;
+    fn deref(&self) -> &Self::Target;
+}
+
+struct Guard<T>;
+impl<T> Deref for Guard<T> {
+    type Target = T;
+}
+
+struct S;
+impl S {
+    fn get(&self) {}
+}
+
+fn test(g: &Guard<S>) {
+    g<|>.get();
+}
+"#,
+    );
+    assert_eq!(
+        "Deref -> Guard<S>, OverloadedDeref(Shared) -> S, Borrow(Shared) -> &S",
+        adjustments_at_pos(&db, pos)
+    );
+}
+
+#[test]
+fn field_assignment_adjustments_through_deref_mut() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs
+#[lang = "deref"]
+trait Deref {
+    type Target;
+    fn deref(&self) -> &Self::Target;
+}
+#[lang = "deref_mut"]
+trait DerefMut: Deref {
+    fn deref_mut(&mut self) -> &mut Self::Target;
+}
+
+struct Guard<T>;
+impl<T> Deref for Guard<T> {
+    type Target = T;
+}
+impl<T> DerefMut for Guard<T> {}
+
+struct S { x: u32 }
+
+fn test(mut g: Guard<S>) {
+    g<|>.x = 1;
+}
+"#,
+    );
+    assert_eq!("OverloadedDeref(Mut) -> S", adjustments_at_pos(&db, pos));
+}
+
+#[test]
+fn method_receiver_adjustments_array_unsize() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs
+#[lang = "slice"]
+impl<T> [T] {
+    fn len(&self) -> usize {}
+}
+
+fn test() {
+    [1u8, 2]<|>.len();
+}
+"#,
+    );
+    assert_eq!("Unsize -> [u8], Borrow(Shared) -> &[u8]", adjustments_at_pos(&db, pos));
+}
@@ -261,3 +261,90 @@ fn test(a: i32) {
     );
     assert_eq!(t, "f64");
 }
+
+#[test]
+fn loop_break_with_value() {
+    let t = type_at(
+        r#"
+//- /main.rs
+fn test() {
+    let x = loop {
+        if true {
+            break 1u32;
+        }
+    };
+    x<|>;
+}
+"#,
+    );
+    assert_eq!(t, "u32");
+}
+
+#[test]
+fn loop_break_without_value() {
+    let t = type_at(
+        r#"
+//- /main.rs
+fn test() {
+    let x = loop {
+        break;
+    };
+    x<|>;
+}
+"#,
+    );
+    assert_eq!(t, "()");
+}
+
+#[test]
+fn labeled_loop_break_with_value() {
+    let t = type_at(
+        r#"
+//- /main.rs
+fn test() {
+    let x = 'outer: loop {
+        let y = loop {
+            break 'outer 1u64;
+        };
+    };
+    x<|>;
+}
+"#,
+    );
+    assert_eq!(t, "u64");
+}
+
+#[test]
+fn labeled_block_break_with_value() {
+    let t = type_at(
+        r#"
+//- /main.rs
+fn test(c: bool) {
+    let x = 'a: {
+        if c {
+            break 'a 1u8;
+        }
+        2
+    };
+    x<|>;
+}
+"#,
+    );
+    assert_eq!(t, "u8");
+}
+
+#[test]
+fn diverging_let_makes_block_never() {
+    let t = type_at(
+        r#"
+//- /main.rs
+fn test() -> u32 {
+    let x = {
+        let y = return 1;
+    };
+    x<|>;
+}
+"#,
+    );
+    assert_eq!(t, "!");
+}
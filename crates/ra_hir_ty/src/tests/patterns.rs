@@ -1,4 +1,4 @@
-use super::{infer, infer_with_mismatches};
+use super::{infer, infer_with_mismatches, type_at};
 use insta::assert_snapshot;
 use test_utils::covers;
 
@@ -159,8 +159,8 @@ fn test() {
     @r###"
     [11; 210) '{     ...   } }': ()
     [21; 26) 'slice': &[f64]
-    [37; 43) '&[0.0]': &[f64; _]
-    [38; 43) '[0.0]': [f64; _]
+    [37; 43) '&[0.0]': &[f64; 1]
+    [38; 43) '[0.0]': [f64; 1]
     [39; 42) '0.0': f64
     [49; 208) 'match ...     }': ()
     [55; 60) 'slice': &[f64]
@@ -204,18 +204,18 @@ fn test() {
 "#),
     @r###"
     [11; 180) '{     ...   } }': ()
-    [21; 24) 'arr': [f64; _]
-    [37; 47) '[0.0, 1.0]': [f64; _]
+    [21; 24) 'arr': [f64; 2]
+    [37; 47) '[0.0, 1.0]': [f64; 2]
     [38; 41) '0.0': f64
     [43; 46) '1.0': f64
     [53; 178) 'match ...     }': ()
-    [59; 62) 'arr': [f64; _]
-    [73; 81) '[1.0, a]': [f64; _]
+    [59; 62) 'arr': [f64; 2]
+    [73; 81) '[1.0, a]': [f64; 2]
     [74; 77) '1.0': f64
     [79; 80) 'a': f64
     [85; 111) '{     ...     }': ()
     [99; 100) 'a': f64
-    [121; 127) '[b, c]': [f64; _]
+    [121; 127) '[b, c]': [f64; 2]
     [122; 123) 'b': f64
     [125; 126) 'c': f64
     [131; 172) '{     ...     }': ()
@@ -365,7 +365,108 @@ fn test() {
     [274; 277) 'Bar': usize
     [281; 284) 'Bar': usize
     [201; 224): expected (), got Foo
+    [212; 215): expected (), got Foo
     [263; 286): expected (), got usize
+    [274; 277): expected (), got usize
     "###
     );
 }
+
+#[test]
+fn infer_pattern_match_ergonomics_nested_refs() {
+    let t = type_at(
+        r#"
+//- /main.rs
+enum Option<T> { None, Some(T) }
+
+fn test(opt: &&Option<(u32, &str)>) {
+    match opt {
+        Option::Some((x, "s")) => { x<|>; }
+        _ => {}
+    }
+}
+"#,
+    );
+    assert_eq!(t, "&u32");
+}
+
+#[test]
+fn infer_pattern_match_ergonomics_or_pattern() {
+    let t = type_at(
+        r#"
+//- /main.rs
+enum E { A(u8), B(u8) }
+
+fn test(e: &mut E) {
+    match e {
+        E::A(x) | E::B(x) => { x<|>; }
+    }
+}
+"#,
+    );
+    assert_eq!(t, "&mut u8");
+}
+
+#[test]
+fn infer_string_literal_pattern_does_not_deref() {
+    let t = type_at(
+        r#"
+//- /main.rs
+fn test(s: &str) {
+    match s {
+        "a" => {}
+        other => { other<|>; }
+    }
+}
+"#,
+    );
+    assert_eq!(t, "&str");
+}
+
+#[test]
+fn infer_const_ref_pattern_does_not_deref() {
+    let t = type_at(
+        r#"
+//- /main.rs
+struct S;
+const C: &S = &S;
+
+fn test(s: &S) {
+    match s {
+        C => {}
+        other => { other<|>; }
+    }
+}
+"#,
+    );
+    assert_eq!(t, "&S");
+}
+
+#[test]
+fn infer_slice_rest_pattern() {
+    let t = type_at(
+        r#"
+//- /main.rs
+fn test(s: &[u32]) {
+    if let [first, rest @ ..] = s {
+        rest<|>;
+    }
+}
+"#,
+    );
+    assert_eq!(t, "&[u32]");
+}
+
+#[test]
+fn infer_array_rest_pattern() {
+    let t = type_at(
+        r#"
+//- /main.rs
+fn test(a: [u32; 4]) {
+    let [first, rest @ .., last] = a;
+    rest<|>;
+}
+"#,
+    );
+    assert_eq!(t, "[u32; 2]");
+}
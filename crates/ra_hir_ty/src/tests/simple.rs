@@ -1,4 +1,4 @@
-use super::{infer, type_at, type_at_pos};
+use super::{infer, infer_with_mismatches, type_at, type_at_pos};
 use crate::test_db::TestDB;
 use insta::assert_snapshot;
 use ra_db::fixture::WithFixture;
@@ -28,7 +28,7 @@ mod boxed {
 
 "#,
     );
-    assert_eq!("(Box<i32>, Box<Box<i32>>, Box<&i32>, Box<[i32; _]>)", type_at_pos(&db, pos));
+    assert_eq!("(Box<i32>, Box<Box<i32>>, Box<&i32>, Box<[i32; 1]>)", type_at_pos(&db, pos));
 }
 
 #[test]
@@ -1102,59 +1102,184 @@ fn test(x: &str, y: isize) {
     [9; 10) 'x': &str
     [18; 19) 'y': isize
     [28; 293) '{     ... []; }': ()
-    [38; 39) 'a': [&str; _]
-    [42; 45) '[x]': [&str; _]
+    [38; 39) 'a': [&str; 1]
+    [42; 45) '[x]': [&str; 1]
     [43; 44) 'x': &str
-    [55; 56) 'b': [[&str; _]; _]
-    [59; 65) '[a, a]': [[&str; _]; _]
-    [60; 61) 'a': [&str; _]
-    [63; 64) 'a': [&str; _]
-    [75; 76) 'c': [[[&str; _]; _]; _]
-    [79; 85) '[b, b]': [[[&str; _]; _]; _]
-    [80; 81) 'b': [[&str; _]; _]
-    [83; 84) 'b': [[&str; _]; _]
-    [96; 97) 'd': [isize; _]
-    [100; 112) '[y, 1, 2, 3]': [isize; _]
+    [55; 56) 'b': [[&str; 1]; 2]
+    [59; 65) '[a, a]': [[&str; 1]; 2]
+    [60; 61) 'a': [&str; 1]
+    [63; 64) 'a': [&str; 1]
+    [75; 76) 'c': [[[&str; 1]; 2]; 2]
+    [79; 85) '[b, b]': [[[&str; 1]; 2]; 2]
+    [80; 81) 'b': [[&str; 1]; 2]
+    [83; 84) 'b': [[&str; 1]; 2]
+    [96; 97) 'd': [isize; 4]
+    [100; 112) '[y, 1, 2, 3]': [isize; 4]
     [101; 102) 'y': isize
     [104; 105) '1': isize
     [107; 108) '2': isize
     [110; 111) '3': isize
-    [122; 123) 'd': [isize; _]
-    [126; 138) '[1, y, 2, 3]': [isize; _]
+    [122; 123) 'd': [isize; 4]
+    [126; 138) '[1, y, 2, 3]': [isize; 4]
     [127; 128) '1': isize
     [130; 131) 'y': isize
     [133; 134) '2': isize
     [136; 137) '3': isize
-    [148; 149) 'e': [isize; _]
-    [152; 155) '[y]': [isize; _]
+    [148; 149) 'e': [isize; 1]
+    [152; 155) '[y]': [isize; 1]
     [153; 154) 'y': isize
-    [165; 166) 'f': [[isize; _]; _]
-    [169; 175) '[d, d]': [[isize; _]; _]
-    [170; 171) 'd': [isize; _]
-    [173; 174) 'd': [isize; _]
-    [185; 186) 'g': [[isize; _]; _]
-    [189; 195) '[e, e]': [[isize; _]; _]
-    [190; 191) 'e': [isize; _]
-    [193; 194) 'e': [isize; _]
-    [206; 207) 'h': [i32; _]
-    [210; 216) '[1, 2]': [i32; _]
+    [165; 166) 'f': [[isize; 4]; 2]
+    [169; 175) '[d, d]': [[isize; 4]; 2]
+    [170; 171) 'd': [isize; 4]
+    [173; 174) 'd': [isize; 4]
+    [185; 186) 'g': [[isize; 1]; 2]
+    [189; 195) '[e, e]': [[isize; 1]; 2]
+    [190; 191) 'e': [isize; 1]
+    [193; 194) 'e': [isize; 1]
+    [206; 207) 'h': [i32; 2]
+    [210; 216) '[1, 2]': [i32; 2]
     [211; 212) '1': i32
     [214; 215) '2': i32
-    [226; 227) 'i': [&str; _]
-    [230; 240) '["a", "b"]': [&str; _]
+    [226; 227) 'i': [&str; 2]
+    [230; 240) '["a", "b"]': [&str; 2]
     [231; 234) '"a"': &str
     [236; 239) '"b"': &str
-    [251; 252) 'b': [[&str; _]; _]
-    [255; 265) '[a, ["b"]]': [[&str; _]; _]
-    [256; 257) 'a': [&str; _]
-    [259; 264) '["b"]': [&str; _]
+    [251; 252) 'b': [[&str; 1]; 2]
+    [255; 265) '[a, ["b"]]': [[&str; 1]; 2]
+    [256; 257) 'a': [&str; 1]
+    [259; 264) '["b"]': [&str; 1]
     [260; 263) '"b"': &str
-    [275; 276) 'x': [u8; _]
-    [288; 290) '[]': [u8; _]
+    [275; 276) 'x': [u8; 0]
+    [288; 290) '[]': [u8; 0]
     "###
     );
 }
 
+#[test]
+fn infer_array_lengths() {
+    assert_snapshot!(
+        infer_with_mismatches(r#"
+const N: usize = 2 * 3;
+const M: usize = N + 1;
+
+fn test(a: [u8; N], b: [u8; M]) {
+    let c = [0u8; N];
+    let d = [0u8; 1 << 2];
+    let e: [u8; 3] = [1, 2];
+}
+"#, true),
+        @r###"
+    [18; 19) '2': usize
+    [18; 23) '2 * 3': usize
+    [22; 23) '3': usize
+    [42; 43) 'N': usize
+    [42; 47) 'N + 1': usize
+    [46; 47) '1': usize
+    [58; 59) 'a': [u8; 6]
+    [70; 71) 'b': [u8; 7]
+    [82; 163) '{     ... 2]; }': ()
+    [92; 93) 'c': [u8; 6]
+    [96; 104) '[0u8; N]': [u8; 6]
+    [97; 100) '0u8': u8
+    [102; 103) 'N': usize
+    [114; 115) 'd': [u8; 4]
+    [118; 131) '[0u8; 1 << 2]': [u8; 4]
+    [119; 122) '0u8': u8
+    [124; 125) '1': usize
+    [124; 130) '1 << 2': usize
+    [129; 130) '2': i32
+    [141; 142) 'e': [u8; 3]
+    [154; 160) '[1, 2]': [u8; 2]
+    [155; 156) '1': u8
+    [158; 159) '2': u8
+    [154; 160): expected [u8; 3], got [u8; 2]
+    "###
+    );
+}
+
+#[test]
+fn infer_array_length_cycle() {
+    let t = type_at(
+        r#"
+//- /main.rs
+const A: usize = B;
+const B: usize = A;
+
+fn test(x: [u8; A]) {
+    x<|>;
+}
+"#,
+    );
+    assert_eq!(t, "[u8; _]");
+}
+
+#[test]
+fn infer_const_param_value() {
+    let t = type_at(
+        r#"
+//- /main.rs
+fn test<const N: usize>() {
+    N<|>;
+}
+"#,
+    );
+    assert_eq!(t, "usize");
+}
+
+#[test]
+fn infer_const_generic_args() {
+    let t = type_at(
+        r#"
+//- /main.rs
+struct Foo<T, const N: usize>(T);
+fn make<T, const N: usize>(t: T) -> Foo<T, N> { loop {} }
+
+fn test() {
+    let a = make::<u8, 3>(1);
+    a<|>;
+}
+"#,
+    );
+    assert_eq!(t, "Foo<u8, 3>");
+}
+
+#[test]
+fn infer_const_generic_args_from_expected_type() {
+    let t = type_at(
+        r#"
+//- /main.rs
+const LEN: usize = 2 + 2;
+struct Foo<const N: usize>;
+fn make<const N: usize>() -> Foo<N> { loop {} }
+
+fn test() {
+    let a: Foo<LEN> = make();
+    let b: Foo<{ 4 }> = a;
+    b<|>;
+}
+"#,
+    );
+    assert_eq!(t, "Foo<4>");
+}
+
+#[test]
+fn infer_const_param_in_impl() {
+    let t = type_at(
+        r#"
+//- /main.rs
+struct Foo<const N: usize>;
+impl<const N: usize> Foo<N> {
+    fn get(&self) -> Foo<N> { loop {} }
+}
+
+fn test<const M: usize>(foo: Foo<M>) {
+    foo.get()<|>;
+}
+"#,
+    );
+    assert_eq!(t, "Foo<M>");
+}
+
 #[test]
 fn infer_struct_generics() {
     assert_snapshot!(
@@ -95,6 +95,30 @@ mod future {
     assert_eq!("impl Future<Output = u64>", type_at_pos(&db, pos));
 }
 
+#[test]
+fn infer_async_fn_body() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs crate:main deps:std
+
+async fn foo() -> u64 {
+    let x = 128;
+    x<|>
+}
+
+//- /std.rs crate:std
+#[prelude_import] use future::*;
+mod future {
+    trait Future {
+        type Output;
+    }
+}
+
+"#,
+    );
+    assert_eq!("u64", type_at_pos(&db, pos));
+}
+
 #[test]
 fn infer_try() {
     let (db, pos) = TestDB::with_position(
@@ -176,6 +200,38 @@ mod collections {
     assert_eq!("&str", type_at_pos(&db, pos));
 }
 
+#[test]
+fn infer_for_loop_over_const_generic_array_impl() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs crate:main deps:std
+
+fn test() {
+    let a = [1u8, 2, 3, 4];
+    for x in &a {
+        x<|>;
+    }
+}
+
+//- /std.rs crate:std
+
+#[prelude_import] use iter::*;
+mod iter {
+    trait IntoIterator {
+        type Item;
+    }
+}
+
+mod array {
+    impl<'a, T, const N: usize> crate::iter::IntoIterator for &'a [T; N] {
+        type Item = &'a T;
+    }
+}
+"#,
+    );
+    assert_eq!("&u8", type_at_pos(&db, pos));
+}
+
 #[test]
 fn infer_ops_neg() {
     let (db, pos) = TestDB::with_position(
@@ -526,7 +582,7 @@ fn indexing_arrays() {
         @r###"
     [10; 26) '{ &mut...[2]; }': ()
     [12; 23) '&mut [9][2]': &mut {unknown}
-    [17; 20) '[9]': [i32; _]
+    [17; 20) '[9]': [i32; 1]
     [17; 23) '[9][2]': {unknown}
     [18; 19) '9': i32
     [21; 22) '2': i32
@@ -534,6 +590,67 @@ fn indexing_arrays() {
     )
 }
 
+#[test]
+fn infer_ops_add() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs crate:main deps:std
+
+struct Bar;
+struct Foo;
+
+impl std::ops::Add<Foo> for Bar {
+    type Output = Foo;
+}
+
+fn test() {
+    let a = Bar;
+    let b = a + Foo;
+    b<|>;
+}
+
+//- /std.rs crate:std
+
+#[prelude_import] use ops::*;
+mod ops {
+    #[lang = "add"]
+    pub trait Add<Rhs = Self> {
+        type Output;
+    }
+}
+"#,
+    );
+    assert_eq!("Foo", type_at_pos(&db, pos));
+}
+
+#[test]
+fn infer_ops_add_assign() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs crate:main deps:std
+
+struct Bar;
+
+impl std::ops::AddAssign for Bar {}
+
+fn test() {
+    let mut a = Bar;
+    let b = (a += Bar);
+    b<|>;
+}
+
+//- /std.rs crate:std
+
+#[prelude_import] use ops::*;
+mod ops {
+    #[lang = "add_assign"]
+    pub trait AddAssign<Rhs = Self> {}
+}
+"#,
+    );
+    assert_eq!("()", type_at_pos(&db, pos));
+}
+
 #[test]
 fn infer_ops_index() {
     let (db, pos) = TestDB::with_position(
@@ -1758,6 +1875,46 @@ fn test() {
     );
 }
 
+#[test]
+fn closure_params_from_projection_in_fn_bound() {
+    let t = type_at(
+        r#"
+//- /main.rs
+#[lang = "fn_once"]
+trait FnOnce<Args> {
+    type Output;
+}
+#[lang = "fn_mut"]
+trait FnMut<Args>: FnOnce<Args> {}
+
+trait Iterator {
+    type Item;
+    fn map<B, F: FnMut(Self::Item) -> B>(self, f: F) -> Map<Self, F> {}
+}
+
+struct Map<I, F>;
+impl<B, I: Iterator, F: FnMut(I::Item) -> B> Iterator for Map<I, F> {
+    type Item = B;
+}
+
+struct S;
+impl S {
+    fn foo(&self) -> u32 {}
+}
+
+struct Iter;
+impl Iterator for Iter {
+    type Item = S;
+}
+
+fn test(it: Iter) {
+    it.map(|x| x.foo()).map(|y| y<|>);
+}
+"#,
+    );
+    assert_eq!(t, "u32");
+}
+
 #[test]
 fn unselected_projection_in_trait_env_1() {
     let t = type_at(
@@ -1956,7 +2113,7 @@ fn test() -> impl Trait<i32> {
     [172; 183) '{ loop {} }': T
     [174; 181) 'loop {}': !
     [179; 181) '{}': ()
-    [214; 310) '{     ...t()) }': S<{unknown}>
+    [214; 310) '{     ...t()) }': S<i32>
     [224; 226) 's1': S<u32>
     [229; 230) 'S': S<u32>(u32) -> S<u32>
     [229; 241) 'S(default())': S<u32>
@@ -1972,10 +2129,10 @@ fn test() -> impl Trait<i32> {
     [277; 289) 'S(default())': S<i32>
     [279; 286) 'default': fn default<i32>() -> i32
     [279; 288) 'default()': i32
-    [296; 297) 'S': S<{unknown}>({unknown}) -> S<{unknown}>
-    [296; 308) 'S(default())': S<{unknown}>
-    [298; 305) 'default': fn default<{unknown}>() -> {unknown}
-    [298; 307) 'default()': {unknown}
+    [296; 297) 'S': S<i32>(i32) -> S<i32>
+    [296; 308) 'S(default())': S<i32>
+    [298; 305) 'default': fn default<i32>() -> i32
+    [298; 307) 'default()': i32
     "###
     );
 }
@@ -2021,3 +2178,200 @@ fn main() {
     "###
     );
 }
+
+#[test]
+fn auto_trait_structural_impl() {
+    let t = type_at(
+        r#"
+//- /main.rs
+auto trait Send {}
+
+struct Rc;
+impl !Send for Rc {}
+
+struct Plain { x: u32 }
+struct Wrapper<T> { inner: T }
+enum Either<L, R> { Left(L), Right(R) }
+
+trait IsSend {
+    fn is_send(&self) -> bool;
+}
+impl<T: Send> IsSend for T {}
+
+fn test(a: Wrapper<Plain>, b: (u32, [Plain; 2]), c: Wrapper<Rc>, d: Either<Plain, (Rc,)>) {
+    (a.is_send(), b.is_send(), c.is_send(), d.is_send())<|>;
+}
+"#,
+    );
+    assert_eq!(t, "(bool, bool, {unknown}, {unknown})");
+}
+
+#[test]
+fn return_pos_impl_trait_method_call() {
+    let t = type_at(
+        r#"
+//- /main.rs
+mod m {
+    pub trait Trait<T> {
+        fn foo(&self) -> T;
+    }
+}
+fn bar() -> impl m::Trait<u64> { loop {} }
+
+fn test() {
+    let a = bar();
+    a.foo()<|>;
+}
+"#,
+    );
+    assert_eq!(t, "u64");
+}
+
+#[test]
+fn return_pos_impl_trait_assoc_type() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Iterator {
+    type Item;
+    fn next(&mut self) -> Self::Item;
+}
+fn bar() -> impl Iterator<Item = u32> { loop {} }
+
+fn test() {
+    let mut it = bar();
+    it.next()<|>;
+}
+"#,
+    );
+    assert_eq!(t, "u32");
+}
+
+#[test]
+fn return_pos_impl_trait_with_generics() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Iterator {
+    type Item;
+    fn next(&mut self) -> Self::Item;
+}
+fn iter<T>(t: T) -> impl Iterator<Item = T> { loop {} }
+
+fn test() {
+    let it = iter(1u8);
+    it<|>;
+}
+"#,
+    );
+    assert_eq!(t, "impl Iterator<Item = u8>");
+}
+
+#[test]
+fn return_pos_impl_trait_hidden_type_inferred_from_bounds() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Trait<T> {}
+struct S<T>(T);
+impl<T> Trait<T> for S<T> {}
+fn default<T>() -> T { loop {} }
+
+fn test() -> impl Trait<i32> {
+    S(default()<|>)
+}
+"#,
+    );
+    assert_eq!(t, "i32");
+}
+
+#[test]
+fn auto_trait_shared_ref_requires_sync() {
+    let t = type_at(
+        r#"
+//- /main.rs
+auto trait Send {}
+#[lang = "sync"]
+auto trait Sync {}
+
+struct Cell;
+impl !Sync for Cell {}
+
+struct Wrapper<T> { inner: T }
+
+trait IsSend {
+    fn is_send(&self) -> bool;
+}
+impl<T: Send> IsSend for T {}
+
+fn test(a: Wrapper<&u32>, b: Wrapper<&Cell>, c: Wrapper<&mut Cell>) {
+    (a.is_send(), b.is_send(), c.is_send())<|>;
+}
+"#,
+    );
+    assert_eq!(t, "(bool, {unknown}, bool)");
+}
+
+#[test]
+fn auto_trait_shared_ref_with_explicit_impls() {
+    let t = type_at(
+        r#"
+//- /main.rs crate:main deps:core
+use core::marker::{Send, Sync};
+
+struct Cell;
+impl !Sync for Cell {}
+
+struct Wrapper<T> { inner: T }
+
+trait IsSend {
+    fn is_send(&self) -> bool;
+}
+impl<T: Send> IsSend for T {}
+
+fn test(a: Wrapper<&u32>, b: Wrapper<&Cell>, c: Wrapper<&mut Cell>) {
+    (a.is_send(), b.is_send(), c.is_send())<|>;
+}
+
+//- /core/lib.rs crate:core
+pub mod marker {
+    pub unsafe auto trait Send {}
+    #[lang = "sync"]
+    pub unsafe auto trait Sync {}
+
+    unsafe impl<T: Sync + ?Sized> Send for &T {}
+    unsafe impl<T: Send + ?Sized> Send for &mut T {}
+}
+"#,
+    );
+    assert_eq!(t, "(bool, {unknown}, bool)");
+}
+
+#[test]
+fn auto_trait_closure_captures() {
+    let t = type_at(
+        r#"
+//- /main.rs
+auto trait Send {}
+#[lang = "sync"]
+auto trait Sync {}
+
+struct Rc;
+impl !Send for Rc {}
+impl !Sync for Rc {}
+
+trait IsSend {
+    fn is_send(&self) -> bool;
+}
+impl<T: Send> IsSend for T {}
+
+fn test(x: u32, rc: Rc) {
+    let a = || x;
+    let b = || { let y = x; rc };
+    let c = |rc: Rc| rc;
+    (a.is_send(), b.is_send(), c.is_send())<|>;
+}
+"#,
+    );
+    assert_eq!(t, "(bool, {unknown}, bool)");
+}
@@ -7,9 +7,11 @@ use ra_db::{impl_intern_key, salsa, CrateId};
 use ra_prof::profile;
 use rustc_hash::FxHashSet;
 
-use crate::db::HirDatabase;
+use crate::{db::HirDatabase, primitive::Uncertain};
 
-use super::{Canonical, GenericPredicate, HirDisplay, ProjectionTy, TraitRef, Ty, TypeWalk};
+use super::{
+    Canonical, GenericPredicate, HirDisplay, OpaqueTyId, ProjectionTy, TraitRef, Ty, TypeWalk,
+};
 
 use self::chalk::{from_chalk, Interner, ToChalk};
 
@@ -266,7 +268,7 @@ pub enum FnTrait {
 }
 
 impl FnTrait {
-    fn lang_item_name(self) -> &'static str {
+    pub(crate) fn lang_item_name(self) -> &'static str {
         match self {
             FnTrait::FnOnce => "fn_once",
             FnTrait::FnMut => "fn_mut",
@@ -282,6 +284,12 @@ pub struct ClosureFnTraitImplData {
     fn_trait: FnTrait,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OpaqueTyImplData {
+    opaque_ty_id: OpaqueTyId,
+    trait_: TraitId,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct UnsizeToSuperTraitObjectData {
     trait_: TraitId,
@@ -297,11 +305,15 @@ pub enum Impl {
     /// Closure types implement the Fn traits synthetically.
     ClosureFnTraitImpl(ClosureFnTraitImplData),
     /// [T; n]: Unsize<[T]>
-    UnsizeArray,
+    UnsizeArray(Uncertain<u64>),
     /// T: Unsize<dyn Trait> where T: Trait
     UnsizeToTraitObject(TraitId),
     /// dyn Trait: Unsize<dyn SuperTrait> if Trait: SuperTrait
     UnsizeToSuperTraitObject(UnsizeToSuperTraitObjectData),
+    /// An opaque `impl Trait` type implements the traits in its bounds.
+    OpaqueTyImpl(OpaqueTyImplData),
+    /// &T: AutoTrait where T: Sync
+    RefAutoTraitImpl(TraitId),
 }
 /// This exists just for Chalk, because our ImplIds are only unique per module.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -317,6 +329,9 @@ pub enum AssocTyValue {
     TypeAlias(TypeAliasId),
     /// The output type of the Fn trait implementation.
     ClosureFnTraitImplOutput(ClosureFnTraitImplData),
+    /// An associated type binding in the bounds of an opaque type, like the
+    /// `Item = u32` in `impl Iterator<Item = u32>`.
+    OpaqueTyImplAssocTy(OpaqueTyImplData, TypeAliasId),
 }
 /// This exists just for Chalk, because it needs a unique ID for each associated
 /// type value in an impl (even synthetic ones).
@@ -1,14 +1,15 @@
 //! This module provides the built-in trait implementations, e.g. to make
 //! closures implement `Fn`.
-use hir_def::{expr::Expr, lang_item::LangItemTarget, TraitId, TypeAliasId};
+use hir_def::{expr::Expr, lang_item::LangItemTarget, type_ref::Mutability, TraitId, TypeAliasId};
 use hir_expand::name::name;
 use ra_db::CrateId;
 
-use super::{AssocTyValue, Impl, UnsizeToSuperTraitObjectData};
+use super::{AssocTyValue, Impl, OpaqueTyImplData, UnsizeToSuperTraitObjectData};
 use crate::{
     db::HirDatabase,
+    primitive::Uncertain,
     utils::{all_super_traits, generics},
-    ApplicationTy, Binders, GenericPredicate, Substs, TraitRef, Ty, TypeCtor,
+    ApplicationTy, Binders, GenericPredicate, OpaqueTyId, Substs, TraitRef, Ty, TypeCtor,
 };
 
 pub(super) struct BuiltinImplData {
@@ -50,6 +51,23 @@ pub(super) fn get_builtin_impls(
         }
     }
 
+    if let Ty::Apply(ApplicationTy { ctor: TypeCtor::OpaqueType(opaque_ty_id), .. }) = ty {
+        let bounds = ty.impl_trait_bounds(db).unwrap_or_default();
+        let is_bound = bounds.iter().any(|bound| match bound {
+            GenericPredicate::Implemented(trait_ref) => trait_ref.trait_ == trait_,
+            _ => false,
+        });
+        if is_bound {
+            callback(Impl::OpaqueTyImpl(OpaqueTyImplData { opaque_ty_id: *opaque_ty_id, trait_ }));
+        }
+    }
+
+    if let Ty::Apply(ApplicationTy { ctor: TypeCtor::Ref(Mutability::Shared), .. }) = ty {
+        if has_ref_auto_trait_impl(db, krate, trait_) {
+            callback(Impl::RefAutoTraitImpl(trait_));
+        }
+    }
+
     let unsize_trait = get_unsize_trait(db, krate);
     if let Some(actual_trait) = unsize_trait {
         if trait_ == actual_trait {
@@ -70,8 +88,8 @@ fn get_builtin_unsize_impls(
         return;
     }
 
-    if let Ty::Apply(ApplicationTy { ctor: TypeCtor::Array, .. }) = ty {
-        callback(Impl::UnsizeArray);
+    if let Ty::Apply(ApplicationTy { ctor: TypeCtor::Array(len), .. }) = ty {
+        callback(Impl::UnsizeArray(*len));
         return; // array is unsized, the rest of the impls shouldn't apply
     }
 
@@ -98,11 +116,13 @@ pub(super) fn impl_datum(db: &dyn HirDatabase, krate: CrateId, impl_: Impl) -> B
     match impl_ {
         Impl::ImplDef(_) => unreachable!(),
         Impl::ClosureFnTraitImpl(data) => closure_fn_trait_impl_datum(db, krate, data),
-        Impl::UnsizeArray => array_unsize_impl_datum(db, krate),
+        Impl::UnsizeArray(len) => array_unsize_impl_datum(db, krate, len),
         Impl::UnsizeToTraitObject(trait_) => trait_object_unsize_impl_datum(db, krate, trait_),
         Impl::UnsizeToSuperTraitObject(data) => {
             super_trait_object_unsize_impl_datum(db, krate, data)
         }
+        Impl::OpaqueTyImpl(data) => opaque_ty_impl_datum(db, data),
+        Impl::RefAutoTraitImpl(trait_) => ref_auto_trait_impl_datum(db, krate, trait_),
     }
 }
 
@@ -116,6 +136,9 @@ pub(super) fn associated_ty_value(
         AssocTyValue::ClosureFnTraitImplOutput(data) => {
             closure_fn_trait_output_assoc_ty_value(db, krate, data)
         }
+        AssocTyValue::OpaqueTyImplAssocTy(data, assoc_ty_id) => {
+            opaque_ty_impl_assoc_ty_value(db, data, assoc_ty_id)
+        }
     }
 }
 
@@ -171,7 +194,11 @@ fn closure_fn_trait_impl_datum(
         Substs::builder(num_args as usize + 1).fill_with_bound_vars(0).build(),
     );
 
-    let self_ty = Ty::apply_one(TypeCtor::Closure { def: data.def, expr: data.expr }, sig_ty);
+    let captures_ty = Ty::Bound(num_args as u32 + 1);
+    let self_ty = Ty::apply(
+        TypeCtor::Closure { def: data.def, expr: data.expr },
+        Substs(vec![sig_ty, captures_ty].into()),
+    );
 
     let trait_ref = TraitRef {
         trait_,
@@ -181,7 +208,7 @@ fn closure_fn_trait_impl_datum(
     let output_ty_id = AssocTyValue::ClosureFnTraitImplOutput(data);
 
     BuiltinImplData {
-        num_vars: num_args as usize + 1,
+        num_vars: num_args as usize + 2,
         trait_ref,
         where_clauses: Vec::new(),
         assoc_ty_values: vec![output_ty_id],
@@ -216,11 +243,138 @@ fn closure_fn_trait_output_assoc_ty_value(
     BuiltinImplAssocTyValueData {
         impl_,
         assoc_ty_id: output_ty_id,
-        num_vars: num_args as usize + 1,
+        num_vars: num_args as usize + 2,
         value: output_ty,
     }
 }
 
+// Opaque type impls
+
+fn opaque_ty_bounds(
+    db: &dyn HirDatabase,
+    opaque_ty_id: OpaqueTyId,
+) -> Binders<Vec<GenericPredicate>> {
+    let OpaqueTyId::ReturnTypeImplTrait(func, idx) = opaque_ty_id;
+    let num_vars = generics(db.upcast(), func.into()).len();
+    let bounds = db
+        .return_type_impl_traits(func)
+        .and_then(|it| it.value.impl_traits.get(idx as usize).map(|it| it.bounds.clone()))
+        .unwrap_or_default();
+    Binders::new(num_vars, bounds)
+}
+
+fn opaque_ty_impl_datum(db: &dyn HirDatabase, data: OpaqueTyImplData) -> BuiltinImplData {
+    // for some `fn foo<T>() -> impl Trait<Assoc = X>`:
+    // impl<T> Trait for Opaque<T> { type Assoc = X; }
+
+    let bounds = opaque_ty_bounds(db, data.opaque_ty_id);
+    let trait_ref = bounds
+        .value
+        .iter()
+        .find_map(|bound| match bound {
+            GenericPredicate::Implemented(trait_ref) if trait_ref.trait_ == data.trait_ => {
+                Some(trait_ref.clone())
+            }
+            _ => None,
+        })
+        // the impl only exists if the bound does, which has been checked before
+        .expect("bound for opaque type impl missing");
+
+    let assoc_ty_values = bounds
+        .value
+        .iter()
+        .filter_map(|bound| match bound {
+            GenericPredicate::Projection(proj) if proj.projection_ty.trait_(db) == data.trait_ => {
+                Some(AssocTyValue::OpaqueTyImplAssocTy(data, proj.projection_ty.associated_ty))
+            }
+            _ => None,
+        })
+        .collect();
+
+    BuiltinImplData {
+        num_vars: bounds.num_binders,
+        trait_ref,
+        where_clauses: Vec::new(),
+        assoc_ty_values,
+    }
+}
+
+fn opaque_ty_impl_assoc_ty_value(
+    db: &dyn HirDatabase,
+    data: OpaqueTyImplData,
+    assoc_ty_id: TypeAliasId,
+) -> BuiltinImplAssocTyValueData {
+    let bounds = opaque_ty_bounds(db, data.opaque_ty_id);
+    let value = bounds
+        .value
+        .iter()
+        .find_map(|bound| match bound {
+            GenericPredicate::Projection(proj)
+                if proj.projection_ty.associated_ty == assoc_ty_id =>
+            {
+                Some(proj.ty.clone())
+            }
+            _ => None,
+        })
+        .expect("assoc ty value should not exist");
+
+    BuiltinImplAssocTyValueData {
+        impl_: Impl::OpaqueTyImpl(data),
+        assoc_ty_id,
+        num_vars: bounds.num_binders,
+        value,
+    }
+}
+
+// Auto trait impls for references
+
+/// Whether shared references implement the auto trait `trait_` via a builtin
+/// impl, instead of structurally. `&T` is `Send` only if `T` is `Sync`, which
+/// can't be expressed by a field of type `T`.
+pub(super) fn has_ref_auto_trait_impl(
+    db: &dyn HirDatabase,
+    krate: CrateId,
+    trait_: TraitId,
+) -> bool {
+    if !db.trait_data(trait_).auto || get_sync_trait(db, krate).is_none() {
+        return false;
+    }
+    // libcore spells these out for the standard auto traits.
+    let has_explicit_impl = db.impls_for_trait(krate, trait_).iter().any(|&impl_id| {
+        match db.impl_self_ty(impl_id).value {
+            Ty::Apply(ApplicationTy { ctor, .. }) => ctor == TypeCtor::Ref(Mutability::Shared),
+            _ => false,
+        }
+    });
+    !has_explicit_impl
+}
+
+fn ref_auto_trait_impl_datum(
+    db: &dyn HirDatabase,
+    krate: CrateId,
+    trait_: TraitId,
+) -> BuiltinImplData {
+    // impl<T> AutoTrait for &T where T: Sync
+
+    let sync_trait = get_sync_trait(db, krate)
+        // the existence of the Sync trait has been checked before
+        .expect("Sync trait missing");
+
+    let var = Ty::Bound(0);
+    let trait_ref = TraitRef {
+        trait_,
+        substs: Substs::single(Ty::apply_one(TypeCtor::Ref(Mutability::Shared), var.clone())),
+    };
+    let sync_trait_ref = TraitRef { trait_: sync_trait, substs: Substs::single(var) };
+
+    BuiltinImplData {
+        num_vars: 1,
+        trait_ref,
+        where_clauses: vec![GenericPredicate::Implemented(sync_trait_ref)],
+        assoc_ty_values: Vec::new(),
+    }
+}
+
 // Array unsizing
 
 fn check_unsize_impl_prerequisites(db: &dyn HirDatabase, krate: CrateId) -> bool {
@@ -233,9 +387,13 @@ fn check_unsize_impl_prerequisites(db: &dyn HirDatabase, krate: CrateId) -> bool
     generic_params.len() == 2
 }
 
-fn array_unsize_impl_datum(db: &dyn HirDatabase, krate: CrateId) -> BuiltinImplData {
-    // impl<T> Unsize<[T]> for [T; _]
-    // (this can be a single impl because we don't distinguish array sizes currently)
+fn array_unsize_impl_datum(
+    db: &dyn HirDatabase,
+    krate: CrateId,
+    len: Uncertain<u64>,
+) -> BuiltinImplData {
+    // impl<T> Unsize<[T]> for [T; len]
+    // (there's one impl per length, since we can't quantify over it)
 
     let trait_ = get_unsize_trait(db, krate) // get unsize trait
         // the existence of the Unsize trait has been checked before
@@ -243,7 +401,7 @@ fn array_unsize_impl_datum(db: &dyn HirDatabase, krate: CrateId) -> BuiltinImplD
 
     let var = Ty::Bound(0);
     let substs = Substs::builder(2)
-        .push(Ty::apply_one(TypeCtor::Array, var.clone()))
+        .push(Ty::apply_one(TypeCtor::Array(len), var.clone()))
         .push(Ty::apply_one(TypeCtor::Slice, var))
         .build();
 
@@ -359,3 +517,11 @@ fn get_unsize_trait(db: &dyn HirDatabase, krate: CrateId) -> Option<TraitId> {
         _ => None,
     }
 }
+
+fn get_sync_trait(db: &dyn HirDatabase, krate: CrateId) -> Option<TraitId> {
+    let target = db.lang_item(krate, "sync".into())?;
+    match target {
+        LangItemTarget::TraitId(t) => Some(t),
+        _ => None,
+    }
+}
@@ -5,7 +5,10 @@ use log::debug;
 
 use chalk_ir::{cast::Cast, Goal, GoalData, Parameter, PlaceholderIndex, TypeName, UniverseIndex};
 
-use hir_def::{AssocContainerId, AssocItemId, GenericDefId, HasModule, Lookup, TypeAliasId};
+use hir_def::{
+    type_ref::Mutability, AdtId, AssocContainerId, AssocItemId, EnumVariantId, GenericDefId,
+    HasModule, Lookup, TypeAliasId, VariantId,
+};
 use ra_db::{
     salsa::{InternId, InternKey},
     CrateId,
@@ -172,7 +175,7 @@ impl ToChalk for Ty {
                 let bounded_ty = chalk_ir::DynTy { bounds: make_binders(where_clauses, 1) };
                 chalk_ir::TyData::Dyn(bounded_ty).intern(&Interner)
             }
-            Ty::Opaque(_) | Ty::Unknown => {
+            Ty::Unknown => {
                 let substitution = chalk_ir::Substitution::empty();
                 let name = TypeName::Error;
                 chalk_ir::ApplicationTy { name, substitution }.cast().intern(&Interner)
@@ -272,7 +275,7 @@ impl ToChalk for TypeCtor {
             }
             _ => {
                 // other TypeCtors get interned and turned into a chalk StructId
-                let struct_id = db.intern_type_ctor(self).into();
+                let struct_id = db.intern_type_ctor(self.without_array_len()).into();
                 TypeName::Struct(struct_id)
             }
         }
@@ -591,7 +594,21 @@ impl<'a> chalk_solve::RustIrDatabase<Interner> for ChalkContext<'a> {
     }
     fn impl_provided_for(&self, auto_trait_id: TraitId, struct_id: StructId) -> bool {
         debug!("impl_provided_for {:?}, {:?}", auto_trait_id, struct_id);
-        false // FIXME
+        let trait_: hir_def::TraitId = from_chalk(self.db, auto_trait_id);
+        let type_ctor: TypeCtor = from_chalk(self.db, TypeName::Struct(struct_id));
+        if type_ctor == TypeCtor::Ref(Mutability::Shared)
+            && builtin::has_ref_auto_trait_impl(self.db, self.krate, trait_)
+        {
+            return true;
+        }
+        // An explicit impl, positive or negative, replaces the structural
+        // auto trait impl that Chalk would otherwise derive from the fields.
+        self.db.impls_for_trait(self.krate, trait_).iter().any(|&impl_id| {
+            match self.db.impl_self_ty(impl_id).value {
+                Ty::Apply(ApplicationTy { ctor, .. }) => ctor == type_ctor,
+                _ => false,
+            }
+        })
     }
     fn associated_ty_value(&self, id: AssociatedTyValueId) -> Arc<AssociatedTyValue> {
         self.db.associated_ty_value(self.krate, id)
@@ -696,7 +713,7 @@ pub(crate) fn struct_datum_query(
         fundamental: false,
     };
     let struct_datum_bound = chalk_rust_ir::StructDatumBound {
-        fields: Vec::new(), // FIXME add fields (only relevant for auto traits)
+        fields: struct_fields(db, type_ctor).into_iter().map(|ty| ty.to_chalk(db)).collect(),
         where_clauses,
     };
     let struct_datum =
@@ -704,6 +721,37 @@ pub(crate) fn struct_datum_query(
     Arc::new(struct_datum)
 }
 
+/// The types of the fields of `type_ctor`, in terms of its bound type
+/// parameters. These are only used by Chalk to decide whether an auto trait is
+/// implemented structurally.
+fn struct_fields(db: &dyn HirDatabase, type_ctor: TypeCtor) -> Vec<Ty> {
+    let variant_fields = |variant: VariantId| -> Vec<Ty> {
+        db.field_types(variant).values().map(|ty| ty.value.clone()).collect()
+    };
+    match type_ctor {
+        TypeCtor::Adt(AdtId::StructId(it)) => variant_fields(it.into()),
+        TypeCtor::Adt(AdtId::UnionId(it)) => variant_fields(it.into()),
+        TypeCtor::Adt(AdtId::EnumId(it)) => db
+            .enum_data(it)
+            .variants
+            .iter()
+            .flat_map(|(local_id, _)| variant_fields(EnumVariantId { parent: it, local_id }.into()))
+            .collect(),
+        TypeCtor::Slice
+        | TypeCtor::Array(_)
+        | TypeCtor::Ref(Mutability::Mut)
+        | TypeCtor::Tuple { .. } => {
+            (0..type_ctor.num_ty_params(db)).map(|idx| Ty::Bound(idx as u32)).collect()
+        }
+        // Shared references get their auto trait impls from
+        // `builtin::has_ref_auto_trait_impl`.
+        TypeCtor::Ref(Mutability::Shared) => Vec::new(),
+        // The second parameter of a closure is the tuple of its captured types.
+        TypeCtor::Closure { .. } => vec![Ty::Bound(1)],
+        _ => Vec::new(),
+    }
+}
+
 pub(crate) fn impl_datum_query(
     db: &dyn HirDatabase,
     krate: CrateId,
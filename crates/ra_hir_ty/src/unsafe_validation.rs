@@ -0,0 +1,118 @@
+//! Checks that unsafe operations, i.e. calls to unsafe functions, dereferences
+//! of raw pointers and accesses to mutable statics, only happen in an unsafe
+//! context.
+
+use std::sync::Arc;
+
+use hir_def::{
+    body::Body,
+    expr::{Expr, ExprId, UnaryOp},
+    resolver::{resolver_for_expr, ValueNs},
+    DefWithBodyId, FunctionId,
+};
+use hir_expand::diagnostics::DiagnosticSink;
+
+use crate::{
+    db::HirDatabase, diagnostics::MissingUnsafe, ApplicationTy, CallableDef, InferenceResult, Ty,
+    TypeCtor,
+};
+
+pub struct UnsafeValidator<'a, 'b: 'a> {
+    func: FunctionId,
+    infer: Arc<InferenceResult>,
+    sink: &'a mut DiagnosticSink<'b>,
+}
+
+impl<'a, 'b> UnsafeValidator<'a, 'b> {
+    pub fn new(
+        func: FunctionId,
+        infer: Arc<InferenceResult>,
+        sink: &'a mut DiagnosticSink<'b>,
+    ) -> UnsafeValidator<'a, 'b> {
+        UnsafeValidator { func, infer, sink }
+    }
+
+    pub fn validate_body(&mut self, db: &dyn HirDatabase) {
+        // The body of an unsafe function is an unsafe context as a whole.
+        if db.function_data(self.func).is_unsafe {
+            return;
+        }
+
+        let def = self.func.into();
+        let (_, source_map) = db.body_with_source_map(def);
+        for unsafe_expr in unsafe_expressions(db, &self.infer, def) {
+            if unsafe_expr.inside_unsafe_block {
+                continue;
+            }
+            if let Ok(source_ptr) = source_map.expr_syntax(unsafe_expr.expr) {
+                if let Some(expr) = source_ptr.value.left() {
+                    self.sink.push(MissingUnsafe { file: source_ptr.file_id, expr });
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsafeExpr {
+    pub expr: ExprId,
+    pub inside_unsafe_block: bool,
+}
+
+/// Collects all unsafe operations in the body of `def`, noting for each of
+/// them whether it is inside of an `unsafe` block.
+pub fn unsafe_expressions(
+    db: &dyn HirDatabase,
+    infer: &InferenceResult,
+    def: DefWithBodyId,
+) -> Vec<UnsafeExpr> {
+    let body = db.body(def);
+    let mut acc = Vec::new();
+    walk_unsafe(&mut acc, db, infer, def, &body, body.body_expr, false);
+    acc
+}
+
+fn walk_unsafe(
+    acc: &mut Vec<UnsafeExpr>,
+    db: &dyn HirDatabase,
+    infer: &InferenceResult,
+    def: DefWithBodyId,
+    body: &Body,
+    current: ExprId,
+    inside_unsafe_block: bool,
+) {
+    let is_unsafe = match &body[current] {
+        Expr::Call { callee, .. } => match &infer[*callee] {
+            Ty::Apply(ApplicationTy {
+                ctor: TypeCtor::FnDef(CallableDef::FunctionId(func)),
+                ..
+            }) => db.function_data(*func).is_unsafe,
+            _ => false,
+        },
+        Expr::MethodCall { .. } => {
+            infer.method_resolution(current).map_or(false, |func| db.function_data(func).is_unsafe)
+        }
+        Expr::UnaryOp { expr, op: UnaryOp::Deref } => {
+            matches!(&infer[*expr], Ty::Apply(ApplicationTy { ctor: TypeCtor::RawPtr(..), .. }))
+        }
+        Expr::Path(path) => {
+            let resolver = resolver_for_expr(db.upcast(), def, current);
+            match resolver.resolve_path_in_value_ns_hygienic_fully(db.upcast(), path) {
+                Some(ValueNs::StaticId(id)) => db.static_data(id).mutable,
+                _ => false,
+            }
+        }
+        Expr::Unsafe { body: child } => {
+            walk_unsafe(acc, db, infer, def, body, *child, true);
+            return;
+        }
+        _ => false,
+    };
+    if is_unsafe {
+        acc.push(UnsafeExpr { expr: current, inside_unsafe_block });
+    }
+
+    body[current].walk_child_exprs(|child| {
+        walk_unsafe(acc, db, infer, def, body, child, inside_unsafe_block)
+    });
+}
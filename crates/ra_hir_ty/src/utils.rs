@@ -10,7 +10,8 @@ use hir_def::{
     path::Path,
     resolver::{HasResolver, TypeNs},
     type_ref::TypeRef,
-    AssocContainerId, GenericDefId, Lookup, TraitId, TypeAliasId, TypeParamId, VariantId,
+    AssocContainerId, ConstParamId, GenericDefId, Lookup, TraitId, TypeAliasId, TypeParamId,
+    VariantId,
 };
 use hir_expand::name::{name, Name};
 
@@ -128,6 +129,17 @@ pub(crate) fn generics(db: &dyn DefDatabase, def: GenericDefId) -> Generics {
     Generics { def, params: db.generic_params(def), parent_generics }
 }
 
+/// A generic parameter of some item, as it corresponds to an entry in the
+/// `Substs` of that item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GenericParamId {
+    Type(TypeParamId),
+    Const(ConstParamId),
+}
+
+/// The generic parameters of an item, including the ones of its parent (e.g.
+/// the impl of a method). In `Substs`, the parent's parameters come first;
+/// for each item, the type parameters come before the const parameters.
 pub(crate) struct Generics {
     def: GenericDefId,
     pub(crate) params: Arc<GenericParams>,
@@ -135,6 +147,24 @@ pub(crate) struct Generics {
 }
 
 impl Generics {
+    /// Iterates over all parameters, in the order of their `Substs` entries.
+    pub(crate) fn iter_all<'a>(&'a self) -> impl Iterator<Item = GenericParamId> + 'a {
+        self.parent_generics.iter().flat_map(|it| it.iter_self()).chain(self.iter_self())
+    }
+
+    fn iter_self<'a>(&'a self) -> impl Iterator<Item = GenericParamId> + 'a {
+        let def = self.def;
+        let types =
+            self.params.types.iter().map(move |(local_id, _)| {
+                GenericParamId::Type(TypeParamId { parent: def, local_id })
+            });
+        let consts = self.params.consts.iter().map(move |(local_id, _)| {
+            GenericParamId::Const(ConstParamId { parent: def, local_id })
+        });
+        types.chain(consts)
+    }
+
+    /// Iterates over the type parameters only.
     pub(crate) fn iter<'a>(
         &'a self,
     ) -> impl Iterator<Item = (TypeParamId, &'a TypeParamData)> + 'a {
@@ -173,12 +203,12 @@ impl Generics {
     /// (total, parents, child)
     pub(crate) fn len_split(&self) -> (usize, usize, usize) {
         let parent = self.parent_generics.as_ref().map_or(0, |p| p.len());
-        let child = self.params.types.len();
+        let child = self.params.types.len() + self.params.consts.len();
         (parent + child, parent, child)
     }
 
-    /// (parent total, self param, type param list, impl trait)
-    pub(crate) fn provenance_split(&self) -> (usize, usize, usize, usize) {
+    /// (parent total, self param, type param list, impl trait, const params)
+    pub(crate) fn provenance_split(&self) -> (usize, usize, usize, usize, usize) {
         let parent = self.parent_generics.as_ref().map_or(0, |p| p.len());
         let self_params = self
             .params
@@ -198,7 +228,8 @@ impl Generics {
             .iter()
             .filter(|(_, p)| p.provenance == TypeParamProvenance::ArgumentImplTrait)
             .count();
-        (parent, self_params, list_params, impl_trait_params)
+        let const_params = self.params.consts.len();
+        (parent, self_params, list_params, impl_trait_params, const_params)
     }
 
     pub(crate) fn param_idx(&self, param: TypeParamId) -> Option<u32> {
@@ -220,6 +251,16 @@ impl Generics {
             self.parent_generics.as_ref().and_then(|g| g.find_param(param))
         }
     }
+
+    pub(crate) fn const_param_idx(&self, param: ConstParamId) -> Option<u32> {
+        if param.parent == self.def {
+            let idx = self.params.consts.iter().position(|(id, _)| id == param.local_id)?;
+            let (_total, parent_len, _child) = self.len_split();
+            Some((parent_len + self.params.types.len() + idx) as u32)
+        } else {
+            self.parent_generics.as_ref().and_then(|g| g.const_param_idx(param))
+        }
+    }
 }
 
 fn parent_generic_def(db: &dyn DefDatabase, def: GenericDefId) -> Option<GenericDefId> {
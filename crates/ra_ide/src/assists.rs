@@ -3,6 +3,7 @@
 use ra_assists::{resolved_assists, AssistAction, AssistLabel};
 use ra_db::{FilePosition, FileRange};
 use ra_ide_db::RootDatabase;
+use ra_syntax::TextRange;
 
 use crate::{FileId, SourceChange, SourceFileEdit};
 
@@ -32,6 +33,18 @@ pub(crate) fn assists(db: &RootDatabase, frange: FileRange) -> Vec<Assist> {
         .collect()
 }
 
+/// Returns the first `auto_import` fix for the path at `position`.
+pub(crate) fn auto_import_fix(db: &RootDatabase, position: FilePosition) -> Option<SourceChange> {
+    let frange = FileRange {
+        file_id: position.file_id,
+        range: TextRange::offset_len(position.offset, 0.into()),
+    };
+    let assist = resolved_assists(db, frange)
+        .into_iter()
+        .find(|assist| assist.label.id == AssistId("auto_import"))?;
+    Some(action_to_edit(assist.action, position.file_id, &assist.label))
+}
+
 fn action_to_edit(
     action: AssistAction,
     file_id: FileId,
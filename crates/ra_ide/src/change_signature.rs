@@ -0,0 +1,320 @@
+//! Changes the parameters of a function, updating its declaration, the
+//! declarations of related trait methods and all of the call sites.
+
+use hir::{
+    AsAssocItem, AssocItem, AssocItemContainer, Crate, Function, HasSource, ImplDef, ModuleDef,
+    Semantics,
+};
+use ra_db::FileId;
+use ra_ide_db::{
+    defs::{classify_name, classify_name_ref, Definition},
+    RootDatabase,
+};
+use ra_syntax::{
+    algo::find_node_at_offset,
+    ast::{self, ArgListOwner},
+    AstNode, TextRange, TextUnit,
+};
+use ra_text_edit::TextEditBuilder;
+use rustc_hash::FxHashMap;
+
+use crate::{FilePosition, RangeInfo, SourceChange, SourceFileEdit};
+
+/// A parameter of the new signature, described in terms of the old one. The
+/// `self` parameter of a method always stays in place and is not listed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureParam {
+    /// The parameter with the given index (not counting `self`) in the old
+    /// signature.
+    Existing(usize),
+    /// A new parameter; existing call sites pass `default` for it.
+    New { pattern: String, ty: String, default: String },
+}
+
+pub(crate) fn change_signature(
+    db: &RootDatabase,
+    position: FilePosition,
+    new_params: &[SignatureParam],
+) -> Option<RangeInfo<SourceChange>> {
+    let sema = Semantics::new(db);
+    let source_file = sema.parse(position.file_id);
+    let syntax = source_file.syntax();
+    let (range, def) = match find_node_at_offset::<ast::Name>(syntax, position.offset) {
+        Some(name) => (name.syntax().text_range(), classify_name(&sema, &name)?.definition()),
+        None => {
+            let name_ref = find_node_at_offset::<ast::NameRef>(syntax, position.offset)?;
+            (name_ref.syntax().text_range(), classify_name_ref(&sema, &name_ref)?.definition())
+        }
+    };
+    let func = match def {
+        Definition::ModuleDef(ModuleDef::Function(it)) => it,
+        _ => return None,
+    };
+
+    let mut edits: FxHashMap<FileId, TextEditBuilder> = FxHashMap::default();
+    let mut call_sites: FxHashMap<FileId, Vec<CallSite>> = FxHashMap::default();
+    for func in related_functions(db, func) {
+        let src = func.source(db);
+        if src.file_id.call_node(db).is_some() {
+            // Declared by a macro, we can't rewrite it.
+            continue;
+        }
+        let param_list = src.value.param_list()?;
+        let params = render_params(&param_list, new_params)?;
+        edits
+            .entry(src.file_id.original_file(db))
+            .or_default()
+            .replace(param_list.syntax().text_range(), params);
+
+        let has_self_param = func.has_self_param(db);
+        for reference in Definition::ModuleDef(func.into()).find_usages(db, None) {
+            let file_id = reference.file_range.file_id;
+            let file = sema.parse(file_id);
+            let offset = reference.file_range.range.start();
+            let site = find_node_at_offset::<ast::NameRef>(file.syntax(), offset)
+                .and_then(|name_ref| CallSite::new(&name_ref, has_self_param));
+            if let Some(site) = site {
+                call_sites.entry(file_id).or_default().push(site);
+            }
+        }
+    }
+
+    for (file_id, mut sites) in call_sites {
+        sites.sort_by_key(|site| site.range().start());
+        sites.dedup_by_key(|site| site.range());
+        let text = db.file_text(file_id);
+        let renderer = Renderer { text: &text, sites: &sites, new_params };
+        let builder = edits.entry(file_id).or_default();
+        let file_range = TextRange::offset_len(0.into(), TextUnit::of_str(&text));
+        for site in renderer.outermost(file_range) {
+            builder.replace(site.range(), renderer.render_call_site(site));
+        }
+    }
+
+    let mut edits: Vec<SourceFileEdit> = edits
+        .into_iter()
+        .map(|(file_id, builder)| SourceFileEdit { file_id, edit: builder.finish() })
+        .collect();
+    edits.sort_by_key(|edit| edit.file_id);
+    Some(RangeInfo::new(range, SourceChange::source_file_edits("change signature", edits)))
+}
+
+/// Returns the functions whose signatures have to change together with
+/// `func`: for a trait method or an implementation of one, these are the
+/// method in the trait and all of its implementations.
+fn related_functions(db: &RootDatabase, func: Function) -> Vec<Function> {
+    let trait_ = match func.as_assoc_item(db).map(|it| it.container(db)) {
+        Some(AssocItemContainer::Trait(it)) => it,
+        Some(AssocItemContainer::ImplDef(it)) => match it.target_trait_def(db) {
+            Some(it) => it,
+            None => return vec![func],
+        },
+        None => return vec![func],
+    };
+    let name = func.name(db);
+    let impl_items = Crate::all(db)
+        .into_iter()
+        .flat_map(|krate| ImplDef::for_trait(db, krate, trait_))
+        .flat_map(|impl_def| impl_def.items(db));
+    let mut res: Vec<Function> = trait_
+        .items(db)
+        .into_iter()
+        .chain(impl_items)
+        .filter_map(|item| match item {
+            AssocItem::Function(it) if it.name(db) == name => Some(it),
+            _ => None,
+        })
+        .collect();
+    if !res.contains(&func) {
+        res.push(func);
+    }
+    res
+}
+
+fn render_params(param_list: &ast::ParamList, new_params: &[SignatureParam]) -> Option<String> {
+    let old_params: Vec<ast::Param> = param_list.params().collect();
+    let mut res: Vec<String> =
+        param_list.self_param().map(|it| it.syntax().to_string()).into_iter().collect();
+    for param in new_params {
+        let param = match param {
+            SignatureParam::Existing(idx) => old_params.get(*idx)?.syntax().to_string(),
+            SignatureParam::New { pattern, ty, .. } => format!("{}: {}", pattern, ty),
+        };
+        res.push(param);
+    }
+    Some(format!("({})", res.join(", ")))
+}
+
+/// The argument list of a call to a function whose signature is changing.
+struct CallSite {
+    arg_list: ast::ArgList,
+    /// Whether the first argument is the `self` argument, like in a
+    /// `Type::method(receiver, ..)` call.
+    has_receiver_arg: bool,
+}
+
+impl CallSite {
+    fn new(name_ref: &ast::NameRef, has_self_param: bool) -> Option<CallSite> {
+        let parent = name_ref.syntax().parent()?;
+        if let Some(method_call) = ast::MethodCallExpr::cast(parent.clone()) {
+            return Some(CallSite { arg_list: method_call.arg_list()?, has_receiver_arg: false });
+        }
+        // `foo(..)` or `Type::foo(..)`; a path used as a qualifier has a
+        // path as its parent, and other uses of the function are left alone.
+        let path = ast::PathSegment::cast(parent)?.parent_path();
+        let path_expr = ast::PathExpr::cast(path.syntax().parent()?)?;
+        let call = ast::CallExpr::cast(path_expr.syntax().parent()?)?;
+        Some(CallSite { arg_list: call.arg_list()?, has_receiver_arg: has_self_param })
+    }
+
+    fn range(&self) -> TextRange {
+        self.arg_list.syntax().text_range()
+    }
+}
+
+struct Renderer<'a> {
+    text: &'a str,
+    /// Sorted by start offset.
+    sites: &'a [CallSite],
+    new_params: &'a [SignatureParam],
+}
+
+impl<'a> Renderer<'a> {
+    /// Returns the call sites inside of `range` which are not nested in the
+    /// arguments of another such call site.
+    fn outermost(&self, range: TextRange) -> impl Iterator<Item = &'a CallSite> + 'a {
+        let mut last_end = range.start();
+        self.sites.iter().filter(move |site| {
+            let site_range = site.range();
+            if site_range.is_subrange(&range) && site_range.start() >= last_end {
+                last_end = site_range.end();
+                true
+            } else {
+                false
+            }
+        })
+    }
+
+    fn render_call_site(&self, site: &CallSite) -> String {
+        let args: Vec<ast::Expr> = site.arg_list.args().collect();
+        let (receiver, args) = match args.split_first() {
+            Some((receiver, rest)) if site.has_receiver_arg => (Some(receiver), rest),
+            _ => (None, &args[..]),
+        };
+        let mut res: Vec<String> =
+            receiver.map(|it| self.render(it.syntax().text_range())).into_iter().collect();
+        for param in self.new_params {
+            match param {
+                SignatureParam::Existing(idx) => {
+                    if let Some(arg) = args.get(*idx) {
+                        res.push(self.render(arg.syntax().text_range()));
+                    }
+                }
+                SignatureParam::New { default, .. } => res.push(default.clone()),
+            }
+        }
+        format!("({})", res.join(", "))
+    }
+
+    /// Returns the text of `range`, with the call sites inside of it rewritten.
+    fn render(&self, range: TextRange) -> String {
+        let mut buf = String::new();
+        let mut pos = range.start();
+        for site in self.outermost(range) {
+            buf.push_str(&self.text[TextRange::from_to(pos, site.range().start())]);
+            buf.push_str(&self.render_call_site(site));
+            pos = site.range().end();
+        }
+        buf.push_str(&self.text[TextRange::from_to(pos, range.end())]);
+        buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ra_text_edit::TextEditBuilder;
+    use test_utils::assert_eq_text;
+
+    use crate::mock_analysis::single_file_with_position;
+
+    use super::SignatureParam;
+
+    fn check(text: &str, new_params: &[SignatureParam], expected: &str) {
+        let (analysis, position) = single_file_with_position(text);
+        let change = analysis.change_signature(position, new_params).unwrap().unwrap();
+        let mut builder = TextEditBuilder::default();
+        for edit in change.info.source_file_edits {
+            assert_eq!(edit.file_id, position.file_id);
+            for atom in edit.edit.as_atoms() {
+                builder.replace(atom.delete, atom.insert.clone());
+            }
+        }
+        let actual = builder.finish().apply(&*analysis.file_text(position.file_id).unwrap());
+        assert_eq_text!(expected, &*actual);
+    }
+
+    #[test]
+    fn swaps_parameters_of_function() {
+        check(
+            r#"
+fn foo<|>(a: u32, b: bool) -> u32 { a }
+
+fn main() {
+    foo(foo(1, true), false);
+    let f = foo;
+}
+"#,
+            &[SignatureParam::Existing(1), SignatureParam::Existing(0)],
+            r#"
+fn foo(b: bool, a: u32) -> u32 { a }
+
+fn main() {
+    foo(false, foo(true, 1));
+    let f = foo;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn adds_and_removes_parameters_of_trait_method() {
+        check(
+            r#"
+trait Tr {
+    fn frob(&self, a: u32, b: u32);
+}
+struct S;
+impl Tr for S {
+    fn frob(&self, x: u32, y: u32) {}
+}
+fn main() {
+    S.frob<|>(1, 2);
+    S::frob(&S, 3, 4);
+    <S as Tr>::frob(&S, 5, 6);
+}
+"#,
+            &[
+                SignatureParam::Existing(1),
+                SignatureParam::New {
+                    pattern: "c".to_string(),
+                    ty: "bool".to_string(),
+                    default: "false".to_string(),
+                },
+            ],
+            r#"
+trait Tr {
+    fn frob(&self, b: u32, c: bool);
+}
+struct S;
+impl Tr for S {
+    fn frob(&self, y: u32, c: bool) {}
+}
+fn main() {
+    S.frob(2, false);
+    S::frob(&S, 4, false);
+    <S as Tr>::frob(&S, 6, false);
+}
+"#,
+        );
+    }
+}
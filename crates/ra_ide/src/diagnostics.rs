@@ -3,11 +3,12 @@
 use std::cell::RefCell;
 
 use hir::{
-    diagnostics::{AstDiagnostic, Diagnostic as _, DiagnosticSink},
-    Semantics,
+    db::AstDatabase,
+    diagnostics::{AstDiagnostic, Diagnostic as _, DiagnosticSink, TypeMismatchFix},
+    InFile, ModuleDef, Mutability, ScopeDef, Semantics,
 };
 use itertools::Itertools;
-use ra_db::{RelativePath, SourceDatabase, SourceDatabaseExt};
+use ra_db::{FilePosition, RelativePath, SourceDatabase, SourceDatabaseExt};
 use ra_ide_db::RootDatabase;
 use ra_prof::profile;
 use ra_syntax::{
@@ -16,8 +17,11 @@ use ra_syntax::{
     SyntaxNode, TextRange, T,
 };
 use ra_text_edit::{TextEdit, TextEditBuilder};
+use rustc_hash::FxHashMap;
 
-use crate::{Diagnostic, FileId, FileSystemEdit, SourceChange, SourceFileEdit};
+use crate::{
+    assists::auto_import_fix, Diagnostic, FileId, FileSystemEdit, SourceChange, SourceFileEdit,
+};
 
 #[derive(Debug, Copy, Clone)]
 pub enum Severity {
@@ -25,7 +29,25 @@ pub enum Severity {
     WeakWarning,
 }
 
-pub(crate) fn diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic> {
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiagnosticsOptions {
+    /// Type mismatches are off by default, as our type inference still
+    /// produces some false positives.
+    pub type_mismatch: bool,
+    pub unused_import: bool,
+}
+
+impl Default for DiagnosticsOptions {
+    fn default() -> Self {
+        Self { type_mismatch: false, unused_import: true }
+    }
+}
+
+pub(crate) fn diagnostics(
+    db: &RootDatabase,
+    file_id: FileId,
+    options: &DiagnosticsOptions,
+) -> Vec<Diagnostic> {
     let _p = profile("diagnostics");
     let sema = Semantics::new(db);
     let parse = db.parse(file_id);
@@ -68,32 +90,50 @@ pub(crate) fn diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic>
             fix: Some(fix),
         })
     })
-    .on::<hir::diagnostics::MissingFields, _>(|d| {
-        // Note that although we could add a diagnostics to
-        // fill the missing tuple field, e.g :
-        // `struct A(usize);`
-        // `let a = A { 0: () }`
-        // but it is uncommon usage and it should not be encouraged.
-        let fix = if d.missed_fields.iter().any(|it| it.as_tuple_index().is_some()) {
+    .on::<hir::diagnostics::UnresolvedImport, _>(|d| {
+        let fix = if d.file == file_id.into() {
+            unresolved_import_fix(&sema, file_id, &d.ast(db))
+        } else {
             None
+        };
+        res.borrow_mut().push(Diagnostic {
+            range: d.highlight_range(),
+            message: d.message(),
+            severity: Severity::Error,
+            fix,
+        })
+    })
+    .on::<hir::diagnostics::UnresolvedPath, _>(|d| {
+        let fix = if d.file == file_id.into() {
+            let offset = d.highlight_range().start();
+            auto_import_fix(db, FilePosition { file_id, offset })
         } else {
-            let mut field_list = d.ast(db);
-            for f in d.missed_fields.iter() {
-                let field =
-                    make::record_field(make::name_ref(&f.to_string()), Some(make::expr_unit()));
-                field_list = field_list.append_field(&field);
+            None
+        };
+        res.borrow_mut().push(Diagnostic {
+            range: d.highlight_range(),
+            message: d.message(),
+            severity: Severity::Error,
+            fix,
+        })
+    })
+    .on::<hir::diagnostics::MissingFields, _>(|d| {
+        let fix =
+            if d.file == file_id.into() { missing_fields_fix(&sema, file_id, d) } else { None };
+        res.borrow_mut().push(Diagnostic {
+            range: d.highlight_range(),
+            message: d.message(),
+            severity: Severity::Error,
+            fix,
+        })
+    })
+    .on::<hir::diagnostics::TypeMismatch, _>(|d| {
+        let fix = match d.fix {
+            Some(fix) if d.file == file_id.into() => {
+                Some(type_mismatch_fix(file_id, &d.ast(db), fix))
             }
-
-            let mut builder = TextEditBuilder::default();
-            algo::diff(&d.ast(db).syntax(), &field_list.syntax()).into_text_edit(&mut builder);
-
-            Some(SourceChange::source_file_edit_from(
-                "fill struct fields",
-                file_id,
-                builder.finish(),
-            ))
+            _ => None,
         };
-
         res.borrow_mut().push(Diagnostic {
             range: d.highlight_range(),
             message: d.message(),
@@ -101,6 +141,52 @@ pub(crate) fn diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic>
             fix,
         })
     })
+    .on::<hir::diagnostics::PatTypeMismatch, _>(|d| {
+        res.borrow_mut().push(Diagnostic {
+            range: d.highlight_range(),
+            message: d.message(),
+            severity: Severity::Error,
+            fix: None,
+        })
+    })
+    .on::<hir::diagnostics::MacroError, _>(|d| {
+        // The macro call might itself come from a macro expansion; report the
+        // error on the outermost call in this file.
+        let src = d.source();
+        let range = if src.file_id == file_id.into() {
+            d.highlight_range()
+        } else {
+            let root = match db.parse_or_expand(src.file_id) {
+                Some(it) => it,
+                None => return,
+            };
+            let node = src.value.to_node(&root);
+            let range = hir::original_range(db, InFile::new(src.file_id, &node));
+            if range.file_id != file_id {
+                return;
+            }
+            range.range
+        };
+        res.borrow_mut().push(Diagnostic {
+            range,
+            message: d.message(),
+            severity: Severity::Error,
+            fix: None,
+        })
+    })
+    .on::<hir::diagnostics::UnusedImport, _>(|d| {
+        let fix = if d.file == file_id.into() {
+            Some(unused_import_fix(file_id, &d.ast(db)))
+        } else {
+            None
+        };
+        res.borrow_mut().push(Diagnostic {
+            range: d.highlight_range(),
+            message: d.message(),
+            severity: Severity::WeakWarning,
+            fix,
+        })
+    })
     .on::<hir::diagnostics::MissingOkInTailExpr, _>(|d| {
         let node = d.ast(db);
         let replacement = format!("Ok({})", node.syntax());
@@ -113,6 +199,14 @@ pub(crate) fn diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic>
             fix: Some(fix),
         })
     });
+    if !options.type_mismatch {
+        sink = sink
+            .disable::<hir::diagnostics::TypeMismatch>()
+            .disable::<hir::diagnostics::PatTypeMismatch>();
+    }
+    if !options.unused_import {
+        sink = sink.disable::<hir::diagnostics::UnusedImport>();
+    }
     if let Some(m) = sema.to_module_def(file_id) {
         m.diagnostics(db, &mut sink);
     };
@@ -120,6 +214,178 @@ pub(crate) fn diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic>
     res.into_inner()
 }
 
+/// Replaces the path of an unresolved `use` with the path to an item of the
+/// same name, if we can find one.
+fn unresolved_import_fix(
+    sema: &Semantics<RootDatabase>,
+    file_id: FileId,
+    use_tree: &ast::UseTree,
+) -> Option<SourceChange> {
+    // Paths inside of `{}` are relative to their prefix, so we only handle the
+    // simple `use foo::Bar;` case.
+    ast::UseItem::cast(use_tree.syntax().parent()?)?;
+    if use_tree.use_tree_list().is_some() || use_tree.has_star() {
+        return None;
+    }
+    let path = use_tree.path()?;
+    let import = ra_assists::use_path_candidates(sema, &path).into_iter().next()?;
+    let edit = TextEdit::replace(path.syntax().text_range(), import.to_string());
+    Some(SourceChange::source_file_edit_from(format!("Import `{}`", import), file_id, edit))
+}
+
+/// Removes an unused `use` tree, together with the `{}` trees and the `use`
+/// item which would be left empty.
+fn unused_import_fix(file_id: FileId, use_tree: &ast::UseTree) -> SourceChange {
+    let mut use_tree = use_tree.clone();
+    while let Some(use_tree_list) = use_tree.syntax().parent().and_then(ast::UseTreeList::cast) {
+        if use_tree_list.use_trees().count() > 1 {
+            break;
+        }
+        use_tree = use_tree_list.parent_use_tree();
+    }
+    let rewriter = match use_tree.syntax().parent().and_then(ast::UseItem::cast) {
+        Some(use_item) => use_item.remove(),
+        None => use_tree.remove(),
+    };
+    let root = use_tree.syntax().ancestors().last().unwrap();
+    let mut builder = TextEditBuilder::default();
+    algo::diff(&root, &rewriter.rewrite(&root)).into_text_edit(&mut builder);
+    SourceChange::source_file_edit_from("remove unused import", file_id, builder.finish())
+}
+
+/// Adds the missing fields to a record literal. A field is initialized with a
+/// local of the same name and type if there's one in scope, with
+/// `Default::default()` if its type implements `core::default::Default`, and
+/// with `todo!()` otherwise.
+fn missing_fields_fix(
+    sema: &Semantics<RootDatabase>,
+    file_id: FileId,
+    d: &hir::diagnostics::MissingFields,
+) -> Option<SourceChange> {
+    // Note that although we could add a diagnostics to
+    // fill the missing tuple field, e.g :
+    // `struct A(usize);`
+    // `let a = A { 0: () }`
+    // but it is uncommon usage and it should not be encouraged.
+    if d.missed_fields.iter().any(|it| it.as_tuple_index().is_some()) {
+        return None;
+    }
+    let db = sema.db;
+    let source_file = sema.parse(file_id);
+    let old_field_list = d.field_list.to_node(source_file.syntax());
+    let record_lit = ast::RecordLit::cast(old_field_list.syntax().parent()?)?;
+    let variant = sema.resolve_record_literal(&record_lit)?;
+    let field_types = sema.type_of_expr(&record_lit.clone().into())?.variant_fields(db, variant);
+
+    // Inner scopes come first, so shadowed locals are skipped.
+    let scope = sema.scope(record_lit.syntax());
+    let mut locals = FxHashMap::default();
+    scope.process_all_names(&mut |name, def| {
+        if let ScopeDef::Local(local) = def {
+            locals.entry(name).or_insert(local);
+        }
+    });
+    let default_trait = resolve_default_trait(&scope);
+    let default_path = default_trait.and_then(|trait_| {
+        let path = scope.module()?.find_use_path(db, ModuleDef::Trait(trait_))?;
+        Some(ra_assists::ast_transform::path_to_ast(path))
+    });
+
+    let mut field_list = old_field_list.clone();
+    for f in d.missed_fields.iter() {
+        let ty = field_types.iter().find(|(field, _)| field.name(db) == *f).map(|(_, ty)| ty);
+        let expr = match (locals.get(f), ty) {
+            (Some(local), Some(ty)) if local.ty(db) == *ty => None,
+            _ => match (default_trait, &default_path, ty) {
+                (Some(trait_), Some(path), Some(ty)) if ty.impls_trait(db, trait_) => {
+                    let path = make::path_qualified(
+                        path.clone(),
+                        make::path_segment(make::name_ref("default")),
+                    );
+                    Some(make::expr_call(make::expr_path(path), make::arg_list(None)))
+                }
+                _ => Some(make::expr_todo()),
+            },
+        };
+        let field = make::record_field(make::name_ref(&f.to_string()), expr);
+        field_list = field_list.append_field(&field);
+    }
+
+    let mut builder = TextEditBuilder::default();
+    algo::diff(&old_field_list.syntax(), &field_list.syntax()).into_text_edit(&mut builder);
+    Some(SourceChange::source_file_edit_from("fill struct fields", file_id, builder.finish()))
+}
+
+/// Resolves `core::default::Default` from `scope`, whether or not the trait
+/// itself is imported there.
+fn resolve_default_trait(scope: &hir::SemanticsScope<RootDatabase>) -> Option<hir::Trait> {
+    let segment = |name| make::path_segment(make::name_ref(name));
+    let path = make::path_qualified(
+        make::path_qualified(make::path_unqualified(segment("core")), segment("default")),
+        segment("Default"),
+    );
+    match scope.resolve_hir_path(&hir::Path::from_ast(path)?)? {
+        hir::PathResolution::Def(ModuleDef::Trait(trait_)) => Some(trait_),
+        _ => None,
+    }
+}
+
+fn type_mismatch_fix(file_id: FileId, expr: &ast::Expr, fix: TypeMismatchFix) -> SourceChange {
+    let (label, replacement) = match fix {
+        TypeMismatchFix::Borrow(mutability) => {
+            let prefix = match mutability {
+                Mutability::Shared => "&",
+                Mutability::Mut => "&mut ",
+            };
+            let operand = if is_prefix_operand(expr) {
+                expr.syntax().to_string()
+            } else {
+                format!("({})", expr.syntax())
+            };
+            (format!("Add `{}`", prefix.trim_end()), format!("{}{}", prefix, operand))
+        }
+        TypeMismatchFix::Into | TypeMismatchFix::ToString => {
+            let method = if fix == TypeMismatchFix::Into { "into" } else { "to_string" };
+            let receiver = if is_postfix_operand(expr) {
+                expr.syntax().to_string()
+            } else {
+                format!("({})", expr.syntax())
+            };
+            (format!("Add `.{}()`", method), format!("{}.{}()", receiver, method))
+        }
+    };
+    let edit = TextEdit::replace(expr.syntax().text_range(), replacement);
+    SourceChange::source_file_edit_from(label, file_id, edit)
+}
+
+/// Checks whether `expr` can be the receiver of a method call without parens.
+fn is_postfix_operand(expr: &ast::Expr) -> bool {
+    match expr {
+        ast::Expr::TupleExpr(_)
+        | ast::Expr::ArrayExpr(_)
+        | ast::Expr::ParenExpr(_)
+        | ast::Expr::PathExpr(_)
+        | ast::Expr::RecordLit(_)
+        | ast::Expr::CallExpr(_)
+        | ast::Expr::IndexExpr(_)
+        | ast::Expr::MethodCallExpr(_)
+        | ast::Expr::FieldExpr(_)
+        | ast::Expr::AwaitExpr(_)
+        | ast::Expr::TryExpr(_)
+        | ast::Expr::Literal(_)
+        | ast::Expr::MacroCall(_) => true,
+        _ => false,
+    }
+}
+
+/// Checks whether `expr` can be the operand of a prefix operator without parens.
+fn is_prefix_operand(expr: &ast::Expr) -> bool {
+    match expr {
+        ast::Expr::RefExpr(_) | ast::Expr::PrefixExpr(_) | ast::Expr::BoxExpr(_) => true,
+        _ => is_postfix_operand(expr),
+    }
+}
+
 fn check_unnecessary_braces_in_use_statement(
     acc: &mut Vec<Diagnostic>,
     file_id: FileId,
@@ -233,14 +499,27 @@ mod tests {
         assert_eq_text!(after, &actual);
     }
 
+    /// Returns the diagnostics of `file_id` whose message starts with
+    /// `message`, so that tests aren't affected by unrelated diagnostics.
+    fn diagnostics_with_message(
+        analysis: &crate::Analysis,
+        file_id: FileId,
+        message: &str,
+    ) -> Vec<Diagnostic> {
+        let diagnostics = analysis.diagnostics(file_id, &DiagnosticsOptions::default()).unwrap();
+        diagnostics.into_iter().filter(|it| it.message.starts_with(message)).collect()
+    }
+
     /// Takes a multi-file input fixture with annotated cursor positions,
     /// and checks that:
-    ///  * a diagnostic is produced
+    ///  * a diagnostic with the given message is produced
     ///  * this diagnostic touches the input cursor position
     ///  * that the contents of the file containing the cursor match `after` after the diagnostic fix is applied
-    fn check_apply_diagnostic_fix_from_position(fixture: &str, after: &str) {
+    fn check_apply_diagnostic_fix_from_position(message: &str, fixture: &str, after: &str) {
         let (analysis, file_position) = analysis_and_position(fixture);
-        let diagnostic = analysis.diagnostics(file_position.file_id).unwrap().pop().unwrap();
+        let diagnostic = diagnostics_with_message(&analysis, file_position.file_id, message)
+            .pop()
+            .unwrap_or_else(|| panic!("no `{}` diagnostic", message));
         let mut fix = diagnostic.fix.unwrap();
         let edit = fix.source_file_edits.pop().unwrap().edit;
         let target_file_contents = analysis.file_text(file_position.file_id).unwrap();
@@ -271,9 +550,21 @@ mod tests {
         );
     }
 
-    fn check_apply_diagnostic_fix(before: &str, after: &str) {
+    fn check_apply_diagnostic_fix(message: &str, before: &str, after: &str) {
         let (analysis, file_id) = single_file(before);
-        let diagnostic = analysis.diagnostics(file_id).unwrap().pop().unwrap();
+        let diagnostic = diagnostics_with_message(&analysis, file_id, message)
+            .pop()
+            .unwrap_or_else(|| panic!("no `{}` diagnostic", message));
+        let mut fix = diagnostic.fix.unwrap();
+        let edit = fix.source_file_edits.pop().unwrap().edit;
+        let actual = edit.apply(&before);
+        assert_eq_text!(after, &actual);
+    }
+
+    fn check_apply_type_mismatch_fix(before: &str, after: &str) {
+        let (analysis, file_id) = single_file(before);
+        let options = DiagnosticsOptions { type_mismatch: true, ..DiagnosticsOptions::default() };
+        let diagnostic = analysis.diagnostics(file_id, &options).unwrap().pop().unwrap();
         let mut fix = diagnostic.fix.unwrap();
         let edit = fix.source_file_edits.pop().unwrap().edit;
         let actual = edit.apply(&before);
@@ -281,17 +572,19 @@ mod tests {
     }
 
     /// Takes a multi-file input fixture with annotated cursor position and checks that no diagnostics
-    /// apply to the file containing the cursor.
+    /// apply to the file containing the cursor. Unused imports are not reported, as the fixtures
+    /// import items which are only used after a fix.
     fn check_no_diagnostic_for_target_file(fixture: &str) {
         let (analysis, file_position) = analysis_and_position(fixture);
-        let diagnostics = analysis.diagnostics(file_position.file_id).unwrap();
-        assert_eq!(diagnostics.len(), 0);
+        let options = DiagnosticsOptions { unused_import: false, ..DiagnosticsOptions::default() };
+        let diagnostics = analysis.diagnostics(file_position.file_id, &options).unwrap();
+        assert_eq!(diagnostics.len(), 0, "unexpected diagnostics: {:?}", diagnostics);
     }
 
     fn check_no_diagnostic(content: &str) {
         let (analysis, file_id) = single_file(content);
-        let diagnostics = analysis.diagnostics(file_id).unwrap();
-        assert_eq!(diagnostics.len(), 0);
+        let diagnostics = analysis.diagnostics(file_id, &DiagnosticsOptions::default()).unwrap();
+        assert_eq!(diagnostics.len(), 0, "unexpected diagnostics: {:?}", diagnostics);
     }
 
     #[test]
@@ -325,7 +618,7 @@ mod tests {
                 Ok(x / y)
             }
         "#;
-        check_apply_diagnostic_fix_from_position(before, after);
+        check_apply_diagnostic_fix_from_position("wrap return expression in Ok", before, after);
     }
 
     #[test]
@@ -356,7 +649,7 @@ mod tests {
                 Ok(x)
             }
         "#;
-        check_apply_diagnostic_fix_from_position(before, after);
+        check_apply_diagnostic_fix_from_position("wrap return expression in Ok", before, after);
     }
 
     #[test]
@@ -393,7 +686,7 @@ mod tests {
                 Ok(x / y)
             }
         "#;
-        check_apply_diagnostic_fix_from_position(before, after);
+        check_apply_diagnostic_fix_from_position("wrap return expression in Ok", before, after);
     }
 
     #[test]
@@ -462,10 +755,10 @@ mod tests {
             }
 
             fn test_fn() {
-                let s = TestStruct{ one: (), two: ()};
+                let s = TestStruct{ one: todo!(), two: todo!()};
             }
         ";
-        check_apply_diagnostic_fix(before, after);
+        check_apply_diagnostic_fix("Missing structure fields", before, after);
     }
 
     #[test]
@@ -488,11 +781,11 @@ mod tests {
 
             impl TestStruct {
                 fn test_fn() {
-                    let s = Self { one: ()};
+                    let s = Self { one: todo!()};
                 }
             }
         ";
-        check_apply_diagnostic_fix(before, after);
+        check_apply_diagnostic_fix("Missing structure fields", before, after);
     }
 
     #[test]
@@ -516,12 +809,12 @@ mod tests {
 
             impl Expr {
                 fn new_bin(lhs: Box<Expr>, rhs: Box<Expr>) -> Expr {
-                    Expr::Bin { lhs: (), rhs: () <|> }
+                    Expr::Bin { lhs, rhs <|> }
                 }
             }
 
         ";
-        check_apply_diagnostic_fix(before, after);
+        check_apply_diagnostic_fix("Missing structure fields", before, after);
     }
 
     #[test]
@@ -543,10 +836,127 @@ mod tests {
             }
 
             fn test_fn() {
-                let s = TestStruct{ two: 2, one: () };
+                let s = TestStruct{ two: 2, one: todo!() };
+            }
+        ";
+        check_apply_diagnostic_fix("Missing structure fields", before, after);
+    }
+
+    #[test]
+    fn test_fill_struct_fields_from_locals_and_defaults() {
+        let fixture = r#"
+            //- /main.rs crate:main deps:core
+            struct Config;
+            impl Default for Config {
+                fn default() -> Config { Config }
+            }
+            struct TestStruct {
+                enabled: bool,
+                config: Config,
+                name: u32,
+            }
+
+            fn test_fn(enabled: bool) {
+                let name = "foo";
+                let s = TestStruct {<|>};
+            }
+            //- /core/lib.rs crate:core
+            #[prelude_import]
+            use prelude::*;
+
+            pub mod prelude {
+                pub use crate::default::Default;
+            }
+            pub mod default {
+                pub trait Default {
+                    fn default() -> Self;
+                }
+            }
+        "#;
+        let after = r#"
+            struct Config;
+            impl Default for Config {
+                fn default() -> Config { Config }
+            }
+            struct TestStruct {
+                enabled: bool,
+                config: Config,
+                name: u32,
+            }
+
+            fn test_fn(enabled: bool) {
+                let name = "foo";
+                let s = TestStruct { enabled, config: Default::default(), name: todo!()};
+            }
+        "#;
+        check_apply_diagnostic_fix_from_position("Missing structure fields", fixture, after);
+    }
+
+    #[test]
+    fn test_fill_struct_fields_without_default_trait_in_scope() {
+        let fixture = r#"
+            //- /main.rs crate:main deps:core
+            struct Config;
+            impl core::default::Default for Config {
+                fn default() -> Config { Config }
+            }
+            struct TestStruct {
+                config: Config,
+            }
+
+            fn test_fn() {
+                let s = TestStruct {<|>};
+            }
+            //- /core/lib.rs crate:core
+            pub mod default {
+                pub trait Default {
+                    fn default() -> Self;
+                }
+            }
+        "#;
+        let after = r#"
+            struct Config;
+            impl core::default::Default for Config {
+                fn default() -> Config { Config }
+            }
+            struct TestStruct {
+                config: Config,
+            }
+
+            fn test_fn() {
+                let s = TestStruct { config: core::default::Default::default()};
+            }
+        "#;
+        check_apply_diagnostic_fix_from_position("Missing structure fields", fixture, after);
+    }
+
+    #[test]
+    fn test_fill_struct_fields_nested() {
+        let before = r"
+            struct Inner<T> {
+                value: T,
+            }
+            enum Outer {
+                Wrapped { inner: Inner<u8> },
+            }
+
+            fn test_fn(value: u8) -> Outer {
+                Outer::Wrapped { inner: Inner {} }
+            }
+        ";
+        let after = r"
+            struct Inner<T> {
+                value: T,
+            }
+            enum Outer {
+                Wrapped { inner: Inner<u8> },
+            }
+
+            fn test_fn(value: u8) -> Outer {
+                Outer::Wrapped { inner: Inner { value} }
             }
         ";
-        check_apply_diagnostic_fix(before, after);
+        check_apply_diagnostic_fix("Missing structure fields", before, after);
     }
 
     #[test]
@@ -574,7 +984,7 @@ mod tests {
                 two: i64,
             }
 
-            fn test_fn() {
+            fn test_fn(a: TestStruct) {
                 let one = 1;
                 let s = TestStruct{ ..a };
             }
@@ -583,10 +993,386 @@ mod tests {
         check_no_diagnostic(content);
     }
 
+    #[test]
+    fn test_unresolved_path_auto_import_fix() {
+        let before = r"
+            mod foo {
+                pub struct Bar;
+            }
+
+            fn main() {
+                let _ = Bar;
+            }
+        ";
+        let after = r"
+            use foo::Bar;
+
+            mod foo {
+                pub struct Bar;
+            }
+
+            fn main() {
+                let _ = Bar;
+            }
+        ";
+        check_apply_diagnostic_fix("unresolved path", before, after);
+    }
+
+    #[test]
+    fn test_unresolved_type_path_auto_import_fix() {
+        let before = r"
+            mod foo {
+                pub struct Bar;
+            }
+
+            fn f(bar: Bar) {}
+        ";
+        let after = r"
+            use foo::Bar;
+
+            mod foo {
+                pub struct Bar;
+            }
+
+            fn f(bar: Bar) {}
+        ";
+        check_apply_diagnostic_fix("unresolved path", before, after);
+    }
+
+    #[test]
+    fn test_unresolved_import_fix() {
+        let before = r"
+            mod foo {
+                pub struct Bar;
+            }
+
+            use baz::Bar;
+        ";
+        let after = r"
+            mod foo {
+                pub struct Bar;
+            }
+
+            use foo::Bar;
+        ";
+        check_apply_diagnostic_fix("unresolved import", before, after);
+    }
+
+    #[test]
+    fn test_unused_import_fix() {
+        let before = r"
+            mod foo {
+                pub struct Bar;
+            }
+
+            use foo::Bar;
+            fn main() {}
+        ";
+        let after = r"
+            mod foo {
+                pub struct Bar;
+            }
+
+            fn main() {}
+        ";
+        check_apply_diagnostic_fix("unused import", before, after);
+    }
+
+    #[test]
+    fn test_unused_import_fix_in_use_tree_list() {
+        let before = r"
+            mod foo {
+                pub struct Bar;
+                pub struct Baz;
+                pub mod inner {
+                    pub struct Qux;
+                }
+            }
+
+            use foo::{Bar, inner::{Qux}};
+
+            fn main() {
+                let _ = Bar;
+            }
+        ";
+        let after = r"
+            mod foo {
+                pub struct Bar;
+                pub struct Baz;
+                pub mod inner {
+                    pub struct Qux;
+                }
+            }
+
+            use foo::{Bar};
+
+            fn main() {
+                let _ = Bar;
+            }
+        ";
+        check_apply_diagnostic_fix("unused import", before, after);
+    }
+
+    #[test]
+    fn test_no_unused_import_diagnostic() {
+        check_no_diagnostic(
+            r"
+            mod foo {
+                pub struct Bar;
+                pub trait Method {
+                    fn method(&self) {}
+                }
+                impl Method for Bar {}
+                pub fn baz() {}
+            }
+
+            use foo::{Bar, Method};
+            use foo::baz as qux;
+            pub use foo::baz;
+
+            fn main() {
+                Bar.method();
+                qux();
+            }
+
+            mod tests {
+                use super::*;
+
+                fn test() {
+                    let _ = Bar;
+                }
+            }
+            ",
+        );
+    }
+
+    #[test]
+    fn test_no_unused_import_diagnostic_for_assoc_type_bound() {
+        check_no_diagnostic(
+            r"
+            mod fmt {
+                pub trait Debug {}
+            }
+
+            use fmt::Debug;
+
+            trait Trait {
+                type Item: Debug;
+            }
+            ",
+        );
+    }
+
+    #[test]
+    fn test_no_unresolved_path_diagnostic_with_unexpanded_proc_macros() {
+        let content = r"
+            #[derive(Deserialize)]
+            struct S;
+
+            fn main() {
+                let _ = deserialize_s;
+            }
+        ";
+
+        check_no_diagnostic(content);
+    }
+
+    #[test]
+    fn test_type_mismatch_is_disabled_by_default() {
+        let content = r"
+            fn test_fn() {
+                let x: bool = 1;
+            }
+        ";
+        check_no_diagnostic(content);
+    }
+
+    #[test]
+    fn test_pat_type_mismatch_is_disabled_by_default() {
+        check_no_diagnostic("fn f(x: &mut u32) { let &y = x; }");
+    }
+
+    #[test]
+    fn test_type_mismatch_borrow_fix() {
+        let before = r"
+            struct S;
+            fn takes_ref(s: &S) {}
+            fn test_fn() {
+                let s = S;
+                takes_ref(s);
+            }
+        ";
+        let after = r"
+            struct S;
+            fn takes_ref(s: &S) {}
+            fn test_fn() {
+                let s = S;
+                takes_ref(&s);
+            }
+        ";
+        check_apply_type_mismatch_fix(before, after);
+    }
+
+    #[test]
+    fn test_type_mismatch_into_fix() {
+        let before = r"
+            mod std {
+                pub mod convert {
+                    pub trait Into<T> {
+                        fn into(self) -> T;
+                    }
+                    impl Into<u64> for u32 {
+                        fn into(self) -> u64 { self as u64 }
+                    }
+                }
+            }
+            fn takes_u64(x: u64) {}
+            fn test_fn(x: u32) {
+                takes_u64(x + 1);
+            }
+        ";
+        let after = r"
+            mod std {
+                pub mod convert {
+                    pub trait Into<T> {
+                        fn into(self) -> T;
+                    }
+                    impl Into<u64> for u32 {
+                        fn into(self) -> u64 { self as u64 }
+                    }
+                }
+            }
+            fn takes_u64(x: u64) {}
+            fn test_fn(x: u32) {
+                takes_u64((x + 1).into());
+            }
+        ";
+        check_apply_type_mismatch_fix(before, after);
+    }
+
+    #[test]
+    fn test_type_mismatch_to_string_fix() {
+        let before = r#"
+            mod std {
+                pub mod string {
+                    pub struct String;
+                }
+            }
+            fn takes_string(s: std::string::String) {}
+            fn test_fn() {
+                takes_string("foo");
+            }
+        "#;
+        let after = r#"
+            mod std {
+                pub mod string {
+                    pub struct String;
+                }
+            }
+            fn takes_string(s: std::string::String) {}
+            fn test_fn() {
+                takes_string("foo".to_string());
+            }
+        "#;
+        check_apply_type_mismatch_fix(before, after);
+    }
+
+    #[test]
+    fn test_pattern_type_mismatch() {
+        let (analysis, file_id) = single_file("fn f(x: &mut u32) { let &y = x; }");
+        let options = DiagnosticsOptions { type_mismatch: true, ..DiagnosticsOptions::default() };
+        let diagnostics = analysis.diagnostics(file_id, &options).unwrap();
+        assert_debug_snapshot!(diagnostics, @r###"
+        [
+            Diagnostic {
+                message: "expected &mut u32, found &u32",
+                range: [24; 26),
+                fix: None,
+                severity: Error,
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn test_macro_error_diagnostic() {
+        let (analysis, file_id) = single_file(
+            r"
+            macro_rules! m { (a ; b) => {}; }
+            fn f() {
+                m!(a , b);
+            }
+        ",
+        );
+        let diagnostics = analysis.diagnostics(file_id, &DiagnosticsOptions::default()).unwrap();
+        assert_debug_snapshot!(diagnostics, @r###"
+        [
+            Diagnostic {
+                message: "expected `;`, found `,`",
+                range: [89; 90),
+                fix: None,
+                severity: Error,
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn test_macro_error_diagnostic_in_const_and_static() {
+        let (analysis, file_id) = single_file(
+            r"
+            macro_rules! m { (a ; b) => { 0 }; }
+            const C: i32 = m!(a , b);
+            static S: i32 = m!(a ; b c);
+        ",
+        );
+        let diagnostics = analysis.diagnostics(file_id, &DiagnosticsOptions::default()).unwrap();
+        assert_debug_snapshot!(diagnostics, @r###"
+        [
+            Diagnostic {
+                message: "expected `;`, found `,`",
+                range: [82; 83),
+                fix: None,
+                severity: Error,
+            },
+            Diagnostic {
+                message: "leftover tokens, starting at `c`",
+                range: [125; 126),
+                fix: None,
+                severity: Error,
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn test_builtin_macro_error_diagnostic() {
+        let (analysis, file_id) = single_file(
+            r"
+            #[rustc_builtin_macro]
+            macro_rules! env {() => {}}
+            fn f() {
+                let x = env!(1);
+            }
+        ",
+        );
+        let diagnostics = analysis.diagnostics(file_id, &DiagnosticsOptions::default()).unwrap();
+        assert_debug_snapshot!(diagnostics, @r###"
+        [
+            Diagnostic {
+                message: "could not convert tokens",
+                range: [121; 128),
+                fix: None,
+                severity: Error,
+            },
+        ]
+        "###);
+    }
+
     #[test]
     fn test_unresolved_module_diagnostic() {
         let (analysis, file_id) = single_file("mod foo;");
-        let diagnostics = analysis.diagnostics(file_id).unwrap();
+        let diagnostics = analysis.diagnostics(file_id, &DiagnosticsOptions::default()).unwrap();
         assert_debug_snapshot!(diagnostics, @r###"
         [
             Diagnostic {
@@ -52,6 +52,7 @@ pub(crate) fn generic_parameters<N: TypeParamsOwner>(node: &N) -> Vec<String> {
     if let Some(type_params) = node.type_param_list() {
         res.extend(type_params.lifetime_params().map(|p| p.syntax().text().to_string()));
         res.extend(type_params.type_params().map(|p| p.syntax().text().to_string()));
+        res.extend(type_params.const_params().map(|p| p.syntax().text().to_string()));
     }
     res
 }
@@ -4,16 +4,41 @@ use hir::Semantics;
 use ra_ide_db::RootDatabase;
 use ra_syntax::{
     algo::{find_node_at_offset, SyntaxRewriter},
-    ast, AstNode, NodeOrToken, SyntaxKind, SyntaxNode, WalkEvent, T,
+    ast, AstNode, NodeOrToken, SyntaxKind, SyntaxNode, TextRange, WalkEvent, T,
 };
 
-use crate::FilePosition;
+use crate::{FileId, FilePosition};
 
 pub struct ExpandedMacro {
     pub name: String,
     pub expansion: String,
 }
 
+/// A single, non-recursive expansion of a macro call.
+#[derive(Debug)]
+pub struct MacroExpansionStep {
+    pub name: String,
+    /// The text of the macro call, which might come from an earlier step.
+    pub call: String,
+    /// The index of the `macro_rules!` arm the call was expanded with. This is
+    /// `None` for other kinds of macros.
+    pub rule: Option<usize>,
+    pub bindings: Vec<MacroBinding>,
+    pub expansion: String,
+}
+
+/// A fragment of the macro input bound to a `$var`.
+#[derive(Debug)]
+pub struct MacroBinding {
+    pub name: String,
+    /// The iteration of each enclosing `$(...)*` repetition.
+    pub nesting: Vec<usize>,
+    pub text: String,
+    /// The range of the fragment in the file, if it was written there and
+    /// not produced by another macro.
+    pub range: Option<TextRange>,
+}
+
 pub(crate) fn expand_macro(db: &RootDatabase, position: FilePosition) -> Option<ExpandedMacro> {
     let sema = Semantics::new(db);
     let file = sema.parse(position.file_id);
@@ -29,6 +54,66 @@ pub(crate) fn expand_macro(db: &RootDatabase, position: FilePosition) -> Option<
     Some(ExpandedMacro { name: name_ref.text().to_string(), expansion })
 }
 
+/// Expands the macro call at `position` step by step: the call itself first,
+/// followed by the macro calls in each expansion, depth first.
+pub(crate) fn expand_macro_trace(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<Vec<MacroExpansionStep>> {
+    let sema = Semantics::new(db);
+    let file = sema.parse(position.file_id);
+    let name_ref = find_node_at_offset::<ast::NameRef>(file.syntax(), position.offset)?;
+    let mac = name_ref.syntax().ancestors().find_map(ast::MacroCall::cast)?;
+
+    let mut steps = Vec::new();
+    trace_macro_recur(&sema, position.file_id, &mac, &mut steps)?;
+    Some(steps)
+}
+
+fn trace_macro_recur(
+    sema: &Semantics<RootDatabase>,
+    file_id: FileId,
+    macro_call: &ast::MacroCall,
+    acc: &mut Vec<MacroExpansionStep>,
+) -> Option<()> {
+    let expanded = sema.expand(macro_call)?;
+    let trace = sema.expansion_trace(macro_call);
+
+    let name = macro_call
+        .path()
+        .and_then(|it| it.segment())
+        .and_then(|it| it.name_ref())
+        .map_or_else(String::new, |it| it.text().to_string());
+    let (rule, bindings) = match trace {
+        Some(trace) => {
+            let bindings = trace
+                .bindings
+                .into_iter()
+                .map(|it| MacroBinding {
+                    name: it.name.to_string(),
+                    nesting: it.nesting,
+                    text: it.text,
+                    range: it.range.filter(|it| it.file_id == file_id.into()).map(|it| it.value),
+                })
+                .collect();
+            (trace.rule, bindings)
+        }
+        None => (None, Vec::new()),
+    };
+    acc.push(MacroExpansionStep {
+        name,
+        call: macro_call.syntax().text().to_string(),
+        rule,
+        bindings,
+        expansion: insert_whitespaces(expanded.clone()),
+    });
+
+    for child in expanded.descendants().filter_map(ast::MacroCall::cast) {
+        trace_macro_recur(sema, file_id, &child, acc);
+    }
+    Some(())
+}
+
 fn expand_macro_recur(
     sema: &Semantics<RootDatabase>,
     macro_call: &ast::MacroCall,
@@ -284,4 +369,45 @@ fn some_thing() -> u32 {
         assert_eq!(res.name, "foo");
         assert_snapshot!(res.expansion, @r###"0"###);
     }
+
+    #[test]
+    fn macro_expand_trace() {
+        let (analysis, pos) = analysis_and_position(
+            r#"
+        //- /lib.rs
+        macro_rules! bar {
+            ($e:expr) => { $e };
+        }
+        macro_rules! foo {
+            (fn $i:ident) => {};
+            ($($i:ident),*) => { fn f() { $(bar!($i);)* } };
+        }
+        fo<|>o!(a, b);
+        "#,
+        );
+        let text = analysis.file_text(pos.file_id).unwrap();
+        let steps = analysis.expand_macro_trace(pos).unwrap().unwrap();
+        let steps = steps
+            .iter()
+            .map(|step| {
+                let bindings = step
+                    .bindings
+                    .iter()
+                    .map(|it| {
+                        let source = it.range.map(|range| &text[range]);
+                        format!("{}{:?} = {} ({:?})", it.name, it.nesting, it.text, source)
+                    })
+                    .collect::<Vec<_>>();
+                format!("{}: rule {:?}, {}", step.name, step.rule, bindings.join(", "))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            steps,
+            vec![
+                r#"foo: rule Some(1), i[0] = a (Some("a")), i[1] = b (Some("b"))"#,
+                r#"bar: rule Some(0), e[] = a (Some("a"))"#,
+                r#"bar: rule Some(0), e[] = b (Some("b"))"#,
+            ]
+        );
+    }
 }
@@ -2,7 +2,7 @@
 
 use hir::Semantics;
 use ra_ide_db::{
-    defs::{classify_name, classify_name_ref},
+    defs::{classify_lifetime, classify_name, classify_name_ref},
     symbol_index, RootDatabase,
 };
 use ra_syntax::{
@@ -26,6 +26,12 @@ pub(crate) fn goto_definition(
     let original_token = pick_best(file.token_at_offset(position.offset))?;
     let token = sema.descend_into_macros(original_token.clone());
 
+    if token.kind() == LIFETIME {
+        let def = classify_lifetime(&sema, &token)?;
+        let nav = def.try_to_nav(sema.db)?;
+        return Some(RangeInfo::new(original_token.text_range(), vec![nav]));
+    }
+
     let nav_targets = match_ast! {
         match (token.parent()) {
             ast::NameRef(name_ref) => {
@@ -36,6 +42,13 @@ pub(crate) fn goto_definition(
                 let nav = def.try_to_nav(sema.db)?;
                 vec![nav]
             },
+            ast::BinExpr(bin_expr) => {
+                if bin_expr.op_token()? != token {
+                    return None;
+                }
+                let func = sema.resolve_bin_expr(&bin_expr)?;
+                vec![func.to_nav(sema.db)]
+            },
             _ => return None,
         }
     };
@@ -47,7 +60,7 @@ fn pick_best(tokens: TokenAtOffset<SyntaxToken>) -> Option<SyntaxToken> {
     return tokens.max_by_key(priority);
     fn priority(n: &SyntaxToken) -> usize {
         match n.kind() {
-            IDENT | INT_NUMBER => 2,
+            IDENT | INT_NUMBER | LIFETIME => 2,
             kind if kind.is_trivia() => 0,
             _ => 1,
         }
@@ -790,6 +803,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn goto_within_macro_respects_hygiene() {
+        check_goto(
+            "
+            //- /lib.rs
+            macro_rules! shadow {
+                ($e:expr) => {{ let x = 0; $e }};
+            }
+            fn foo() {
+                let x = 1;
+                shadow!(<|>x);
+            }
+            ",
+            "x BIND_PAT FileId(1) [81; 82)",
+            "x",
+        );
+    }
+
     #[test]
     fn goto_def_in_local_fn() {
         check_goto(
@@ -838,4 +869,58 @@ mod tests {
             "x",
         )
     }
+
+    #[test]
+    fn goto_def_for_label() {
+        check_goto(
+            "
+            //- /lib.rs
+            fn foo() {
+                'outer: loop {
+                    loop {
+                        break 'outer<|>;
+                    }
+                }
+            }
+            ",
+            "'outer LABEL FileId(1) [15; 22) [15; 21)",
+            "'outer:|'outer",
+        );
+    }
+
+    #[test]
+    fn goto_def_for_lifetime() {
+        check_goto(
+            "
+            //- /lib.rs
+            fn foo<'a>(x: &'a<|> str) -> &'a str { x }
+            ",
+            "'a LIFETIME_PARAM FileId(1) [7; 9) [7; 9)",
+            "'a|'a",
+        );
+    }
+
+    #[test]
+    fn goto_def_for_overloaded_binary_operator() {
+        check_goto(
+            r#"
+            //- /lib.rs
+            #[lang = "add"]
+            trait Add<Rhs = Self> {
+                type Output;
+                fn add(self, rhs: Rhs) -> Self::Output;
+            }
+            struct Foo;
+            impl Add for Foo {
+                type Output = Foo;
+                fn add(self, rhs: Foo) -> Foo { rhs }
+            }
+            fn bar(a: Foo, b: Foo) {
+                a +<|> b;
+            }
+            "#,
+            "add FN_DEF FileId(1) [161; 198) [164; 167)",
+            "fn add(self, rhs: Foo) -> Foo { rhs }|add",
+        );
+    }
 }
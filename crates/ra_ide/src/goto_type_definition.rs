@@ -27,10 +27,12 @@ pub(crate) fn goto_type_definition(
         Some((ty, node))
     })?;
 
-    let adt_def = ty.autoderef(db).find_map(|ty| ty.as_adt())?;
+    let navs = ty.autoderef(db).find_map(|ty| match ty.as_adt() {
+        Some(adt_def) => Some(vec![adt_def.to_nav(db)]),
+        None => ty.as_impl_traits(db).map(|traits| traits.iter().map(|it| it.to_nav(db)).collect()),
+    })?;
 
-    let nav = adt_def.to_nav(db);
-    Some(RangeInfo::new(node.text_range(), vec![nav]))
+    Some(RangeInfo::new(node.text_range(), navs))
 }
 
 fn pick_best(tokens: TokenAtOffset<SyntaxToken>) -> Option<SyntaxToken> {
@@ -133,4 +135,20 @@ mod tests {
             "Foo STRUCT_DEF FileId(1) [0; 11) [7; 10)",
         );
     }
+
+    #[test]
+    fn goto_type_definition_for_return_pos_impl_trait() {
+        check_goto(
+            "
+            //- /lib.rs
+            trait Foo {}
+            fn make() -> impl Foo { loop {} }
+            fn foo() {
+                let f = make();
+                f<|>
+            }
+            ",
+            "Foo TRAIT_DEF FileId(1) [0; 12) [6; 9)",
+        );
+    }
 }
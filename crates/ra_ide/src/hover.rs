@@ -137,14 +137,27 @@ fn hover_text_from_name_kind(db: &RootDatabase, def: Definition) -> Option<Strin
             ModuleDef::Adt(Adt::Union(it)) => from_def_source(db, it, mod_path),
             ModuleDef::Adt(Adt::Enum(it)) => from_def_source(db, it, mod_path),
             ModuleDef::EnumVariant(it) => from_def_source(db, it, mod_path),
-            ModuleDef::Const(it) => from_def_source(db, it, mod_path),
+            ModuleDef::Const(it) => {
+                let src = it.source(db);
+                let label = src.value.short_label().map(|label| match it.eval(db) {
+                    Some(value) => format!("{} = {}", label, value),
+                    None => label,
+                });
+                hover_text(src.value.doc_comment_text(), label, mod_path)
+            }
             ModuleDef::Static(it) => from_def_source(db, it, mod_path),
             ModuleDef::Trait(it) => from_def_source(db, it, mod_path),
             ModuleDef::TypeAlias(it) => from_def_source(db, it, mod_path),
             ModuleDef::BuiltinType(it) => Some(it.to_string()),
         },
         Definition::Local(it) => Some(rust_code_markup(&it.ty(db).display_truncated(db, None))),
-        Definition::TypeParam(_) | Definition::SelfType(_) => {
+        Definition::ConstParam(it) => {
+            Some(rust_code_markup(&format!("const {}: {}", it.name(db), it.ty(db).display(db))))
+        }
+        Definition::TypeParam(_)
+        | Definition::LifetimeParam(_)
+        | Definition::Label(_)
+        | Definition::SelfType(_) => {
             // FIXME: Hover for generic param
             None
         }
@@ -227,10 +240,14 @@ fn pick_best(tokens: TokenAtOffset<SyntaxToken>) -> Option<SyntaxToken> {
 
 #[cfg(test)]
 mod tests {
+    use hir::Semantics;
     use ra_db::FileLoader;
-    use ra_syntax::TextRange;
+    use ra_syntax::{
+        ast::{self, NameOwner},
+        AstNode, TextRange,
+    };
 
-    use crate::mock_analysis::{analysis_and_position, single_file_with_position};
+    use crate::mock_analysis::{analysis_and_position, single_file, single_file_with_position};
 
     fn trim_markup(s: &str) -> &str {
         s.trim_start_matches("```rust\n").trim_end_matches("\n```")
@@ -334,6 +351,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn hover_shows_fn_signature_with_const_params() {
+        check_hover_result(
+            r#"
+            //- /main.rs
+            pub fn foo<T, const N: usize>(a: [T; N]) -> usize { N }
+
+            fn main() {
+                let foo_test = fo<|>o();
+            }
+        "#,
+            &["pub fn foo<T, const N: usize>(a: [T; N]) -> usize"],
+        );
+    }
+
+    #[test]
+    fn hover_const_param() {
+        check_hover_result(
+            r#"
+            //- /main.rs
+            fn foo<const N: usize>() -> usize { N<|> }
+        "#,
+            &["const N: usize"],
+        );
+    }
+
     #[test]
     fn hover_shows_fn_signature_on_fn_name() {
         check_hover_result(
@@ -392,7 +435,7 @@ mod tests {
             //- /main.rs
             const foo<|>: u32 = 0;
         "#,
-            &["const foo: u32"],
+            &["const foo: u32 = 0"],
         );
 
         check_hover_result(
@@ -404,6 +447,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn hover_const_shows_evaluated_value() {
+        check_hover_result(
+            r#"
+            //- /main.rs
+            const A: usize = 4;
+            const B<|>: usize = A * 2 + (1 << 3) - 1;
+        "#,
+            &["const B: usize = 15"],
+        );
+
+        check_hover_result(
+            r#"
+            //- /main.rs
+            const FLAG<|>: bool = 1u8 as i8 == -1 || !false;
+        "#,
+            &["const FLAG: bool = true"],
+        );
+
+        check_hover_result(
+            r#"
+            //- /main.rs
+            const OVERFLOW<|>: u8 = 255 + 1;
+        "#,
+            &["const OVERFLOW: u8"],
+        );
+    }
+
     #[test]
     fn hover_omits_default_generic_types() {
         check_hover_result(
@@ -587,7 +658,7 @@ fn func(foo: i32) { if true { <|>foo; }; }
             ",
         );
         let hover = analysis.hover(position).unwrap().unwrap();
-        assert_eq!(trim_markup_opt(hover.info.first()), Some("const C: u32"));
+        assert_eq!(trim_markup_opt(hover.info.first()), Some("const C: u32 = 1"));
     }
 
     #[test]
@@ -844,4 +915,59 @@ fn func(foo: i32) { if true { <|>foo; }; }
             &["fn foo()\n```\n\n<- `\u{3000}` here"],
         );
     }
+
+    #[test]
+    fn test_hover_return_pos_impl_trait() {
+        check_hover_result(
+            r#"
+            //- /main.rs
+            trait Foo<T> {}
+            fn make() -> impl Foo<u32> { loop {} }
+
+            fn main() {
+                let foo<|> = make();
+            }
+            "#,
+            &["impl Foo<u32>"],
+        );
+    }
+
+    #[test]
+    fn test_type_impls_trait() {
+        let (analysis, file_id) = single_file(
+            r"
+            auto trait Send {}
+            trait Default {}
+            struct Rc;
+            impl !Send for Rc {}
+            struct Config;
+            impl Default for Config {}
+            fn f(config: Config, rc: Rc) {}
+            ",
+        );
+        let sema = Semantics::new(&*analysis.db);
+        let source_file = sema.parse(file_id);
+        let trait_named = |name: &str| {
+            let trait_def = source_file
+                .syntax()
+                .descendants()
+                .filter_map(ast::TraitDef::cast)
+                .find(|it| it.name().unwrap().text() == name)
+                .unwrap();
+            sema.to_def(&trait_def).unwrap()
+        };
+        let (send, default) = (trait_named("Send"), trait_named("Default"));
+        let param_tys: Vec<_> = source_file
+            .syntax()
+            .descendants()
+            .filter_map(ast::Param::cast)
+            .map(|it| sema.type_of_pat(&it.pat().unwrap()).unwrap())
+            .collect();
+        let db = &*analysis.db;
+        let impls: Vec<_> = param_tys
+            .iter()
+            .map(|ty| (ty.impls_trait(db, send), ty.impls_trait(db, default)))
+            .collect();
+        assert_eq!(impls, vec![(true, true), (false, false)]);
+    }
 }
@@ -23,6 +23,7 @@ mod extend_selection;
 mod hover;
 mod call_hierarchy;
 mod call_info;
+mod change_signature;
 mod syntax_highlighting;
 mod parent_module;
 mod references;
@@ -62,10 +63,11 @@ use crate::display::ToNav;
 pub use crate::{
     assists::{Assist, AssistId},
     call_hierarchy::CallItem,
+    change_signature::SignatureParam,
     completion::{CompletionItem, CompletionItemKind, CompletionOptions, InsertTextFormat},
-    diagnostics::Severity,
+    diagnostics::{DiagnosticsOptions, Severity},
     display::{file_structure, FunctionSignature, NavigationTarget, StructureNode},
-    expand_macro::ExpandedMacro,
+    expand_macro::{ExpandedMacro, MacroBinding, MacroExpansionStep},
     folding_ranges::{Fold, FoldKind},
     hover::HoverResult,
     inlay_hints::{InlayHint, InlayHintsOptions, InlayKind},
@@ -280,6 +282,15 @@ impl Analysis {
         self.with_db(|db| expand_macro::expand_macro(db, position))
     }
 
+    /// Returns each step of expanding the macro call at `position`, with the
+    /// `macro_rules!` arm used and the fragments bound to its metavariables.
+    pub fn expand_macro_trace(
+        &self,
+        position: FilePosition,
+    ) -> Cancelable<Option<Vec<MacroExpansionStep>>> {
+        self.with_db(|db| expand_macro::expand_macro_trace(db, position))
+    }
+
     /// Returns an edit to remove all newlines in the range, cleaning up minor
     /// stuff like trailing commas.
     pub fn join_lines(&self, frange: FileRange) -> Cancelable<SourceChange> {
@@ -462,8 +473,12 @@ impl Analysis {
     }
 
     /// Computes the set of diagnostics for the given file.
-    pub fn diagnostics(&self, file_id: FileId) -> Cancelable<Vec<Diagnostic>> {
-        self.with_db(|db| diagnostics::diagnostics(db, file_id))
+    pub fn diagnostics(
+        &self,
+        file_id: FileId,
+        options: &DiagnosticsOptions,
+    ) -> Cancelable<Vec<Diagnostic>> {
+        self.with_db(|db| diagnostics::diagnostics(db, file_id, options))
     }
 
     /// Returns the edit required to rename reference at the position to the new
@@ -476,6 +491,16 @@ impl Analysis {
         self.with_db(|db| references::rename(db, position, new_name))
     }
 
+    /// Changes the parameters of the function at `position`, rewriting its
+    /// declaration, related trait methods and all call sites.
+    pub fn change_signature(
+        &self,
+        position: FilePosition,
+        new_params: &[SignatureParam],
+    ) -> Cancelable<Option<RangeInfo<SourceChange>>> {
+        self.with_db(|db| change_signature::change_signature(db, position, new_params))
+    }
+
     pub fn structural_search_replace(
         &self,
         query: &str,
@@ -13,7 +13,7 @@ mod rename;
 
 use hir::Semantics;
 use ra_ide_db::{
-    defs::{classify_name, classify_name_ref, Definition},
+    defs::{classify_lifetime, classify_name, classify_name_ref, Definition},
     search::SearchScope,
     RootDatabase,
 };
@@ -135,6 +135,12 @@ fn find_name(
         let range = name.syntax().text_range();
         return Some(RangeInfo::new(range, def));
     }
+    if let Some(lifetime) =
+        syntax.token_at_offset(position.offset).find(|it| it.kind() == SyntaxKind::LIFETIME)
+    {
+        let def = classify_lifetime(sema, &lifetime)?;
+        return Some(RangeInfo::new(lifetime.text_range(), def));
+    }
     let name_ref =
         sema.find_node_at_offset_with_descend::<ast::NameRef>(&syntax, position.offset)?;
     let def = classify_name_ref(sema, &name_ref)?.definition();
@@ -609,6 +615,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_find_all_refs_for_label() {
+        let code = r#"
+    fn foo() {
+        'outer<|>: loop {
+            loop {
+                continue 'outer;
+            }
+            break 'outer;
+        }
+    }"#;
+
+        let refs = get_all_refs(code);
+        check_result(
+            refs,
+            "'outer LABEL FileId(1) [24; 31) [24; 30) Other",
+            &["FileId(1) [83; 89) Other", "FileId(1) [123; 129) Other"],
+        );
+    }
+
+    #[test]
+    fn test_find_all_refs_for_lifetime() {
+        let code = r#"
+    fn foo<'a<|>>(x: &'a u32) -> &'a u32 {
+        x
+    }"#;
+
+        let refs = get_all_refs(code);
+        check_result(
+            refs,
+            "'a LIFETIME_PARAM FileId(1) [12; 14) [12; 14) Other",
+            &["FileId(1) [20; 22) Other", "FileId(1) [32; 34) Other"],
+        );
+    }
+
     fn get_all_refs(text: &str) -> ReferenceSearchResult {
         let (analysis, position) = single_file_with_position(text);
         analysis.find_all_refs(position, None).unwrap().unwrap()
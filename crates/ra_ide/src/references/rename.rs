@@ -20,13 +20,19 @@ pub(crate) fn rename(
     position: FilePosition,
     new_name: &str,
 ) -> Option<RangeInfo<SourceChange>> {
+    let sema = Semantics::new(db);
+    let source_file = sema.parse(position.file_id);
+
+    // Lifetimes and labels can only be renamed to another lifetime.
+    let is_lifetime = source_file
+        .syntax()
+        .token_at_offset(position.offset)
+        .any(|it| it.kind() == SyntaxKind::LIFETIME);
     match lex_single_valid_syntax_kind(new_name)? {
-        SyntaxKind::IDENT | SyntaxKind::UNDERSCORE => (),
+        SyntaxKind::LIFETIME if is_lifetime => (),
+        SyntaxKind::IDENT | SyntaxKind::UNDERSCORE if !is_lifetime => (),
         _ => return None,
     }
-
-    let sema = Semantics::new(db);
-    let source_file = sema.parse(position.file_id);
     if let Some((ast_name, ast_module)) =
         find_name_and_module_at_offset(source_file.syntax(), position)
     {
@@ -712,6 +718,54 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_rename_label() {
+        test_rename(
+            r#"
+    fn main() {
+        'outer<|>: loop {
+            loop {
+                break 'outer;
+            }
+        }
+    }"#,
+            "'inner",
+            r#"
+    fn main() {
+        'inner: loop {
+            loop {
+                break 'inner;
+            }
+        }
+    }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_lifetime() {
+        test_rename(
+            r#"
+    fn foo<'a>(x: &'a<|> u32) -> &'a u32 {
+        x
+    }"#,
+            "'b",
+            r#"
+    fn foo<'b>(x: &'b u32) -> &'b u32 {
+        x
+    }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_lifetime_to_identifier() {
+        let (analysis, position) = single_file_with_position(
+            "
+    fn foo<'a<|>>(x: &'a u32) {}",
+        );
+        let source_change = analysis.rename(position, "a").unwrap();
+        assert!(source_change.is_none());
+    }
+
     fn test_rename(text: &str, new_name: &str, expected: &str) {
         let (analysis, position) = single_file_with_position(text);
         let source_change = analysis.rename(position, new_name).unwrap();
@@ -7,7 +7,9 @@ mod tests;
 
 use hir::{Name, Semantics};
 use ra_ide_db::{
-    defs::{classify_name, classify_name_ref, Definition, NameClass, NameRefClass},
+    defs::{
+        classify_lifetime, classify_name, classify_name_ref, Definition, NameClass, NameRefClass,
+    },
     RootDatabase,
 };
 use ra_prof::profile;
@@ -200,7 +202,17 @@ fn highlight_element(
                             binding_hash = Some(calc_binding_hash(&name, *shadow_count))
                         }
                     };
-                    highlight_name(db, def)
+                    let is_unsafe = match &def {
+                        Definition::ModuleDef(hir::ModuleDef::Function(func)) => func.is_unsafe(db),
+                        Definition::ModuleDef(hir::ModuleDef::Static(it)) => it.is_mut(db),
+                        _ => false,
+                    };
+                    let h = highlight_name(db, def);
+                    if is_unsafe {
+                        h | HighlightModifier::Unsafe
+                    } else {
+                        h
+                    }
                 }
                 NameRefClass::FieldShorthand { .. } => HighlightTag::Field.into(),
             }
@@ -214,9 +226,13 @@ fn highlight_element(
         BYTE => HighlightTag::ByteLiteral.into(),
         CHAR => HighlightTag::CharLiteral.into(),
         LIFETIME => {
-            let h = Highlight::new(HighlightTag::Lifetime);
-            match element.parent().map(|it| it.kind()) {
-                Some(LIFETIME_PARAM) | Some(LABEL) => h | HighlightModifier::Definition,
+            let lifetime = element.into_token().unwrap();
+            let h = match classify_lifetime(sema, &lifetime) {
+                Some(def) => highlight_name(db, def),
+                None => HighlightTag::Lifetime.into(),
+            };
+            match lifetime.parent().kind() {
+                LIFETIME_PARAM | LABEL => h | HighlightModifier::Definition,
                 _ => h,
             }
         }
@@ -238,6 +254,16 @@ fn highlight_element(
             }
         }
 
+        // Dereferencing a raw pointer is unsafe.
+        T![*] => {
+            let prefix_expr = element.parent().and_then(ast::PrefixExpr::cast)?;
+            let ty = sema.type_of_expr(&prefix_expr.expr()?)?;
+            if !ty.is_raw_ptr() {
+                return None;
+            }
+            HighlightTag::Operator | HighlightModifier::Unsafe
+        }
+
         _ => return None,
     };
 
@@ -275,6 +301,9 @@ fn highlight_name(db: &RootDatabase, def: Definition) -> Highlight {
         },
         Definition::SelfType(_) => HighlightTag::SelfType,
         Definition::TypeParam(_) => HighlightTag::TypeParam,
+        Definition::LifetimeParam(_) => HighlightTag::Lifetime,
+        Definition::ConstParam(_) => HighlightTag::Constant,
+        Definition::Label(_) => HighlightTag::Label,
         // FIXME: distinguish between locals and parameters
         Definition::Local(local) => {
             let mut h = Highlight::new(HighlightTag::Local);
@@ -25,10 +25,12 @@ pub enum HighlightTag {
     Field,
     Function,
     Keyword,
+    Label,
     Lifetime,
     Macro,
     Module,
     NumericLiteral,
+    Operator,
     SelfType,
     Static,
     StringLiteral,
@@ -66,10 +68,12 @@ impl HighlightTag {
             HighlightTag::Field => "field",
             HighlightTag::Function => "function",
             HighlightTag::Keyword => "keyword",
+            HighlightTag::Label => "label",
             HighlightTag::Lifetime => "lifetime",
             HighlightTag::Macro => "macro",
             HighlightTag::Module => "module",
             HighlightTag::NumericLiteral => "numeric_literal",
+            HighlightTag::Operator => "operator",
             HighlightTag::SelfType => "self_type",
             HighlightTag::Static => "static",
             HighlightTag::StringLiteral => "string_literal",
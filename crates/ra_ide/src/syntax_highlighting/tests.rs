@@ -131,3 +131,41 @@ fn test_ranges() {
 
     assert_eq!(&highlights[0].highlight.to_string(), "field.declaration");
 }
+
+#[test]
+fn test_unsafe_highlighting() {
+    let (analysis, file_id) = single_file(
+        r#"
+unsafe fn unsafe_fn() {}
+static mut GLOBAL: u32 = 0;
+
+fn main() {
+    let x = &5 as *const usize;
+    unsafe {
+        unsafe_fn();
+        let y = *x;
+        GLOBAL = 1;
+    }
+}
+"#
+        .trim(),
+    );
+    let text = analysis.file_text(file_id).unwrap();
+    let highlights = analysis.highlight(file_id).unwrap();
+    let actual = highlights
+        .iter()
+        .filter(|it| it.highlight.to_string().contains("unsafe"))
+        .map(|it| format!("{} {}", &text[it.range], it.highlight))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        actual,
+        vec![
+            "unsafe keyword.unsafe",
+            "unsafe keyword.unsafe",
+            "unsafe_fn function.unsafe",
+            "* operator.unsafe",
+            "GLOBAL static.unsafe",
+        ]
+    );
+}
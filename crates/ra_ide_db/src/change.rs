@@ -9,7 +9,8 @@ use ra_db::{
     SourceRootId,
 };
 use ra_prof::{memory_usage, profile, Bytes};
-use ra_syntax::SourceFile;
+use ra_syntax::{AstNode, Parse, SourceFile};
+use ra_text_edit::AtomTextEdit;
 #[cfg(not(feature = "wasm"))]
 use rayon::prelude::*;
 use rustc_hash::FxHashMap;
@@ -23,7 +24,7 @@ use crate::{
 pub struct AnalysisChange {
     new_roots: Vec<(SourceRootId, bool)>,
     roots_changed: FxHashMap<SourceRootId, RootChange>,
-    files_changed: Vec<(FileId, Arc<String>)>,
+    files_changed: Vec<(FileId, Arc<String>, Option<Vec<AtomTextEdit>>)>,
     libraries_added: Vec<LibraryData>,
     crate_graph: Option<CrateGraph>,
     debug_data: DebugData,
@@ -72,7 +73,19 @@ impl AnalysisChange {
     }
 
     pub fn change_file(&mut self, file_id: FileId, new_text: Arc<String>) {
-        self.files_changed.push((file_id, new_text))
+        self.files_changed.push((file_id, new_text, None))
+    }
+
+    /// Like `change_file`, but also records the `edits` which turn the
+    /// current text of the file into `new_text`, so that the syntax tree can
+    /// be reparsed incrementally.
+    pub fn change_file_with_edits(
+        &mut self,
+        file_id: FileId,
+        new_text: Arc<String>,
+        edits: Vec<AtomTextEdit>,
+    ) {
+        self.files_changed.push((file_id, new_text, Some(edits)))
     }
 
     pub fn remove_file(&mut self, root_id: SourceRootId, file_id: FileId, path: RelativePathBuf) {
@@ -191,11 +204,13 @@ impl RootDatabase {
         for (root_id, root_change) in change.roots_changed {
             self.apply_root_change(root_id, root_change);
         }
-        for (file_id, text) in change.files_changed {
+        for (file_id, text, edits) in change.files_changed {
+            let reparse = edits.and_then(|edits| self.reparse_file(file_id, &text, &edits));
             let source_root_id = self.file_source_root(file_id);
             let source_root = self.source_root(source_root_id);
             let durability = durability(&source_root);
-            self.set_file_text_with_durability(file_id, text, durability)
+            self.set_file_text_with_durability(file_id, text, durability);
+            self.set_file_reparse_with_durability(file_id, reparse, durability)
         }
         if !change.libraries_added.is_empty() {
             let mut libraries = Vec::clone(&self.library_roots());
@@ -222,11 +237,36 @@ impl RootDatabase {
         Arc::make_mut(&mut self.debug_data).merge(change.debug_data)
     }
 
+    /// Applies `edits` to the current syntax tree of the file, so that the
+    /// `parse` query for `new_text` can reuse the unchanged parts of the tree.
+    fn reparse_file(
+        &self,
+        file_id: FileId,
+        new_text: &str,
+        edits: &[AtomTextEdit],
+    ) -> Option<Parse<SourceFile>> {
+        let _p = profile("RootDatabase::reparse_file");
+        let mut parse = self.parse(file_id);
+        for edit in edits {
+            if edit.delete.end() > parse.tree().syntax().text_range().end() {
+                return None;
+            }
+            parse = parse.reparse(edit);
+        }
+        // The edits might be relative to a different version of the file, in
+        // which case we have to parse it from scratch.
+        if parse.tree().syntax().text() != new_text {
+            return None;
+        }
+        Some(parse)
+    }
+
     fn apply_root_change(&mut self, root_id: SourceRootId, root_change: RootChange) {
         let mut source_root = SourceRoot::clone(&self.source_root(root_id));
         let durability = durability(&source_root);
         for add_file in root_change.added {
             self.set_file_text_with_durability(add_file.file_id, add_file.text, durability);
+            self.set_file_reparse_with_durability(add_file.file_id, None, durability);
             self.set_file_relative_path_with_durability(
                 add_file.file_id,
                 add_file.path.clone(),
@@ -237,6 +277,7 @@ impl RootDatabase {
         }
         for remove_file in root_change.removed {
             self.set_file_text_with_durability(remove_file.file_id, Default::default(), durability);
+            self.set_file_reparse_with_durability(remove_file.file_id, None, durability);
             source_root.remove_file(&remove_file.path);
         }
         self.set_source_root_with_durability(root_id, Arc::new(source_root), durability);
@@ -311,6 +352,7 @@ impl RootDatabase {
             hir::db::MacroDefQuery
             hir::db::ParseMacroQuery
             hir::db::MacroExpandQuery
+            hir::db::MacroExpandErrorQuery
             hir::db::InternEagerExpansionQuery
 
             // DefDatabase
@@ -330,6 +372,7 @@ impl RootDatabase {
             hir::db::ExprScopesQuery
             hir::db::GenericParamsQuery
             hir::db::AttrsQuery
+            hir::db::ModuleImportUsageQuery
             hir::db::ModuleLangItemsQuery
             hir::db::CrateLangItemsQuery
             hir::db::LangItemQuery
@@ -354,9 +397,11 @@ impl RootDatabase {
             hir::db::ImplTraitQuery
             hir::db::FieldTypesQuery
             hir::db::CallableItemSignatureQuery
+            hir::db::ReturnTypeImplTraitsQuery
             hir::db::GenericPredicatesForParamQuery
             hir::db::GenericPredicatesQuery
             hir::db::GenericDefaultsQuery
+            hir::db::ConstEvalQuery
             hir::db::ImplsInCrateQuery
             hir::db::ImplsForTraitQuery
             hir::db::InternTypeCtorQuery
@@ -388,3 +433,42 @@ fn durability(source_root: &SourceRoot) -> Durability {
         Durability::LOW
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ra_db::{FileId, SourceDatabase, SourceDatabaseExt, SourceRootId};
+    use ra_syntax::{AstNode, TextRange};
+    use ra_text_edit::AtomTextEdit;
+
+    use crate::{change::AnalysisChange, RootDatabase};
+
+    #[test]
+    fn parse_uses_incremental_reparse() {
+        let source_root = SourceRootId(0);
+        let file_id = FileId(0);
+        let mut db = RootDatabase::default();
+        let mut change = AnalysisChange::new();
+        change.add_root(source_root, true);
+        change.add_file(source_root, file_id, "main.rs".into(), Arc::new("fn foo() { 1 }".into()));
+        db.apply_change(change);
+        db.parse(file_id);
+
+        let mut change = AnalysisChange::new();
+        let edit = AtomTextEdit::replace(TextRange::from_to(11.into(), 12.into()), "92".into());
+        change.change_file_with_edits(file_id, Arc::new("fn foo() { 92 }".into()), vec![edit]);
+        db.apply_change(change);
+        let reparse = db.file_reparse(file_id).expect("file should have been reparsed");
+        assert_eq!(reparse.tree().syntax().text(), "fn foo() { 92 }");
+        assert_eq!(db.parse(file_id), reparse);
+
+        // Edits which don't produce the new text drop the previous tree.
+        let mut change = AnalysisChange::new();
+        let edit = AtomTextEdit::insert(0.into(), "pub ".into());
+        change.change_file_with_edits(file_id, Arc::new("fn bar() {}".into()), vec![edit]);
+        db.apply_change(change);
+        assert!(db.file_reparse(file_id).is_none());
+        assert_eq!(db.parse(file_id).tree().syntax().text(), "fn bar() {}");
+    }
+}
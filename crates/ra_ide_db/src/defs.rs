@@ -6,13 +6,13 @@
 // FIXME: this badly needs rename/rewrite (matklad, 2020-02-06).
 
 use hir::{
-    HasVisibility, ImplDef, Local, MacroDef, Module, ModuleDef, Name, PathResolution, Semantics,
-    StructField, TypeParam, Visibility,
+    ConstParam, HasVisibility, ImplDef, Label, LifetimeParam, Local, MacroDef, Module, ModuleDef,
+    Name, PathResolution, Semantics, StructField, TypeParam, Visibility,
 };
 use ra_prof::profile;
 use ra_syntax::{
     ast::{self, AstNode},
-    match_ast,
+    match_ast, SyntaxToken,
 };
 use test_utils::tested_by;
 
@@ -27,6 +27,9 @@ pub enum Definition {
     SelfType(ImplDef),
     Local(Local),
     TypeParam(TypeParam),
+    LifetimeParam(LifetimeParam),
+    ConstParam(ConstParam),
+    Label(Label),
 }
 
 impl Definition {
@@ -38,6 +41,9 @@ impl Definition {
             Definition::SelfType(it) => Some(it.module(db)),
             Definition::Local(it) => Some(it.module(db)),
             Definition::TypeParam(it) => Some(it.module(db)),
+            Definition::LifetimeParam(it) => Some(it.module(db)),
+            Definition::ConstParam(it) => Some(it.module(db)),
+            Definition::Label(it) => Some(it.module(db)),
         }
     }
 
@@ -51,6 +57,9 @@ impl Definition {
             Definition::SelfType(_) => None,
             Definition::Local(_) => None,
             Definition::TypeParam(_) => None,
+            Definition::LifetimeParam(_) => None,
+            Definition::ConstParam(_) => None,
+            Definition::Label(_) => None,
         }
     }
 
@@ -76,6 +85,9 @@ impl Definition {
             Definition::SelfType(_) => return None,
             Definition::Local(it) => it.name(db)?,
             Definition::TypeParam(it) => it.name(db),
+            Definition::LifetimeParam(it) => it.name(db),
+            Definition::ConstParam(it) => it.name(db),
+            Definition::Label(it) => it.name(db),
         };
         Some(name)
     }
@@ -175,6 +187,10 @@ fn classify_name_inner(sema: &Semantics<RootDatabase>, name: &ast::Name) -> Opti
                 let def = sema.to_def(&it)?;
                 Some(Definition::TypeParam(def))
             },
+            ast::ConstParam(it) => {
+                let def = sema.to_def(&it)?;
+                Some(Definition::ConstParam(def))
+            },
             _ => None,
         }
     }
@@ -250,8 +266,44 @@ pub fn classify_name_ref(
         }
         PathResolution::Local(local) => Definition::Local(local),
         PathResolution::TypeParam(par) => Definition::TypeParam(par),
+        PathResolution::ConstParam(par) => Definition::ConstParam(par),
         PathResolution::Macro(def) => Definition::Macro(def),
         PathResolution::SelfType(impl_def) => Definition::SelfType(impl_def),
     };
     Some(NameRefClass::Definition(res))
 }
+
+/// Classifies a `LIFETIME` token, which is either a lifetime or a label, both
+/// at the definition and at the use site.
+pub fn classify_lifetime(
+    sema: &Semantics<RootDatabase>,
+    lifetime: &SyntaxToken,
+) -> Option<Definition> {
+    let _p = profile("classify_lifetime");
+
+    let parent = lifetime.parent();
+    match_ast! {
+        match parent {
+            ast::LifetimeParam(it) => {
+                let def = sema.to_def(&it)?;
+                Some(Definition::LifetimeParam(def))
+            },
+            ast::Label(it) => {
+                let def = sema.to_def(&it)?;
+                Some(Definition::Label(def))
+            },
+            ast::BreakExpr(_it) => {
+                let def = sema.resolve_label(lifetime)?;
+                Some(Definition::Label(def))
+            },
+            ast::ContinueExpr(_it) => {
+                let def = sema.resolve_label(lifetime)?;
+                Some(Definition::Label(def))
+            },
+            _ => {
+                let def = sema.resolve_lifetime_param(lifetime)?;
+                Some(Definition::LifetimeParam(def))
+            },
+        }
+    }
+}
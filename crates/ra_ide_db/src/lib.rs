@@ -18,8 +18,9 @@ use hir::db::{AstDatabase, DefDatabase};
 use ra_db::{
     salsa::{self, Database, Durability},
     Canceled, CheckCanceled, CrateId, FileId, FileLoader, FileLoaderDelegate, RelativePath,
-    SourceDatabase, SourceRootId, Upcast,
+    SourceDatabase, SourceDatabaseExt, SourceRootId, Upcast,
 };
+use ra_syntax::{Parse, SourceFile};
 use rustc_hash::FxHashMap;
 
 use crate::{line_index::LineIndex, symbol_index::SymbolsDatabase};
@@ -75,6 +76,9 @@ impl FileLoader for RootDatabase {
     ) -> Option<FileId> {
         FileLoaderDelegate(self).resolve_extern_path(extern_id, relative_path)
     }
+    fn reparsed_file(&self, file_id: FileId) -> Option<Parse<SourceFile>> {
+        self.file_reparse(file_id)
+    }
 }
 
 impl salsa::Database for RootDatabase {
@@ -27,9 +27,26 @@ pub(crate) struct Utf16Char {
 }
 
 impl Utf16Char {
+    /// Length in UTF-8 code units.
     fn len(&self) -> TextUnit {
         self.end - self.start
     }
+
+    /// Length in UTF-16 code units.
+    fn len_utf16(&self) -> usize {
+        utf16_len(self.len())
+    }
+}
+
+/// Length in UTF-16 code units of a char whose UTF-8 encoding is `len_utf8` long.
+pub(crate) fn utf16_len(len_utf8: TextUnit) -> usize {
+    // Only chars outside of the Basic Multilingual Plane, which take four
+    // bytes in UTF-8, need a surrogate pair.
+    if len_utf8 == TextUnit::from_usize(4) {
+        2
+    } else {
+        1
+    }
 }
 
 impl LineIndex {
@@ -106,7 +123,7 @@ impl LineIndex {
             let mut correction = 0;
             for c in utf16_chars {
                 if col >= c.end {
-                    correction += c.len().to_usize() - 1;
+                    correction += c.len().to_usize() - c.len_utf16();
                 } else {
                     // From here on, all utf16 characters come *after* the character we are mapping,
                     // so we don't need to take them into account
@@ -125,7 +142,7 @@ impl LineIndex {
         if let Some(utf16_chars) = self.utf16_lines.get(&line) {
             for c in utf16_chars {
                 if col >= c.start {
-                    col += c.len() - TextUnit::from_usize(1);
+                    col += c.len() - TextUnit::from_usize(c.len_utf16());
                 } else {
                     // From here on, all utf16 characters come *after* the character we are mapping,
                     // so we don't need to take them into account
@@ -236,6 +253,25 @@ const C: char = \"メ メ\";
         assert_eq!(col_index.utf16_to_utf8_col(2, 15), TextUnit::from_usize(15));
     }
 
+    #[test]
+    fn test_surrogate_pair() {
+        let col_index = LineIndex::new(
+            "
+const C: &str = \"\u{1F600}x\";
+",
+        );
+
+        assert_eq!(col_index.utf16_lines[&1], vec![Utf16Char { start: 17.into(), end: 21.into() }]);
+
+        // UTF-8 to UTF-16
+        assert_eq!(col_index.utf8_to_utf16_col(1, 21.into()), 19);
+        assert_eq!(col_index.utf8_to_utf16_col(1, 22.into()), 20);
+
+        // UTF-16 to UTF-8
+        assert_eq!(col_index.utf16_to_utf8_col(1, 19), TextUnit::from_usize(21));
+        assert_eq!(col_index.utf16_to_utf8_col(1, 20), TextUnit::from_usize(22));
+    }
+
     #[test]
     fn test_splitlines() {
         fn r(lo: u32, hi: u32) -> TextRange {
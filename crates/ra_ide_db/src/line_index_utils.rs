@@ -10,7 +10,7 @@
 use ra_syntax::{TextRange, TextUnit};
 use ra_text_edit::{AtomTextEdit, TextEdit};
 
-use crate::line_index::{LineCol, LineIndex, Utf16Char};
+use crate::line_index::{utf16_len, LineCol, LineIndex, Utf16Char};
 
 pub fn translate_offset_with_edit(
     line_index: &LineIndex,
@@ -294,6 +294,6 @@ impl RunningLineCol {
     }
 
     fn adjust_col(&mut self, range: TextRange) {
-        self.col_adjust += range.len() - TextUnit::from(1);
+        self.col_adjust += range.len() - TextUnit::from_usize(utf16_len(range.len()));
     }
 }
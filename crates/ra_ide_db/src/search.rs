@@ -10,12 +10,16 @@ use hir::{DefWithBody, HasSource, Module, ModuleSource, Semantics, Visibility};
 use once_cell::unsync::Lazy;
 use ra_db::{FileId, FileRange, SourceDatabaseExt};
 use ra_prof::profile;
-use ra_syntax::{ast, match_ast, AstNode, TextRange, TextUnit};
+use ra_syntax::{
+    ast, match_ast, AstNode,
+    SyntaxKind::{LABEL, LIFETIME, LIFETIME_PARAM},
+    SyntaxNode, TextRange, TextUnit,
+};
 use rustc_hash::FxHashMap;
 use test_utils::tested_by;
 
 use crate::{
-    defs::{classify_name_ref, Definition, NameRefClass},
+    defs::{classify_lifetime, classify_name_ref, Definition, NameRefClass},
     RootDatabase,
 };
 
@@ -112,8 +116,13 @@ impl Definition {
         let module_src = module.definition_source(db);
         let file_id = module_src.file_id.original_file(db);
 
-        if let Definition::Local(var) = self {
-            let range = match var.parent(db) {
+        let body_owner = match self {
+            Definition::Local(var) => Some(var.parent(db)),
+            Definition::Label(label) => Some(label.parent(db)),
+            _ => None,
+        };
+        if let Some(body_owner) = body_owner {
+            let range = match body_owner {
                 DefWithBody::Function(f) => f.source(db).value.syntax().text_range(),
                 DefWithBody::Const(c) => c.source(db).value.syntax().text_range(),
                 DefWithBody::Static(s) => s.source(db).value.syntax().text_range(),
@@ -123,6 +132,23 @@ impl Definition {
             return SearchScope::new(res);
         }
 
+        let param_src = match self {
+            Definition::LifetimeParam(param) => {
+                Some(param.source(db).map(|it| it.syntax().clone()))
+            }
+            Definition::ConstParam(param) => Some(param.source(db).map(|it| it.syntax().clone())),
+            _ => None,
+        };
+        if let Some(src) = param_src {
+            // Lifetime and const parameters are only visible inside the item
+            // declaring them.
+            let owner = src.value.ancestors().nth(2);
+            let range = owner.map(|it| it.text_range());
+            let mut res = FxHashMap::default();
+            res.insert(src.file_id.original_file(db), range);
+            return SearchScope::new(res);
+        }
+
         let vis = self.visibility(db);
 
         // FIXME:
@@ -213,6 +239,13 @@ impl Definition {
                     continue;
                 }
 
+                if let Definition::LifetimeParam(_) | Definition::Label(_) = self {
+                    if let Some(reference) = self.lifetime_usage(&sema, &tree, file_id, offset) {
+                        refs.push(reference);
+                    }
+                    continue;
+                }
+
                 let name_ref: ast::NameRef =
                     if let Some(name_ref) = sema.find_node_at_offset_with_descend(&tree, offset) {
                         name_ref
@@ -262,6 +295,31 @@ impl Definition {
         }
         refs
     }
+
+    fn lifetime_usage(
+        &self,
+        sema: &Semantics<RootDatabase>,
+        tree: &SyntaxNode,
+        file_id: FileId,
+        offset: TextUnit,
+    ) -> Option<Reference> {
+        let token = tree.token_at_offset(offset).right_biased()?;
+        if token.kind() != LIFETIME || token.text_range().start() != offset {
+            return None;
+        }
+        // The declaration itself is not a usage.
+        if let LIFETIME_PARAM | LABEL = token.parent().kind() {
+            return None;
+        }
+        if &classify_lifetime(sema, &token)? != self {
+            return None;
+        }
+        Some(Reference {
+            file_range: FileRange { file_id, range: token.text_range() },
+            kind: ReferenceKind::Other,
+            access: None,
+        })
+    }
 }
 
 fn reference_access(def: &Definition, name_ref: &ast::NameRef) -> Option<ReferenceAccess> {
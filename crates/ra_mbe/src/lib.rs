@@ -9,6 +9,10 @@ mod syntax_bridge;
 mod tt_iter;
 mod subtree_source;
 
+use std::fmt;
+
+use ra_syntax::SmolStr;
+
 pub use tt::{Delimiter, Punct};
 
 use crate::{
@@ -21,7 +25,7 @@ pub enum ParseError {
     Expected(String),
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ExpandError {
     NoMatchingRule,
     UnexpectedToken,
@@ -29,6 +33,43 @@ pub enum ExpandError {
     ConversionError,
     InvalidRepeat,
     ProcMacroError(tt::ExpansionError),
+    Other(String),
+    /// The error happened while matching the given token of the macro input.
+    AtToken(tt::TokenId, Box<ExpandError>),
+}
+
+impl ExpandError {
+    /// Returns the token of the macro input at which the expansion failed, if
+    /// known.
+    pub fn token(&self) -> Option<tt::TokenId> {
+        match self {
+            ExpandError::AtToken(token, _) => Some(*token),
+            _ => None,
+        }
+    }
+
+    /// Returns the error without the token it happened at.
+    pub fn without_token(&self) -> &ExpandError {
+        match self {
+            ExpandError::AtToken(_, err) => err.without_token(),
+            err => err,
+        }
+    }
+}
+
+impl fmt::Display for ExpandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExpandError::NoMatchingRule => f.write_str("no rules matched"),
+            ExpandError::UnexpectedToken => f.write_str("unexpected token in input"),
+            ExpandError::BindingError(e) => f.write_str(e),
+            ExpandError::ConversionError => f.write_str("could not convert tokens"),
+            ExpandError::InvalidRepeat => f.write_str("invalid repetition in macro definition"),
+            ExpandError::ProcMacroError(e) => write!(f, "proc macro failed: {:?}", e),
+            ExpandError::Other(e) => f.write_str(e),
+            ExpandError::AtToken(_, e) => fmt::Display::fmt(e, f),
+        }
+    }
 }
 
 impl From<tt::ExpansionError> for ExpandError {
@@ -39,7 +80,7 @@ impl From<tt::ExpansionError> for ExpandError {
 
 pub use crate::syntax_bridge::{
     ast_to_token_tree, parse_to_token_tree, syntax_node_to_token_tree, token_tree_to_syntax_node,
-    TokenMap,
+    TokenMap, TokenTextRange,
 };
 
 /// This struct contains AST for a single `macro_rules` definition. What might
@@ -126,6 +167,27 @@ impl Shift {
     }
 }
 
+/// How a single `macro_rules` expansion came about.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExpansionTrace {
+    /// The index of the rule used for the expansion. If no rule matched
+    /// without errors, this is the rule that came closest.
+    pub rule: Option<usize>,
+    pub bindings: Vec<MetaVarBinding>,
+}
+
+/// A fragment of the macro input bound to a metavariable.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MetaVarBinding {
+    pub name: SmolStr,
+    /// The iteration of each enclosing repetition this fragment was bound in;
+    /// empty if the metavariable is not inside a repetition.
+    pub nesting: Vec<usize>,
+    /// The bound tokens; their ids can be mapped back to the input with
+    /// `MacroRules::map_id_up`.
+    pub fragment: tt::TokenTree,
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum Origin {
     Def,
@@ -161,7 +223,31 @@ impl MacroRules {
         // apply shift
         let mut tt = tt.clone();
         self.shift.shift_all(&mut tt);
-        mbe_expander::expand(self, &tt)
+        let ExpandResult(tt, err) = mbe_expander::expand(self, &tt);
+        ExpandResult(tt, err.map(|err| self.unshift_error(err)))
+    }
+
+    /// Like `expand`, but also reports which rule was used and what its
+    /// metavariables were bound to. This is meant for debugging macros.
+    pub fn expand_with_trace(
+        &self,
+        tt: &tt::Subtree,
+    ) -> (ExpandResult<tt::Subtree>, ExpansionTrace) {
+        let mut tt = tt.clone();
+        self.shift.shift_all(&mut tt);
+        let (ExpandResult(tt, err), trace) = mbe_expander::expand_with_trace(self, &tt);
+        (ExpandResult(tt, err.map(|err| self.unshift_error(err))), trace)
+    }
+
+    /// Maps the token of a matching error back to the token of the input.
+    fn unshift_error(&self, err: ExpandError) -> ExpandError {
+        match err {
+            ExpandError::AtToken(token, err) => match self.shift.unshift(token) {
+                Some(token) => ExpandError::AtToken(token, err),
+                None => *err,
+            },
+            err => err,
+        }
     }
 
     pub fn map_id_down(&self, id: tt::TokenId) -> tt::TokenId {
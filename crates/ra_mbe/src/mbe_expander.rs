@@ -8,15 +8,33 @@ mod transcriber;
 use ra_syntax::SmolStr;
 use rustc_hash::FxHashMap;
 
-use crate::{ExpandError, ExpandResult};
+use crate::{ExpandError, ExpandResult, ExpansionTrace, MetaVarBinding};
 
 pub(crate) fn expand(rules: &crate::MacroRules, input: &tt::Subtree) -> ExpandResult<tt::Subtree> {
-    expand_rules(&rules.rules, input)
+    expand_rules(&rules.rules, input).0
 }
 
-fn expand_rules(rules: &[crate::Rule], input: &tt::Subtree) -> ExpandResult<tt::Subtree> {
-    let mut match_: Option<(matcher::Match, &crate::Rule)> = None;
-    for rule in rules {
+pub(crate) fn expand_with_trace(
+    rules: &crate::MacroRules,
+    input: &tt::Subtree,
+) -> (ExpandResult<tt::Subtree>, ExpansionTrace) {
+    let (result, matched) = expand_rules(&rules.rules, input);
+    let trace = match matched {
+        Some((rule, bindings)) => ExpansionTrace { rule: Some(rule), bindings: bindings.flatten() },
+        None => ExpansionTrace::default(),
+    };
+    (result, trace)
+}
+
+/// Expands `input` with the first rule that matches it, or with the rule that
+/// came closest to matching it. Also returns the index of the used rule and the
+/// bindings of its metavariables.
+fn expand_rules(
+    rules: &[crate::Rule],
+    input: &tt::Subtree,
+) -> (ExpandResult<tt::Subtree>, Option<(usize, Bindings)>) {
+    let mut match_: Option<(matcher::Match, usize)> = None;
+    for (idx, rule) in rules.iter().enumerate() {
         let new_match = match matcher::match_(&rule.lhs, input) {
             Ok(m) => m,
             Err(_e) => {
@@ -31,7 +49,7 @@ fn expand_rules(rules: &[crate::Rule], input: &tt::Subtree) -> ExpandResult<tt::
             let ExpandResult(res, transcribe_err) =
                 transcriber::transcribe(&rule.rhs, &new_match.bindings);
             if transcribe_err.is_none() {
-                return ExpandResult::ok(res);
+                return (ExpandResult::ok(res), Some((idx, new_match.bindings)));
             }
         }
         // Use the rule if we matched more tokens, or had fewer errors
@@ -39,19 +57,19 @@ fn expand_rules(rules: &[crate::Rule], input: &tt::Subtree) -> ExpandResult<tt::
             if (new_match.unmatched_tts, new_match.err_count)
                 < (prev_match.unmatched_tts, prev_match.err_count)
             {
-                match_ = Some((new_match, rule));
+                match_ = Some((new_match, idx));
             }
         } else {
-            match_ = Some((new_match, rule));
+            match_ = Some((new_match, idx));
         }
     }
-    if let Some((match_, rule)) = match_ {
+    if let Some((mut match_, idx)) = match_ {
         // if we got here, there was no match without errors
         let ExpandResult(result, transcribe_err) =
-            transcriber::transcribe(&rule.rhs, &match_.bindings);
-        ExpandResult(result, match_.err.or(transcribe_err))
+            transcriber::transcribe(&rules[idx].rhs, &match_.bindings);
+        (ExpandResult(result, match_.take_err().or(transcribe_err)), Some((idx, match_.bindings)))
     } else {
-        ExpandResult(tt::Subtree::default(), Some(ExpandError::NoMatchingRule))
+        (ExpandResult(tt::Subtree::default(), Some(ExpandError::NoMatchingRule)), None)
     }
 }
 
@@ -105,6 +123,42 @@ struct Bindings {
     inner: FxHashMap<SmolStr, Binding>,
 }
 
+impl Bindings {
+    /// Lists the bound fragments, ordered by metavariable name and position.
+    fn flatten(self) -> Vec<MetaVarBinding> {
+        let mut res = Vec::new();
+        for (name, binding) in self.inner {
+            flatten_binding(&mut res, &name, &mut Vec::new(), binding);
+        }
+        res.sort_by(|a, b| (&a.name, &a.nesting).cmp(&(&b.name, &b.nesting)));
+        return res;
+
+        fn flatten_binding(
+            acc: &mut Vec<MetaVarBinding>,
+            name: &SmolStr,
+            nesting: &mut Vec<usize>,
+            binding: Binding,
+        ) {
+            match binding {
+                Binding::Fragment(Fragment::Tokens(fragment))
+                | Binding::Fragment(Fragment::Ast(fragment)) => acc.push(MetaVarBinding {
+                    name: name.clone(),
+                    nesting: nesting.clone(),
+                    fragment,
+                }),
+                Binding::Nested(bindings) => {
+                    for (idx, binding) in bindings.into_iter().enumerate() {
+                        nesting.push(idx);
+                        flatten_binding(acc, name, nesting, binding);
+                        nesting.pop();
+                    }
+                }
+                Binding::Empty => (),
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 enum Binding {
     Fragment(Fragment),
@@ -146,6 +200,30 @@ mod tests {
         assert_eq!(expand_first(&create_rules(&format_macro(macro_body)), invocation).1, Some(err));
     }
 
+    #[test]
+    fn test_expand_trace() {
+        let rules = create_rules(&format_macro(
+            "
+            (fn $i:ident) => ();
+            ($($i:ident = $e:expr),*) => ();
+            ",
+        ));
+        let source_file = ast::SourceFile::parse("foo!(a = 1, b = 2 + 2)").ok().unwrap();
+        let macro_invocation =
+            source_file.syntax().descendants().find_map(ast::MacroCall::cast).unwrap();
+        let (invocation_tt, _) =
+            ast_to_token_tree(&macro_invocation.token_tree().unwrap()).unwrap();
+
+        let (_, trace) = expand_with_trace(&rules, &invocation_tt);
+        assert_eq!(trace.rule, Some(1));
+        let bindings = trace
+            .bindings
+            .iter()
+            .map(|it| format!("{}{:?} = {}", it.name, it.nesting, it.fragment))
+            .collect::<Vec<_>>();
+        assert_eq!(bindings, vec!["e[0] = 1", "e[1] = 2 + 2", "i[0] = a", "i[1] = b"]);
+    }
+
     fn format_macro(macro_body: &str) -> String {
         format!(
             "
@@ -175,6 +253,6 @@ mod tests {
         let (invocation_tt, _) =
             ast_to_token_tree(&macro_invocation.token_tree().unwrap()).unwrap();
 
-        expand_rules(&rules.rules, &invocation_tt)
+        expand_rules(&rules.rules, &invocation_tt).0
     }
 }
@@ -64,6 +64,8 @@ pub(super) struct Match {
     pub bindings: Bindings,
     /// We currently just keep the first error and count the rest to compare matches.
     pub err: Option<ExpandError>,
+    /// The input token at which `err` happened, if known.
+    pub err_token: Option<tt::TokenId>,
     pub err_count: usize,
     /// How many top-level token trees were left to match.
     pub unmatched_tts: usize,
@@ -75,6 +77,36 @@ impl Match {
         self.err = prev_err.or(Some(err));
         self.err_count += 1;
     }
+
+    /// Remembers the next token of `src` as the location of the first error,
+    /// as long as there is none yet.
+    fn mark(&mut self, src: &TtIter) {
+        if self.err.is_none() {
+            self.err_token =
+                src.peek_n(0).and_then(first_token).filter(|&it| it != tt::TokenId::unspecified());
+        }
+    }
+
+    /// Takes the first error, together with the token it happened at.
+    pub fn take_err(&mut self) -> Option<ExpandError> {
+        let err = self.err.take()?;
+        Some(match self.err_token {
+            Some(token) => ExpandError::AtToken(token, Box::new(err)),
+            None => err,
+        })
+    }
+}
+
+fn first_token(tt: &tt::TokenTree) -> Option<tt::TokenId> {
+    match tt {
+        tt::TokenTree::Leaf(tt::Leaf::Ident(it)) => Some(it.id),
+        tt::TokenTree::Leaf(tt::Leaf::Punct(it)) => Some(it.id),
+        tt::TokenTree::Leaf(tt::Leaf::Literal(it)) => Some(it.id),
+        tt::TokenTree::Subtree(subtree) => match subtree.delimiter {
+            Some(delimiter) => Some(delimiter.id),
+            None => subtree.token_trees.first().and_then(first_token),
+        },
+    }
 }
 
 // General note: These functions have two channels to return errors, a `Result`
@@ -91,9 +123,10 @@ pub(super) fn match_(pattern: &tt::Subtree, src: &tt::Subtree) -> Result<Match,
 
     match_subtree(&mut res, pattern, &mut src)?;
 
-    if src.len() > 0 {
+    res.mark(&src);
+    if let Some(first) = src.peek_n(0) {
         res.unmatched_tts += src.len();
-        res.add_err(err!("leftover tokens"));
+        res.add_err(err!("leftover tokens, starting at `{}`", first));
     }
 
     Ok(res)
@@ -105,6 +138,7 @@ fn match_subtree(
     src: &mut TtIter,
 ) -> Result<(), ExpandError> {
     for op in parse_pattern(pattern) {
+        res.mark(src);
         match op? {
             Op::TokenTree(tt::TokenTree::Leaf(lhs)) => {
                 let rhs = match src.expect_leaf() {
@@ -128,7 +162,7 @@ fn match_subtree(
                         tt::Leaf::Literal(tt::Literal { text: rhs, .. }),
                     ) if lhs == rhs => (),
                     _ => {
-                        res.add_err(ExpandError::UnexpectedToken);
+                        res.add_err(err!("expected `{}`, found `{}`", lhs, rhs));
                     }
                 }
             }
@@ -146,6 +180,7 @@ fn match_subtree(
                 }
                 let mut src = TtIter::new(rhs);
                 match_subtree(res, lhs, &mut src)?;
+                res.mark(&src);
                 if src.len() > 0 {
                     res.add_err(err!("leftover tokens"));
                 }
@@ -388,6 +423,7 @@ pub(super) fn match_repeat(
 
     match (kind, counter) {
         (RepeatKind::OneOrMore, 0) => {
+            res.mark(src);
             res.add_err(ExpandError::UnexpectedToken);
         }
         (_, 0) => {
@@ -1455,8 +1455,20 @@ impl MacroFixture {
         self.rules.expand(&invocation_tt).result()
     }
 
-    fn assert_expand_err(&self, invocation: &str, err: &ExpandError) {
-        assert_eq!(self.try_expand_tt(invocation).as_ref(), Err(err));
+    /// Checks that expanding `invocation` fails with `err`, reported at the
+    /// input token `at`.
+    fn assert_expand_err(&self, invocation: &str, err: &ExpandError, at: &str) {
+        let source_file = ast::SourceFile::parse(invocation).tree();
+        let token_tree = source_file.syntax().descendants().find_map(ast::TokenTree::cast).unwrap();
+        let (invocation_tt, token_map) = ast_to_token_tree(&token_tree).unwrap();
+
+        let actual = self.rules.expand(&invocation_tt).result().unwrap_err();
+        assert_eq!(actual.without_token(), err);
+        let range = match actual.token().and_then(|it| token_map.range_by_token(it)) {
+            Some(TokenTextRange::Token(it)) | Some(TokenTextRange::Delimiter(it, _)) => it,
+            None => panic!("no token for error {:?}", actual),
+        };
+        assert_eq!(&token_tree.syntax().text().to_string()[range], at);
     }
 
     fn expand_items(&self, invocation: &str) -> SyntaxNode {
@@ -1694,5 +1706,24 @@ fn test_expand_bad_literal() {
         macro_rules! foo { ($i:literal) => {}; }
     "#,
     )
-    .assert_expand_err(r#"foo!(&k");"#, &ExpandError::BindingError("".into()));
+    .assert_expand_err(r#"foo!(&k");"#, &ExpandError::BindingError("".into()), "&");
+}
+
+#[test]
+fn test_expand_err_reports_failed_matcher() {
+    let macro_fixture = parse_macro(
+        r#"
+        macro_rules! foo { (a ; b) => {}; }
+    "#,
+    );
+    macro_fixture.assert_expand_err(
+        "foo!(a , b);",
+        &ExpandError::BindingError("expected `;`, found `,`".into()),
+        ",",
+    );
+    macro_fixture.assert_expand_err(
+        "foo!(a ; b c);",
+        &ExpandError::BindingError("leftover tokens, starting at `c`".into()),
+        "c",
+    );
 }
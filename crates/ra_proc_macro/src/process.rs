@@ -0,0 +1,406 @@
+//! Handle process life-time and message passing for proc-macro client
+
+use crossbeam_channel::{bounded, unbounded, Receiver, RecvTimeoutError, Sender};
+use ra_tt::{ExpansionError, Subtree};
+
+use crate::msg::{Message, Request, Response, PROTOCOL_VERSION};
+use crate::rpc::{ExpansionResult, ExpansionTask, ListMacrosResult, ListMacrosTask, ProcMacroKind};
+
+use std::{
+    convert::{TryFrom, TryInto},
+    ffi::{OsStr, OsString},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    sync::{Arc, Mutex, Weak},
+    time::Duration,
+};
+
+/// How long we wait for the server to answer a single request.
+///
+/// A proc macro which loops forever would otherwise block the whole analysis,
+/// so after this timeout the server process is killed and restarted. Time
+/// spent waiting in the queue behind other requests doesn't count.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The currently running server process, shared between the client thread
+/// (which owns its stdio) and the requesting threads (which may need to kill
+/// it on timeout).
+type ChildSlot = Arc<Mutex<ChildState>>;
+
+#[derive(Debug, Default)]
+struct ChildState {
+    child: Option<Child>,
+    /// The number of the request the server is working on, if any. A request
+    /// which timed out must not kill the server once it serves the next one.
+    in_flight: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ProcMacroProcessSrv {
+    inner: Option<Weak<Sender<Task>>>,
+    child: ChildSlot,
+    timeout: Duration,
+}
+
+#[derive(Debug)]
+pub(crate) struct ProcMacroProcessThread {
+    // XXX: drop order is significant
+    sender: Arc<Sender<Task>>,
+    handle: jod_thread::JoinHandle<()>,
+}
+
+struct Task {
+    req: Request,
+    /// Notified with the number of the request when the client thread sends
+    /// it to the server.
+    started_tx: Sender<u64>,
+    result_tx: Sender<Option<Response>>,
+}
+
+impl ProcMacroProcessSrv {
+    pub fn run(
+        process_path: PathBuf,
+        args: impl IntoIterator<Item = impl AsRef<OsStr>>,
+    ) -> io::Result<(ProcMacroProcessThread, ProcMacroProcessSrv)> {
+        ProcMacroProcessSrv::run_with_timeout(process_path, args, REQUEST_TIMEOUT)
+    }
+
+    fn run_with_timeout(
+        process_path: PathBuf,
+        args: impl IntoIterator<Item = impl AsRef<OsStr>>,
+        timeout: Duration,
+    ) -> io::Result<(ProcMacroProcessThread, ProcMacroProcessSrv)> {
+        let args = args.into_iter().map(|it| it.as_ref().to_os_string()).collect::<Vec<_>>();
+        let child = ChildSlot::default();
+        let process = Process::run(process_path, args, child.clone())?;
+
+        let (task_tx, task_rx) = unbounded();
+        let handle = jod_thread::spawn(move || client_loop(task_rx, process));
+
+        let task_tx = Arc::new(task_tx);
+        let srv = ProcMacroProcessSrv { inner: Some(Arc::downgrade(&task_tx)), child, timeout };
+        let thread = ProcMacroProcessThread { handle, sender: task_tx };
+
+        Ok((thread, srv))
+    }
+
+    pub fn find_proc_macros(
+        &self,
+        dylib_path: &Path,
+    ) -> Result<Vec<(String, ProcMacroKind)>, ExpansionError> {
+        let task = ListMacrosTask { lib: dylib_path.to_path_buf() };
+
+        let result: ListMacrosResult = self.send_task(Request::ListMacro(task))?;
+        Ok(result.macros)
+    }
+
+    pub fn expand(
+        &self,
+        dylib_path: &Path,
+        macro_name: &str,
+        subtree: &Subtree,
+        attr: Option<&Subtree>,
+    ) -> Result<Subtree, ExpansionError> {
+        let task = ExpansionTask {
+            macro_body: subtree.clone(),
+            macro_name: macro_name.to_string(),
+            attributes: attr.cloned(),
+            lib: dylib_path.to_path_buf(),
+        };
+
+        let result: ExpansionResult = self.send_task(Request::ExpansionMacro(task))?;
+        Ok(result.expansion)
+    }
+
+    pub fn send_task<R>(&self, req: Request) -> Result<R, ExpansionError>
+    where
+        R: TryFrom<Response, Error = &'static str>,
+    {
+        let sender = match &self.inner {
+            None => return Err(ExpansionError::Unknown("No sender is found.".to_string())),
+            Some(it) => it,
+        };
+        let sender = match sender.upgrade() {
+            None => {
+                return Err(ExpansionError::Unknown("Proc macro process is closed.".to_string()))
+            }
+            Some(it) => it,
+        };
+
+        // The server handles one request at a time, so we queue up behind the
+        // requests of other threads, and only start the clock once the client
+        // thread picks ours up.
+        let (started_tx, started_rx) = bounded(0);
+        let (result_tx, result_rx) = bounded(0);
+        let request = match sender.send(Task { req, started_tx, result_tx }) {
+            Ok(()) => started_rx.recv().ok(),
+            Err(_) => None,
+        };
+        let request = match request {
+            Some(it) => it,
+            None => {
+                return Err(ExpansionError::Unknown("Proc macro thread is closed.".to_string()))
+            }
+        };
+
+        let res = match result_rx.recv_timeout(self.timeout) {
+            Ok(it) => it,
+            Err(RecvTimeoutError::Timeout) => {
+                self.kill_process(request);
+                return Err(ExpansionError::Timeout);
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(ExpansionError::Unknown("Proc macro thread is closed.".to_string()))
+            }
+        };
+
+        match res {
+            Some(Response::Error(err)) => Err(ExpansionError::ExpansionError(err.message)),
+            Some(res) => res.try_into().map_err(|err| {
+                ExpansionError::Unknown(format!("Fail to get response, reason : {:#?} ", err))
+            }),
+            None => Err(ExpansionError::Unknown("Proc macro server crashed".to_string())),
+        }
+    }
+
+    /// Kills a server hanging on `request`, which unblocks the client thread:
+    /// it sees the closed pipe, reaps the process and starts a fresh one for
+    /// the next request.
+    fn kill_process(&self, request: u64) {
+        let mut state = self.child.lock().unwrap();
+        if state.in_flight != Some(request) {
+            return;
+        }
+        if let Some(child) = state.child.as_mut() {
+            log::error!("Proc macro server timed out, killing it");
+            let _ = child.kill();
+        }
+    }
+}
+
+fn client_loop(task_rx: Receiver<Task>, mut process: Process) {
+    let (mut stdin, mut stdout) = match process.stdio() {
+        None => return,
+        Some(it) => it,
+    };
+
+    for (request, task) in (0..).zip(task_rx) {
+        let Task { req, started_tx, result_tx } = task;
+        process.set_in_flight(Some(request));
+        let _ = started_tx.send(request);
+
+        // EOF or a broken pipe means that the server died, or was killed
+        // after a timeout.
+        let res = send_request(&mut stdin, &mut stdout, req).ok().flatten();
+        process.set_in_flight(None);
+        let crashed = res.is_none();
+
+        // The requester may have given up waiting already.
+        let _ = result_tx.send(res);
+
+        if crashed {
+            if let Err(err) = process.restart() {
+                log::error!("Failed to restart proc macro server: {}", err);
+                break;
+            }
+            let stdio = match process.stdio() {
+                None => break,
+                Some(it) => it,
+            };
+            stdin = stdio.0;
+            stdout = stdio.1;
+        }
+    }
+}
+
+struct Process {
+    path: PathBuf,
+    args: Vec<OsString>,
+    child: ChildSlot,
+    stdio: Option<(ChildStdin, BufReader<ChildStdout>)>,
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.lock().unwrap().child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Process {
+    fn run(path: PathBuf, args: Vec<OsString>, child: ChildSlot) -> io::Result<Process> {
+        let mut process = Process { path, args, child, stdio: None };
+        process.restart()?;
+        Ok(process)
+    }
+
+    fn restart(&mut self) -> io::Result<()> {
+        let mut state = self.child.lock().unwrap();
+        if let Some(mut child) = state.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        self.stdio = None;
+
+        let mut child = mk_child(&self.path, &self.args)?;
+        let mut stdio = match (child.stdin.take(), child.stdout.take()) {
+            (Some(stdin), Some(stdout)) => (stdin, BufReader::new(stdout)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe, "no stdio"));
+            }
+        };
+        state.child = Some(child);
+        drop(state);
+
+        check_version(&mut stdio.0, &mut stdio.1)?;
+        self.stdio = Some(stdio);
+        Ok(())
+    }
+
+    fn set_in_flight(&self, request: Option<u64>) {
+        self.child.lock().unwrap().in_flight = request;
+    }
+
+    fn stdio(&mut self) -> Option<(impl Write, impl BufRead)> {
+        self.stdio.take()
+    }
+}
+
+fn mk_child(path: &Path, args: impl IntoIterator<Item = impl AsRef<OsStr>>) -> io::Result<Child> {
+    Command::new(path)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+}
+
+/// Makes sure that both sides of the pipe speak the same protocol.
+fn check_version(writer: &mut impl Write, reader: &mut impl BufRead) -> io::Result<()> {
+    let version: Option<u32> =
+        send_request(writer, reader, Request::Version)?.and_then(|it| it.try_into().ok());
+    match version {
+        Some(PROTOCOL_VERSION) => Ok(()),
+        Some(version) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "proc macro server speaks protocol version {}, expected {}",
+                version, PROTOCOL_VERSION
+            ),
+        )),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "proc macro server did not report its protocol version",
+        )),
+    }
+}
+
+fn send_request(
+    writer: &mut impl Write,
+    reader: &mut impl BufRead,
+    req: Request,
+) -> io::Result<Option<Response>> {
+    req.write(writer)?;
+    Response::read(reader)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    /// Spawns a shell script which answers the version handshake with
+    /// `version`, and then handles `ListMacro` requests by the `lib` path:
+    /// `/hang` never gets an answer, `/crash` kills the server, `/slow` is
+    /// answered after a short delay and everything else right away.
+    fn fake_server(
+        version: u32,
+        timeout: Duration,
+    ) -> io::Result<(ProcMacroProcessThread, ProcMacroProcessSrv)> {
+        let script = format!(
+            r#"
+read line
+echo '{{"Version":{}}}'
+while read line; do
+    case "$line" in
+        *'"/hang"'*) read never ;;
+        *'"/crash"'*) exit 1 ;;
+        *'"/slow"'*) sleep 0.2; echo '{{"ListMacro":{{"macros":[]}}}}' ;;
+        *) echo '{{"ListMacro":{{"macros":[["Foo","CustomDerive"]]}}}}' ;;
+    esac
+done
+"#,
+            version
+        );
+        ProcMacroProcessSrv::run_with_timeout("sh".into(), &["-c".to_string(), script], timeout)
+    }
+
+    fn list_foo(srv: &ProcMacroProcessSrv) {
+        let macros = srv.find_proc_macros(Path::new("/lib")).unwrap();
+        assert_eq!(macros, vec![("Foo".to_string(), ProcMacroKind::CustomDerive)]);
+    }
+
+    #[test]
+    fn rejects_server_with_other_protocol_version() {
+        let err = fake_server(PROTOCOL_VERSION + 1, REQUEST_TIMEOUT).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let (_thread, srv) = fake_server(PROTOCOL_VERSION, REQUEST_TIMEOUT).unwrap();
+        list_foo(&srv);
+    }
+
+    #[test]
+    fn kills_and_restarts_hanging_server() {
+        let (_thread, srv) = fake_server(PROTOCOL_VERSION, Duration::from_millis(500)).unwrap();
+        match srv.find_proc_macros(Path::new("/hang")) {
+            Err(ExpansionError::Timeout) => (),
+            res => panic!("expected a timeout, got {:?}", res),
+        }
+        list_foo(&srv);
+    }
+
+    #[test]
+    fn restarts_crashed_server() {
+        let (_thread, srv) = fake_server(PROTOCOL_VERSION, REQUEST_TIMEOUT).unwrap();
+        match srv.find_proc_macros(Path::new("/crash")) {
+            Err(ExpansionError::Unknown(_)) => (),
+            res => panic!("expected a crash, got {:?}", res),
+        }
+        list_foo(&srv);
+    }
+
+    #[test]
+    fn timed_out_request_does_not_kill_server_serving_the_next_one() {
+        let (_thread, srv) = fake_server(PROTOCOL_VERSION, REQUEST_TIMEOUT).unwrap();
+        // This is request 0.
+        list_foo(&srv);
+        let srv = Arc::new(srv);
+        let slow = {
+            let srv = srv.clone();
+            std::thread::spawn(move || srv.find_proc_macros(Path::new("/slow")))
+        };
+        std::thread::sleep(Duration::from_millis(50));
+        srv.kill_process(0);
+        assert_eq!(slow.join().unwrap().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn queues_requests_behind_busy_server() {
+        // Each request fits in the timeout, but all of them together don't.
+        let (_thread, srv) = fake_server(PROTOCOL_VERSION, Duration::from_millis(500)).unwrap();
+        let srv = Arc::new(srv);
+        let handles: Vec<_> = (0..5)
+            .map(|_| {
+                let srv = srv.clone();
+                std::thread::spawn(move || srv.find_proc_macros(Path::new("/slow")))
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap().unwrap(), vec![]);
+        }
+    }
+}
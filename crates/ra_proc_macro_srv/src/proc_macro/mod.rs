@@ -0,0 +1,72 @@
+//! lib-proc-macro main module
+//!
+//! Copy from https://github.com/rust-lang/rust/blob/1.43.0/src/libproc_macro/lib.rs
+//! augmented with removing unstable features
+//!
+//! Only the types which appear in the bridge protocol are kept here: the
+//! user-facing `proc_macro` API lives in the copy compiled into each dylib.
+
+// NOTE: Because we just copy the bridge module from rustc for ABI compatibility,
+// there are some unused items inside it. We suppress these warnings here.
+#[doc(hidden)]
+#[allow(unused_macros)]
+#[allow(unused_variables)]
+#[allow(dead_code)]
+pub mod bridge;
+
+/// The main type provided by `proc_macro`, representing an abstract stream of
+/// tokens.
+///
+/// We never look inside of it: it only appears in the signatures of the
+/// client functions stored in `bridge::client::ProcMacro`.
+#[repr(C)]
+#[allow(dead_code)]
+pub struct TokenStream(bridge::client::TokenStream);
+
+/// Describes how a sequence of token trees is delimited.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Delimiter {
+    /// `( ... )`
+    Parenthesis,
+    /// `{ ... }`
+    Brace,
+    /// `[ ... ]`
+    Bracket,
+    /// `Ø ... Ø`
+    /// An implicit delimiter, that may, for example, appear around tokens coming from a
+    /// "macro variable" `$var`.
+    None,
+}
+
+/// Whether an `Op` is either followed immediately by another `Op` or followed by whitespace.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Spacing {
+    /// e.g., `+` is `Alone` in `+ =`, `+ident` or `+()`.
+    Alone,
+    /// e.g., `+` is `Joint` in `+=` or `'#`.
+    /// Additionally, single quote `'` can join with identifiers to form lifetimes `'ident`.
+    Joint,
+}
+
+/// A line-column pair representing the start or end of a `Span`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LineColumn {
+    /// The 1-indexed line in the source file on which the span starts or ends (inclusive).
+    pub line: usize,
+    /// The 0-indexed column (in UTF-8 characters) in the source file on which
+    /// the span starts or ends (inclusive).
+    pub column: usize,
+}
+
+/// An enum representing a diagnostic level.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Level {
+    /// An error.
+    Error,
+    /// A warning.
+    Warning,
+    /// A note.
+    Note,
+    /// A help message.
+    Help,
+}
@@ -262,6 +262,25 @@ impl ast::UseItem {
     pub fn remove(&self) -> SyntaxRewriter<'static> {
         let mut res = SyntaxRewriter::default();
         res.delete(self.syntax());
+        // Prefer removing the line the item starts on, so that the indentation
+        // of the next line is kept as is.
+        let prev_ws = self
+            .syntax()
+            .prev_sibling_or_token()
+            .and_then(|it| it.into_token())
+            .and_then(ast::Whitespace::cast);
+        if let Some(prev_ws) = prev_ws {
+            let ws_text = prev_ws.syntax().text();
+            if let Some(idx) = ws_text.rfind('\n') {
+                let rest = &ws_text[..idx];
+                if rest.is_empty() {
+                    res.delete(prev_ws.syntax())
+                } else {
+                    res.replace(prev_ws.syntax(), &make::tokens::whitespace(rest));
+                }
+                return res;
+            }
+        }
         let next_ws = self
             .syntax()
             .next_sibling_or_token()
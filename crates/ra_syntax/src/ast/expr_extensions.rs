@@ -308,7 +308,7 @@ pub enum LiteralKind {
     Byte,
     IntNumber { suffix: Option<SmolStr> },
     FloatNumber { suffix: Option<SmolStr> },
-    Bool,
+    Bool(bool),
 }
 
 impl ast::Literal {
@@ -355,13 +355,34 @@ impl ast::Literal {
                 LiteralKind::FloatNumber { suffix: Self::find_suffix(&text, &FLOAT_SUFFIXES) }
             }
             STRING | RAW_STRING => LiteralKind::String,
-            T![true] | T![false] => LiteralKind::Bool,
+            T![true] => LiteralKind::Bool(true),
+            T![false] => LiteralKind::Bool(false),
             BYTE_STRING | RAW_BYTE_STRING => LiteralKind::ByteString,
             CHAR => LiteralKind::Char,
             BYTE => LiteralKind::Byte,
             _ => unreachable!(),
         }
     }
+
+    /// The value of an integer literal, or `None` if this is not an integer
+    /// literal or if its value does not fit into an `u128`.
+    pub fn int_value(&self) -> Option<u128> {
+        let suffix = match self.kind() {
+            LiteralKind::IntNumber { suffix } => suffix,
+            _ => return None,
+        };
+        let token = self.token();
+        let text = token.text().as_str();
+        let text = &text[..text.len() - suffix.map_or(0, |it| it.len())];
+        let (radix, digits) = match text.get(..2) {
+            Some("0x") => (16, &text[2..]),
+            Some("0o") => (8, &text[2..]),
+            Some("0b") => (2, &text[2..]),
+            _ => (10, text),
+        };
+        let digits = digits.replace('_', "");
+        u128::from_str_radix(&digits, radix).ok()
+    }
 }
 
 impl ast::BlockExpr {
@@ -382,6 +403,18 @@ impl ast::BlockExpr {
             _ => true,
         }
     }
+
+    /// The label of a labeled block, like `'a` in `'a: { break 'a 92; }`.
+    pub fn label(&self) -> Option<ast::Label> {
+        child_opt(self)
+    }
+
+    pub fn unsafe_token(&self) -> Option<SyntaxToken> {
+        self.syntax()
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == T![unsafe])
+    }
 }
 
 #[test]
@@ -391,6 +424,24 @@ fn test_literal_with_attr() {
     assert_eq!(lit.token().text(), r#""Hello""#);
 }
 
+#[test]
+fn test_literal_int_value() {
+    let parse = ast::SourceFile::parse(
+        r#"const _: [u64; 6] = [92, 1_000u64, 0xFF, 0o17, 0b101, 1f64]; const _: &str = "92";"#,
+    );
+    let values = parse
+        .tree()
+        .syntax()
+        .descendants()
+        .filter_map(ast::Literal::cast)
+        .map(|lit| lit.int_value())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        values,
+        vec![Some(6), Some(92), Some(1000), Some(255), Some(15), Some(5), None, None]
+    );
+}
+
 impl ast::RecordField {
     pub fn parent_record_lit(&self) -> ast::RecordLit {
         self.syntax().ancestors().find_map(ast::RecordLit::cast).unwrap()
@@ -259,6 +259,16 @@ impl ast::FnDef {
     pub fn is_async(&self) -> bool {
         self.syntax().children_with_tokens().any(|it| it.kind() == T![async])
     }
+
+    pub fn is_unsafe(&self) -> bool {
+        self.syntax().children_with_tokens().any(|it| it.kind() == T![unsafe])
+    }
+}
+
+impl ast::StaticDef {
+    pub fn is_mut(&self) -> bool {
+        self.syntax().children_with_tokens().any(|it| it.kind() == T![mut])
+    }
 }
 
 impl ast::LetStmt {
@@ -421,6 +431,33 @@ impl ast::LifetimeParam {
     }
 }
 
+impl ast::Label {
+    pub fn lifetime_token(&self) -> Option<SyntaxToken> {
+        self.syntax()
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == LIFETIME)
+    }
+}
+
+impl ast::BreakExpr {
+    pub fn lifetime_token(&self) -> Option<SyntaxToken> {
+        self.syntax()
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == LIFETIME)
+    }
+}
+
+impl ast::ContinueExpr {
+    pub fn lifetime_token(&self) -> Option<SyntaxToken> {
+        self.syntax()
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == LIFETIME)
+    }
+}
+
 impl ast::TypeParam {
     pub fn colon_token(&self) -> Option<SyntaxToken> {
         self.syntax()
@@ -2951,6 +2951,9 @@ impl TypeParamList {
     pub fn lifetime_params(&self) -> AstChildren<LifetimeParam> {
         AstChildren::new(&self.syntax)
     }
+    pub fn const_params(&self) -> AstChildren<ConstParam> {
+        AstChildren::new(&self.syntax)
+    }
 }
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct TypeParam {
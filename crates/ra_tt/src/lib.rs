@@ -188,8 +188,14 @@ impl Subtree {
 
 pub mod buffer;
 
-#[derive(Debug, PartialEq, Eq)]
-pub enum ExpansionError {}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpansionError {
+    IOError(String),
+    JsonError(String),
+    Unknown(String),
+    ExpansionError(String),
+    Timeout,
+}
 
 pub trait TokenExpander: Debug + Send + Sync + RefUnwindSafe {
     fn expand(&self, subtree: &Subtree, attrs: Option<&Subtree>)
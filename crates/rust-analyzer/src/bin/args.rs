@@ -35,6 +35,7 @@ pub(crate) enum Command {
         what: BenchWhat,
         load_output_dirs: bool,
     },
+    ProcMacro,
     RunServer,
     Version,
 }
@@ -209,6 +210,7 @@ ARGS:
                 let load_output_dirs = matches.contains("--load-output-dirs");
                 Command::Bench { path, what, load_output_dirs }
             }
+            "proc-macro" => Command::ProcMacro,
             _ => {
                 eprintln!(
                     "\
@@ -225,6 +227,7 @@ SUBCOMMANDS:
     analysis-stats
     highlight
     parse
+    proc-macro
     symbols"
                 );
                 return Ok(Err(HelpPrinted));
@@ -40,6 +40,7 @@ fn main() -> Result<()> {
             cli::analysis_bench(args.verbosity, path.as_ref(), what, load_output_dirs)?
         }
 
+        args::Command::ProcMacro => run_proc_macro_srv()?,
         args::Command::RunServer => run_server()?,
         args::Command::Version => println!("rust-analyzer {}", env!("REV")),
     }
@@ -53,6 +54,11 @@ fn setup_logging() -> Result<()> {
     Ok(())
 }
 
+fn run_proc_macro_srv() -> Result<()> {
+    ra_proc_macro_srv::cli::run()?;
+    Ok(())
+}
+
 fn run_server() -> Result<()> {
     log::info!("lifecycle: server started");
 
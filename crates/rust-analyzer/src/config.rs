@@ -38,6 +38,10 @@ pub struct ServerConfig {
     pub inlay_hints_chaining: bool,
     pub inlay_hints_max_length: Option<usize>,
 
+    /// Report type mismatches found by type inference.
+    #[serde(deserialize_with = "nullable_bool_false")]
+    pub diagnostics_type_mismatch: bool,
+
     pub cargo_watch_enable: bool,
     pub cargo_watch_args: Vec<String>,
     pub cargo_watch_command: String,
@@ -57,6 +61,13 @@ pub struct ServerConfig {
 
     /// Enabled if the vscode_lldb extension is available.
     pub vscode_lldb: bool,
+
+    /// Expand procedural macros by running them in a separate
+    /// `rust-analyzer proc-macro` server process.
+    ///
+    /// Defaults to `false`
+    #[serde(deserialize_with = "nullable_bool_false")]
+    pub proc_macro_enable: bool,
 }
 
 impl Default for ServerConfig {
@@ -70,6 +81,7 @@ impl Default for ServerConfig {
             inlay_hints_parameter: true,
             inlay_hints_chaining: true,
             inlay_hints_max_length: None,
+            diagnostics_type_mismatch: false,
             cargo_watch_enable: true,
             cargo_watch_args: Vec::new(),
             cargo_watch_command: "check".to_string(),
@@ -79,6 +91,7 @@ impl Default for ServerConfig {
             cargo_features: Default::default(),
             rustfmt_args: Vec::new(),
             vscode_lldb: false,
+            proc_macro_enable: false,
         }
     }
 }
@@ -5,8 +5,9 @@ use lsp_types::{
     self, CreateFile, DiagnosticSeverity, DocumentChangeOperation, DocumentChanges, Documentation,
     Location, LocationLink, MarkupContent, MarkupKind, ParameterInformation, ParameterLabel,
     Position, Range, RenameFile, ResourceOp, SemanticTokenModifier, SemanticTokenType,
-    SignatureInformation, SymbolKind, TextDocumentEdit, TextDocumentIdentifier, TextDocumentItem,
-    TextDocumentPositionParams, Url, VersionedTextDocumentIdentifier, WorkspaceEdit,
+    SignatureInformation, SymbolKind, TextDocumentContentChangeEvent, TextDocumentEdit,
+    TextDocumentIdentifier, TextDocumentItem, TextDocumentPositionParams, Url,
+    VersionedTextDocumentIdentifier, WorkspaceEdit,
 };
 use ra_ide::{
     translate_offset_with_edit, CompletionItem, CompletionItemKind, FileId, FilePosition,
@@ -210,6 +211,72 @@ impl ConvWith<&LineIndex> for Range {
     }
 }
 
+/// Applies the changes of a `textDocument/didChange` notification to `text`.
+///
+/// Returns the edits corresponding to the changes, or `None` if one of them
+/// replaced the whole document.
+pub fn apply_document_changes(
+    text: &mut String,
+    changes: Vec<TextDocumentContentChangeEvent>,
+) -> Option<Vec<AtomTextEdit>> {
+    let mut edits = Some(Vec::new());
+    let mut line_index = LineIndex::new(text);
+    // Each change is relative to the text after the previous one, but the
+    // line index stays valid before the first line touched by the previous
+    // change. Clients usually send changes from the bottom of the document up,
+    // so we rarely need to rebuild the index.
+    let mut valid_lines = u64::max_value();
+    for change in changes {
+        let mut new_text = change.text;
+        if new_text.contains('\r') {
+            new_text = new_text.replace("\r\n", "\n");
+        }
+        let range = match change.range {
+            Some(range) => range,
+            None => {
+                *text = new_text;
+                edits = None;
+                valid_lines = 0;
+                continue;
+            }
+        };
+        if range.end.line >= valid_lines {
+            line_index = LineIndex::new(text);
+        }
+        valid_lines = range.start.line;
+
+        let start = clamped_offset(text, &line_index, range.start);
+        let end = clamped_offset(text, &line_index, range.end).max(start);
+        text.replace_range(start.to_usize()..end.to_usize(), &new_text);
+        if let Some(edits) = &mut edits {
+            edits.push(AtomTextEdit::replace(TextRange::from_to(start, end), new_text));
+        }
+    }
+    edits
+}
+
+/// Like `Position::conv_with`, but positions past the end of a line or of the
+/// document are moved back to that end, as required by the LSP spec.
+fn clamped_offset(text: &str, line_index: &LineIndex, position: Position) -> TextUnit {
+    let text_len = TextUnit::of_str(text);
+    let last_line = line_index.line_col(text_len).line;
+    let line = position.line as u32;
+    if line > last_line {
+        return text_len;
+    }
+    let line_end = if line == last_line {
+        text_len
+    } else {
+        line_index.offset(LineCol { line: line + 1, col_utf16: 0 }) - TextUnit::of_char('\n')
+    };
+    let mut offset = position.conv_with(line_index).min(line_end).to_usize();
+    // A position in the middle of a surrogate pair points into a character.
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    TextUnit::from_usize(offset)
+}
+
 impl Conv for ra_ide::Documentation {
     type Output = lsp_types::Documentation;
     fn conv(self) -> Documentation {
@@ -367,12 +434,14 @@ impl Conv for Highlight {
             HighlightTag::Macro => SemanticTokenType::MACRO,
             HighlightTag::Local => SemanticTokenType::VARIABLE,
             HighlightTag::TypeParam => SemanticTokenType::TYPE_PARAMETER,
+            HighlightTag::Label => SemanticTokenType::LABEL,
             HighlightTag::Lifetime => LIFETIME,
             HighlightTag::ByteLiteral | HighlightTag::NumericLiteral => SemanticTokenType::NUMBER,
             HighlightTag::CharLiteral | HighlightTag::StringLiteral => SemanticTokenType::STRING,
             HighlightTag::Comment => SemanticTokenType::COMMENT,
             HighlightTag::Attribute => ATTRIBUTE,
             HighlightTag::Keyword => SemanticTokenType::KEYWORD,
+            HighlightTag::Operator => SemanticTokenType::OPERATOR,
         };
 
         for modifier in self.modifiers.iter() {
@@ -714,4 +783,69 @@ fn main() <fold>{
             assert_eq!(folding_range.end_character, None);
         }
     }
+
+    #[test]
+    fn test_apply_document_changes() {
+        fn change(
+            range: Option<(u64, u64, u64, u64)>,
+            text: &str,
+        ) -> TextDocumentContentChangeEvent {
+            TextDocumentContentChangeEvent {
+                range: range.map(|(start_line, start_col, end_line, end_col)| {
+                    Range::new(
+                        Position::new(start_line, start_col),
+                        Position::new(end_line, end_col),
+                    )
+                }),
+                range_length: None,
+                text: text.to_string(),
+            }
+        }
+        fn edits(edits: &[AtomTextEdit]) -> Vec<(TextRange, &str)> {
+            edits.iter().map(|it| (it.delete, it.insert.as_str())).collect()
+        }
+
+        let mut text = String::new();
+        assert!(
+            apply_document_changes(&mut text, vec![change(None, "the\nquick\nbrown")]).is_none()
+        );
+        assert_eq!(text, "the\nquick\nbrown");
+
+        // Changes are relative to the text after the previous change.
+        let res = apply_document_changes(
+            &mut text,
+            vec![
+                change(Some((2, 0, 2, 5)), "fox"),
+                change(Some((0, 0, 0, 3)), "a"),
+                change(Some((1, 0, 1, 0)), "very\n"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(text, "a\nvery\nquick\nfox");
+        assert_eq!(
+            edits(&res),
+            vec![
+                (TextRange::from_to(10.into(), 15.into()), "fox"),
+                (TextRange::from_to(0.into(), 3.into()), "a"),
+                (TextRange::from_to(2.into(), 2.into()), "very\n"),
+            ]
+        );
+
+        // Columns count UTF-16 code units, and positions past the end of a
+        // line or of the document are clamped.
+        let mut text = "a\u{1F600}b\ncd".to_string();
+        let res = apply_document_changes(
+            &mut text,
+            vec![change(Some((1, 1, 7, 0)), "!"), change(Some((0, 3, 0, 9)), "x\r\ny")],
+        )
+        .unwrap();
+        assert_eq!(text, "a\u{1F600}x\ny\nc!");
+        assert_eq!(
+            edits(&res),
+            vec![
+                (TextRange::from_to(8.into(), 9.into()), "!"),
+                (TextRange::from_to(5.into(), 6.into()), "x\ny"),
+            ]
+        );
+    }
 }
@@ -22,7 +22,7 @@ use lsp_types::{
     WorkDoneProgressReport,
 };
 use ra_cargo_watch::{url_from_path_with_drive_lowercasing, CheckOptions, CheckTask};
-use ra_ide::{Canceled, FileId, InlayHintsOptions, LibraryData, SourceRootId};
+use ra_ide::{Canceled, DiagnosticsOptions, FileId, InlayHintsOptions, LibraryData, SourceRootId};
 use ra_prof::profile;
 use ra_vfs::{VfsFile, VfsTask, Watch};
 use relative_path::RelativePathBuf;
@@ -101,6 +101,10 @@ fn get_options(
             chaining_hints: config.inlay_hints_chaining,
             max_length: config.inlay_hints_max_length,
         },
+        diagnostics: DiagnosticsOptions {
+            type_mismatch: config.diagnostics_type_mismatch,
+            ..DiagnosticsOptions::default()
+        },
         cargo_watch: CheckOptions {
             enable: config.cargo_watch_enable,
             args: config.cargo_watch_args.clone(),
@@ -109,6 +113,18 @@ fn get_options(
         },
         rustfmt_args: config.rustfmt_args.clone(),
         vscode_lldb: config.vscode_lldb,
+        proc_macro_srv: if config.proc_macro_enable {
+            // The server is built into our own binary, see the `proc-macro` subcommand.
+            match std::env::current_exe() {
+                Ok(path) => Some((path, vec!["proc-macro".into()])),
+                Err(err) => {
+                    log::error!("failed to find the rust-analyzer executable: {}", err);
+                    None
+                }
+            }
+        } else {
+            None
+        },
     }
 }
 
@@ -571,6 +587,8 @@ fn on_request(
         .on::<req::AnalyzerStatus>(handlers::handle_analyzer_status)?
         .on::<req::SyntaxTree>(handlers::handle_syntax_tree)?
         .on::<req::ExpandMacro>(handlers::handle_expand_macro)?
+        .on::<req::ExpandMacroTrace>(handlers::handle_expand_macro_trace)?
+        .on::<req::ChangeSignature>(handlers::handle_change_signature)?
         .on::<req::OnTypeFormatting>(handlers::handle_on_type_formatting)?
         .on::<req::DocumentSymbolRequest>(handlers::handle_document_symbol)?
         .on::<req::WorkspaceSymbol>(handlers::handle_workspace_symbol)?
@@ -641,12 +659,10 @@ fn on_notification(
         Err(not) => not,
     };
     let not = match notification_cast::<req::DidChangeTextDocument>(not) {
-        Ok(mut params) => {
+        Ok(params) => {
             let uri = params.text_document.uri;
             let path = uri.to_file_path().map_err(|()| format!("invalid uri: {}", uri))?;
-            let text =
-                params.content_changes.pop().ok_or_else(|| "empty changes".to_string())?.text;
-            state.vfs.write().change_file_overlay(path.as_path(), text);
+            state.change_file_overlay(path.as_path(), params.content_changes)?;
             return Ok(());
         }
         Err(not) => not,
@@ -20,7 +20,7 @@ use lsp_types::{
 };
 use ra_ide::{
     Assist, AssistId, CompletionOptions, FileId, FilePosition, FileRange, Query, RangeInfo,
-    Runnable, RunnableKind, SearchScope,
+    Runnable, RunnableKind, SearchScope, SignatureParam,
 };
 use ra_prof::profile;
 use ra_syntax::{AstNode, SyntaxKind, TextRange, TextUnit};
@@ -82,6 +82,44 @@ pub fn handle_expand_macro(
     }
 }
 
+pub fn handle_expand_macro_trace(
+    world: WorldSnapshot,
+    params: req::ExpandMacroParams,
+) -> Result<Option<Vec<req::MacroExpansionStep>>> {
+    let _p = profile("handle_expand_macro_trace");
+    let file_id = params.text_document.try_conv_with(&world)?;
+    let line_index = world.analysis().file_line_index(file_id)?;
+    let offset = match params.position {
+        Some(position) => position.conv_with(&line_index),
+        None => return Ok(None),
+    };
+
+    let steps = match world.analysis().expand_macro_trace(FilePosition { file_id, offset })? {
+        Some(it) => it,
+        None => return Ok(None),
+    };
+    let res = steps
+        .into_iter()
+        .map(|step| req::MacroExpansionStep {
+            name: step.name,
+            call: step.call,
+            rule: step.rule,
+            bindings: step
+                .bindings
+                .into_iter()
+                .map(|binding| req::MacroBinding {
+                    name: binding.name,
+                    nesting: binding.nesting,
+                    text: binding.text,
+                    range: binding.range.map(|it| it.conv_with(&line_index)),
+                })
+                .collect(),
+            expansion: step.expansion,
+        })
+        .collect();
+    Ok(Some(res))
+}
+
 pub fn handle_selection_range(
     world: WorldSnapshot,
     params: req::SelectionRangeParams,
@@ -551,6 +589,30 @@ pub fn handle_rename(world: WorldSnapshot, params: RenameParams) -> Result<Optio
     Ok(Some(source_change_req.workspace_edit))
 }
 
+pub fn handle_change_signature(
+    world: WorldSnapshot,
+    params: req::ChangeSignatureParams,
+) -> Result<Option<req::SourceChange>> {
+    let _p = profile("handle_change_signature");
+    let file_id = params.text_document.try_conv_with(&world)?;
+    let line_index = world.analysis().file_line_index(file_id)?;
+    let position = FilePosition { file_id, offset: params.position.conv_with(&line_index) };
+    let new_params: Vec<SignatureParam> = params
+        .params
+        .into_iter()
+        .map(|param| match param {
+            req::SignatureParam::Existing { index } => SignatureParam::Existing(index),
+            req::SignatureParam::New { pattern, ty, default } => {
+                SignatureParam::New { pattern, ty, default }
+            }
+        })
+        .collect();
+    match world.analysis().change_signature(position, &new_params)? {
+        None => Ok(None),
+        Some(change) => Ok(Some(change.info.try_conv_with(&world)?)),
+    }
+}
+
 pub fn handle_references(
     world: WorldSnapshot,
     params: req::ReferenceParams,
@@ -697,7 +759,7 @@ pub fn handle_code_action(
     let line_index = world.analysis().file_line_index(file_id)?;
     let range = params.range.conv_with(&line_index);
 
-    let diagnostics = world.analysis().diagnostics(file_id)?;
+    let diagnostics = world.analysis().diagnostics(file_id, &world.options.diagnostics)?;
     let mut res = CodeActionResponse::default();
 
     let fixes_from_diagnostics = diagnostics
@@ -951,7 +1013,7 @@ pub fn publish_diagnostics(world: &WorldSnapshot, file_id: FileId) -> Result<Dia
     let line_index = world.analysis().file_line_index(file_id)?;
     let diagnostics: Vec<Diagnostic> = world
         .analysis()
-        .diagnostics(file_id)?
+        .diagnostics(file_id, &world.options.diagnostics)?
         .into_iter()
         .map(|d| Diagnostic {
             range: d.range.conv_with(&line_index),
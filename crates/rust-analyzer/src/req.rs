@@ -71,6 +71,56 @@ pub struct ExpandMacroParams {
     pub position: Option<Position>,
 }
 
+pub enum ExpandMacroTrace {}
+
+impl Request for ExpandMacroTrace {
+    type Params = ExpandMacroParams;
+    type Result = Option<Vec<MacroExpansionStep>>;
+    const METHOD: &'static str = "rust-analyzer/expandMacroTrace";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MacroExpansionStep {
+    pub name: String,
+    pub call: String,
+    pub rule: Option<usize>,
+    pub bindings: Vec<MacroBinding>,
+    pub expansion: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MacroBinding {
+    pub name: String,
+    pub nesting: Vec<usize>,
+    pub text: String,
+    pub range: Option<Range>,
+}
+
+pub enum ChangeSignature {}
+
+impl Request for ChangeSignature {
+    type Params = ChangeSignatureParams;
+    type Result = Option<SourceChange>;
+    const METHOD: &'static str = "rust-analyzer/changeSignature";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeSignatureParams {
+    pub text_document: TextDocumentIdentifier,
+    pub position: Position,
+    pub params: Vec<SignatureParam>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum SignatureParam {
+    Existing { index: usize },
+    New { pattern: String, ty: String, default: String },
+}
+
 pub enum FindMatchingBrace {}
 
 impl Request for FindMatchingBrace {
@@ -4,24 +4,28 @@
 //! Each tick provides an immutable snapshot of the state as `WorldSnapshot`.
 
 use std::{
+    ffi::OsString,
+    mem,
     path::{Path, PathBuf},
     sync::Arc,
 };
 
 use crossbeam_channel::{unbounded, Receiver};
-use lsp_types::Url;
+use lsp_types::{TextDocumentContentChangeEvent, Url};
 use parking_lot::RwLock;
 use ra_cargo_watch::{url_from_path_with_drive_lowercasing, CheckOptions, CheckWatcher};
 use ra_ide::{
-    Analysis, AnalysisChange, AnalysisHost, CrateGraph, FileId, InlayHintsOptions, LibraryData,
-    SourceRootId,
+    Analysis, AnalysisChange, AnalysisHost, CrateGraph, DiagnosticsOptions, FileId,
+    InlayHintsOptions, LibraryData, SourceRootId,
 };
 use ra_project_model::{get_rustc_cfg_options, ProcMacroClient, ProjectWorkspace};
+use ra_text_edit::AtomTextEdit;
 use ra_vfs::{LineEndings, RootEntry, Vfs, VfsChange, VfsFile, VfsRoot, VfsTask, Watch};
 use relative_path::RelativePathBuf;
 use stdx::format_to;
 
 use crate::{
+    conv::apply_document_changes,
     diagnostics::{CheckFixes, DiagnosticCollection},
     feature_flags::FeatureFlags,
     main_loop::pending_requests::{CompletedRequest, LatestRequests},
@@ -55,9 +59,11 @@ pub struct Options {
     pub supports_location_link: bool,
     pub line_folding_only: bool,
     pub inlay_hints: InlayHintsOptions,
+    pub diagnostics: DiagnosticsOptions,
     pub rustfmt_args: Vec<String>,
     pub cargo_watch: CheckOptions,
     pub vscode_lldb: bool,
+    pub proc_macro_srv: Option<(PathBuf, Vec<OsString>)>,
 }
 
 /// `WorldState` is the primary mutable state of the language server
@@ -77,6 +83,10 @@ pub struct WorldState {
     pub latest_requests: Arc<RwLock<LatestRequests>>,
     pub check_watcher: Option<CheckWatcher>,
     pub diagnostics: DiagnosticCollection,
+    pub proc_macro_client: ProcMacroClient,
+    /// Edits of open files which have been sent to the VFS, but not yet to
+    /// the analysis.
+    pending_edits: FxHashMap<FileId, Vec<AtomTextEdit>>,
 }
 
 /// An immutable snapshot of the world's state at a point in time.
@@ -167,8 +177,20 @@ impl WorldState {
             vfs_file.map(|f| FileId(f.0))
         };
 
-        let proc_macro_client =
-            ProcMacroClient::extern_process(std::path::Path::new("ra_proc_macro_srv"));
+        let proc_macro_client = match &options.proc_macro_srv {
+            None => ProcMacroClient::dummy(),
+            Some((path, args)) => match ProcMacroClient::extern_process(path.clone(), args) {
+                Ok(it) => it,
+                Err(err) => {
+                    log::error!(
+                        "Failed to run ra_proc_macro_srv from path {}, error: {:?}",
+                        path.display(),
+                        err
+                    );
+                    ProcMacroClient::dummy()
+                }
+            },
+        };
 
         workspaces
             .iter()
@@ -200,6 +222,8 @@ impl WorldState {
             latest_requests: Default::default(),
             check_watcher,
             diagnostics: Default::default(),
+            proc_macro_client,
+            pending_edits: FxHashMap::default(),
         }
     }
 
@@ -221,6 +245,7 @@ impl WorldState {
         &mut self,
         roots_scanned: &mut usize,
     ) -> Option<Vec<(SourceRootId, Vec<(FileId, RelativePathBuf, Arc<String>)>)>> {
+        let mut pending_edits = mem::take(&mut self.pending_edits);
         let changes = self.vfs.write().commit_changes();
         if changes.is_empty() {
             return None;
@@ -252,7 +277,11 @@ impl WorldState {
                     change.remove_file(SourceRootId(root.0), FileId(file.0), path)
                 }
                 VfsChange::ChangeFile { file, text } => {
-                    change.change_file(FileId(file.0), text);
+                    let file_id = FileId(file.0);
+                    match pending_edits.remove(&file_id) {
+                        Some(edits) => change.change_file_with_edits(file_id, text, edits),
+                        None => change.change_file(file_id, text),
+                    }
                 }
             }
         }
@@ -260,6 +289,29 @@ impl WorldState {
         Some(libs)
     }
 
+    /// Applies the changes from a `textDocument/didChange` notification to an
+    /// open file.
+    pub fn change_file_overlay(
+        &mut self,
+        path: &Path,
+        changes: Vec<TextDocumentContentChangeEvent>,
+    ) -> Result<()> {
+        let file_id = match self.vfs.read().path2file(path) {
+            Some(file) => FileId(file.0),
+            None => return Ok(()),
+        };
+        // The VFS normalizes line endings, so positions are interpreted in
+        // the text of the analysis, which is up to date at this point.
+        let mut text = String::clone(&*self.analysis_host.analysis().file_text(file_id)?);
+        let edits = apply_document_changes(&mut text, changes);
+        self.vfs.write().change_file_overlay(path, text);
+        match edits {
+            Some(edits) => self.pending_edits.insert(file_id, edits),
+            None => self.pending_edits.remove(&file_id),
+        };
+        Ok(())
+    }
+
     pub fn add_lib(&mut self, data: LibraryData) {
         let mut change = AnalysisChange::new();
         change.add_library(data);
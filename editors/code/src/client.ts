@@ -26,6 +26,8 @@ export function configToServerOptions(config: Config) {
         withSysroot: config.withSysroot,
         cargoFeatures: config.cargoFeatures,
         rustfmtArgs: config.rustfmtArgs,
+        procMacroEnable: config.procMacroEnabled,
+        diagnosticsTypeMismatch: config.diagnosticsTypeMismatch,
         vscodeLldb: vscode.extensions.getExtension("vadimcn.vscode-lldb") != null,
     };
 }
@@ -0,0 +1,54 @@
+import * as vscode from 'vscode';
+import * as ra from '../rust-analyzer-api';
+
+import { Ctx, Cmd } from '../ctx';
+import { applySourceChange } from '../source_change';
+
+// Parses a list like `1, 0, flag: bool = false`: numbers refer to the
+// existing parameters (not counting `self`), everything else is a new
+// parameter together with the value to pass at existing call sites.
+function parseParams(input: string): ra.SignatureParam[] | string {
+    const params: ra.SignatureParam[] = [];
+    for (const item of input.split(',').map(it => it.trim()).filter(it => it.length > 0)) {
+        if (/^\d+$/.test(item)) {
+            params.push({ kind: "existing", index: Number(item) });
+            continue;
+        }
+        const match = /^([^:]+):([^=]+)=(.+)$/.exec(item);
+        if (!match) {
+            return `expected a parameter index or \`pattern: Type = default\`, found \`${item}\``;
+        }
+        params.push({ kind: "new", pattern: match[1].trim(), ty: match[2].trim(), default: match[3].trim() });
+    }
+    return params;
+}
+
+export function changeSignature(ctx: Ctx): Cmd {
+    return async () => {
+        const editor = ctx.activeRustEditor;
+        const client = ctx.client;
+        if (!editor || !client) return;
+
+        const input = await vscode.window.showInputBox({
+            prompt: "New parameters, for example '1, 0, flag: bool = false'",
+            validateInput: (x: string) => {
+                const params = parseParams(x);
+                return typeof params === "string" ? params : null;
+            }
+        });
+        if (input === undefined) return;
+        const params = parseParams(input);
+        if (typeof params === "string") return;
+
+        const change = await client.sendRequest(ra.changeSignature, {
+            textDocument: { uri: editor.document.uri.toString() },
+            position: client.code2ProtocolConverter.asPosition(editor.selection.active),
+            params,
+        });
+        if (!change) {
+            vscode.window.showInformationMessage("No function to change the signature of at the cursor");
+            return;
+        }
+        await applySourceChange(ctx, change);
+    };
+}
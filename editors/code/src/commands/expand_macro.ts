@@ -64,3 +64,72 @@ class TextDocumentContentProvider
         return this.eventEmitter.event;
     }
 }
+
+// Opens the virtual file that shows how the macro call under the cursor is
+// expanded, step by step
+export function expandMacroTrace(ctx: Ctx): Cmd {
+    const tdcp = new TraceDocumentContentProvider(ctx);
+    ctx.pushCleanup(
+        vscode.workspace.registerTextDocumentContentProvider(
+            'rust-analyzer',
+            tdcp,
+        ),
+    );
+
+    return async () => {
+        const document = await vscode.workspace.openTextDocument(tdcp.uri);
+        tdcp.eventEmitter.fire(tdcp.uri);
+        return vscode.window.showTextDocument(
+            document,
+            vscode.ViewColumn.Two,
+            true,
+        );
+    };
+}
+
+function traceFormat(steps: ra.MacroExpansionStep[]): string {
+    let result = '';
+    steps.forEach((step, i) => {
+        const rule = step.rule == null ? '' : `, using rule #${step.rule}`;
+        result += `// Step ${i + 1}: ${step.call}${rule}\n`;
+        for (const binding of step.bindings) {
+            const nesting = binding.nesting.map(it => `[${it}]`).join('');
+            const location = binding.range == null
+                ? ''
+                : ` (line ${binding.range.start.line + 1})`;
+            result += `//   $${binding.name}${nesting} = ${binding.text}${location}\n`;
+        }
+        result += '\n' + step.expansion + '\n\n';
+    });
+    return result;
+}
+
+class TraceDocumentContentProvider
+    implements vscode.TextDocumentContentProvider {
+    uri = vscode.Uri.parse('rust-analyzer://expandMacroTrace/[TRACE].rs');
+    eventEmitter = new vscode.EventEmitter<vscode.Uri>();
+
+    constructor(private readonly ctx: Ctx) {
+    }
+
+    async provideTextDocumentContent(_uri: vscode.Uri): Promise<string> {
+        const editor = vscode.window.activeTextEditor;
+        const client = this.ctx.client;
+        if (!editor || !client) return '';
+
+        const position = editor.selection.active;
+
+        const steps = await client.sendRequest(ra.expandMacroTrace, {
+            textDocument: { uri: editor.document.uri.toString() },
+            position,
+        });
+
+        if (steps == null) return 'Not available';
+
+        return traceFormat(steps);
+    }
+
+    get onDidChange(): vscode.Event<vscode.Uri> {
+        return this.eventEmitter.event;
+    }
+}
@@ -16,6 +16,7 @@ export class Config {
         "useClientWatching",
         "highlighting",
         "updates.channel",
+        "procMacro",
     ]
         .map(opt => `${this.rootSection}.${opt}`);
 
@@ -78,6 +79,8 @@ export class Config {
     get useClientWatching() { return this.cfg.get<boolean>("useClientWatching")!; }
     get featureFlags() { return this.cfg.get<Record<string, boolean>>("featureFlags")!; }
     get rustfmtArgs() { return this.cfg.get<string[]>("rustfmtArgs")!; }
+    get procMacroEnabled() { return this.cfg.get<boolean>("procMacro.enabled")!; }
+    get diagnosticsTypeMismatch() { return this.cfg.get<boolean>("diagnostics.typeMismatch")!; }
     get loadOutDirsFromCheck() { return this.cfg.get<boolean>("loadOutDirsFromCheck")!; }
     get traceExtension() { return this.cfg.get<boolean>("trace.extension")!; }
 
@@ -70,12 +70,14 @@ export async function activate(context: vscode.ExtensionContext) {
     ctx.registerCommand('parentModule', commands.parentModule);
     ctx.registerCommand('syntaxTree', commands.syntaxTree);
     ctx.registerCommand('expandMacro', commands.expandMacro);
+    ctx.registerCommand('expandMacroTrace', commands.expandMacroTrace);
     ctx.registerCommand('run', commands.run);
 
     defaultOnEnter.dispose();
     ctx.registerCommand('onEnter', commands.onEnter);
 
     ctx.registerCommand('ssr', commands.ssr);
+    ctx.registerCommand('changeSignature', commands.changeSignature);
     ctx.registerCommand('serverVersion', commands.serverVersion);
 
     // Internal commands which are invoked by the server.
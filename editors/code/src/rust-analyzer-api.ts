@@ -39,6 +39,32 @@ export interface ExpandedMacro {
 }
 export const expandMacro = request<ExpandMacroParams, Option<ExpandedMacro>>("expandMacro");
 
+export interface MacroBinding {
+    name: string;
+    nesting: Vec<number>;
+    text: string;
+    range: Option<lc.Range>;
+}
+export interface MacroExpansionStep {
+    name: string;
+    call: string;
+    rule: Option<number>;
+    bindings: Vec<MacroBinding>;
+    expansion: string;
+}
+export const expandMacroTrace = request<ExpandMacroParams, Option<Vec<MacroExpansionStep>>>("expandMacroTrace");
+
+
+export type SignatureParam =
+    | { kind: "existing"; index: number }
+    | { kind: "new"; pattern: string; ty: string; default: string };
+export interface ChangeSignatureParams {
+    textDocument: lc.TextDocumentIdentifier;
+    position: lc.Position;
+    params: Vec<SignatureParam>;
+}
+export const changeSignature = request<ChangeSignatureParams, Option<SourceChange>>("changeSignature");
+
 
 export interface FindMatchingBraceParams {
     textDocument: lc.TextDocumentIdentifier;
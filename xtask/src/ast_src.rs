@@ -463,6 +463,7 @@ pub(crate) const AST_SRC: AstSrc = AstSrc {
         struct TypeParamList {
             type_params: [TypeParam],
             lifetime_params: [LifetimeParam],
+            const_params: [ConstParam],
         }
         struct TypeParam: NameOwner, AttrsOwner, TypeBoundsOwner {
             default_type: TypeRef,
@@ -20,7 +20,18 @@ fn rust_files_are_tidy() {
 }
 
 fn check_todo(path: &Path, text: &str) {
-    if path.ends_with("tests/cli.rs") {
+    let need_todo = &[
+        // This file itself obviously needs to use todo (<- like this!).
+        "tests/cli.rs",
+        // Assists and fixes generate `todo!()` only through `make::expr_todo`.
+        "ast/make.rs",
+        // These only spell it out in the expected output of the assist.
+        "doc_tests/generated.rs",
+        "handlers/generate_function.rs",
+        // The tests of the missing fields fix.
+        "ra_ide/src/diagnostics.rs",
+    ];
+    if need_todo.iter().any(|p| path.ends_with(p)) {
         return;
     }
     if text.contains("TODO") || text.contains("TOOD") || text.contains("todo!") {